@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use tag::*;
+use message::*;
+use pretty::MASK;
+use session_id::*;
+use time::*;
+
+//Credentials are the Username(553), Password(554) and NewPassword(925) of a Logon, empty when absent. Debug
+//masks the passwords.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+    pub new_password: String
+}
+
+impl Credentials {
+
+    pub fn new(username: &str, password: &str) -> Credentials {
+        Credentials{username: username.to_string(), password: password.to_string(), new_password: String::new()}
+    }
+
+    //from_logon reads the credentials of a received Logon
+    pub fn from_logon(logon: &Message) -> Credentials {
+        let get = |tag: Tags| logon.body.get_string(tag.to_num()).unwrap_or_default();
+        Credentials{username: get(Tags::Username), password: get(Tags::Password), new_password: get(Tags::NewPassword)}
+    }
+
+    //set_on adds the credentials that are not empty to the body of logon
+    pub fn set_on(&self, logon: &mut Message) {
+        let fields = [(Tags::Username, &self.username), (Tags::Password, &self.password), (Tags::NewPassword, &self.new_password)];
+        for &(tag, value) in fields.iter() {
+            if !value.is_empty() {
+                logon.body.set_string(tag.to_num(), value);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mask = |value: &str| if value.is_empty() { "" } else { MASK };
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &mask(&self.password))
+            .field("new_password", &mask(&self.new_password))
+            .finish()
+    }
+}
+
+//Authenticator checks the credentials of the Logons an acceptor receives. An error is the reason sent in Text(58)
+//of the Logout refusing the Logon. The remote address is the one given to the session by its transport.
+pub trait Authenticator {
+    fn authenticate(&self, session_id: &SessionID, credentials: &Credentials, remote_address: Option<&str>) -> Result<(), String>;
+}
+
+impl<F> Authenticator for F where F: Fn(&SessionID, &Credentials, Option<&str>) -> Result<(), String> {
+    fn authenticate(&self, session_id: &SessionID, credentials: &Credentials, remote_address: Option<&str>) -> Result<(), String> {
+        self(session_id, credentials, remote_address)
+    }
+}
+
+//LogonRateLimiter refuses the Logons of a remote address once it failed max_failures times within window. It is
+//shared by the sessions of an acceptor so that guessing across SessionIDs counts too.
+pub struct LogonRateLimiter {
+    max_failures: usize,
+    window: Duration,
+    failures: Mutex<HashMap<String, Vec<Tm>>>
+}
+
+impl LogonRateLimiter {
+
+    pub fn new(max_failures: usize, window: Duration) -> LogonRateLimiter {
+        LogonRateLimiter{max_failures: max_failures, window: window, failures: Mutex::new(HashMap::new())}
+    }
+
+    fn with_failures<T, F: FnOnce(&mut HashMap<String, Vec<Tm>>) -> T>(&self, f: F) -> T {
+        match self.failures.lock() {
+            Ok(mut failures) => f(&mut failures),
+            Err(poisoned) => f(&mut poisoned.into_inner())
+        }
+    }
+
+    //is_blocked tells whether address used up its failed attempts, older failures are forgotten
+    pub fn is_blocked(&self, address: &str, now: Tm) -> bool {
+        let window = self.window;
+        let max_failures = self.max_failures;
+        self.with_failures(|failures| {
+            let (blocked, forgotten) = match failures.get_mut(address) {
+                Some(times) => {
+                    times.retain(|time| now - *time < window);
+                    (times.len() >= max_failures, times.is_empty())
+                },
+                None => return false
+            };
+            if forgotten {
+                failures.remove(address);
+            }
+            blocked
+        })
+    }
+
+    //failed records a failure of address. The failures out of the window are dropped for every address, so
+    //addresses that stopped trying do not pile up.
+    pub fn failed(&self, address: &str, now: Tm) {
+        let window = self.window;
+        self.with_failures(|failures| {
+            failures.retain(|_, times| {
+                times.retain(|time| now - *time < window);
+                !times.is_empty()
+            });
+            failures.entry(address.to_string()).or_default().push(now);
+        });
+    }
+
+    //succeeded clears the failures of address
+    pub fn succeeded(&self, address: &str) {
+        self.with_failures(|failures| failures.remove(address));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn credentials_test() {
+        let mut logon = Message::new();
+        let mut credentials = Credentials::new("trader", "secret");
+        credentials.set_on(&mut logon);
+        assert_eq!("trader", logon.body.get_string(Tags::Username.to_num()).unwrap());
+        assert!(!logon.body.has(Tags::NewPassword.to_num()));
+
+        credentials.new_password = "secret2".to_string();
+        credentials.set_on(&mut logon);
+        assert_eq!(credentials, Credentials::from_logon(&logon));
+
+        let debug = format!("{:?}", credentials);
+        assert!(debug.contains("trader") && !debug.contains("secret"));
+    }
+
+    #[test]
+    fn rate_limiter_test() {
+        let limiter = LogonRateLimiter::new(2, Duration::seconds(60));
+        let now = now_utc();
+        assert!(!limiter.is_blocked("10.0.0.1", now));
+
+        limiter.failed("10.0.0.1", now);
+        limiter.failed("10.0.0.1", now + Duration::seconds(1));
+        assert!(limiter.is_blocked("10.0.0.1", now + Duration::seconds(2)));
+        assert!(!limiter.is_blocked("10.0.0.2", now + Duration::seconds(2)));
+        assert!(!limiter.is_blocked("10.0.0.1", now + Duration::seconds(61)));
+
+        limiter.failed("10.0.0.1", now);
+        limiter.failed("10.0.0.1", now);
+        limiter.succeeded("10.0.0.1");
+        assert!(!limiter.is_blocked("10.0.0.1", now));
+    }
+
+    #[test]
+    fn rate_limiter_prune_test() {
+        let limiter = LogonRateLimiter::new(2, Duration::seconds(60));
+        let now = now_utc();
+        for i in 0..100 {
+            limiter.failed(&format!("10.0.0.{}", i), now);
+        }
+        assert_eq!(100, limiter.with_failures(|failures| failures.len()));
+
+        //a failure after the window forgets the addresses that stopped trying
+        limiter.failed("10.0.0.1", now + Duration::seconds(61));
+        assert_eq!(1, limiter.with_failures(|failures| failures.len()));
+        assert_eq!(1, limiter.with_failures(|failures| failures["10.0.0.1"].len()));
+
+        assert!(!limiter.is_blocked("10.0.0.1", now + Duration::seconds(122)));
+        assert!(limiter.with_failures(|failures| failures.is_empty()));
+    }
+}
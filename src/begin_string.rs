@@ -0,0 +1,42 @@
+//BeginString values of the supported FIX versions, they compare in release order
+pub const BEGIN_STRING_FIX40: &'static str = "FIX.4.0";
+pub const BEGIN_STRING_FIX41: &'static str = "FIX.4.1";
+pub const BEGIN_STRING_FIX42: &'static str = "FIX.4.2";
+pub const BEGIN_STRING_FIX43: &'static str = "FIX.4.3";
+pub const BEGIN_STRING_FIX44: &'static str = "FIX.4.4";
+pub const BEGIN_STRING_FIXT11: &'static str = "FIXT.1.1";
+
+//FIX.5.0 and later only travel as FIXT.1.1 sessions, these name the application version of ApplVerID(1128)
+pub const BEGIN_STRING_FIX50: &'static str = "FIX.5.0";
+pub const BEGIN_STRING_FIX50SP1: &'static str = "FIX.5.0SP1";
+pub const BEGIN_STRING_FIX50SP2: &'static str = "FIX.5.0SP2";
+
+//appl_ver_id_begin_string maps an ApplVerID value to the FIX version it stands for, unknown values are returned unchanged
+pub fn appl_ver_id_begin_string(appl_ver_id: &str) -> &str {
+    match appl_ver_id {
+        "2" => BEGIN_STRING_FIX40,
+        "3" => BEGIN_STRING_FIX41,
+        "4" => BEGIN_STRING_FIX42,
+        "5" => BEGIN_STRING_FIX43,
+        "6" => BEGIN_STRING_FIX44,
+        "7" => BEGIN_STRING_FIX50,
+        "8" => BEGIN_STRING_FIX50SP1,
+        "9" => BEGIN_STRING_FIX50SP2,
+        other => other
+    }
+}
+
+//begin_string_appl_ver_id is the ApplVerID value naming begin_string, ApplVerID values are returned unchanged
+pub fn begin_string_appl_ver_id(begin_string: &str) -> &str {
+    match begin_string {
+        BEGIN_STRING_FIX40 => "2",
+        BEGIN_STRING_FIX41 => "3",
+        BEGIN_STRING_FIX42 => "4",
+        BEGIN_STRING_FIX43 => "5",
+        BEGIN_STRING_FIX44 => "6",
+        BEGIN_STRING_FIX50 => "7",
+        BEGIN_STRING_FIX50SP1 => "8",
+        BEGIN_STRING_FIX50SP2 => "9",
+        other => other
+    }
+}
@@ -0,0 +1,362 @@
+extern crate quickfix;
+
+use std::collections::*;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+use std::sync::Arc;
+use quickfix::*;
+
+static USAGE: &'static str = "usage: fixtool [--sbe SCHEMA] [--spec DICTIONARY]... <command> [args] [FILE]
+
+Reads one FIX message per line from FILE or standard input. Fields may be delimited by SOH or '|',
+anything before 8= on a line (log timestamps) is skipped. Given an SBE XML message schema, input made of
+SBE messages framed by the Simple Open Framing Header is decoded first, each message standing for a line.
+Given QuickFIX XML data dictionaries, repeating groups are read and messages validated after them. FIXT.1.1
+sessions need both the FIXT11 transport and the FIX50 application dictionaries.
+
+commands:
+  parse [--multiline] [FILE]   pretty print the messages
+  validate [FILE]              check framing, BodyLength, CheckSum and the session level fields, and with
+                               --spec the fields, required fields, types and enums of each MsgType,
+                               report the SessionRejectReason a session would answer with
+  stats [FILE]                 count messages by MsgType and by session
+  grep <tag=value>... [FILE]   print the lines of the messages matching every tag=value";
+
+//extract finds the FIX message on a log line, '|' delimiters are turned back into SOH
+fn extract(line: &[u8]) -> Option<Vec<u8>> {
+    let start = (0..line.len()).find(|&i| line[i..].starts_with(b"8=FIX"))?;
+    let mut raw: Vec<u8> = line[start..].to_vec();
+    while raw.last().map_or(false, |b| *b == b'\r' || *b == b' ') {
+        raw.pop();
+    }
+    if !raw.contains(&SOH) {
+        for b in raw.iter_mut() {
+            if *b == b'|' {
+                *b = SOH;
+            }
+        }
+    }
+    if raw.last() != Some(&SOH) {
+        raw.push(SOH);
+    }
+    Some(raw)
+}
+
+//Filter is a tag=value grep expression
+struct Filter {
+    tag: u32,
+    value: String
+}
+
+impl Filter {
+
+    fn parse(expression: &str) -> Option<Filter> {
+        let mut parts = expression.splitn(2, '=');
+        let tag = parts.next()?.parse::<u32>().ok()?;
+        let value = parts.next()?.to_string();
+        Some(Filter{tag: tag, value: value})
+    }
+
+    fn matches(&self, msg: &MessageView) -> bool {
+        msg.iter().any(|(tag, value)| tag == self.tag && value == self.value.as_bytes())
+    }
+}
+
+fn session_name(msg: &MessageView) -> String {
+    let field = |tag: Tags| msg.get_str(tag.to_num()).unwrap_or("?").to_string();
+    SessionID::new(&field(Tags::BeginString), &field(Tags::SenderCompID), &field(Tags::TargetCompID)).to_string()
+}
+
+fn reject_description(err: &MessageRejectError) -> String {
+    let code = (err.reject_reason() as i32).to_string();
+    let reason = TagNames.value_name(Tags::SessionRejectReason.to_num(), &code).unwrap_or_default();
+    let mut description = format!("Reject SessionRejectReason={} ({})", code, reason);
+    if err.ref_tag_id() != 0 {
+        description.push_str(&format!(" RefTagID={}", err.ref_tag_id()));
+    }
+    format!("{}: {}", description, err.text())
+}
+
+//add_spec registers dictionary by its version: FIXT.1.1 describes the transport, FIX.5.0 and later the
+//application and FIX.4.x both
+fn add_spec(dictionaries: &mut DataDictionaryProvider, dictionary: DataDictionary) {
+    let dictionary = Arc::new(dictionary);
+    if dictionary.begin_string() == BEGIN_STRING_FIXT11 {
+        dictionaries.add_transport_dictionary(dictionary);
+    } else if dictionary.begin_string().starts_with("FIX.4") {
+        dictionaries.add_dictionary(dictionary);
+    } else {
+        dictionaries.add_application_dictionary(dictionary);
+    }
+}
+
+fn parse(lines: &[&[u8]], dictionaries: &DataDictionaryProvider, multiline: bool) -> i32 {
+    for (number, line) in lines.iter().enumerate() {
+        if let Some(raw) = extract(line) {
+            match dictionaries.parse(&raw, "") {
+                Ok(msg) if multiline => println!("{:#}\n", msg.pretty()),
+                Ok(msg) => println!("{}", msg.pretty()),
+                Err(err) => eprintln!("line {}: {}", number + 1, err)
+            }
+        }
+    }
+    0
+}
+
+fn validate_lines(lines: &[&[u8]], dictionaries: &DataDictionaryProvider) -> i32 {
+    let mut invalid = 0;
+    for (number, line) in lines.iter().enumerate() {
+        if let Some(raw) = extract(line) {
+            let problem = match validate_with_dictionaries(&raw, dictionaries) {
+                Ok(_) => continue,
+                Err(FixError::RejectError(err)) => reject_description(&err),
+                Err(err) => err.to_string()
+            };
+            invalid += 1;
+            println!("line {}: {}", number + 1, problem);
+        }
+    }
+    if invalid > 0 { 1 } else { 0 }
+}
+
+fn stats(lines: &[&[u8]]) -> i32 {
+    let mut msg_types: BTreeMap<String, usize> = BTreeMap::new();
+    let mut sessions: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unparsed = 0;
+
+    for line in lines.iter() {
+        let raw = match extract(line) {
+            Some(raw) => raw,
+            None => continue
+        };
+        match MessageView::parse(&raw) {
+            Ok(msg) => {
+                let msg_type = msg.msg_type().unwrap_or("?");
+                let name = format!("{} ({})", msg_type, msg_type_name(msg_type).unwrap_or("Unknown"));
+                *msg_types.entry(name).or_insert(0) += 1;
+                *sessions.entry(session_name(&msg)).or_insert(0) += 1;
+            },
+            Err(_) => unparsed += 1
+        }
+    }
+
+    println!("MsgType");
+    for (msg_type, count) in msg_types.iter() {
+        println!("  {:<40} {}", msg_type, count);
+    }
+    println!("Session");
+    for (session, count) in sessions.iter() {
+        println!("  {:<40} {}", session, count);
+    }
+    if unparsed > 0 {
+        println!("Unparsed {}", unparsed);
+    }
+    0
+}
+
+fn grep(lines: &[&[u8]], filters: &[Filter]) -> i32 {
+    let mut found = false;
+    for line in lines.iter() {
+        let raw = match extract(line) {
+            Some(raw) => raw,
+            None => continue
+        };
+        if let Ok(msg) = MessageView::parse(&raw) {
+            if filters.iter().all(|filter| filter.matches(&msg)) {
+                found = true;
+                println!("{}", String::from_utf8_lossy(line));
+            }
+        }
+    }
+    if found { 0 } else { 1 }
+}
+
+//sbe_lines decodes a stream of framed SBE messages as tag=value lines, a message that can not be decoded
+//leaves an empty line so line numbers still count messages
+fn sbe_lines(codec: &SbeCodec, input: &[u8]) -> Vec<Vec<u8>> {
+    let mut lines = vec![];
+    let mut pos = 0;
+    while pos < input.len() {
+        match sofh_unframe(&input[pos..]) {
+            Ok(Some((message, length))) => {
+                match codec.decode(message) {
+                    Ok(msg) => lines.push(msg.raw_message().to_vec()),
+                    Err(err) => {
+                        eprintln!("line {}: {}", lines.len() + 1, err);
+                        lines.push(vec![]);
+                    }
+                }
+                pos += length;
+            },
+            Ok(None) => {
+                eprintln!("fixtool: truncated SBE frame at byte {}", pos);
+                break;
+            },
+            Err(err) => {
+                eprintln!("fixtool: {} at byte {}", err, pos);
+                break;
+            }
+        }
+    }
+    lines
+}
+
+fn read_input(path: Option<&String>) -> io::Result<Vec<u8>> {
+    let mut input = vec![];
+    match path {
+        Some(path) => File::open(path)?.read_to_end(&mut input)?,
+        None => io::stdin().read_to_end(&mut input)?
+    };
+    Ok(input)
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut codec = None;
+    let mut dictionaries = DataDictionaryProvider::new();
+    while args.first().map_or(false, |arg| arg == "--sbe" || arg == "--spec") {
+        if args.len() < 2 {
+            usage();
+        }
+        let loaded = if args[0] == "--sbe" {
+            SbeCodec::load(&args[1]).map(|loaded| codec = Some(loaded))
+        } else {
+            DataDictionary::load(&args[1]).map(|dictionary| add_spec(&mut dictionaries, dictionary))
+        };
+        if let Err(err) = loaded {
+            eprintln!("fixtool: {}: {}", args[1], err);
+            process::exit(2)
+        }
+        args.drain(..2);
+    }
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => usage()
+    };
+
+    let mut multiline = false;
+    let mut filters = vec![];
+    let mut path = None;
+    for arg in args[1..].iter() {
+        if command == "parse" && arg == "--multiline" {
+            multiline = true;
+        } else if command == "grep" && path.is_none() && Filter::parse(arg).is_some() {
+            filters.extend(Filter::parse(arg));
+        } else if path.is_none() {
+            path = Some(arg);
+        } else {
+            usage();
+        }
+    }
+
+    let input = match read_input(path) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("fixtool: {}", err);
+            process::exit(2)
+        }
+    };
+    let decoded = match codec {
+        Some(ref codec) if is_sofh_frame(&input) => sbe_lines(codec, &input),
+        _ => vec![]
+    };
+    let lines: Vec<&[u8]> = if decoded.is_empty() {
+        input.split(|b| *b == b'\n').collect()
+    } else {
+        decoded.iter().map(|line| line.as_slice()).collect()
+    };
+
+    let status = match command {
+        "parse" => parse(&lines, &dictionaries, multiline),
+        "validate" => validate_lines(&lines, &dictionaries),
+        "stats" => stats(&lines),
+        "grep" if !filters.is_empty() => grep(&lines, &filters),
+        _ => usage()
+    };
+    process::exit(status)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extract_test() {
+        let expected = b"8=FIX.4.2\x0135=0\x0110=000\x01".to_vec();
+        assert_eq!(Some(expected.clone()), extract(b"20161012-10:05:01.005 : 8=FIX.4.2\x0135=0\x0110=000\x01"));
+        assert_eq!(Some(expected.clone()), extract(b"8=FIX.4.2|35=0|10=000|\r"));
+        assert_eq!(Some(expected), extract(b"8=FIX.4.2|35=0|10=000"));
+        assert_eq!(None, extract(b"<event> Received logon"));
+    }
+
+    #[test]
+    fn filter_test() {
+        assert!(Filter::parse("55").is_none());
+        assert!(Filter::parse("x=1").is_none());
+
+        let raw = extract(b"8=FIX.4.2|9=5|35=D|55=MSFT|10=000").unwrap();
+        let msg = MessageView::parse(&raw).unwrap();
+        assert!(Filter::parse("55=MSFT").unwrap().matches(&msg));
+        assert!(!Filter::parse("55=IBM").unwrap().matches(&msg));
+    }
+
+    #[test]
+    fn sbe_lines_test() {
+        let schema = r#"<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" id="1" semanticVersion="FIX.4.4">
+  <types>
+    <composite name="messageHeader">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="templateId" primitiveType="uint16"/>
+      <type name="schemaId" primitiveType="uint16"/>
+      <type name="version" primitiveType="uint16"/>
+    </composite>
+    <type name="Symbol" primitiveType="char" length="4"/>
+  </types>
+  <sbe:message name="News" id="1" semanticType="B">
+    <field name="Symbol" id="55" type="Symbol"/>
+  </sbe:message>
+</sbe:messageSchema>"#;
+        let codec = SbeCodec::new(SbeSchema::parse(schema).unwrap());
+        let msg = Message::parse(b"8=FIX.4.4\x019=0\x0135=B\x0155=MSFT\x0110=000\x01").unwrap();
+        let mut input = sofh_frame(&codec.encode(&msg).unwrap());
+        input.extend(sofh_frame(b"bad"));
+        input.extend(sofh_frame(&codec.encode(&msg).unwrap()));
+
+        let lines = sbe_lines(&codec, &input);
+        assert_eq!(3, lines.len());
+        assert_eq!(b"8=FIX.4.4\x019=13\x0135=B\x0155=MSFT\x0110=198\x01".to_vec(), lines[0]);
+        assert!(lines[1].is_empty());
+        assert_eq!(lines[0], lines[2]);
+    }
+
+    #[test]
+    fn add_spec_test() {
+        let spec = |fix_type: &str, major: u32, minor: u32| {
+            DataDictionary::parse(&format!(r#"<fix type="{}" major="{}" minor="{}"><fields/></fix>"#, fix_type, major, minor)).unwrap()
+        };
+        let mut dictionaries = DataDictionaryProvider::new();
+        add_spec(&mut dictionaries, spec("FIX", 4, 2));
+        add_spec(&mut dictionaries, spec("FIXT", 1, 1));
+        add_spec(&mut dictionaries, spec("FIX", 5, 0));
+
+        assert!(dictionaries.transport_dictionary("FIX.4.2").is_some());
+        assert!(dictionaries.application_dictionary("FIX.4.2", None).is_some());
+        assert!(dictionaries.transport_dictionary("FIXT.1.1").is_some());
+        assert!(dictionaries.application_dictionary("FIXT.1.1", None).is_none());
+        assert!(dictionaries.transport_dictionary("FIX.5.0").is_none());
+        assert!(dictionaries.application_dictionary("7", None).is_some());
+    }
+
+    #[test]
+    fn reject_description_test() {
+        let err = MessageRejectError::required_tag_missing(52);
+        assert_eq!("Reject SessionRejectReason=1 (RequiredTagMissing) RefTagID=52: Required tag missing", reject_description(&err));
+    }
+}
@@ -0,0 +1,989 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str;
+use std::sync::Arc;
+use tag::*;
+use field::*;
+use field_map::*;
+use message::*;
+use message_view::*;
+use begin_string::*;
+use msg_type::*;
+use fix_utc_timestamp::*;
+use settings::*;
+use error::FixError;
+use error::error::MessageRejectError;
+use xml::{self, Element};
+use pretty::FieldNames;
+use fixml::{self, FixmlSchema, FixmlComponent, FixmlTags};
+
+fn dictionary_error(text: &str) -> FixError {
+    FixError::ConfigError(format!("DataDictionary: {}", text))
+}
+
+//FieldType is the FIX data type of a field, it decides which values are well formed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Int,
+    Length,
+    NumInGroup,
+    SeqNum,
+    TagNum,
+    DayOfMonth,
+    //FLOAT and its QTY, PRICE, PRICEOFFSET, AMT and PERCENTAGE flavours
+    Float,
+    Char,
+    Boolean,
+    String,
+    //space separated values, each one checked against the enum of the field
+    MultipleValue,
+    UtcTimestamp,
+    UtcTimeOnly,
+    UtcDateOnly,
+    LocalMktDate,
+    MonthYear,
+    //DATA and XMLDATA may hold any byte, SOH included
+    Data
+}
+
+impl FieldType {
+
+    //from_name maps the type attribute of a QuickFIX dictionary, types without a dedicated check are strings
+    pub fn from_name(name: &str) -> FieldType {
+        match name {
+            "INT" => FieldType::Int,
+            "LENGTH" => FieldType::Length,
+            "NUMINGROUP" => FieldType::NumInGroup,
+            "SEQNUM" => FieldType::SeqNum,
+            "TAGNUM" => FieldType::TagNum,
+            "DAYOFMONTH" => FieldType::DayOfMonth,
+            "FLOAT" | "QTY" | "QUANTITY" | "PRICE" | "PRICEOFFSET" | "AMT" | "PERCENTAGE" => FieldType::Float,
+            "CHAR" => FieldType::Char,
+            "BOOLEAN" => FieldType::Boolean,
+            "MULTIPLECHARVALUE" | "MULTIPLEVALUESTRING" | "MULTIPLESTRINGVALUE" => FieldType::MultipleValue,
+            "UTCTIMESTAMP" | "TIME" => FieldType::UtcTimestamp,
+            "UTCTIMEONLY" => FieldType::UtcTimeOnly,
+            "UTCDATEONLY" | "UTCDATE" => FieldType::UtcDateOnly,
+            "LOCALMKTDATE" | "DATE" => FieldType::LocalMktDate,
+            "MONTHYEAR" => FieldType::MonthYear,
+            "DATA" | "XMLDATA" => FieldType::Data,
+            _ => FieldType::String
+        }
+    }
+
+    pub fn is_data(self) -> bool {
+        self == FieldType::Data
+    }
+
+    //is_valid tells whether value is well formed for the type, it does not look at the enum of the field
+    pub fn is_valid(self, value: &[u8]) -> bool {
+        if self == FieldType::Data {
+            return true;
+        }
+        let value = match str::from_utf8(value) {
+            Ok(value) => value,
+            Err(_) => return false
+        };
+        match self {
+            FieldType::Int => value.parse::<i64>().is_ok(),
+            FieldType::Length | FieldType::NumInGroup | FieldType::SeqNum | FieldType::TagNum => is_digits(value),
+            FieldType::DayOfMonth => value.parse::<u32>().is_ok_and(|day| (1..=31).contains(&day)),
+            FieldType::Float => is_decimal(value),
+            FieldType::Char => value.len() == 1,
+            FieldType::Boolean => value == "Y" || value == "N",
+            FieldType::UtcTimestamp => FIXUTCTimestamp::empty().read(value.as_bytes()).is_ok(),
+            FieldType::UtcTimeOnly => is_time_only(value),
+            FieldType::UtcDateOnly | FieldType::LocalMktDate => value.len() == 8 && is_digits(value),
+            //YYYYMM, YYYYMMDD or YYYYMMwN
+            FieldType::MonthYear => value.len() >= 6 && is_digits(&value[..6]) &&
+                (value.len() == 6 || (value.len() == 8 && (is_digits(&value[6..]) || value.as_bytes()[6] == b'w'))),
+            _ => true
+        }
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_decimal(value: &str) -> bool {
+    let value = value.strip_prefix('-').unwrap_or(value);
+    let mut parts = value.splitn(2, '.');
+    let integer = parts.next().unwrap_or("");
+    let fraction = parts.next().unwrap_or("");
+    (!integer.is_empty() || !fraction.is_empty()) && integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+}
+
+//is_time_only accepts HH:MM:SS with optional milliseconds
+fn is_time_only(value: &str) -> bool {
+    let (time, millis) = match value.find('.') {
+        Some(dot) => (&value[..dot], Some(&value[dot + 1..])),
+        None => (value, None)
+    };
+    let parts: Vec<&str> = time.split(':').collect();
+    parts.len() == 3 && parts.iter().all(|part| part.len() == 2 && is_digits(part)) &&
+        millis.is_none_or(|millis| millis.len() == 3 && is_digits(millis))
+}
+
+//DictionaryField is the definition of a tag: its name, type and, for enumerated fields, the allowed values with
+//their description
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryField {
+    pub tag: u32,
+    pub name: String,
+    pub field_type: FieldType,
+    pub values: Vec<(String, String)>
+}
+
+impl DictionaryField {
+
+    pub fn has_value(&self, value: &str) -> bool {
+        self.values.iter().any(|(allowed, _)| allowed == value)
+    }
+}
+
+//DictionaryMember is a field or a repeating group of a message, a header, a trailer or a group instance.
+//Components are expanded in place when the dictionary is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DictionaryMember {
+    Field{tag: u32, required: bool},
+    Group(DictionaryGroup)
+}
+
+impl DictionaryMember {
+
+    pub fn tag(&self) -> u32 {
+        match *self {
+            DictionaryMember::Field{tag, ..} => tag,
+            DictionaryMember::Group(ref group) => group.tag
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        match *self {
+            DictionaryMember::Field{required, ..} => required,
+            DictionaryMember::Group(ref group) => group.required
+        }
+    }
+}
+
+//DictionaryGroup is a repeating group, tag being its NumInGroup field. The first member delimits the instances.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryGroup {
+    pub tag: u32,
+    pub required: bool,
+    pub members: Vec<DictionaryMember>
+}
+
+impl DictionaryGroup {
+
+    pub fn delimiter(&self) -> Option<u32> {
+        self.members.first().map(|member| member.tag())
+    }
+
+    //contains tells whether tag belongs to an instance, the fields of nested groups are found through their own
+    //NumInGroup member
+    pub fn contains(&self, tag: u32) -> bool {
+        self.members.iter().any(|member| member.tag() == tag)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryMessage {
+    pub name: String,
+    pub msg_type: String,
+    //admin is true for session level messages, msgcat="admin"
+    pub admin: bool,
+    pub members: Vec<DictionaryMember>
+}
+
+//FixmlLayout is the FIXML element of the header, a message, a component or a group: the abbreviations of the
+//fields written as its attributes and its child elements
+#[derive(Debug, Clone, PartialEq)]
+struct FixmlLayout {
+    abbreviations: Vec<(u32, String)>,
+    components: Vec<FixmlComponent>
+}
+
+//DataDictionary is a loaded QuickFIX XML data dictionary: the fields, header, trailer and messages of one FIX
+//version. FIXT.1.1 splits them in a transport dictionary holding the header, the trailer and the admin messages
+//and application dictionaries holding the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataDictionary {
+    begin_string: String,
+    fields: HashMap<u32, DictionaryField>,
+    names: HashMap<String, u32>,
+    header: Vec<DictionaryMember>,
+    trailer: Vec<DictionaryMember>,
+    messages: Vec<DictionaryMessage>,
+    //FIXML elements by abbreviation and the abbreviation of each MsgType
+    fixml: HashMap<String, FixmlLayout>,
+    fixml_messages: HashMap<String, String>
+}
+
+impl DataDictionary {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DataDictionary, FixError> {
+        let mut xml = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut xml)).map_err(FixError::IoError)?;
+        DataDictionary::parse(&xml)
+    }
+
+    pub fn parse(xml: &str) -> Result<DataDictionary, FixError> {
+        let root = xml::parse(xml).map_err(|err| dictionary_error(&err))?;
+        if root.name != "fix" {
+            return Err(dictionary_error(&format!("unexpected root element {}", root.name)));
+        }
+
+        let mut begin_string = format!("{}.{}.{}", root.attribute("type").unwrap_or("FIX"), required(&root, "major")?, required(&root, "minor")?);
+        match root.attribute("servicepack") {
+            Some(service_pack) if service_pack != "0" => begin_string.push_str(&format!("SP{}", service_pack)),
+            _ => ()
+        }
+
+        let mut fields = HashMap::new();
+        let mut names = HashMap::new();
+        let mut abbreviations = HashMap::new();
+        for field in children(&root, "fields") {
+            let tag = required(field, "number")?.parse::<u32>().map_err(|_| dictionary_error("field number is not a number"))?;
+            let name = required(field, "name")?.to_string();
+            let values = field.children.iter().filter(|value| value.name == "value")
+                .map(|value| (value.attribute("enum").unwrap_or("").to_string(), value.attribute("description").unwrap_or("").to_string()))
+                .collect();
+            if let Some(abbreviation) = field.attribute("abbr") {
+                abbreviations.insert(tag, abbreviation.to_string());
+            }
+            names.insert(name.clone(), tag);
+            fields.insert(tag, DictionaryField{tag: tag, name: name, field_type: FieldType::from_name(required(field, "type")?), values: values});
+        }
+
+        let mut loader = DictionaryLoader{names: &names, abbreviations: &abbreviations, components: HashMap::new()};
+        for component in children(&root, "components") {
+            loader.components.insert(required(component, "name")?, component);
+        }
+
+        let section = |name: &str| -> Result<Vec<DictionaryMember>, FixError> {
+            match root.children.iter().find(|child| child.name == name) {
+                Some(section) => loader.members(section, true, 0),
+                None => Ok(vec![])
+            }
+        };
+        let header = section("header")?;
+        let trailer = section("trailer")?;
+
+        let mut fixml = HashMap::new();
+        if let Some(section) = root.children.iter().find(|child| child.name == "header") {
+            loader.fixml_layout(section, fixml::HEADER, &mut fixml, 0)?;
+        }
+
+        let mut messages = vec![];
+        let mut fixml_messages = HashMap::new();
+        for message in children(&root, "messages") {
+            let name = required(message, "name")?.to_string();
+            let msg_type = required(message, "msgtype")?.to_string();
+            let abbreviation = message.attribute("abbr").map(|abbreviation| abbreviation.to_string())
+                .or_else(|| FixmlTags.message_abbreviation(&msg_type)).unwrap_or_else(|| name.clone());
+            loader.fixml_layout(message, &abbreviation, &mut fixml, 0)?;
+            fixml_messages.insert(msg_type.clone(), abbreviation);
+            messages.push(DictionaryMessage{
+                name: name,
+                msg_type: msg_type,
+                admin: message.attribute("msgcat") == Some("admin"),
+                members: loader.members(message, true, 0)?
+            });
+        }
+
+        Ok(DataDictionary{begin_string: begin_string, fields: fields, names: names, header: header, trailer: trailer, messages: messages,
+                          fixml: fixml, fixml_messages: fixml_messages})
+    }
+
+    //begin_string is the FIX version the dictionary describes, e.g. FIX.4.4, FIXT.1.1 or FIX.5.0SP2
+    pub fn begin_string(&self) -> &str {
+        &self.begin_string
+    }
+
+    pub fn field(&self, tag: u32) -> Option<&DictionaryField> {
+        self.fields.get(&tag)
+    }
+
+    pub fn field_by_name(&self, name: &str) -> Option<&DictionaryField> {
+        self.names.get(name).and_then(|tag| self.fields.get(tag))
+    }
+
+    pub fn header(&self) -> &[DictionaryMember] {
+        &self.header
+    }
+
+    pub fn trailer(&self) -> &[DictionaryMember] {
+        &self.trailer
+    }
+
+    pub fn messages(&self) -> &[DictionaryMessage] {
+        &self.messages
+    }
+
+    pub fn message(&self, msg_type: &str) -> Option<&DictionaryMessage> {
+        self.messages.iter().find(|message| message.msg_type == msg_type)
+    }
+
+    //group finds the layout of the repeating group counted by tag in the header, the trailer or the message of
+    //msg_type, nested groups included
+    pub fn group(&self, msg_type: &str, tag: u32) -> Option<&DictionaryGroup> {
+        let body = self.message(msg_type).map(|message| message.members.as_slice()).unwrap_or(&[]);
+        find_group(&self.header, tag).or_else(|| find_group(body, tag)).or_else(|| find_group(&self.trailer, tag))
+    }
+
+    //validate checks msg against this dictionary alone, which must describe its header, trailer and body
+    pub fn validate(&self, msg: &Message) -> Result<(), MessageRejectError> {
+        validate_message(self, self, msg)
+    }
+}
+
+//A DataDictionary names every field of its version, value names are the descriptions of the enums. Groups are looked
+//up in the header, the messages and the trailer, the first group counted by a tag wins.
+impl FieldNames for DataDictionary {
+    fn field_name(&self, tag: u32) -> Option<String> {
+        self.field(tag).map(|field| field.name.clone())
+    }
+
+    fn field_tag(&self, name: &str) -> Option<u32> {
+        self.field_by_name(name).map(|field| field.tag)
+    }
+
+    fn value_name(&self, tag: u32, value: &str) -> Option<String> {
+        let field = self.field(tag)?;
+        field.values.iter().find(|(enum_value, _)| enum_value == value).map(|(_, description)| description.clone())
+    }
+
+    fn group_fields(&self, count_tag: u32) -> Option<Vec<u32>> {
+        let group = find_group(&self.header, count_tag)
+            .or_else(|| self.messages.iter().filter_map(|message| find_group(&message.members, count_tag)).next())
+            .or_else(|| find_group(&self.trailer, count_tag))?;
+        Some(group.members.iter().map(|member| member.tag()).collect())
+    }
+
+    fn is_data(&self, tag: u32) -> bool {
+        self.field(tag).is_some_and(|field| field.field_type.is_data())
+    }
+}
+
+//A DataDictionary lays FIXML out after its messages, components and groups, abbreviated by their abbr attributes.
+//Elements it does not define, such as the header of an application dictionary, are those of FixmlTags.
+impl FixmlSchema for DataDictionary {
+    fn message_abbreviation(&self, msg_type: &str) -> Option<String> {
+        self.fixml_messages.get(msg_type).cloned()
+    }
+
+    fn message_type(&self, abbreviation: &str) -> Option<String> {
+        self.fixml_messages.iter().find(|&(_, a)| a == abbreviation).map(|(msg_type, _)| msg_type.clone())
+    }
+
+    fn field_abbreviation(&self, element: &str, tag: u32) -> Option<String> {
+        match self.fixml.get(element) {
+            Some(layout) => layout.abbreviations.iter().find(|&&(t, _)| t == tag).map(|(_, abbreviation)| abbreviation.clone()),
+            None => FixmlTags.field_abbreviation(element, tag)
+        }
+    }
+
+    fn field_tag(&self, element: &str, abbreviation: &str) -> Option<u32> {
+        match self.fixml.get(element) {
+            Some(layout) => layout.abbreviations.iter().find(|&(_, a)| a == abbreviation).map(|&(tag, _)| tag),
+            None => FixmlTags.field_tag(element, abbreviation)
+        }
+    }
+
+    fn components(&self, element: &str) -> Vec<FixmlComponent> {
+        match self.fixml.get(element) {
+            Some(layout) => layout.components.clone(),
+            None => FixmlTags.components(element)
+        }
+    }
+}
+
+fn find_group(members: &[DictionaryMember], tag: u32) -> Option<&DictionaryGroup> {
+    for member in members.iter() {
+        if let DictionaryMember::Group(ref group) = *member {
+            if group.tag == tag {
+                return Some(group);
+            }
+            if let Some(nested) = find_group(&group.members, tag) {
+                return Some(nested);
+            }
+        }
+    }
+    None
+}
+
+fn required<'e>(element: &'e Element, name: &str) -> Result<&'e str, FixError> {
+    element.attribute(name).ok_or_else(|| dictionary_error(&format!("{} without {}", element.name, name)))
+}
+
+//children lists the grand children of root found under its section element, e.g. the field elements of fields
+fn children<'e>(root: &'e Element, section: &str) -> Vec<&'e Element> {
+    root.children.iter().filter(|child| child.name == section).flat_map(|child| child.children.iter()).collect()
+}
+
+//MAX_COMPONENT_DEPTH stops a component that includes itself
+const MAX_COMPONENT_DEPTH: usize = 32;
+
+//DictionaryLoader expands components by name, definitions may come after their use
+struct DictionaryLoader<'x> {
+    names: &'x HashMap<String, u32>,
+    //the abbr attributes of the field definitions
+    abbreviations: &'x HashMap<u32, String>,
+    components: HashMap<&'x str, &'x Element>
+}
+
+impl<'x> DictionaryLoader<'x> {
+
+    fn tag(&self, name: &str) -> Result<u32, FixError> {
+        self.names.get(name).cloned().ok_or_else(|| dictionary_error(&format!("unknown field {}", name)))
+    }
+
+    //members reads the fields, groups and components of element, the members of an optional component or group
+    //are never required on their own
+    fn members(&self, element: &Element, parent_required: bool, depth: usize) -> Result<Vec<DictionaryMember>, FixError> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(dictionary_error(&format!("components nested too deep in {}", element.name)));
+        }
+        let mut members = vec![];
+        for child in element.children.iter() {
+            let required = parent_required && child.attribute("required") == Some("Y");
+            match child.name.as_str() {
+                "field" => members.push(DictionaryMember::Field{tag: self.tag(required_name(child)?)?, required: required}),
+                "group" => {
+                    let group = DictionaryGroup{tag: self.tag(required_name(child)?)?, required: required, members: self.members(child, true, depth + 1)?};
+                    if group.members.is_empty() {
+                        return Err(dictionary_error(&format!("group {} without members", required_name(child)?)));
+                    }
+                    members.push(DictionaryMember::Group(group));
+                },
+                "component" => {
+                    let name = required_name(child)?;
+                    let component = self.components.get(name).ok_or_else(|| dictionary_error(&format!("unknown component {}", name)))?;
+                    members.extend(self.members(component, required, depth + 1)?);
+                },
+                _ => ()
+            }
+        }
+        Ok(members)
+    }
+
+    //fixml_layout records the FIXML element abbreviation of element and of the components and groups it holds, the
+    //first layout of an abbreviation wins
+    fn fixml_layout(&self, element: &Element, abbreviation: &str, layouts: &mut HashMap<String, FixmlLayout>, depth: usize) -> Result<FixmlLayout, FixError> {
+        let mut layout = FixmlLayout{abbreviations: vec![], components: vec![]};
+        self.fixml_members(element, abbreviation, &mut layout, layouts, depth)?;
+        layouts.entry(abbreviation.to_string()).or_insert_with(|| layout.clone());
+        Ok(layout)
+    }
+
+    //fixml_members adds the members of element to layout. Groups and the components carrying an abbr attribute are
+    //child elements, other components are written in place. A field is abbreviated by the abbr attribute of its
+    //reference or definition, else by the standard abbreviations of FixmlTags, else by its name.
+    fn fixml_members(&self, element: &Element, abbreviation: &str, layout: &mut FixmlLayout, layouts: &mut HashMap<String, FixmlLayout>,
+                     depth: usize) -> Result<(), FixError> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(dictionary_error(&format!("components nested too deep in {}", element.name)));
+        }
+        for child in element.children.iter() {
+            match child.name.as_str() {
+                "field" => {
+                    let name = required_name(child)?;
+                    let tag = self.tag(name)?;
+                    let field_abbreviation = child.attribute("abbr").map(|abbreviation| abbreviation.to_string())
+                        .or_else(|| self.abbreviations.get(&tag).cloned())
+                        .or_else(|| FixmlTags.field_abbreviation(abbreviation, tag))
+                        .unwrap_or_else(|| name.to_string());
+                    layout.abbreviations.push((tag, field_abbreviation));
+                },
+                "group" => {
+                    let name = required_name(child)?;
+                    let tag = self.tag(name)?;
+                    let group_abbreviation = child.attribute("abbr").map(|abbreviation| abbreviation.to_string())
+                        .or_else(|| FixmlTags.components(abbreviation).into_iter().find(|group| group.count_tag == Some(tag)).map(|group| group.abbreviation))
+                        .unwrap_or_else(|| name.to_string());
+                    let group = self.fixml_layout(child, &group_abbreviation, layouts, depth + 1)?;
+                    let mut fields: Vec<u32> = group.abbreviations.iter().map(|&(tag, _)| tag).collect();
+                    //an instance starts with the first member, which may sit in a child element
+                    if let Some(delimiter) = self.members(child, true, depth + 1)?.first().map(|member| member.tag()) {
+                        if !fields.contains(&delimiter) {
+                            fields.insert(0, delimiter);
+                        }
+                    }
+                    layout.components.push(FixmlComponent::group(&group_abbreviation, tag, fields));
+                },
+                "component" => {
+                    let name = required_name(child)?;
+                    let component = self.components.get(name).ok_or_else(|| dictionary_error(&format!("unknown component {}", name)))?;
+                    match child.attribute("abbr").or_else(|| component.attribute("abbr")) {
+                        Some(component_abbreviation) => {
+                            let nested = self.fixml_layout(component, component_abbreviation, layouts, depth + 1)?;
+                            let fields = nested.abbreviations.iter().map(|&(tag, _)| tag).collect();
+                            layout.components.push(FixmlComponent::new(component_abbreviation, fields));
+                        },
+                        None => self.fixml_members(component, abbreviation, layout, layouts, depth + 1)?
+                    }
+                },
+                _ => ()
+            }
+        }
+        Ok(())
+    }
+}
+
+fn required_name(element: &Element) -> Result<&str, FixError> {
+    required(element, "name")
+}
+
+//body_dictionary is the dictionary describing the body of msg_type: transport for admin messages, application for
+//the others
+pub fn body_dictionary<'d>(transport: &'d DataDictionary, application: &'d DataDictionary, msg_type: &str) -> &'d DataDictionary {
+    if is_admin_message_type(msg_type) { transport } else { application }
+}
+
+//validate_message checks the header and trailer of msg against transport and its body against body_dictionary.
+//Outside FIXT.1.1 both are the same dictionary.
+fn validate_message(transport: &DataDictionary, application: &DataDictionary, msg: &Message) -> Result<(), MessageRejectError> {
+    let msg_type = match msg.header.get_string(Tags::MsgType.to_num()) {
+        Ok(msg_type) => msg_type,
+        Err(_) => return Err(MessageRejectError::required_tag_missing(Tags::MsgType.to_num()))
+    };
+    let dictionary = body_dictionary(transport, application, &msg_type);
+    let message = dictionary.message(&msg_type).ok_or_else(MessageRejectError::invalid_message_type)?;
+
+    validate_section(transport, &transport.header, &msg.header)?;
+    validate_section(dictionary, &message.members, &msg.body)?;
+    validate_section(transport, &transport.trailer, &msg.trailer)
+}
+
+fn validate_section(dictionary: &DataDictionary, members: &[DictionaryMember], section: &FieldMap) -> Result<(), MessageRejectError> {
+    for field in section.tags() {
+        let tag = field.field_tag();
+        let definition = dictionary.field(tag).ok_or_else(|| MessageRejectError::invalid_tag_number(tag))?;
+        let member = members.iter().find(|member| member.tag() == tag)
+            .ok_or_else(|| MessageRejectError::tag_not_defined_for_this_message_type(tag))?;
+        validate_value(definition, field.tag_values()[0].value())?;
+
+        if let DictionaryMember::Group(ref group) = *member {
+            let count = section.get_int(tag)?;
+            let instances = section.get_group(tag)?;
+            if count as usize != instances.len() {
+                return Err(MessageRejectError::incorrect_num_in_group_count_for_repeating_group(tag));
+            }
+            for instance in instances.iter() {
+                validate_section(dictionary, &group.members, instance)?;
+            }
+        }
+    }
+
+    for member in members.iter().filter(|member| member.is_required()) {
+        if !section.has(member.tag()) {
+            return Err(MessageRejectError::required_tag_missing(member.tag()));
+        }
+    }
+    Ok(())
+}
+
+fn validate_value(definition: &DictionaryField, value: &[u8]) -> Result<(), MessageRejectError> {
+    if value.is_empty() {
+        return Err(MessageRejectError::tag_specified_without_a_value(definition.tag));
+    }
+    if !definition.field_type.is_valid(value) {
+        return Err(MessageRejectError::incorrect_data_format_for_value(definition.tag));
+    }
+    if definition.values.is_empty() || definition.field_type.is_data() {
+        return Ok(());
+    }
+
+    let value = str::from_utf8(value).map_err(|_| MessageRejectError::incorrect_data_format_for_value(definition.tag))?;
+    let allowed = match definition.field_type {
+        FieldType::MultipleValue => value.split(' ').all(|value| definition.has_value(value)),
+        _ => definition.has_value(value)
+    };
+    if !allowed {
+        return Err(MessageRejectError::value_is_incorrect(definition.tag));
+    }
+    Ok(())
+}
+
+//DataDictionaryProvider holds the dictionaries of a session and picks the ones a message is read and validated
+//with: the transport dictionary of its BeginString and the application dictionary of its ApplVerID(1128) and
+//CstmApplVerID(1129). Outside FIXT.1.1 the application version is the BeginString itself.
+#[derive(Debug, Clone, Default)]
+pub struct DataDictionaryProvider {
+    transport: HashMap<String, Arc<DataDictionary>>,
+    //keyed by the application version and the CstmApplVerID, empty for the standard dictionary of the version
+    application: HashMap<(String, String), Arc<DataDictionary>>
+}
+
+impl DataDictionaryProvider {
+
+    pub fn new() -> DataDictionaryProvider {
+        DataDictionaryProvider::default()
+    }
+
+    //from_settings loads DataDictionary for FIX.4.x sessions, TransportDataDictionary and AppDataDictionary for
+    //FIXT.1.1 ones. AppDataDictionary.<suffix> settings add more application dictionaries, a suffix not naming a
+    //FIX version is the CstmApplVerID the dictionary is used for.
+    pub fn from_settings(settings: &SessionSettings, begin_string: &str) -> Result<DataDictionaryProvider, FixError> {
+        let mut provider = DataDictionaryProvider::new();
+        if begin_string != BEGIN_STRING_FIXT11 {
+            if settings.has(DATA_DICTIONARY) {
+                provider.add_dictionary(Arc::new(DataDictionary::load(settings.setting(DATA_DICTIONARY)?)?));
+            }
+            return Ok(provider);
+        }
+
+        if settings.has(TRANSPORT_DATA_DICTIONARY) {
+            provider.add_transport_dictionary(Arc::new(DataDictionary::load(settings.setting(TRANSPORT_DATA_DICTIONARY)?)?));
+        }
+        for (setting, path) in settings.with_prefix(APP_DATA_DICTIONARY) {
+            let dictionary = Arc::new(DataDictionary::load(path)?);
+            let suffix = setting[APP_DATA_DICTIONARY.len()..].trim_start_matches('.');
+            if suffix.is_empty() || suffix.starts_with("FIX") {
+                provider.add_application_dictionary(dictionary);
+            } else {
+                provider.add_custom_application_dictionary(suffix, dictionary);
+            }
+        }
+        Ok(provider)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transport.is_empty() && self.application.is_empty()
+    }
+
+    //add_dictionary registers a FIX.4.x dictionary, which is both the transport and the application dictionary of
+    //its version
+    pub fn add_dictionary(&mut self, dictionary: Arc<DataDictionary>) {
+        self.add_transport_dictionary(dictionary.clone());
+        self.add_application_dictionary(dictionary);
+    }
+
+    pub fn add_transport_dictionary(&mut self, dictionary: Arc<DataDictionary>) {
+        self.transport.insert(dictionary.begin_string().to_string(), dictionary);
+    }
+
+    pub fn add_application_dictionary(&mut self, dictionary: Arc<DataDictionary>) {
+        self.application.insert((dictionary.begin_string().to_string(), String::new()), dictionary);
+    }
+
+    //add_custom_application_dictionary registers the dictionary of messages carrying CstmApplVerID
+    pub fn add_custom_application_dictionary(&mut self, cstm_appl_ver_id: &str, dictionary: Arc<DataDictionary>) {
+        self.application.insert((dictionary.begin_string().to_string(), cstm_appl_ver_id.to_string()), dictionary);
+    }
+
+    pub fn transport_dictionary(&self, begin_string: &str) -> Option<&DataDictionary> {
+        self.transport.get(begin_string).map(|dictionary| dictionary.as_ref())
+    }
+
+    //application_dictionary is the dictionary of application_version, a begin string such as FIX.5.0SP2 or an
+    //ApplVerID value, customized by cstm_appl_ver_id when one is registered for it
+    pub fn application_dictionary(&self, application_version: &str, cstm_appl_ver_id: Option<&str>) -> Option<&DataDictionary> {
+        let version = appl_ver_id_begin_string(application_version).to_string();
+        if let Some(cstm_appl_ver_id) = cstm_appl_ver_id {
+            if let Some(dictionary) = self.application.get(&(version.clone(), cstm_appl_ver_id.to_string())) {
+                return Some(dictionary);
+            }
+        }
+        self.application.get(&(version, String::new())).map(|dictionary| dictionary.as_ref())
+    }
+
+    //dictionaries picks the transport and application dictionaries of a message, None when its BeginString has no
+    //dictionary and an InvalidApplVerID reject when its application version has none
+    fn dictionaries(&self, begin_string: &str, msg_type: &str, application_version: &str, cstm_appl_ver_id: Option<&str>)
+        -> Result<Option<(&DataDictionary, &DataDictionary)>, MessageRejectError> {
+        let transport = match self.transport_dictionary(begin_string) {
+            Some(transport) => transport,
+            None => return Ok(None)
+        };
+        match self.application_dictionary(application_version, cstm_appl_ver_id) {
+            Some(application) => Ok(Some((transport, application))),
+            None if is_admin_message_type(msg_type) => Ok(Some((transport, transport))),
+            None => Err(MessageRejectError::invalid_appl_ver_id())
+        }
+    }
+
+    //validate checks msg against the dictionaries of its BeginString, application_version and CstmApplVerID, a
+    //message whose BeginString has no dictionary is not checked
+    pub fn validate(&self, msg: &Message, application_version: &str) -> Result<(), MessageRejectError> {
+        let begin_string = msg.header.get_string(Tags::BeginString.to_num()).unwrap_or_default();
+        let msg_type = msg.header.get_string(Tags::MsgType.to_num()).unwrap_or_default();
+        let cstm_appl_ver_id = msg.cstm_appl_ver_id();
+        match self.dictionaries(&begin_string, &msg_type, application_version, cstm_appl_ver_id.as_deref())? {
+            Some((transport, application)) => validate_message(transport, application, msg),
+            None => Ok(())
+        }
+    }
+
+    //parse reads raw with the repeating groups of its dictionaries, default_appl_ver_id standing for a missing
+    //ApplVerID on FIXT.1.1. Without a dictionary for its BeginString raw is parsed as by Message::parse.
+    pub fn parse(&self, raw: &[u8], default_appl_ver_id: &str) -> Result<Message, FixError> {
+        let view = MessageView::parse(raw)?;
+        let header = |tag: Tags| view.get_str(tag.to_num()).ok().map(|value| value.to_string());
+        let begin_string = header(Tags::BeginString).unwrap_or_default();
+        let msg_type = header(Tags::MsgType).unwrap_or_default();
+        let mut application_version = begin_string.clone();
+        if begin_string == BEGIN_STRING_FIXT11 {
+            application_version = header(Tags::ApplVerID).unwrap_or_else(|| default_appl_ver_id.to_string());
+        }
+
+        let cstm_appl_ver_id = header(Tags::CstmApplVerID);
+        match self.dictionaries(&begin_string, &msg_type, &application_version, cstm_appl_ver_id.as_deref()) {
+            Ok(Some((transport, application))) => Message::parse_with_dictionary(raw, transport, application),
+            Ok(None) => Message::parse(raw),
+            Err(err) => Err(FixError::RejectError(err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Reject;
+    use error::MessageRejectError as MessageRejectErrorTrait;
+
+    static FIX44: &'static str = r#"<fix type="FIX" major="4" minor="4" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+    <field name="SenderCompID" required="Y"/>
+    <field name="TargetCompID" required="Y"/>
+    <field name="MsgSeqNum" required="Y"/>
+    <field name="SendingTime" required="Y"/>
+    <field name="ApplVerID" required="N"/>
+    <field name="CstmApplVerID" required="N"/>
+  </header>
+  <trailer>
+    <field name="SignatureLength" required="N"/>
+    <field name="Signature" required="N"/>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="Heartbeat" msgtype="0" msgcat="admin">
+      <field name="TestReqID" required="N"/>
+    </message>
+    <message name="NewOrderSingle" msgtype="D" msgcat="app">
+      <field name="ClOrdID" required="Y"/>
+      <component name="Parties" required="N"/>
+      <component name="Instrument" required="Y"/>
+      <field name="Side" required="Y"/>
+      <field name="OrderQty" required="N"/>
+      <field name="Price" required="N"/>
+      <field name="ExecInst" required="N"/>
+    </message>
+  </messages>
+  <components>
+    <component name="Instrument">
+      <field name="Symbol" required="Y"/>
+    </component>
+    <component name="Parties">
+      <group name="NoPartyIDs" required="N">
+        <field name="PartyID" required="Y"/>
+        <field name="PartyRole" required="N"/>
+        <component name="PtysSubGrp" required="N"/>
+      </group>
+    </component>
+    <component name="PtysSubGrp">
+      <group name="NoPartySubIDs" required="N">
+        <field name="PartySubID" required="N"/>
+      </group>
+    </component>
+  </components>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="11" name="ClOrdID" type="STRING"/>
+    <field number="18" name="ExecInst" type="MULTIPLECHARVALUE">
+      <value enum="1" description="NOT_HELD"/>
+      <value enum="G" description="ALL_OR_NONE"/>
+    </field>
+    <field number="34" name="MsgSeqNum" type="SEQNUM"/>
+    <field number="35" name="MsgType" type="STRING">
+      <value enum="0" description="HEARTBEAT"/>
+      <value enum="D" description="ORDER_SINGLE"/>
+    </field>
+    <field number="38" name="OrderQty" type="QTY"/>
+    <field number="44" name="Price" type="PRICE"/>
+    <field number="49" name="SenderCompID" type="STRING"/>
+    <field number="52" name="SendingTime" type="UTCTIMESTAMP"/>
+    <field number="54" name="Side" type="CHAR">
+      <value enum="1" description="BUY"/>
+      <value enum="2" description="SELL"/>
+    </field>
+    <field number="55" name="Symbol" type="STRING"/>
+    <field number="56" name="TargetCompID" type="STRING"/>
+    <field number="89" name="Signature" type="DATA"/>
+    <field number="93" name="SignatureLength" type="LENGTH"/>
+    <field number="112" name="TestReqID" type="STRING"/>
+    <field number="448" name="PartyID" type="STRING"/>
+    <field number="452" name="PartyRole" type="INT"/>
+    <field number="453" name="NoPartyIDs" type="NUMINGROUP"/>
+    <field number="523" name="PartySubID" type="STRING"/>
+    <field number="802" name="NoPartySubIDs" type="NUMINGROUP"/>
+    <field number="1128" name="ApplVerID" type="STRING"/>
+    <field number="1129" name="CstmApplVerID" type="STRING"/>
+  </fields>
+</fix>"#;
+
+    //frame wraps body in BeginString, BodyLength and CheckSum
+    fn frame(begin_string: &str, body: &str) -> Vec<u8> {
+        let body = body.replace("|", "\x01");
+        let mut raw = format!("8={}\x019={}\x01{}", begin_string, body.len(), body).into_bytes();
+        let sum = raw.iter().fold(0u32, |sum, b| sum + *b as u32) % 256;
+        raw.extend_from_slice(format!("10={:03}\x01", sum).as_bytes());
+        raw
+    }
+
+    const HEADER: &'static str = "49=TW|56=ISLD|34=2|52=20161012-10:05:01|";
+
+    fn order(body: &str) -> Vec<u8> {
+        frame("FIX.4.4", &format!("35=D|{}{}", HEADER, body))
+    }
+
+    //reject_reason is the reason raw is refused for, when it is parsed or when it is validated
+    fn reject_reason(dictionary: &DataDictionary, raw: &[u8]) -> Reject {
+        match Message::parse_with_dictionary(raw, dictionary, dictionary) {
+            Ok(msg) => dictionary.validate(&msg).unwrap_err().reject_reason(),
+            Err(FixError::RejectError(err)) => err.reject_reason(),
+            Err(err) => panic!("unexpected {:?}", err)
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        assert_eq!("FIX.4.4", dictionary.begin_string());
+        assert_eq!(FieldType::Float, dictionary.field(44).unwrap().field_type);
+        assert_eq!(54, dictionary.field_by_name("Side").unwrap().tag);
+        assert!(dictionary.message("0").unwrap().admin);
+
+        //the optional Parties component makes PartyID optional, the required Instrument keeps Symbol required
+        let order = dictionary.message("D").unwrap();
+        assert!(order.members.iter().any(|member| member.tag() == 55 && member.is_required()));
+        let parties = dictionary.group("D", 453).unwrap();
+        assert!(!parties.required);
+        assert_eq!(Some(448), parties.delimiter());
+        assert_eq!(Some(523), dictionary.group("D", 802).unwrap().delimiter());
+
+        assert!(DataDictionary::parse("<fix major=\"4\" minor=\"4\"><messages><message name=\"X\" msgtype=\"X\">\
+            <component name=\"Missing\"/></message></messages></fix>").is_err());
+    }
+
+    #[test]
+    fn field_names_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        assert_eq!(Some("Side".to_string()), dictionary.field_name(54));
+        assert_eq!(Some(54), FieldNames::field_tag(&dictionary, "Side"));
+        assert_eq!(Some("BUY".to_string()), dictionary.value_name(54, "1"));
+        assert_eq!(None, dictionary.value_name(54, "9"));
+        assert_eq!(Some(vec![448, 452, 802]), dictionary.group_fields(453));
+        assert_eq!(Some(vec![523]), dictionary.group_fields(802));
+        assert_eq!(None, dictionary.group_fields(55));
+        assert!(dictionary.is_data(89) && !dictionary.is_data(93));
+    }
+
+    #[test]
+    fn parse_with_dictionary_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        //a single instance group, which Message::parse cannot tell from plain fields
+        let raw = order("11=ID1|453=1|448=TW|452=1|802=2|523=A|523=B|55=MSFT|54=1|");
+        let msg = Message::parse_with_dictionary(&raw, &dictionary, &dictionary).unwrap();
+        let parties = msg.body.get_group(453).unwrap();
+        assert_eq!(1, parties.len());
+        assert_eq!("TW", parties[0].get_string(448).unwrap());
+        assert_eq!(2, parties[0].get_group(802).unwrap().len());
+        assert_eq!("MSFT", msg.body.get_string(55).unwrap());
+        assert!(dictionary.validate(&msg).is_ok());
+
+        //received order keeps the single instance group and writes it back as received
+        let msg = Message::parse_with_dictionary_and_order(&raw, &dictionary, &dictionary, FieldOrder::Received).unwrap();
+        assert_eq!(1, msg.body.get_group(453).unwrap().len());
+        assert_eq!(raw, msg.build());
+
+        let raw = order("11=ID1|453=2|448=TW|452=1|55=MSFT|54=1|");
+        match Message::parse_with_dictionary(&raw, &dictionary, &dictionary) {
+            Err(FixError::RejectError(err)) => assert_eq!(Reject::IncorrectNumInGroupCountForRepeatingGroup, err.reject_reason()),
+            _ => panic!("expected a reject")
+        }
+    }
+
+    #[test]
+    fn validate_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        assert_eq!(Reject::RequiredTagMissing, reject_reason(&dictionary, &order("11=ID1|54=1|")));
+        assert_eq!(Reject::ValueIsIncorrect, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=7|")));
+        assert_eq!(Reject::ValueIsIncorrect, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|18=1 X|")));
+        assert_eq!(Reject::IncorrectDataFormatForValue, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|44=1.2.3|")));
+        assert_eq!(Reject::IncorrectDataFormatForValue, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|453=1|448=TW|452=x|")));
+        assert_eq!(Reject::TagNotDefinedForThisMessageType, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|112=T|")));
+        assert_eq!(Reject::InvalidTagNumber, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|9999=x|")));
+        assert_eq!(Reject::IncorrectNumInGroupCountForRepeatingGroup, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|453=1|452=1|")));
+        assert_eq!(Reject::IncorrectNumInGroupCountForRepeatingGroup, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|453=1|448=A|448=B|")));
+        assert_eq!(Reject::IncorrectDataFormatForValue, reject_reason(&dictionary, &order("11=ID1|55=MSFT|54=1|453=x|448=A|")));
+        assert_eq!(Reject::InvalidMsgType, reject_reason(&dictionary, &frame("FIX.4.4", &format!("35=Z|{}", HEADER))));
+
+        let msg = Message::parse(&order("11=ID1|55=MSFT|54=1|18=1 G|38=100|44=-52.10|")).unwrap();
+        assert!(dictionary.validate(&msg).is_ok());
+    }
+
+    #[test]
+    fn field_type_test() {
+        assert!(FieldType::from_name("PRICE").is_valid(b"-0.5"));
+        assert!(!FieldType::Float.is_valid(b"1e5"));
+        assert!(!FieldType::Float.is_valid(b"."));
+        assert!(FieldType::UtcTimestamp.is_valid(b"20161012-10:05:01.005"));
+        assert!(!FieldType::UtcTimestamp.is_valid(b"2016-10-12"));
+        assert!(FieldType::UtcTimeOnly.is_valid(b"10:05:01.005"));
+        assert!(FieldType::MonthYear.is_valid(b"201610w2"));
+        assert!(!FieldType::DayOfMonth.is_valid(b"32"));
+        assert!(!FieldType::SeqNum.is_valid(b"-1"));
+        assert!(FieldType::Data.is_valid(b"\x01\xff"));
+        assert!(!FieldType::String.is_valid(b"\xff"));
+    }
+
+    #[test]
+    fn provider_test() {
+        let transport = DataDictionary::parse(&FIX44.replace("type=\"FIX\" major=\"4\" minor=\"4\"", "type=\"FIXT\" major=\"1\" minor=\"1\"")).unwrap();
+        let fix50sp2 = DataDictionary::parse(&FIX44.replace("major=\"4\" minor=\"4\" servicepack=\"0\"", "major=\"5\" minor=\"0\" servicepack=\"2\"")).unwrap();
+        //the custom dictionary of VENUE1 makes Price required
+        let venue = DataDictionary::parse(&FIX44.replace("major=\"4\" minor=\"4\" servicepack=\"0\"", "major=\"5\" minor=\"0\" servicepack=\"2\"")
+            .replace("<field name=\"Price\" required=\"N\"/>", "<field name=\"Price\" required=\"Y\"/>")).unwrap();
+        assert_eq!("FIXT.1.1", transport.begin_string());
+        assert_eq!("FIX.5.0SP2", fix50sp2.begin_string());
+
+        let mut provider = DataDictionaryProvider::new();
+        provider.add_transport_dictionary(Arc::new(transport));
+        provider.add_application_dictionary(Arc::new(fix50sp2));
+        provider.add_custom_application_dictionary("VENUE1", Arc::new(venue));
+        assert!(provider.application_dictionary("9", None).is_some());
+        assert!(provider.application_dictionary("FIX.5.0SP1", None).is_none());
+
+        let raw = frame("FIXT.1.1", &format!("35=D|{}11=ID1|453=1|448=TW|55=MSFT|54=1|", HEADER));
+        let msg = provider.parse(&raw, "9").unwrap();
+        assert_eq!(1, msg.body.get_group(453).unwrap().len());
+        assert!(provider.validate(&msg, &msg.application_version("9")).is_ok());
+
+        //ApplVerID selects the dictionary, there is none for FIX.5.0SP1
+        let raw = frame("FIXT.1.1", &format!("35=D|{}1128=8|11=ID1|55=MSFT|54=1|", HEADER));
+        assert!(provider.parse(&raw, "9").is_err());
+        let msg = Message::parse(&raw).unwrap();
+        assert_eq!(Reject::InvalidApplVerID, provider.validate(&msg, &msg.application_version("9")).unwrap_err().reject_reason());
+
+        //CstmApplVerID selects the custom dictionary
+        let mut msg = Message::parse(&frame("FIXT.1.1", &format!("35=D|{}11=ID1|55=MSFT|54=1|", HEADER))).unwrap();
+        assert!(provider.validate(&msg, "9").is_ok());
+        msg.header.set_string(Tags::CstmApplVerID.to_num(), "VENUE1");
+        assert_eq!(Reject::RequiredTagMissing, provider.validate(&msg, "9").unwrap_err().reject_reason());
+
+        //admin messages only need the transport dictionary
+        let heartbeat = Message::parse(&frame("FIXT.1.1", &format!("35=0|{}1128=8|", HEADER))).unwrap();
+        assert!(provider.validate(&heartbeat, "FIX.5.0SP1").is_ok());
+        assert!(DataDictionaryProvider::new().validate(&heartbeat, "9").is_ok());
+    }
+}
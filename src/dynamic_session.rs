@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use tag::*;
+use message::*;
+use msg_type::*;
+use error::FixError;
+use session::*;
+use session_id::*;
+use session_handle::*;
+use settings::*;
+use store::*;
+use log::*;
+
+//WILDCARD in a CompID, SubID or LocationID setting of a template matches any value
+pub const WILDCARD: &'static str = "*";
+
+//SessionTemplate describes the acceptor sessions created on demand, its SessionID settings may hold WILDCARDs
+#[derive(Clone, Debug)]
+pub struct SessionTemplate {
+    session_id: SessionID,
+    settings: SessionSettings
+}
+
+impl SessionTemplate {
+
+    pub fn new(settings: SessionSettings) -> Result<SessionTemplate, FixError> {
+        let session_id = settings.session_id()?;
+        if session_id.begin_string == WILDCARD {
+            return Err(FixError::ConfigError(format!("{} cannot be a wildcard", BEGIN_STRING)));
+        }
+        Ok(SessionTemplate{session_id: session_id, settings: settings})
+    }
+
+    pub fn session_id(&self) -> &SessionID {
+        &self.session_id
+    }
+
+    //matches tells whether session_id is one of the sessions of the template, an empty SubID or LocationID of the
+    //template only matches an empty one
+    pub fn matches(&self, session_id: &SessionID) -> bool {
+        let pattern = &self.session_id;
+        let fields = [
+            (&pattern.sender_comp_id, &session_id.sender_comp_id),
+            (&pattern.sender_sub_id, &session_id.sender_sub_id),
+            (&pattern.sender_location_id, &session_id.sender_location_id),
+            (&pattern.target_comp_id, &session_id.target_comp_id),
+            (&pattern.target_sub_id, &session_id.target_sub_id),
+            (&pattern.target_location_id, &session_id.target_location_id),
+        ];
+        pattern.begin_string == session_id.begin_string && pattern.qualifier == session_id.qualifier
+            && fields.iter().all(|&(pattern, value)| pattern == WILDCARD || pattern == value)
+    }
+
+    //matches_unqualified matches session_id whatever its qualifier, the one of a Logon is always empty
+    fn matches_unqualified(&self, session_id: &SessionID) -> bool {
+        self.matches(&SessionID{qualifier: self.session_id.qualifier.clone(), ..session_id.clone()})
+    }
+
+    //settings gives the settings of the concrete session session_id, a WILDCARD SubID or LocationID matching an
+    //empty one is left out
+    pub fn settings(&self, session_id: &SessionID) -> SessionSettings {
+        let mut settings = self.settings.clone();
+        let ids = [
+            (SENDER_COMP_ID, &session_id.sender_comp_id),
+            (SENDER_SUB_ID, &session_id.sender_sub_id),
+            (SENDER_LOCATION_ID, &session_id.sender_location_id),
+            (TARGET_COMP_ID, &session_id.target_comp_id),
+            (TARGET_SUB_ID, &session_id.target_sub_id),
+            (TARGET_LOCATION_ID, &session_id.target_location_id),
+        ];
+        for &(setting, value) in ids.iter() {
+            if !value.is_empty() {
+                settings.set(setting, value);
+            } else if settings.setting(setting).ok() == Some(WILDCARD) {
+                settings.remove(setting);
+            }
+        }
+        settings
+    }
+}
+
+type AllowList = Box<dyn Fn(&SessionID, Option<&str>) -> bool + Send + Sync>;
+
+//DynamicSessions holds the sessions of an acceptor, the configured ones and those created from templates when
+//their first Logon arrives. run_dynamic_acceptor asks it for the session of every new connection.
+pub struct DynamicSessions {
+    templates: Vec<SessionTemplate>,
+    store_factory: Box<dyn MessageStoreFactory + Send>,
+    log_factory: Box<dyn LogFactory + Send>,
+    allow_list: Option<AllowList>,
+    sessions: HashMap<SessionID, SessionHandle>
+}
+
+impl DynamicSessions {
+
+    pub fn new(store_factory: Box<dyn MessageStoreFactory + Send>, log_factory: Box<dyn LogFactory + Send>) -> DynamicSessions {
+        DynamicSessions{
+            templates: vec![],
+            store_factory: store_factory,
+            log_factory: log_factory,
+            allow_list: None,
+            sessions: HashMap::new()
+        }
+    }
+
+    //add_session registers a configured session, it is found before any template
+    pub fn add_session(&mut self, session: Session) {
+        self.sessions.insert(session.session_id().clone(), SessionHandle::new(session));
+    }
+
+    //add_template adds a template, templates are tried in the order they were added
+    pub fn add_template(&mut self, template: SessionTemplate) {
+        self.templates.push(template);
+    }
+
+    //add_settings adds a template when settings has AcceptorTemplate=Y and a configured session otherwise, settings
+    //is typically a [SESSION] given by SessionSettings::load
+    pub fn add_settings(&mut self, settings: &SessionSettings) -> Result<(), FixError> {
+        if settings.bool_setting_or(ACCEPTOR_TEMPLATE, false)? {
+            self.add_template(SessionTemplate::new(settings.clone())?);
+            return Ok(());
+        }
+        let session = Session::new_with_log(settings.session_id()?, settings, self.store_factory.as_ref(), self.log_factory.as_ref())?;
+        self.add_session(session);
+        Ok(())
+    }
+
+    //set_allow_list makes templates only create the sessions allow accepts, it is given the SessionID and the
+    //remote address of the connection
+    pub fn set_allow_list<F>(&mut self, allow: F) where F: Fn(&SessionID, Option<&str>) -> bool + Send + Sync + 'static {
+        self.allow_list = Some(Box::new(allow));
+    }
+
+    pub fn session(&self, session_id: &SessionID) -> Option<SessionHandle> {
+        self.sessions.get(session_id).cloned()
+    }
+
+    pub fn session_ids(&self) -> Vec<SessionID> {
+        self.sessions.keys().cloned().collect()
+    }
+
+    //session_for_logon finds the session a Logon received on a new connection is for, creating it from the first
+    //matching template when needed. The remote address is handed to the session.
+    pub fn session_for_logon(&mut self, logon: &Message, remote_address: Option<&str>) -> Result<SessionHandle, FixError> {
+        if logon.header.get_string(Tags::MsgType.to_num()).unwrap_or_default() != MSG_TYPE_LOGON {
+            return Err(FixError::SessionError("First message is not a Logon".to_string()));
+        }
+        let header = |tag: Tags| logon.header.get_string(tag.to_num()).unwrap_or_default();
+        let mut session_id = SessionID{
+            begin_string: header(Tags::BeginString),
+            sender_comp_id: header(Tags::TargetCompID),
+            sender_sub_id: header(Tags::TargetSubID),
+            sender_location_id: header(Tags::TargetLocationID),
+            target_comp_id: header(Tags::SenderCompID),
+            target_sub_id: header(Tags::SenderSubID),
+            target_location_id: header(Tags::SenderLocationID),
+            qualifier: String::new()
+        };
+
+        let handle = match self.find_session(&session_id) {
+            Some(handle) => handle,
+            None => {
+                let template = match self.templates.iter().find(|template| template.matches_unqualified(&session_id)) {
+                    Some(template) => template.clone(),
+                    None => return Err(FixError::SessionError(format!("Unknown session {}", session_id)))
+                };
+                session_id.qualifier = template.session_id.qualifier.clone();
+                if let Some(ref allow) = self.allow_list {
+                    if !allow(&session_id, remote_address) {
+                        return Err(FixError::SessionError(format!("Session {} not allowed", session_id)));
+                    }
+                }
+                let settings = template.settings(&session_id);
+                let session = Session::new_with_log(session_id.clone(), &settings, self.store_factory.as_ref(), self.log_factory.as_ref())?;
+                let handle = SessionHandle::new(session);
+                self.sessions.insert(session_id, handle.clone());
+                handle
+            }
+        };
+        handle.lock().set_remote_address(remote_address.map(|address| address.to_string()));
+        Ok(handle)
+    }
+
+    //find_session looks the session up whatever its qualifier, the Logon does not carry one
+    fn find_session(&self, session_id: &SessionID) -> Option<SessionHandle> {
+        self.sessions.iter()
+            .find(|&(id, _)| SessionID{qualifier: String::new(), ..id.clone()} == *session_id)
+            .map(|(_, handle)| handle.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use begin_string::*;
+    use time::*;
+
+    fn template_settings(sender_comp_id: &str, target_comp_id: &str) -> SessionSettings {
+        let mut settings = SessionSettings::new();
+        settings.set(ACCEPTOR_TEMPLATE, "Y");
+        settings.set(BEGIN_STRING, BEGIN_STRING_FIX44);
+        settings.set(SENDER_COMP_ID, sender_comp_id);
+        settings.set(TARGET_COMP_ID, target_comp_id);
+        settings.set(HEART_BT_INT, "20");
+        settings
+    }
+
+    fn logon(sender_comp_id: &str, target_comp_id: &str, now: Tm) -> Message {
+        let mut logon = Message::new();
+        logon.header.set_string(Tags::BeginString.to_num(), BEGIN_STRING_FIX44);
+        logon.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGON);
+        logon.header.set_string(Tags::SenderCompID.to_num(), sender_comp_id);
+        logon.header.set_string(Tags::TargetCompID.to_num(), target_comp_id);
+        logon.header.set_int(Tags::MsgSeqNum.to_num(), 1);
+        logon.header.set_time(Tags::SendingTime.to_num(), now);
+        logon.body.set_int(Tags::HeartBtInt.to_num(), 20);
+        logon
+    }
+
+    fn new_sessions() -> DynamicSessions {
+        DynamicSessions::new(Box::new(MemoryStoreFactory::new()), Box::new(NullLogFactory::new()))
+    }
+
+    #[test]
+    fn template_matches_test() {
+        let template = SessionTemplate::new(template_settings("ISLD", WILDCARD)).unwrap();
+        assert!(template.matches(&SessionID::new(BEGIN_STRING_FIX44, "ISLD", "TW")));
+        assert!(!template.matches(&SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW")));
+        assert!(!template.matches(&SessionID::new(BEGIN_STRING_FIX44, "OTHER", "TW")));
+
+        let mut session_id = SessionID::new(BEGIN_STRING_FIX44, "ISLD", "TW");
+        session_id.target_sub_id = "DESK".to_string();
+        assert!(!template.matches(&session_id));
+
+        let settings = template.settings(&SessionID::new(BEGIN_STRING_FIX44, "ISLD", "TW"));
+        assert_eq!("TW", settings.setting(TARGET_COMP_ID).unwrap());
+        assert_eq!("20", settings.setting(HEART_BT_INT).unwrap());
+
+        let mut settings = template_settings("ISLD", WILDCARD);
+        settings.set(TARGET_SUB_ID, WILDCARD);
+        settings.set(TARGET_LOCATION_ID, WILDCARD);
+        let template = SessionTemplate::new(settings).unwrap();
+        let mut session_id = SessionID::new(BEGIN_STRING_FIX44, "ISLD", "TW");
+        session_id.target_sub_id = "DESK".to_string();
+        let settings = template.settings(&session_id);
+        assert_eq!("DESK", settings.setting(TARGET_SUB_ID).unwrap());
+        assert!(!settings.has(TARGET_LOCATION_ID));
+        assert_eq!(session_id, settings.session_id().unwrap());
+
+        let mut settings = template_settings(WILDCARD, WILDCARD);
+        settings.set(BEGIN_STRING, WILDCARD);
+        assert!(SessionTemplate::new(settings).is_err());
+    }
+
+    #[test]
+    fn session_for_logon_test() {
+        let now = now_utc();
+        let mut sessions = new_sessions();
+        sessions.add_settings(&template_settings("ISLD", WILDCARD)).unwrap();
+        sessions.set_allow_list(|session_id: &SessionID, _: Option<&str>| session_id.target_comp_id != "BANNED");
+
+        let handle = sessions.session_for_logon(&logon("TW", "ISLD", now), Some("10.0.0.1")).unwrap();
+        assert_eq!("FIX.4.4:ISLD->TW", handle.session_id().to_string());
+        handle.lock().incoming(&logon("TW", "ISLD", now), now).unwrap();
+        assert!(handle.is_logged_on());
+        let reply = handle.lock().take_outgoing().remove(0);
+        assert_eq!(20, reply.body.get_int(Tags::HeartBtInt.to_num()).unwrap());
+
+        //the next connection of the same counterparty finds its session
+        handle.set_next_sender_msg_seq_num(5).unwrap();
+        assert_eq!(5, sessions.session_for_logon(&logon("TW", "ISLD", now), None).unwrap().next_sender_msg_seq_num());
+        assert_eq!(1, sessions.session_ids().len());
+
+        assert!(sessions.session_for_logon(&logon("BANNED", "ISLD", now), None).is_err());
+        assert!(sessions.session_for_logon(&logon("TW", "OTHER", now), None).is_err());
+        assert_eq!(1, sessions.session_ids().len());
+
+        let mut heartbeat = logon("TW", "ISLD", now);
+        heartbeat.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_HEARTBEAT);
+        assert!(sessions.session_for_logon(&heartbeat, None).is_err());
+    }
+
+    #[test]
+    fn configured_session_first_test() {
+        let now = now_utc();
+        let mut sessions = new_sessions();
+        sessions.add_settings(&template_settings(WILDCARD, WILDCARD)).unwrap();
+        let mut settings = template_settings("ISLD", "TW");
+        settings.set(ACCEPTOR_TEMPLATE, "N");
+        settings.set(SESSION_QUALIFIER, "Q");
+        sessions.add_settings(&settings).unwrap();
+
+        let handle = sessions.session_for_logon(&logon("TW", "ISLD", now), None).unwrap();
+        assert_eq!("FIX.4.4:ISLD->TW:Q", handle.session_id().to_string());
+        let handle = sessions.session_for_logon(&logon("OTHER", "ISLD", now), None).unwrap();
+        assert_eq!("FIX.4.4:ISLD->OTHER", handle.session_id().to_string());
+        assert!(sessions.session(&SessionID::new(BEGIN_STRING_FIX44, "ISLD", "OTHER")).is_some());
+    }
+
+    #[test]
+    fn settings_file_test() {
+        let now = now_utc();
+        let text = "[DEFAULT]\nBeginString=FIX.4.4\nSenderCompID=ISLD\nHeartBtInt=20\n\
+            [SESSION]\nTargetCompID=TW\nSessionQualifier=Q\n\
+            [SESSION]\nTargetCompID=*\nAcceptorTemplate=Y\n";
+        let mut sessions = new_sessions();
+        for settings in SessionSettings::parse(text).unwrap() {
+            sessions.add_settings(&settings).unwrap();
+        }
+        assert_eq!(1, sessions.session_ids().len());
+
+        let handle = sessions.session_for_logon(&logon("TW", "ISLD", now), None).unwrap();
+        assert_eq!("FIX.4.4:ISLD->TW:Q", handle.session_id().to_string());
+        let handle = sessions.session_for_logon(&logon("OTHER", "ISLD", now), None).unwrap();
+        assert_eq!("FIX.4.4:ISLD->OTHER", handle.session_id().to_string());
+        assert_eq!(2, sessions.session_ids().len());
+    }
+}
@@ -1,26 +1,63 @@
 use tag::*;
-use std::{string, fmt};
+use std::{string, str, fmt, io};
 use std;
 use std::error::Error;
 use time::*;
 
+//Reject lists the SessionRejectReason(373) values, `reason as i32` is the value sent on the wire
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Reject {
     InvalidTagNumber = 0,
     RequiredTagMissing = 1,
     TagNotDefinedForThisMessageType = 2,
-    UnsupportedMessageType = 3,
+    UndefinedTag = 3,
     TagSpecifiedWithoutAValue = 4,
     ValueIsIncorrect = 5,
-    ConditionallyRequiredFieldMissing = 6,
-    IncorrectDataFormatForValue = 7,
-    CompIDProblem = 8,
-    SendingTimeAccuracyProblem = 9,
+    IncorrectDataFormatForValue = 6,
+    DecryptionProblem = 7,
+    SignatureProblem = 8,
+    CompIDProblem = 9,
+    SendingTimeAccuracyProblem = 10,
     InvalidMsgType = 11,
+    XMLValidationError = 12,
     TagAppearsMoreThanOnce = 13,
     SpecifiedOutOfRequiredOrder = 14,
     RepeatingGroupFieldsOutOfOrder = 15,
-    IncorrectNumInGroupCountForRepeatingGroup = 16
+    IncorrectNumInGroupCountForRepeatingGroup = 16,
+    NonDataValueIncludesFieldDelimiter = 17,
+    InvalidApplVerID = 18,
+    Other = 99
+}
+
+//BusinessRejectReason lists the BusinessRejectReason(380) values of a BusinessMessageReject
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BusinessRejectReason {
+    Other = 0,
+    UnknownID = 1,
+    UnknownSecurity = 2,
+    UnsupportedMessageType = 3,
+    ApplicationNotAvailable = 4,
+    ConditionallyRequiredFieldMissing = 5,
+    NotAuthorized = 6,
+    DeliverToFirmNotAvailableAtThisTime = 7,
+    InvalidPriceIncrement = 18
+}
+
+impl BusinessRejectReason {
+
+    //code is the BusinessRejectReason(380) value sent on the wire
+    pub fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    //session_reject_reason is the closest session level reason, used where BusinessMessageReject does not exist
+    pub fn session_reject_reason(&self) -> Reject {
+        match *self {
+            BusinessRejectReason::UnsupportedMessageType => Reject::InvalidMsgType,
+            BusinessRejectReason::ConditionallyRequiredFieldMissing => Reject::RequiredTagMissing,
+            _ => Reject::Other
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,8 +87,15 @@ pub enum FixError {
     Error(fmt::Error),
     IntError(std::num::ParseIntError),
     FromUtf8Error(string::FromUtf8Error),
+    Utf8Error(str::Utf8Error),
     BooleanParseError(FixBooleanParseError),
-    TimeParseError(ParseError)
+    TimeParseError(ParseError),
+    ParseMessageError(String),
+    ConfigError(String),
+    SessionError(String),
+    IoError(io::Error),
+    SerializeError(String),
+    RejectError(error::MessageRejectError)
 }
 
 impl std::error::Error for FixError {
@@ -60,8 +104,15 @@ impl std::error::Error for FixError {
             FixError::Error(ref err) => err.description(),
             FixError::IntError(ref err) => err.description(),
             FixError::FromUtf8Error(ref err) => err.description(),
+            FixError::Utf8Error(ref err) => err.description(),
             FixError::BooleanParseError(ref err) => err.description(),
             FixError::TimeParseError(ref err) => err.description(),
+            FixError::ParseMessageError(ref err) => err.as_str(),
+            FixError::ConfigError(ref err) => err.as_str(),
+            FixError::SessionError(ref err) => err.as_str(),
+            FixError::IoError(ref err) => err.description(),
+            FixError::SerializeError(ref err) => err.as_str(),
+            FixError::RejectError(ref err) => err.description(),
         }
     }
 }
@@ -72,14 +123,22 @@ impl std::fmt::Display for FixError {
             FixError::Error(ref err) => write!(f, "({})", self.description()),
             FixError::IntError(ref err) => write!(f, "({})", err.description()),
             FixError::FromUtf8Error(ref err) => write!(f, "({})", self.description()),
+            FixError::Utf8Error(ref err) => write!(f, "({})", err),
             FixError::BooleanParseError(ref err) => write!(f, "({})", self.description()),
-            FixError::TimeParseError(ref err) => write!(f, "({})", self.description())
+            FixError::TimeParseError(ref err) => write!(f, "({})", self.description()),
+            FixError::ParseMessageError(ref err) => write!(f, "({})", err),
+            FixError::ConfigError(ref err) => write!(f, "({})", err),
+            FixError::SessionError(ref err) => write!(f, "({})", err),
+            FixError::IoError(ref err) => write!(f, "({})", err),
+            FixError::SerializeError(ref err) => write!(f, "({})", err),
+            FixError::RejectError(ref err) => write!(f, "{}", err)
         }
     }
 }
 
-trait MessageRejectError : std::error::Error {
+pub trait MessageRejectError : std::error::Error {
     fn reject_reason(&self) -> Reject;
+    fn business_reject_reason(&self) -> Option<BusinessRejectReason>;
     fn ref_tag_id(&self) -> u32;
     fn is_business_reject(&self) -> bool;
 }
@@ -96,6 +155,7 @@ pub mod error {
     #[derive(Debug)]
     pub struct MessageRejectError {
         reject_reason: Reject,
+        business_reject_reason: Option<BusinessRejectReason>,
         description: String,
         ref_tag_id: u32,
         id_business_reject: bool
@@ -104,24 +164,113 @@ pub mod error {
     impl MessageRejectError {
 
         pub fn new_message_reject_error(err: String, reject_reason: Reject, ref_tag_id: u32) -> MessageRejectError {
-            MessageRejectError{description: err, reject_reason: reject_reason, ref_tag_id: ref_tag_id, id_business_reject: false}
+            MessageRejectError{description: err, reject_reason: reject_reason, business_reject_reason: None, ref_tag_id: ref_tag_id, id_business_reject: false}
         }
 
-        pub fn new_business_message_reject_error(err: String, reject_reason: Reject, ref_tag_id: u32) -> MessageRejectError {
-            MessageRejectError{description: err, reject_reason: reject_reason, ref_tag_id: ref_tag_id, id_business_reject: true}
+        pub fn new_business_message_reject_error(err: String, reject_reason: BusinessRejectReason, ref_tag_id: u32) -> MessageRejectError {
+            MessageRejectError{description: err, reject_reason: reject_reason.session_reject_reason(), business_reject_reason: Some(reject_reason), ref_tag_id: ref_tag_id, id_business_reject: true}
+        }
+
+        //text is the human readable reason, it is sent in Text(58)
+        pub fn text(&self) -> &str {
+            self.description.as_str()
         }
 
         pub fn conditionally_required_field_missing(tag:u32) -> MessageRejectError {
-            Self::new_message_reject_error(format!("Conditionally Required Field Missing {}", tag), Reject::ConditionallyRequiredFieldMissing, tag)
+            Self::new_message_reject_error("Conditionally required field missing".to_string(), Reject::RequiredTagMissing, tag)
         }
 
         pub fn incorrect_data_format_for_value(tag:u32) -> MessageRejectError {
             Self::new_message_reject_error("Incorrect data format for value".to_string(), Reject::IncorrectDataFormatForValue, tag)
         }
+
+        pub fn tag_appears_more_than_once(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Tag appears more than once".to_string(), Reject::TagAppearsMoreThanOnce, tag)
+        }
+
+        pub fn invalid_tag_number(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Invalid tag number".to_string(), Reject::InvalidTagNumber, tag)
+        }
+
+        pub fn required_tag_missing(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Required tag missing".to_string(), Reject::RequiredTagMissing, tag)
+        }
+
+        pub fn tag_not_defined_for_this_message_type(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Tag not defined for this message type".to_string(), Reject::TagNotDefinedForThisMessageType, tag)
+        }
+
+        pub fn tag_specified_without_a_value(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Tag specified without a value".to_string(), Reject::TagSpecifiedWithoutAValue, tag)
+        }
+
+        pub fn value_is_incorrect(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Value is incorrect (out of range) for this tag".to_string(), Reject::ValueIsIncorrect, tag)
+        }
+
+        pub fn value_is_incorrect_no_tag() -> MessageRejectError {
+            Self::new_message_reject_error("Value is incorrect (out of range) for this tag".to_string(), Reject::ValueIsIncorrect, 0)
+        }
+
+        pub fn comp_id_problem() -> MessageRejectError {
+            Self::new_message_reject_error("CompID problem".to_string(), Reject::CompIDProblem, 0)
+        }
+
+        pub fn signature_problem() -> MessageRejectError {
+            Self::new_message_reject_error("Signature problem".to_string(), Reject::SignatureProblem, Tags::Signature.to_num())
+        }
+
+        pub fn sending_time_accuracy_problem() -> MessageRejectError {
+            Self::new_message_reject_error("SendingTime accuracy problem".to_string(), Reject::SendingTimeAccuracyProblem, 0)
+        }
+
+        pub fn invalid_message_type() -> MessageRejectError {
+            Self::new_message_reject_error("Invalid MsgType".to_string(), Reject::InvalidMsgType, 0)
+        }
+
+        pub fn tag_specified_out_of_required_order(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Tag specified out of required order".to_string(), Reject::SpecifiedOutOfRequiredOrder, tag)
+        }
+
+        pub fn repeating_group_fields_out_of_order(tag:u32, reason:&str) -> MessageRejectError {
+            let text = if reason.is_empty() {
+                "Repeating group fields out of order".to_string()
+            } else {
+                format!("Repeating group fields out of order, {}", reason)
+            };
+            Self::new_message_reject_error(text, Reject::RepeatingGroupFieldsOutOfOrder, tag)
+        }
+
+        pub fn incorrect_num_in_group_count_for_repeating_group(tag:u32) -> MessageRejectError {
+            Self::new_message_reject_error("Incorrect NumInGroup count for repeating group".to_string(), Reject::IncorrectNumInGroupCountForRepeatingGroup, tag)
+        }
+
+        //invalid_appl_ver_id rejects a message whose ApplVerID(1128) names a version the session has no dictionary for
+        pub fn invalid_appl_ver_id() -> MessageRejectError {
+            Self::new_message_reject_error("Invalid or unsupported application version".to_string(), Reject::InvalidApplVerID, Tags::ApplVerID.to_num())
+        }
+
+        //unsupported_message_type is a business level reject, the session is fine but the application does not handle the MsgType
+        pub fn unsupported_message_type() -> MessageRejectError {
+            Self::new_business_message_reject_error("Unsupported message type".to_string(), BusinessRejectReason::UnsupportedMessageType, 0)
+        }
+
+        pub fn application_not_available() -> MessageRejectError {
+            Self::new_business_message_reject_error("Application not available".to_string(), BusinessRejectReason::ApplicationNotAvailable, 0)
+        }
+
+        pub fn not_authorized() -> MessageRejectError {
+            Self::new_business_message_reject_error("Not authorized".to_string(), BusinessRejectReason::NotAuthorized, 0)
+        }
+
+        pub fn unknown_id(tag:u32) -> MessageRejectError {
+            Self::new_business_message_reject_error("Unknown ID".to_string(), BusinessRejectReason::UnknownID, tag)
+        }
     }
 
     impl super::MessageRejectError for MessageRejectError {
         fn reject_reason(&self) -> Reject { self.reject_reason }
+        fn business_reject_reason(&self) -> Option<BusinessRejectReason> { self.business_reject_reason }
         fn ref_tag_id(&self) -> u32 { self.ref_tag_id }
         fn is_business_reject(&self) -> bool { self.id_business_reject }
     }
@@ -135,4 +284,58 @@ pub mod error {
             write!(f, "({})", self.description)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::error::MessageRejectError;
+    use super::MessageRejectError as MessageRejectErrorTrait;
+
+    #[test]
+    fn reject_code_test() {
+        assert_eq!(1, Reject::RequiredTagMissing as i32);
+        assert_eq!(6, Reject::IncorrectDataFormatForValue as i32);
+        assert_eq!(8, Reject::SignatureProblem as i32);
+        assert_eq!(9, Reject::CompIDProblem as i32);
+        assert_eq!(10, Reject::SendingTimeAccuracyProblem as i32);
+        assert_eq!(16, Reject::IncorrectNumInGroupCountForRepeatingGroup as i32);
+        assert_eq!(99, Reject::Other as i32);
+    }
+
+    #[test]
+    fn reject_text_test() {
+        //the tag goes in RefTagID(371) or, before FIX.4.2, is appended by the session, never in the text itself
+        assert_eq!("Conditionally required field missing", MessageRejectError::conditionally_required_field_missing(55).text());
+        assert_eq!("Tag appears more than once", MessageRejectError::tag_appears_more_than_once(55).text());
+        assert_eq!(Reject::RequiredTagMissing, MessageRejectError::conditionally_required_field_missing(55).reject_reason());
+    }
+
+    #[test]
+    fn session_reject_constructors_test() {
+        let err = MessageRejectError::required_tag_missing(35);
+        assert_eq!(Reject::RequiredTagMissing, err.reject_reason());
+        assert_eq!(35, err.ref_tag_id());
+        assert!(!err.is_business_reject());
+        assert_eq!(None, err.business_reject_reason());
+
+        let err = MessageRejectError::comp_id_problem();
+        assert_eq!(Reject::CompIDProblem, err.reject_reason());
+        assert_eq!(0, err.ref_tag_id());
+
+        let err = MessageRejectError::repeating_group_fields_out_of_order(268, "269 expected");
+        assert_eq!("Repeating group fields out of order, 269 expected", err.text());
+    }
+
+    #[test]
+    fn business_reject_constructors_test() {
+        let err = MessageRejectError::unsupported_message_type();
+        assert!(err.is_business_reject());
+        assert_eq!(Some(BusinessRejectReason::UnsupportedMessageType), err.business_reject_reason());
+        assert_eq!(Reject::InvalidMsgType, err.reject_reason());
+
+        let err = MessageRejectError::unknown_id(11);
+        assert_eq!(1, err.business_reject_reason().unwrap().code());
+        assert_eq!(Reject::Other, err.reject_reason());
+    }
+}
@@ -0,0 +1,1461 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tag::*;
+use message::*;
+use message_writer::*;
+use begin_string::*;
+use fix_decimal::*;
+use error::FixError;
+use xml::{self, Element};
+
+fn fast_error(text: &str) -> FixError {
+    FixError::ParseMessageError(format!("FAST: {}", text))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FastType {
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Decimal,
+    AsciiString,
+    UnicodeString,
+    ByteVector
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FastOperator {
+    None,
+    Constant,
+    Default,
+    Copy,
+    Increment,
+    Delta,
+    Tail
+}
+
+impl FastOperator {
+
+    //uses_bit tells whether a field with this operator takes a bit of the presence map
+    fn uses_bit(self, optional: bool) -> bool {
+        match self {
+            FastOperator::None | FastOperator::Delta => false,
+            FastOperator::Constant => optional,
+            FastOperator::Default | FastOperator::Copy | FastOperator::Increment | FastOperator::Tail => true
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastOperation {
+    pub operator: FastOperator,
+    //initial is the value attribute of the operator
+    pub initial: Option<String>,
+    //dictionary is global, template, type or an application defined name, None takes the one of the template
+    pub dictionary: Option<String>,
+    pub key: String
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastField {
+    pub name: String,
+    //id is the FIX tag, fields without id are decoded for their dictionary only
+    pub id: Option<u32>,
+    pub field_type: FastType,
+    pub optional: bool,
+    //operation applies to the whole field, or to the exponent of a decimal given a mantissa operation
+    pub operation: FastOperation,
+    pub mantissa: Option<FastOperation>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FastInstruction {
+    Field(FastField),
+    Group{name: String, optional: bool, instructions: Vec<FastInstruction>},
+    //the length field is the NumInGroup field of the repeating group
+    Sequence{name: String, length: FastField, instructions: Vec<FastInstruction>},
+    //a static reference to the instructions of another template
+    TemplateRef(String)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastTemplate {
+    pub name: String,
+    pub id: u32,
+    pub dictionary: Option<String>,
+    pub instructions: Vec<FastInstruction>
+}
+
+//FastTemplates is a loaded FAST 1.1 template definition document
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastTemplates {
+    pub templates: Vec<FastTemplate>
+}
+
+impl FastTemplates {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<FastTemplates, FixError> {
+        let mut xml = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut xml)).map_err(FixError::IoError)?;
+        FastTemplates::parse(&xml)
+    }
+
+    pub fn parse(xml: &str) -> Result<FastTemplates, FixError> {
+        let root = xml::parse(xml).map_err(|err| fast_error(&err))?;
+        let templates = match root.local_name() {
+            "templates" => root.children.iter().filter(|child| child.local_name() == "template").collect(),
+            "template" => vec![&root],
+            other => return Err(fast_error(&format!("unexpected root element {}", other)))
+        };
+
+        let mut loaded = vec![];
+        for template in templates {
+            let id = required(template, "id")?;
+            loaded.push(FastTemplate{
+                name: required(template, "name")?.to_string(),
+                id: id.parse::<u32>().map_err(|_| fast_error(&format!("template id {} is not a number", id)))?,
+                dictionary: template.attribute("dictionary").or_else(|| root.attribute("dictionary")).map(|d| d.to_string()),
+                instructions: instructions(&template.children)?
+            });
+        }
+        Ok(FastTemplates{templates: loaded})
+    }
+
+    pub fn template(&self, id: u32) -> Option<&FastTemplate> {
+        self.templates.iter().find(|template| template.id == id)
+    }
+
+    pub fn template_by_name(&self, name: &str) -> Option<&FastTemplate> {
+        self.templates.iter().find(|template| template.name == name)
+    }
+
+    fn referenced(&self, name: &str) -> Result<&FastTemplate, FixError> {
+        self.template_by_name(name).ok_or_else(|| fast_error(&format!("unknown template {}", name)))
+    }
+
+    //needs_pmap tells whether instructions take bits of a presence map
+    fn needs_pmap(&self, instructions: &[FastInstruction]) -> Result<bool, FixError> {
+        for instruction in instructions.iter() {
+            let uses_bit = match *instruction {
+                FastInstruction::Field(ref field) => field.operation.operator.uses_bit(field.optional) ||
+                    field.mantissa.as_ref().is_some_and(|mantissa| mantissa.operator.uses_bit(false)),
+                FastInstruction::Group{optional, ..} => optional,
+                FastInstruction::Sequence{ref length, ..} => length.operation.operator.uses_bit(length.optional),
+                FastInstruction::TemplateRef(ref name) => self.needs_pmap(&self.referenced(name)?.instructions)?
+            };
+            if uses_bit {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    //contains tells whether tag is one of the fields of instructions
+    fn contains(&self, instructions: &[FastInstruction], tag: u32) -> bool {
+        instructions.iter().any(|instruction| match *instruction {
+            FastInstruction::Field(ref field) => field.id == Some(tag),
+            FastInstruction::Group{ref instructions, ..} => self.contains(instructions, tag),
+            FastInstruction::Sequence{ref length, ref instructions, ..} => length.id == Some(tag) || self.contains(instructions, tag),
+            FastInstruction::TemplateRef(ref name) => self.template_by_name(name).is_some_and(|template| self.contains(&template.instructions, tag))
+        })
+    }
+
+    //first_id is the tag of the first field, the delimiter of the instances of a repeating group
+    fn first_id(&self, instructions: &[FastInstruction]) -> Option<u32> {
+        instructions.iter().filter_map(|instruction| match *instruction {
+            FastInstruction::Field(ref field) => field.id,
+            FastInstruction::Group{ref instructions, ..} => self.first_id(instructions),
+            FastInstruction::Sequence{ref length, ..} => length.id,
+            FastInstruction::TemplateRef(ref name) => self.template_by_name(name).and_then(|template| self.first_id(&template.instructions))
+        }).next()
+    }
+
+    //sequences lists the sequences of instructions, those of groups and referenced templates included
+    fn sequences<'t>(&'t self, instructions: &'t [FastInstruction], sequences: &mut Vec<&'t FastInstruction>) {
+        for instruction in instructions.iter() {
+            match *instruction {
+                FastInstruction::Sequence{..} => sequences.push(instruction),
+                FastInstruction::Group{ref instructions, ..} => self.sequences(instructions, sequences),
+                FastInstruction::TemplateRef(ref name) => if let Some(template) = self.template_by_name(name) {
+                    self.sequences(&template.instructions, sequences)
+                },
+                FastInstruction::Field(_) => {}
+            }
+        }
+    }
+}
+
+fn required<'e>(element: &'e Element, name: &str) -> Result<&'e str, FixError> {
+    element.attribute(name).ok_or_else(|| fast_error(&format!("{} without {}", element.name, name)))
+}
+
+fn instructions(elements: &[Element]) -> Result<Vec<FastInstruction>, FixError> {
+    let mut instructions = vec![];
+    for element in elements.iter() {
+        let field_type = match element.local_name() {
+            "int32" => FastType::Int32,
+            "uInt32" => FastType::UInt32,
+            "int64" => FastType::Int64,
+            "uInt64" => FastType::UInt64,
+            "decimal" => FastType::Decimal,
+            "string" if element.attribute("charset") == Some("unicode") => FastType::UnicodeString,
+            "string" => FastType::AsciiString,
+            "byteVector" => FastType::ByteVector,
+            "group" => {
+                instructions.push(FastInstruction::Group{
+                    name: required(element, "name")?.to_string(),
+                    optional: element.attribute("presence") == Some("optional"),
+                    instructions: instructions_of(element)?
+                });
+                continue;
+            },
+            "sequence" => {
+                let name = required(element, "name")?;
+                let optional = element.attribute("presence") == Some("optional");
+                let length = match element.children.iter().find(|child| child.local_name() == "length") {
+                    Some(length) => {
+                        let mut length = field(length, FastType::UInt32, optional)?;
+                        if length.name.is_empty() {
+                            length.name = format!("{}Length", name);
+                        }
+                        length
+                    },
+                    None => FastField{name: format!("{}Length", name), id: None, field_type: FastType::UInt32, optional: optional,
+                                      operation: operation(None, &format!("{}Length", name))?, mantissa: None}
+                };
+                instructions.push(FastInstruction::Sequence{name: name.to_string(), length: length, instructions: instructions_of(element)?});
+                continue;
+            },
+            "templateRef" => match element.attribute("name") {
+                Some(name) => {
+                    instructions.push(FastInstruction::TemplateRef(name.to_string()));
+                    continue;
+                },
+                None => return Err(fast_error("dynamic template references are not supported"))
+            },
+            _ => continue
+        };
+        let optional = element.attribute("presence") == Some("optional");
+        instructions.push(FastInstruction::Field(field(element, field_type, optional)?));
+    }
+    Ok(instructions)
+}
+
+fn instructions_of(element: &Element) -> Result<Vec<FastInstruction>, FixError> {
+    let children: Vec<Element> = element.children.iter().filter(|child| child.local_name() != "length").cloned().collect();
+    instructions(&children)
+}
+
+fn field(element: &Element, field_type: FastType, optional: bool) -> Result<FastField, FixError> {
+    let name = element.attribute("name").unwrap_or("").to_string();
+    let id = match element.attribute("id") {
+        Some(id) => Some(id.parse::<u32>().map_err(|_| fast_error(&format!("field id {} is not a number", id)))?),
+        None => None
+    };
+    let child = |local_name: &str| element.children.iter().find(|child| child.local_name() == local_name);
+
+    let (operation, mantissa) = if field_type == FastType::Decimal && (child("exponent").is_some() || child("mantissa").is_some()) {
+        let exponent = operation(child("exponent").and_then(|exponent| exponent.children.first()), &format!("{}.exponent", name))?;
+        let mantissa = operation(child("mantissa").and_then(|mantissa| mantissa.children.first()), &format!("{}.mantissa", name))?;
+        (exponent, Some(mantissa))
+    } else {
+        (operation(element.children.first(), &name)?, None)
+    };
+
+    let integer = matches!(field_type, FastType::Int32 | FastType::UInt32 | FastType::Int64 | FastType::UInt64);
+    if operation.operator == FastOperator::Increment && !integer {
+        return Err(fast_error(&format!("increment of {} which is not an integer", name)));
+    }
+    if operation.initial.is_none() && (operation.operator == FastOperator::Constant || (operation.operator == FastOperator::Default && !optional)) {
+        return Err(fast_error(&format!("{} of {} without value", element.name, name)));
+    }
+    Ok(FastField{name: name, id: id, field_type: field_type, optional: optional, operation: operation, mantissa: mantissa})
+}
+
+fn operation(element: Option<&Element>, default_key: &str) -> Result<FastOperation, FixError> {
+    let element = match element {
+        Some(element) => element,
+        None => return Ok(FastOperation{operator: FastOperator::None, initial: None, dictionary: None, key: default_key.to_string()})
+    };
+    let operator = match element.local_name() {
+        "constant" => FastOperator::Constant,
+        "default" => FastOperator::Default,
+        "copy" => FastOperator::Copy,
+        "increment" => FastOperator::Increment,
+        "delta" => FastOperator::Delta,
+        "tail" => FastOperator::Tail,
+        other => return Err(fast_error(&format!("unknown operator {}", other)))
+    };
+    Ok(FastOperation{
+        operator: operator,
+        initial: element.attribute("value").map(|value| value.to_string()),
+        dictionary: element.attribute("dictionary").map(|dictionary| dictionary.to_string()),
+        key: element.attribute("key").unwrap_or(default_key).to_string()
+    })
+}
+
+//Value is a decoded field, strings and byte vectors are Bytes
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Int(i64),
+    UInt(u64),
+    Decimal(i32, i64),
+    Bytes(Vec<u8>)
+}
+
+impl Value {
+
+    //parse reads a FIX value or the initial value of an operator
+    fn parse(field_type: FastType, text: &[u8]) -> Result<Value, FixError> {
+        let string = String::from_utf8_lossy(text);
+        let invalid = || fast_error(&format!("{} is not a valid {:?}", string, field_type));
+        let value = match field_type {
+            FastType::Int32 | FastType::Int64 => Value::Int(string.parse::<i64>().map_err(|_| invalid())?),
+            FastType::UInt32 | FastType::UInt64 => Value::UInt(string.parse::<u64>().map_err(|_| invalid())?),
+            FastType::Decimal => {
+                let (mantissa, exponent) = parse_decimal(&string).ok_or_else(invalid)?;
+                Value::Decimal(exponent, mantissa)
+            },
+            FastType::AsciiString | FastType::UnicodeString | FastType::ByteVector => Value::Bytes(text.to_vec())
+        };
+        value.check(field_type)?;
+        Ok(value)
+    }
+
+    fn default(field_type: FastType) -> Value {
+        match field_type {
+            FastType::Int32 | FastType::Int64 => Value::Int(0),
+            FastType::UInt32 | FastType::UInt64 => Value::UInt(0),
+            FastType::Decimal => Value::Decimal(0, 0),
+            FastType::AsciiString | FastType::UnicodeString | FastType::ByteVector => Value::Bytes(vec![])
+        }
+    }
+
+    //check reports integers out of the range of their type
+    fn check(&self, field_type: FastType) -> Result<(), FixError> {
+        let in_range = match (field_type, self) {
+            (FastType::Int32, &Value::Int(value)) => value >= i32::MIN as i64 && value <= i32::MAX as i64,
+            (FastType::UInt32, &Value::UInt(value)) => value <= u32::MAX as u64,
+            (FastType::Decimal, &Value::Decimal(exponent, _)) => (-63..=63).contains(&exponent),
+            _ => true
+        };
+        if in_range { Ok(()) } else { Err(fast_error(&format!("{:?} is out of the range of {:?}", self, field_type))) }
+    }
+
+    fn to_fix(&self) -> Vec<u8> {
+        match *self {
+            Value::Int(value) => value.to_string().into_bytes(),
+            Value::UInt(value) => value.to_string().into_bytes(),
+            Value::Decimal(exponent, mantissa) => format_decimal(mantissa, exponent).into_bytes(),
+            Value::Bytes(ref value) => value.clone()
+        }
+    }
+
+    fn increment(&self) -> Result<Value, FixError> {
+        match *self {
+            Value::Int(value) => value.checked_add(1).map(Value::Int),
+            Value::UInt(value) => value.checked_add(1).map(Value::UInt),
+            _ => None
+        }.ok_or_else(|| fast_error(&format!("can not increment {:?}", self)))
+    }
+
+    fn apply_delta(&self, delta: &Delta) -> Result<Value, FixError> {
+        let value = match (self, delta) {
+            (&Value::Int(base), &Delta::Int(delta)) => base.checked_add(delta).map(Value::Int),
+            (&Value::UInt(base), &Delta::Int(delta)) => (base as i128).checked_add(delta as i128)
+                .filter(|value| *value >= 0 && *value <= u64::MAX as i128).map(|value| Value::UInt(value as u64)),
+            (&Value::Decimal(exponent, mantissa), &Delta::Decimal(exponent_delta, mantissa_delta)) =>
+                exponent.checked_add(exponent_delta).and_then(|exponent| mantissa.checked_add(mantissa_delta).map(|mantissa| Value::Decimal(exponent, mantissa))),
+            (Value::Bytes(base), Delta::Bytes(subtraction, diff)) => {
+                //a negative subtraction length removes -(length + 1) bytes from the front
+                let (removed, front) = if *subtraction < 0 {
+                    (subtraction.checked_neg().and_then(|length| length.checked_sub(1)), true)
+                } else {
+                    (Some(*subtraction), false)
+                };
+                let removed = removed.map(|removed| removed as u64).unwrap_or(u64::MAX);
+                if removed > base.len() as u64 {
+                    None
+                } else if front {
+                    Some(Value::Bytes([&diff[..], &base[removed as usize..]].concat()))
+                } else {
+                    Some(Value::Bytes([&base[..base.len() - removed as usize], &diff[..]].concat()))
+                }
+            },
+            _ => None
+        };
+        value.ok_or_else(|| fast_error(&format!("can not apply {:?} to {:?}", delta, self)))
+    }
+
+    //delta is the difference from base to self, strings keep the longest common prefix or suffix
+    fn delta(&self, base: &Value) -> Result<Delta, FixError> {
+        let delta = match (self, base) {
+            (&Value::Int(value), &Value::Int(base)) => value.checked_sub(base).map(Delta::Int),
+            (&Value::UInt(value), &Value::UInt(base)) => {
+                let delta = value as i128 - base as i128;
+                if delta >= i64::MIN as i128 && delta <= i64::MAX as i128 { Some(Delta::Int(delta as i64)) } else { None }
+            },
+            (&Value::Decimal(exponent, mantissa), &Value::Decimal(base_exponent, base_mantissa)) =>
+                exponent.checked_sub(base_exponent).and_then(|exponent_delta| mantissa.checked_sub(base_mantissa).map(|mantissa_delta| Delta::Decimal(exponent_delta, mantissa_delta))),
+            (Value::Bytes(value), Value::Bytes(base)) => {
+                let prefix = value.iter().zip(base.iter()).take_while(|(a, b)| a == b).count();
+                let suffix = value.iter().rev().zip(base.iter().rev()).take_while(|(a, b)| a == b).count().min(value.len().min(base.len()));
+                if suffix > prefix {
+                    Some(Delta::Bytes(-((base.len() - suffix) as i64) - 1, value[..value.len() - suffix].to_vec()))
+                } else {
+                    Some(Delta::Bytes((base.len() - prefix) as i64, value[prefix..].to_vec()))
+                }
+            },
+            _ => None
+        };
+        delta.ok_or_else(|| fast_error(&format!("can not take the delta of {:?} from {:?}", self, base)))
+    }
+}
+
+//to_exponent narrows an exponent or exponent delta read from the stream, those beyond an i32 are errors
+fn to_exponent(value: i64) -> Result<i32, FixError> {
+    if value >= i32::MIN as i64 && value <= i32::MAX as i64 {
+        Ok(value as i32)
+    } else {
+        Err(fast_error(&format!("exponent {} is out of range", value)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Delta {
+    Int(i64),
+    Decimal(i32, i64),
+    //the subtraction length, negative to remove from the front, and the bytes to add
+    Bytes(i64, Vec<u8>)
+}
+
+//apply_tail replaces the end of base with tail, a tail as long as base replaces all of it
+fn apply_tail(base: &[u8], tail: &[u8]) -> Vec<u8> {
+    if tail.len() >= base.len() {
+        return tail.to_vec();
+    }
+    [&base[..base.len() - tail.len()], tail].concat()
+}
+
+fn tail(base: &[u8], value: &[u8]) -> Result<Vec<u8>, FixError> {
+    if value.len() > base.len() {
+        return Ok(value.to_vec());
+    }
+    if value.len() < base.len() {
+        return Err(fast_error(&format!("tail can not shorten {} to {}", String::from_utf8_lossy(base), String::from_utf8_lossy(value))));
+    }
+    let prefix = value.iter().zip(base.iter()).take_while(|(a, b)| a == b).count();
+    Ok(value[prefix..].to_vec())
+}
+
+//Reader reads the stop bit encoded primitives of a stream
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+
+    fn stop_bit_bytes(&mut self) -> Result<&'a [u8], FixError> {
+        let start = self.pos;
+        while self.pos < self.buf.len() {
+            self.pos += 1;
+            if self.buf[self.pos - 1] & 0x80 != 0 {
+                return Ok(&self.buf[start..self.pos]);
+            }
+        }
+        Err(fast_error("truncated stream"))
+    }
+
+    fn uint(&mut self) -> Result<u64, FixError> {
+        let mut value: u64 = 0;
+        for b in self.stop_bit_bytes()?.iter() {
+            if value >> 57 != 0 {
+                return Err(fast_error("integer overflow"));
+            }
+            value = (value << 7) | (b & 0x7f) as u64;
+        }
+        Ok(value)
+    }
+
+    fn int(&mut self) -> Result<i64, FixError> {
+        let bytes = self.stop_bit_bytes()?;
+        let mut value: i64 = if bytes[0] & 0x40 != 0 { -1 } else { 0 };
+        for b in bytes.iter() {
+            if !(i64::MIN >> 7..=i64::MAX >> 7).contains(&value) {
+                return Err(fast_error("integer overflow"));
+            }
+            value = (value << 7) | (b & 0x7f) as i64;
+        }
+        Ok(value)
+    }
+
+    //nullable integers are shifted by one for non negative values, 0 stands for null
+    fn nullable_uint(&mut self) -> Result<Option<u64>, FixError> {
+        Ok(match self.uint()? {
+            0 => None,
+            value => Some(value - 1)
+        })
+    }
+
+    fn nullable_int(&mut self) -> Result<Option<i64>, FixError> {
+        Ok(match self.int()? {
+            0 => None,
+            value if value > 0 => Some(value - 1),
+            value => Some(value)
+        })
+    }
+
+    fn int_or_null(&mut self, nullable: bool) -> Result<Option<i64>, FixError> {
+        if nullable { self.nullable_int() } else { self.int().map(Some) }
+    }
+
+    fn uint_or_null(&mut self, nullable: bool) -> Result<Option<u64>, FixError> {
+        if nullable { self.nullable_uint() } else { self.uint().map(Some) }
+    }
+
+    //ascii reads a stop bit encoded string, leading NULs tell the empty string, NUL and null apart
+    fn ascii(&mut self, nullable: bool) -> Result<Option<Vec<u8>>, FixError> {
+        let mut value = self.stop_bit_bytes()?.to_vec();
+        if let Some(last) = value.last_mut() {
+            *last &= 0x7f;
+        }
+        if value[0] != 0 {
+            return Ok(Some(value));
+        }
+        match (nullable, value.len()) {
+            (true, 1) => Ok(None),
+            (true, 2) | (false, 1) => Ok(Some(vec![])),
+            (true, 3) | (false, 2) => Ok(Some(vec![0])),
+            _ => Err(fast_error("overlong string"))
+        }
+    }
+
+    fn bytes(&mut self, nullable: bool) -> Result<Option<Vec<u8>>, FixError> {
+        let length = match self.uint_or_null(nullable)? {
+            Some(length) => length as usize,
+            None => return Ok(None)
+        };
+        if length > self.buf.len() - self.pos {
+            return Err(fast_error("truncated stream"));
+        }
+        self.pos += length;
+        Ok(Some(self.buf[self.pos - length..self.pos].to_vec()))
+    }
+
+    fn pmap(&mut self) -> Result<Pmap, FixError> {
+        let mut bits = vec![];
+        for b in self.stop_bit_bytes()?.iter() {
+            for i in (0..7).rev() {
+                bits.push(b & (1 << i) != 0);
+            }
+        }
+        Ok(Pmap{bits: bits, pos: 0})
+    }
+
+    fn value(&mut self, field_type: FastType, nullable: bool) -> Result<Option<Value>, FixError> {
+        let value = match field_type {
+            FastType::Int32 | FastType::Int64 => self.int_or_null(nullable)?.map(Value::Int),
+            FastType::UInt32 | FastType::UInt64 => self.uint_or_null(nullable)?.map(Value::UInt),
+            FastType::Decimal => match self.int_or_null(nullable)? {
+                Some(exponent) => Some(Value::Decimal(to_exponent(exponent)?, self.int()?)),
+                None => None
+            },
+            FastType::AsciiString => self.ascii(nullable)?.map(Value::Bytes),
+            FastType::UnicodeString | FastType::ByteVector => self.bytes(nullable)?.map(Value::Bytes)
+        };
+        if let Some(ref value) = value {
+            value.check(field_type)?;
+        }
+        Ok(value)
+    }
+
+    fn delta(&mut self, field_type: FastType, nullable: bool) -> Result<Option<Delta>, FixError> {
+        let delta = match field_type {
+            FastType::Int32 | FastType::Int64 | FastType::UInt32 | FastType::UInt64 => self.int_or_null(nullable)?.map(Delta::Int),
+            FastType::Decimal => match self.int_or_null(nullable)? {
+                Some(exponent) => Some(Delta::Decimal(to_exponent(exponent)?, self.int()?)),
+                None => None
+            },
+            FastType::AsciiString | FastType::UnicodeString | FastType::ByteVector => match self.int_or_null(nullable)? {
+                Some(subtraction) => {
+                    let diff = match field_type {
+                        FastType::AsciiString => self.ascii(false)?,
+                        _ => self.bytes(false)?
+                    };
+                    Some(Delta::Bytes(subtraction, diff.unwrap_or_default()))
+                },
+                None => None
+            }
+        };
+        Ok(delta)
+    }
+}
+
+//Pmap is a presence map, bits past its end are not set
+struct Pmap {
+    bits: Vec<bool>,
+    pos: usize
+}
+
+impl Pmap {
+
+    fn empty() -> Pmap {
+        Pmap{bits: vec![], pos: 0}
+    }
+
+    fn next(&mut self) -> bool {
+        self.pos += 1;
+        self.bits.get(self.pos - 1).cloned().unwrap_or(false)
+    }
+}
+
+fn stop_bit(out: &mut Vec<u8>, mut groups: Vec<u8>) {
+    groups.reverse();
+    if let Some(last) = groups.last_mut() {
+        *last |= 0x80;
+    }
+    out.extend(groups);
+}
+
+fn write_uint(out: &mut Vec<u8>, mut value: u64) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value != 0 {
+        groups.push((value & 0x7f) as u8);
+        value >>= 7;
+    }
+    stop_bit(out, groups);
+}
+
+fn write_int(out: &mut Vec<u8>, mut value: i64) {
+    let mut groups = vec![];
+    loop {
+        let group = (value & 0x7f) as u8;
+        let rest = value >> 7;
+        groups.push(group);
+        //stop once the sign bit of the group tells the sign of the value
+        if (rest == 0 && group & 0x40 == 0) || (rest == -1 && group & 0x40 != 0) {
+            break;
+        }
+        value = rest;
+    }
+    stop_bit(out, groups);
+}
+
+fn write_uint_or_null(out: &mut Vec<u8>, value: Option<u64>, nullable: bool) {
+    match (value, nullable) {
+        (None, _) => out.push(0x80),
+        (Some(value), true) => write_uint(out, value + 1),
+        (Some(value), false) => write_uint(out, value)
+    }
+}
+
+fn write_int_or_null(out: &mut Vec<u8>, value: Option<i64>, nullable: bool) {
+    match (value, nullable) {
+        (None, _) => out.push(0x80),
+        (Some(value), true) if value >= 0 => write_int(out, value + 1),
+        (Some(value), _) => write_int(out, value)
+    }
+}
+
+fn write_ascii(out: &mut Vec<u8>, value: Option<&[u8]>, nullable: bool) -> Result<(), FixError> {
+    let value = match value {
+        Some(value) => value,
+        None => {
+            out.push(0x80);
+            return Ok(());
+        }
+    };
+    if value.iter().any(|b| *b & 0x80 != 0) {
+        return Err(fast_error(&format!("{} is not ASCII", String::from_utf8_lossy(value))));
+    }
+    if value.is_empty() || value == [0] {
+        //the empty string and NUL are led by a NUL, one more when nullable
+        if nullable {
+            out.push(0);
+        }
+        if value == [0] {
+            out.push(0);
+        }
+        out.push(0x80);
+    } else {
+        out.extend_from_slice(value);
+        if let Some(last) = out.last_mut() {
+            *last |= 0x80;
+        }
+    }
+    Ok(())
+}
+
+fn write_bytes(out: &mut Vec<u8>, value: Option<&[u8]>, nullable: bool) {
+    match value {
+        Some(value) => {
+            write_uint_or_null(out, Some(value.len() as u64), nullable);
+            out.extend_from_slice(value);
+        },
+        None => out.push(0x80)
+    }
+}
+
+fn write_pmap(out: &mut Vec<u8>, bits: &[bool]) {
+    let used = bits.iter().rposition(|bit| *bit).map_or(0, |last| last + 1);
+    let mut groups = vec![];
+    for chunk in bits[..used].chunks(7) {
+        let mut group = 0u8;
+        for (i, bit) in chunk.iter().enumerate() {
+            if *bit {
+                group |= 1 << (6 - i);
+            }
+        }
+        groups.push(group);
+    }
+    if groups.is_empty() {
+        groups.push(0);
+    }
+    groups.reverse();
+    stop_bit(out, groups);
+}
+
+fn write_value(out: &mut Vec<u8>, field_type: FastType, value: Option<&Value>, nullable: bool) -> Result<(), FixError> {
+    match (field_type, value) {
+        (_, None) => out.push(0x80),
+        (FastType::Int32, Some(&Value::Int(value))) | (FastType::Int64, Some(&Value::Int(value))) => write_int_or_null(out, Some(value), nullable),
+        (FastType::UInt32, Some(&Value::UInt(value))) | (FastType::UInt64, Some(&Value::UInt(value))) => write_uint_or_null(out, Some(value), nullable),
+        (FastType::Decimal, Some(&Value::Decimal(exponent, mantissa))) => {
+            write_int_or_null(out, Some(exponent as i64), nullable);
+            write_int(out, mantissa);
+        },
+        (FastType::AsciiString, Some(Value::Bytes(value))) => write_ascii(out, Some(value), nullable)?,
+        (_, Some(Value::Bytes(value))) => write_bytes(out, Some(value), nullable),
+        (_, Some(value)) => return Err(fast_error(&format!("{:?} is not a {:?}", value, field_type)))
+    }
+    Ok(())
+}
+
+fn write_delta(out: &mut Vec<u8>, field_type: FastType, delta: &Delta, nullable: bool) -> Result<(), FixError> {
+    match *delta {
+        Delta::Int(delta) => write_int_or_null(out, Some(delta), nullable),
+        Delta::Decimal(exponent, mantissa) => {
+            write_int_or_null(out, Some(exponent as i64), nullable);
+            write_int(out, mantissa);
+        },
+        Delta::Bytes(subtraction, ref diff) => {
+            write_int_or_null(out, Some(subtraction), nullable);
+            match field_type {
+                FastType::AsciiString => write_ascii(out, Some(diff), false)?,
+                _ => write_bytes(out, Some(diff), false)
+            }
+        }
+    }
+    Ok(())
+}
+
+//Dictionary holds the previous values of the operators by dictionary and key, a missing entry is undefined and
+//None is empty
+type Dictionary = HashMap<(String, String), Option<Value>>;
+
+//Instance is the fields of an instance of a repeating group
+type Instance<'f> = &'f [(u32, Vec<u8>)];
+
+//Scalar is a field or the exponent or mantissa of a decimal with operators of their own
+struct Scalar<'f> {
+    name: &'f str,
+    field_type: FastType,
+    optional: bool,
+    operation: &'f FastOperation
+}
+
+//Context is the state shared by the fields of a message
+struct Context<'c> {
+    templates: &'c FastTemplates,
+    template: &'c FastTemplate,
+    dictionary: &'c mut Dictionary
+}
+
+impl<'c> Context<'c> {
+
+    fn key(&self, operation: &FastOperation) -> (String, String) {
+        let dictionary = match operation.dictionary.as_ref().or(self.template.dictionary.as_ref()).map(|d| d.as_str()) {
+            Some("template") => format!("template:{}", self.template.name),
+            Some(dictionary) => dictionary.to_string(),
+            None => "global".to_string()
+        };
+        (dictionary, operation.key.clone())
+    }
+
+    fn initial(field_type: FastType, operation: &FastOperation) -> Result<Option<Value>, FixError> {
+        match operation.initial {
+            Some(ref initial) => Value::parse(field_type, initial.as_bytes()).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    fn decode_scalar(&mut self, reader: &mut Reader, pmap: &mut Pmap, scalar: Scalar) -> Result<Option<Value>, FixError> {
+        let Scalar{name, field_type, optional, operation} = scalar;
+        let initial = Context::initial(field_type, operation)?;
+        let key = self.key(operation);
+        match operation.operator {
+            FastOperator::None => reader.value(field_type, optional),
+            FastOperator::Constant => Ok(if !optional || pmap.next() { initial } else { None }),
+            FastOperator::Default => if pmap.next() { reader.value(field_type, optional) } else { Ok(initial) },
+            FastOperator::Copy | FastOperator::Increment | FastOperator::Tail => {
+                let value = if pmap.next() {
+                    match operation.operator {
+                        FastOperator::Tail => {
+                            let tail = match field_type {
+                                FastType::AsciiString => reader.ascii(optional)?,
+                                _ => reader.bytes(optional)?
+                            };
+                            match tail {
+                                Some(tail) => {
+                                    let base = match self.dictionary.get(&key) {
+                                        Some(&Some(Value::Bytes(ref base))) => base.clone(),
+                                        Some(&None) => vec![],
+                                        _ => match initial {
+                                            Some(Value::Bytes(initial)) => initial,
+                                            _ => vec![]
+                                        }
+                                    };
+                                    Some(Value::Bytes(apply_tail(&base, &tail)))
+                                },
+                                None => None
+                            }
+                        },
+                        _ => reader.value(field_type, optional)?
+                    }
+                } else {
+                    match self.dictionary.get(&key) {
+                        None if initial.is_none() && !optional => return Err(fast_error(&format!("[ERR D5] {} has no previous value", name))),
+                        None => initial,
+                        Some(&None) if !optional => return Err(fast_error(&format!("[ERR D6] {} is empty", name))),
+                        Some(&None) => None,
+                        Some(Some(previous)) if operation.operator == FastOperator::Increment => Some(previous.increment()?),
+                        Some(Some(previous)) => Some(previous.clone())
+                    }
+                };
+                if let Some(ref value) = value {
+                    value.check(field_type)?;
+                }
+                self.dictionary.insert(key, value.clone());
+                Ok(value)
+            },
+            FastOperator::Delta => {
+                let delta = match reader.delta(field_type, optional)? {
+                    Some(delta) => delta,
+                    None => return Ok(None)
+                };
+                let base = match self.dictionary.get(&key) {
+                    Some(Some(previous)) => previous.clone(),
+                    Some(&None) => return Err(fast_error(&format!("[ERR D6] {} is empty", name))),
+                    None => initial.unwrap_or_else(|| Value::default(field_type))
+                };
+                let value = base.apply_delta(&delta)?;
+                value.check(field_type)?;
+                self.dictionary.insert(key, Some(value.clone()));
+                Ok(Some(value))
+            }
+        }
+    }
+
+    fn decode_field(&mut self, reader: &mut Reader, pmap: &mut Pmap, field: &FastField) -> Result<Option<Value>, FixError> {
+        let mantissa = match field.mantissa {
+            Some(ref mantissa) => mantissa,
+            None => return self.decode_scalar(reader, pmap, Scalar{name: &field.name, field_type: field.field_type, optional: field.optional, operation: &field.operation})
+        };
+        let exponent = match self.decode_scalar(reader, pmap, Scalar{name: &field.name, field_type: FastType::Int32, optional: field.optional, operation: &field.operation})? {
+            Some(exponent) => exponent,
+            None => return Ok(None)
+        };
+        let mantissa = self.decode_scalar(reader, pmap, Scalar{name: &field.name, field_type: FastType::Int64, optional: false, operation: mantissa})?;
+        match (exponent, mantissa) {
+            (Value::Int(exponent), Some(Value::Int(mantissa))) => {
+                let value = Value::Decimal(to_exponent(exponent)?, mantissa);
+                value.check(FastType::Decimal)?;
+                Ok(Some(value))
+            },
+            _ => Err(fast_error(&format!("{} has no mantissa", field.name)))
+        }
+    }
+
+    fn decode(&mut self, reader: &mut Reader, pmap: &mut Pmap, instructions: &[FastInstruction], fields: &mut Vec<(u32, Vec<u8>)>) -> Result<(), FixError> {
+        for instruction in instructions.iter() {
+            match *instruction {
+                FastInstruction::Field(ref field) => {
+                    let value = self.decode_field(reader, pmap, field)?;
+                    if let (Some(id), Some(value)) = (field.id, value) {
+                        fields.push((id, value.to_fix()));
+                    }
+                },
+                FastInstruction::Group{optional, ref instructions, ..} => {
+                    if optional && !pmap.next() {
+                        continue;
+                    }
+                    if self.templates.needs_pmap(instructions)? {
+                        let mut group_pmap = reader.pmap()?;
+                        self.decode(reader, &mut group_pmap, instructions, fields)?;
+                    } else {
+                        self.decode(reader, &mut Pmap::empty(), instructions, fields)?;
+                    }
+                },
+                FastInstruction::Sequence{ref length, ref instructions, ..} => {
+                    let count = match self.decode_field(reader, pmap, length)? {
+                        Some(Value::UInt(count)) => count,
+                        _ => continue
+                    };
+                    if let (Some(id), true) = (length.id, count > 0) {
+                        fields.push((id, count.to_string().into_bytes()));
+                    }
+                    let needs_pmap = self.templates.needs_pmap(instructions)?;
+                    for _ in 0..count {
+                        let mut element_pmap = if needs_pmap { reader.pmap()? } else { Pmap::empty() };
+                        self.decode(reader, &mut element_pmap, instructions, fields)?;
+                    }
+                },
+                FastInstruction::TemplateRef(ref name) => {
+                    let templates = self.templates;
+                    self.decode(reader, pmap, &templates.referenced(name)?.instructions, fields)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_scalar(&mut self, out: &mut Vec<u8>, bits: &mut Vec<bool>, scalar: Scalar, value: Option<&Value>) -> Result<(), FixError> {
+        let Scalar{name, field_type, optional, operation} = scalar;
+        let initial = Context::initial(field_type, operation)?;
+        let key = self.key(operation);
+        if value.is_none() && !optional && operation.operator != FastOperator::Constant {
+            return Err(fast_error(&format!("required field {} is missing", name)));
+        }
+        match operation.operator {
+            FastOperator::None => write_value(out, field_type, value, optional)?,
+            FastOperator::Constant => {
+                if value.is_some() && value != initial.as_ref() {
+                    return Err(fast_error(&format!("{} differs from the constant of {}", String::from_utf8_lossy(&value.map(|v| v.to_fix()).unwrap_or_default()), name)));
+                }
+                if optional {
+                    bits.push(value.is_some());
+                }
+            },
+            FastOperator::Default => {
+                if value == initial.as_ref() {
+                    bits.push(false);
+                } else {
+                    bits.push(true);
+                    write_value(out, field_type, value, optional)?;
+                }
+            },
+            FastOperator::Copy | FastOperator::Increment | FastOperator::Tail => {
+                let previous = self.dictionary.get(&key).cloned();
+                //expected is what the decoder takes when the bit is not set, None when it would fail
+                let expected = match previous {
+                    None if initial.is_none() && !optional => None,
+                    None => Some(initial.clone()),
+                    Some(None) if !optional => None,
+                    Some(None) => Some(None),
+                    Some(Some(ref previous)) if operation.operator == FastOperator::Increment => Some(Some(previous.increment()?)),
+                    Some(Some(ref previous)) => Some(Some(previous.clone()))
+                };
+                if expected.as_ref().is_some_and(|expected| expected.as_ref() == value) {
+                    bits.push(false);
+                } else {
+                    bits.push(true);
+                    match (operation.operator, value) {
+                        (FastOperator::Tail, Some(Value::Bytes(value))) => {
+                            let base = match previous {
+                                Some(Some(Value::Bytes(ref base))) => base.clone(),
+                                Some(None) => vec![],
+                                _ => match initial {
+                                    Some(Value::Bytes(ref initial)) => initial.clone(),
+                                    _ => vec![]
+                                }
+                            };
+                            let tail = tail(&base, value)?;
+                            match field_type {
+                                FastType::AsciiString => write_ascii(out, Some(&tail), optional)?,
+                                _ => write_bytes(out, Some(&tail), optional)
+                            }
+                        },
+                        _ => write_value(out, field_type, value, optional)?
+                    }
+                }
+                self.dictionary.insert(key, value.cloned());
+            },
+            FastOperator::Delta => {
+                let value = match value {
+                    Some(value) => value,
+                    None => {
+                        out.push(0x80);
+                        return Ok(());
+                    }
+                };
+                let base = match self.dictionary.get(&key) {
+                    Some(Some(previous)) => previous.clone(),
+                    Some(&None) => return Err(fast_error(&format!("[ERR D6] {} is empty", name))),
+                    None => initial.unwrap_or_else(|| Value::default(field_type))
+                };
+                write_delta(out, field_type, &value.delta(&base)?, optional)?;
+                self.dictionary.insert(key, Some(value.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_field(&mut self, out: &mut Vec<u8>, bits: &mut Vec<bool>, field: &FastField, value: Option<&Value>) -> Result<(), FixError> {
+        let mantissa = match field.mantissa {
+            Some(ref mantissa) => mantissa,
+            None => return self.encode_scalar(out, bits, Scalar{name: &field.name, field_type: field.field_type, optional: field.optional, operation: &field.operation}, value)
+        };
+        let (exponent, mantissa_value) = match value {
+            Some(&Value::Decimal(exponent, mantissa)) => (Some(Value::Int(exponent as i64)), Some(Value::Int(mantissa))),
+            _ => (None, None)
+        };
+        self.encode_scalar(out, bits, Scalar{name: &field.name, field_type: FastType::Int32, optional: field.optional, operation: &field.operation}, exponent.as_ref())?;
+        if mantissa_value.is_some() {
+            self.encode_scalar(out, bits, Scalar{name: &field.name, field_type: FastType::Int64, optional: false, operation: mantissa}, mantissa_value.as_ref())?;
+        }
+        Ok(())
+    }
+
+    //encode writes the fields of instructions, the instances of a repeating group follow its NumInGroup field
+    //and start with its first field
+    fn encode(&mut self, out: &mut Vec<u8>, bits: &mut Vec<bool>, instructions: &[FastInstruction], fields: &[(u32, Vec<u8>)]) -> Result<(), FixError> {
+        let templates = self.templates;
+        let mut sequences = vec![];
+        templates.sequences(instructions, &mut sequences);
+
+        let mut top: Vec<&(u32, Vec<u8>)> = vec![];
+        let mut instances: HashMap<u32, Vec<Instance>> = HashMap::new();
+        let mut pos = 0;
+        while pos < fields.len() {
+            let tag = fields[pos].0;
+            pos += 1;
+            let sequence = sequences.iter().filter_map(|sequence| match **sequence {
+                FastInstruction::Sequence{ref length, ref instructions, ..} if length.id == Some(tag) => Some(instructions),
+                _ => None
+            }).next();
+            let sequence_instructions = match sequence {
+                Some(sequence_instructions) => sequence_instructions,
+                None => {
+                    top.push(&fields[pos - 1]);
+                    continue;
+                }
+            };
+            let delimiter = templates.first_id(sequence_instructions).ok_or_else(|| fast_error(&format!("sequence of {} has no fields", tag)))?;
+            let count = String::from_utf8_lossy(&fields[pos - 1].1).parse::<usize>()
+                .map_err(|_| fast_error(&format!("{} is not a NumInGroup value", String::from_utf8_lossy(&fields[pos - 1].1))))?;
+            let entry = instances.entry(tag).or_default();
+            for _ in 0..count {
+                if pos >= fields.len() || fields[pos].0 != delimiter {
+                    return Err(fast_error(&format!("group {} holds less than {} instances", tag, count)));
+                }
+                let start = pos;
+                pos += 1;
+                while pos < fields.len() && fields[pos].0 != delimiter && templates.contains(sequence_instructions, fields[pos].0) {
+                    pos += 1;
+                }
+                entry.push(&fields[start..pos]);
+            }
+        }
+        let get = |id: Option<u32>| id.and_then(|id| top.iter().find(|field| field.0 == id)).map(|field| &field.1);
+
+        for instruction in instructions.iter() {
+            match *instruction {
+                FastInstruction::Field(ref field) => {
+                    let value = match get(field.id) {
+                        Some(value) => Some(Value::parse(field.field_type, value)?),
+                        None => None
+                    };
+                    self.encode_field(out, bits, field, value.as_ref())?;
+                },
+                FastInstruction::Group{optional, ref instructions, ..} => {
+                    if optional {
+                        let present = top.iter().any(|field| templates.contains(instructions, field.0));
+                        bits.push(present);
+                        if !present {
+                            continue;
+                        }
+                    }
+                    self.encode_nested(out, instructions, fields)?;
+                },
+                FastInstruction::Sequence{ref length, ref instructions, ..} => {
+                    let sequence_instances = length.id.and_then(|id| instances.get(&id));
+                    let count = match sequence_instances {
+                        Some(sequence_instances) => Some(Value::UInt(sequence_instances.len() as u64)),
+                        None if length.optional => None,
+                        None => Some(Value::UInt(0))
+                    };
+                    self.encode_field(out, bits, length, count.as_ref())?;
+                    for instance in sequence_instances.map(|i| i.as_slice()).unwrap_or(&[]).iter() {
+                        self.encode_nested(out, instructions, instance)?;
+                    }
+                },
+                FastInstruction::TemplateRef(ref name) => {
+                    self.encode(out, bits, &templates.referenced(name)?.instructions, fields)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //encode_nested writes a group or an element of a sequence preceded by its own presence map when it needs one
+    fn encode_nested(&mut self, out: &mut Vec<u8>, instructions: &[FastInstruction], fields: &[(u32, Vec<u8>)]) -> Result<(), FixError> {
+        let mut nested_bits = vec![];
+        let mut nested = vec![];
+        self.encode(&mut nested, &mut nested_bits, instructions, fields)?;
+        if self.templates.needs_pmap(instructions)? {
+            write_pmap(out, &nested_bits);
+        }
+        out.extend(nested);
+        Ok(())
+    }
+}
+
+//message builds a Message of decoded fields, MsgType must be one of them. The header fields come first and a
+//FIX.5.0 or later version is carried by FIXT.1.1 with its ApplVerID.
+fn message(begin_string: &str, fields: &[(u32, Vec<u8>)]) -> Result<Message, FixError> {
+    let get = |tag: Tags| fields.iter().find(|field| field.0 == tag.to_num()).map(|field| String::from_utf8_lossy(&field.1).into_owned());
+    let msg_type = get(Tags::MsgType).ok_or_else(|| fast_error("the template has no MessageType"))?;
+    let begin_string = get(Tags::BeginString).unwrap_or_else(|| begin_string.to_string());
+
+    let mut raw = vec![];
+    {
+        let mut writer = if begin_string.starts_with(BEGIN_STRING_FIX50) {
+            let mut writer = MessageWriter::new(&mut raw, BEGIN_STRING_FIXT11, &msg_type);
+            if get(Tags::ApplVerID).is_none() {
+                writer.write_str(Tags::ApplVerID.to_num(), begin_string_appl_ver_id(&begin_string));
+            }
+            writer
+        } else {
+            MessageWriter::new(&mut raw, &begin_string, &msg_type)
+        };
+        let skipped = [Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::MsgType.to_num(), Tags::CheckSum.to_num()];
+        let fields: Vec<&(u32, Vec<u8>)> = fields.iter().filter(|field| !skipped.contains(&field.0)).collect();
+        for field in fields.iter().filter(|field| field.0.is_header()) {
+            writer.write_bytes(field.0, &field.1);
+        }
+        for field in fields.iter().filter(|field| !field.0.is_header()) {
+            writer.write_bytes(field.0, &field.1);
+        }
+        writer.finish();
+    }
+    Message::parse(&raw)
+}
+
+//FastDecoder decodes a stream of FAST messages, its dictionaries carry the previous values from one message to
+//the next until reset
+pub struct FastDecoder {
+    templates: FastTemplates,
+    dictionary: Dictionary,
+    template_id: Option<u32>,
+    begin_string: String
+}
+
+impl FastDecoder {
+
+    //new decodes messages as FIX.5.0SP2 unless their template gives the BeginString
+    pub fn new(templates: FastTemplates) -> FastDecoder {
+        FastDecoder{templates: templates, dictionary: HashMap::new(), template_id: None, begin_string: BEGIN_STRING_FIX50SP2.to_string()}
+    }
+
+    pub fn with_begin_string(mut self, begin_string: &str) -> FastDecoder {
+        self.begin_string = begin_string.to_string();
+        self
+    }
+
+    pub fn templates(&self) -> &FastTemplates {
+        &self.templates
+    }
+
+    //reset empties the dictionaries, as done by a FAST reset message or at the start of a feed packet
+    pub fn reset(&mut self) {
+        self.dictionary.clear();
+        self.template_id = None;
+    }
+
+    //decode reads the message at the start of buf and gives the number of bytes it took
+    pub fn decode(&mut self, buf: &[u8]) -> Result<(Message, usize), FixError> {
+        let mut reader = Reader{buf: buf, pos: 0};
+        let mut pmap = reader.pmap()?;
+        //the template identifier is a copy operator
+        if pmap.next() {
+            self.template_id = Some(reader.uint()? as u32);
+        }
+        let template_id = self.template_id.ok_or_else(|| fast_error("[ERR D5] no template identifier"))?;
+        let template = self.templates.template(template_id).ok_or_else(|| fast_error(&format!("[ERR D9] unknown template {}", template_id)))?;
+
+        let mut fields = vec![];
+        {
+            let mut context = Context{templates: &self.templates, template: template, dictionary: &mut self.dictionary};
+            context.decode(&mut reader, &mut pmap, &template.instructions, &mut fields)?;
+        }
+        Ok((message(&self.begin_string, &fields)?, reader.pos))
+    }
+}
+
+//FastEncoder encodes messages with a template, mainly to feed decoders under test
+pub struct FastEncoder {
+    templates: FastTemplates,
+    dictionary: Dictionary,
+    template_id: Option<u32>
+}
+
+impl FastEncoder {
+
+    pub fn new(templates: FastTemplates) -> FastEncoder {
+        FastEncoder{templates: templates, dictionary: HashMap::new(), template_id: None}
+    }
+
+    pub fn reset(&mut self) {
+        self.dictionary.clear();
+        self.template_id = None;
+    }
+
+    pub fn encode(&mut self, template_id: u32, msg: &Message) -> Result<Vec<u8>, FixError> {
+        let template = self.templates.template(template_id).ok_or_else(|| fast_error(&format!("unknown template {}", template_id)))?;
+        let fields: Vec<(u32, Vec<u8>)> = if msg.fields().is_empty() {
+            msg.header.iter().chain(msg.body.iter()).chain(msg.trailer.iter()).map(|(tag, value)| (tag, value.to_vec())).collect()
+        } else {
+            msg.fields().iter().map(|field| (field.tag(), field.value().to_vec())).collect()
+        };
+        let skipped = [Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::CheckSum.to_num()];
+        let fields: Vec<(u32, Vec<u8>)> = fields.into_iter().filter(|field| !skipped.contains(&field.0)).collect();
+
+        let mut bits = vec![];
+        let mut body = vec![];
+        if self.template_id == Some(template_id) {
+            bits.push(false);
+        } else {
+            bits.push(true);
+            write_uint(&mut body, template_id as u64);
+        }
+        {
+            let mut context = Context{templates: &self.templates, template: template, dictionary: &mut self.dictionary};
+            context.encode(&mut body, &mut bits, &template.instructions, &fields)?;
+        }
+        self.template_id = Some(template_id);
+
+        let mut out = vec![];
+        write_pmap(&mut out, &bits);
+        out.extend(body);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static TEMPLATES: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<templates xmlns="http://www.fixprotocol.org/ns/fast/td/1.1">
+  <template name="Heartbeat" id="1">
+    <string name="MessageType" id="35"><constant value="0"/></string>
+    <uInt32 name="MsgSeqNum" id="34"><increment/></uInt32>
+  </template>
+  <template name="MDIncRefresh" id="2" dictionary="template">
+    <string name="MessageType" id="35"><constant value="X"/></string>
+    <string name="ApplVerID" id="1128"><constant value="9"/></string>
+    <uInt32 name="MsgSeqNum" id="34"><increment/></uInt32>
+    <uInt64 name="SendingTime" id="52"><delta/></uInt64>
+    <sequence name="MDEntries">
+      <length name="NoMDEntries" id="268"/>
+      <uInt32 name="MDUpdateAction" id="279"><copy value="0"/></uInt32>
+      <decimal name="MDEntryPx" id="270" presence="optional">
+        <exponent><copy value="-2"/></exponent>
+        <mantissa><delta/></mantissa>
+      </decimal>
+      <string name="Symbol" id="55"><copy/></string>
+      <string name="MDEntryID" id="278" presence="optional"><tail/></string>
+      <int32 name="MDEntrySize" id="271" presence="optional"><default/></int32>
+      <group name="Instrument" presence="optional">
+        <string name="SecurityID" id="48"><delta/></string>
+      </group>
+    </sequence>
+  </template>
+</templates>"#;
+
+    fn templates() -> FastTemplates {
+        FastTemplates::parse(TEMPLATES).unwrap()
+    }
+
+    fn msg(fields: &str) -> Message {
+        Message::parse(format!("8=FIXT.1.1\x019=0\x01{}10=000\x01", fields.replace("|", "\x01")).as_bytes()).unwrap()
+    }
+
+    fn fields(msg: &Message) -> String {
+        String::from_utf8_lossy(msg.raw_message()).replace("\x01", "|")
+    }
+
+    #[test]
+    fn integer_test() {
+        let encode_uint = |value: u64| { let mut out = vec![]; write_uint(&mut out, value); out };
+        let encode_int = |value: i64| { let mut out = vec![]; write_int(&mut out, value); out };
+        assert_eq!(vec![0x39, 0x45, 0xa3], encode_uint(942755));
+        assert_eq!(vec![0x46, 0x3a, 0xdd], encode_int(-942755));
+        assert_eq!(vec![0x00, 0xc0], encode_int(64));
+        assert_eq!(vec![0x00, 0x40, 0x81], encode_int(8193));
+        assert_eq!(vec![0x7f, 0x3f, 0xff], encode_int(-8193));
+
+        for value in [0, 1, 63, 64, -64, -65, 942755, -942755, i64::MAX, i64::MIN].iter() {
+            let buf = encode_int(*value);
+            assert_eq!(*value, Reader{buf: &buf, pos: 0}.int().unwrap());
+        }
+        let buf = encode_uint(u64::MAX);
+        assert_eq!(u64::MAX, Reader{buf: &buf, pos: 0}.uint().unwrap());
+
+        let mut out = vec![];
+        write_uint_or_null(&mut out, None, true);
+        write_uint_or_null(&mut out, Some(0), true);
+        write_int_or_null(&mut out, Some(-1), true);
+        assert_eq!(vec![0x80, 0x81, 0xff], out);
+        let mut reader = Reader{buf: &out, pos: 0};
+        assert_eq!(None, reader.nullable_uint().unwrap());
+        assert_eq!(Some(0), reader.nullable_uint().unwrap());
+        assert_eq!(Some(-1), reader.nullable_int().unwrap());
+        assert!(Reader{buf: &[0x01], pos: 0}.uint().is_err());
+    }
+
+    #[test]
+    fn string_test() {
+        let encode = |value: Option<&[u8]>, nullable: bool| { let mut out = vec![]; write_ascii(&mut out, value, nullable).unwrap(); out };
+        assert_eq!(vec![0x41, 0x42, 0xc3], encode(Some(b"ABC"), false));
+        assert_eq!(vec![0x80], encode(Some(b""), false));
+        assert_eq!(vec![0x00, 0x80], encode(Some(b"\x00"), false));
+        assert_eq!(vec![0x80], encode(None, true));
+        assert_eq!(vec![0x00, 0x80], encode(Some(b""), true));
+        assert_eq!(vec![0x00, 0x00, 0x80], encode(Some(b"\x00"), true));
+
+        for &(value, nullable) in [(Some(&b"ABC"[..]), false), (Some(&b""[..]), false), (Some(&b"\x00"[..]), false),
+                                   (None, true), (Some(&b""[..]), true), (Some(&b"\x00"[..]), true)].iter() {
+            let buf = encode(value, nullable);
+            assert_eq!(value.map(|v| v.to_vec()), Reader{buf: &buf, pos: 0}.ascii(nullable).unwrap());
+        }
+    }
+
+    #[test]
+    fn pmap_test() {
+        let mut out = vec![];
+        write_pmap(&mut out, &[true, false, true, false, false, false, false, false, true, false]);
+        assert_eq!(vec![0x50, 0xa0], out);
+        let mut pmap = Reader{buf: &out, pos: 0}.pmap().unwrap();
+        assert!(pmap.next() && !pmap.next() && pmap.next());
+
+        let mut out = vec![];
+        write_pmap(&mut out, &[false, false]);
+        assert_eq!(vec![0x80], out);
+    }
+
+    #[test]
+    fn delta_and_tail_test() {
+        let base = Value::Bytes(b"GEH6".to_vec());
+        assert_eq!(Value::Bytes(b"GEM6".to_vec()), base.apply_delta(&Delta::Bytes(2, b"M6".to_vec())).unwrap());
+        assert_eq!(Value::Bytes(b"ESH6".to_vec()), base.apply_delta(&Delta::Bytes(-3, b"ES".to_vec())).unwrap());
+        assert!(base.apply_delta(&Delta::Bytes(5, vec![])).is_err());
+        assert!(base.apply_delta(&Delta::Bytes(i64::MIN, vec![])).is_err());
+        assert!(base.apply_delta(&Delta::Bytes(i64::MAX, vec![])).is_err());
+        assert!(Value::Int(i64::MAX).apply_delta(&Delta::Int(1)).is_err());
+        assert!(Value::Decimal(2, 1).apply_delta(&Delta::Decimal(i32::MAX, 0)).is_err());
+        assert!(Value::Decimal(2, i64::MAX).apply_delta(&Delta::Decimal(0, 1)).is_err());
+        for value in [&b"GEM6"[..], b"ESH6", b"GE", b"GEH6X", b""].iter() {
+            let value = Value::Bytes(value.to_vec());
+            assert_eq!(value, base.apply_delta(&value.delta(&base).unwrap()).unwrap());
+        }
+
+        assert_eq!(b"GEM6".to_vec(), apply_tail(b"GEH6", b"M6"));
+        assert_eq!(b"ABCDE".to_vec(), apply_tail(b"GEH6", b"ABCDE"));
+        assert_eq!(b"M6".to_vec(), tail(b"GEH6", b"GEM6").unwrap());
+        assert!(tail(b"GEH6", b"GE").is_err());
+    }
+
+    #[test]
+    fn decode_test() {
+        //template 1 and MsgSeqNum 5, then the same template and MsgSeqNum incremented
+        let mut decoder = FastDecoder::new(templates()).with_begin_string("FIX.4.4");
+        let buf = [0xe0, 0x81, 0x85, 0x80];
+        let (msg, length) = decoder.decode(&buf).unwrap();
+        assert_eq!(3, length);
+        assert_eq!("8=FIX.4.4|9=10|35=0|34=5|10=169|", fields(&msg));
+        let (msg, length) = decoder.decode(&buf[3..]).unwrap();
+        assert_eq!(1, length);
+        assert_eq!("6", msg.header.get_string(34).unwrap());
+
+        decoder.reset();
+        assert!(decoder.decode(&buf[3..]).is_err());
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let mut encoder = FastEncoder::new(templates());
+        let mut decoder = FastDecoder::new(templates());
+        let messages = [
+            "35=X|1128=9|34=1|52=20161012100501005|268=2|279=0|270=52.10|55=MSFT|278=ORD1|271=100|48=US5949181045|\
+279=0|270=52.12|55=MSFT|278=ORD2|48=US5949181046|",
+            "35=X|1128=9|34=2|52=20161012100501010|268=1|279=1|270=52.12|55=MSFT|278=ORD2|271=200|",
+            "35=X|1128=9|34=3|52=20161012100501011|268=1|279=2|55=IBM|"
+        ];
+
+        let mut stream = vec![];
+        for fields in messages.iter() {
+            stream.extend(encoder.encode(2, &msg(fields)).unwrap());
+        }
+        let mut pos = 0;
+        for expected in messages.iter() {
+            let (decoded, length) = decoder.decode(&stream[pos..]).unwrap();
+            pos += length;
+            //the fields between BodyLength and CheckSum
+            let decoded = fields(&decoded);
+            let start = decoded.find("35=").unwrap();
+            let end = decoded.rfind("10=").unwrap();
+            assert_eq!(*expected, &decoded[start..end]);
+        }
+        assert_eq!(stream.len(), pos);
+    }
+
+    #[test]
+    fn operators_save_bytes_test() {
+        let mut encoder = FastEncoder::new(templates());
+        let first = encoder.encode(2, &msg("35=X|1128=9|34=1|52=20161012100501005|268=1|279=0|270=52.10|55=MSFT|")).unwrap();
+        let second = encoder.encode(2, &msg("35=X|1128=9|34=2|52=20161012100501006|268=1|279=0|270=52.11|55=MSFT|")).unwrap();
+        //pmap, SendingTime delta, NoMDEntries, element pmap and the MDEntryPx mantissa delta
+        assert_eq!(vec![0x80, 0x81, 0x81, 0x80, 0x81], second);
+        assert!(first.len() > second.len());
+    }
+
+    #[test]
+    fn decimal_delta_overflow_test() {
+        let templates = FastTemplates::parse(r#"<templates xmlns="http://www.fixprotocol.org/ns/fast/td/1.1">
+  <template name="Quote" id="3">
+    <string name="MessageType" id="35"><constant value="S"/></string>
+    <decimal name="BidPx" id="132"><delta/></decimal>
+  </template>
+</templates>"#).unwrap();
+        let mut decoder = FastDecoder::new(templates);
+        //template 3 and BidPx 100, exponent delta 2 and mantissa delta 1
+        let (msg, _) = decoder.decode(&[0xc0, 0x83, 0x82, 0x81]).unwrap();
+        assert_eq!("100", msg.body.get_string(132).unwrap());
+        //the same template with an exponent delta of i32::MAX
+        assert!(decoder.decode(&[0x80, 0x07, 0x7f, 0x7f, 0x7f, 0xff, 0x80]).is_err());
+        //an exponent delta beyond an i32
+        assert!(decoder.decode(&[0x80, 0x0f, 0x7f, 0x7f, 0x7f, 0xff, 0x80]).is_err());
+    }
+
+    #[test]
+    fn error_test() {
+        assert!(FastTemplates::parse("<templates><template name=\"T\" id=\"1\"><string name=\"S\"><increment/></string></template></templates>").is_err());
+        assert!(FastTemplates::parse("<templates><template name=\"T\" id=\"1\"><uInt32 name=\"S\"><constant/></uInt32></template></templates>").is_err());
+
+        let mut decoder = FastDecoder::new(templates());
+        //unknown template, truncated message
+        assert!(decoder.decode(&[0xc0, 0x89]).is_err());
+        assert!(decoder.decode(&[0xe0, 0x81]).is_err());
+
+        let mut encoder = FastEncoder::new(templates());
+        assert!(encoder.encode(2, &msg("35=X|1128=9|34=1|")).is_err());
+        assert!(encoder.encode(2, &msg("35=Y|1128=9|34=1|52=1|")).is_err());
+        assert!(encoder.encode(3, &msg("35=X|")).is_err());
+    }
+}
@@ -9,29 +9,82 @@ use fix_boolean::*;
 use fix_int::*;
 use time::*;
 use fix_utc_timestamp::*;
+use pretty::{FieldNames, TagNames, escape_data};
 
+//Field is a tag=value pair or, for the NumInGroup field of a repeating group, the count followed by the
+//instances of the group
+#[derive(Clone, Debug, PartialEq)]
 pub struct Field {
-     field: Vec<TagValue>
+     field: Vec<TagValue>,
+     group: Vec<FieldMap>
 }
 
 impl Field {
 
-    fn new() -> Field {
-        Field{field:vec![TagValue::empty()]}
+    pub fn new() -> Field {
+        Field{field:vec![TagValue::empty()], group: vec![]}
     }
 
-    fn field_tag(&self) -> u32 {
+    pub fn from_tag_value(tag_value: TagValue) -> Field {
+        Field{field:vec![tag_value], group: vec![]}
+    }
+
+    //from_group creates the NumInGroup field tag counting instances
+    pub fn from_group(tag: u32, instances: Vec<FieldMap>) -> Field {
+        let mut count = [0u8; MAX_INT_LEN];
+        let count = format_uint(instances.len() as u64, &mut count);
+        Field{field: vec![TagValue::new(tag, count)], group: instances}
+    }
+
+    pub fn field_tag(&self) -> u32 {
         self.field[0].tag()
     }
 
-    fn init_field(&mut self, tag:u32, value:&[u8])
+    pub fn tag_values(&self) -> &[TagValue] {
+        self.field.as_ref()
+    }
+
+    //instances are the instances of the repeating group this field counts, empty for other fields
+    pub fn instances(&self) -> &[FieldMap] {
+        self.group.as_ref()
+    }
+
+    pub fn is_group(&self) -> bool {
+        !self.group.is_empty()
+    }
+
+    //init_field gives the field a plain value, any group it held is dropped
+    pub fn init_field(&mut self, tag:u32, value:&[u8])
     {
         self.field[0].init(tag, value);
+        self.group.clear();
+    }
+
+    //flatten appends the tag=value pairs of the field, those of its group instances included, in the order they are written
+    pub fn flatten<'a>(&'a self, out: &mut Vec<(u32, &'a [u8])>) {
+        for tag_value in self.field.iter() {
+            out.push((tag_value.tag(), tag_value.value()));
+        }
+        for instance in self.group.iter() {
+            for tag in instance.tag_sort.tags.iter() {
+                if let Some(field) = instance.tag_lookup.get(tag) {
+                    field.flatten(out);
+                }
+            }
+        }
     }
 }
 
-type TagOrder = fn(i:u32, j:u32) -> bool;
+pub type TagOrder = fn(i:u32, j:u32) -> bool;
 
+//MergePolicy decides which value wins when both FieldMaps of a merge carry the same tag
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MergePolicy {
+    Overwrite,
+    KeepExisting
+}
+
+#[derive(Clone)]
 pub struct TagSort {
     tags: Vec<u32>,
     compare: TagOrder
@@ -50,6 +103,53 @@ impl TagSort {
     fn less(&self, i:usize, j:usize) -> bool { self.tags[i].eq(&self.tags[j]) }
 }
 
+//FieldMapIter walks the (tag, value) pairs of a FieldMap without collecting them, see FieldMap::iter
+pub struct FieldMapIter<'a> {
+    root: Frame<'a>,
+    //nested holds the groups being walked, it stays unallocated for a FieldMap without groups
+    nested: Vec<Frame<'a>>
+}
+
+enum Frame<'a> {
+    Fields(&'a FieldMap, slice::Iter<'a, u32>),
+    Instances(slice::Iter<'a, FieldMap>)
+}
+
+impl<'a> Iterator for FieldMapIter<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<(u32, &'a [u8])> {
+        loop {
+            let frame = match self.nested.last_mut() {
+                Some(frame) => frame,
+                None => &mut self.root
+            };
+            let next = match *frame {
+                Frame::Fields(map, ref mut tags) => match tags.next() {
+                    Some(tag) => match map.tag_lookup.get(tag) {
+                        Some(field) => Some(Ok(field)),
+                        None => continue
+                    },
+                    None => None
+                },
+                Frame::Instances(ref mut instances) => instances.next().map(Err)
+            };
+            match next {
+                Some(Ok(field)) => {
+                    if field.is_group() {
+                        self.nested.push(Frame::Instances(field.group.iter()));
+                    }
+                    return Some((field.field[0].tag(), field.field[0].value()));
+                },
+                Some(Err(instance)) => self.nested.push(Frame::Fields(instance, instance.tag_sort.tags.iter())),
+                //the root has nothing left once no group is being walked
+                None => { self.nested.pop()?; }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct FieldMap {
     tag_lookup: HashMap<u32, Field>,
     tag_sort: TagSort
@@ -58,44 +158,152 @@ pub struct FieldMap {
 impl FieldMap {
 
     // ascending tags
-    fn normal_field_order(i:u32, j:u32) -> bool { i < j }
+    pub fn normal_field_order(i:u32, j:u32) -> bool { i < j }
+
+    // the order fields were added in, group instances use it so that their delimiter stays first
+    pub fn received_field_order(_i:u32, _j:u32) -> bool { false }
+
+    // BeginString, BodyLength and MsgType lead the header, the rest is ascending
+    pub fn header_field_order(i:u32, j:u32) -> bool {
+        fn rank(tag:u32) -> u32 {
+            match Tags::from_number(tag) {
+                Some(Tags::BeginString) => 1,
+                Some(Tags::BodyLength) => 2,
+                Some(Tags::MsgType) => 3,
+                _ => 4
+            }
+        }
+        let (rank_i, rank_j) = (rank(i), rank(j));
+        if rank_i != rank_j {
+            return rank_i < rank_j;
+        }
+        i < j
+    }
+
+    // Signature follows its SignatureLength and CheckSum closes the trailer, the rest is ascending
+    pub fn trailer_field_order(i:u32, j:u32) -> bool {
+        let rank = |tag: u32| match tag {
+            t if t == Tags::SignatureLength.to_num() => 1,
+            t if t == Tags::Signature.to_num() => 2,
+            t if t == Tags::CheckSum.to_num() => 3,
+            _ => 0
+        };
+        if rank(i) != rank(j) {
+            return rank(i) < rank(j);
+        }
+        i < j
+    }
 
-    fn new() -> FieldMap {
+    pub fn new() -> FieldMap {
         FieldMap{tag_lookup:HashMap::new(),tag_sort: TagSort{tags: vec![], compare: FieldMap::normal_field_order }}
     }
 
-    fn init(&mut self) {
+    pub fn new_with_ordering(ordering:TagOrder) -> FieldMap {
+        FieldMap{tag_lookup:HashMap::new(),tag_sort: TagSort{tags: vec![], compare: ordering }}
+    }
+
+    pub fn init(&mut self) {
         self.init_with_ordering(FieldMap::normal_field_order);
     }
 
-    fn init_with_ordering(&mut self, ordering:TagOrder) {
+    pub fn init_with_ordering(&mut self, ordering:TagOrder) {
       self.tag_lookup = HashMap::new();
+      self.tag_sort.tags.clear();
       self.tag_sort.compare = ordering;
     }
 
-    fn tags(&self) -> Vec<&Field> {
-        let mut fields:Vec<&Field> = vec![];
-        for field in self.tag_lookup.values() {
-            fields.push(field);
+    pub fn tags(&self) -> Vec<&Field> {
+        self.tag_sort.tags.iter().filter_map(|tag| self.tag_lookup.get(tag)).collect()
+    }
+
+    //iter walks (tag, value) pairs according to the ordering of this FieldMap, a repeating group is
+    //followed by the fields of its instances
+    pub fn iter(&self) -> FieldMapIter<'_> {
+        FieldMapIter{root: Frame::Fields(self, self.tag_sort.tags.iter()), nested: vec![]}
+    }
+
+    pub fn len(&self) -> usize {
+        self.tag_lookup.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tag_lookup.is_empty()
+    }
+
+    pub fn has(&self, tag:u32) -> bool {
+        self.tag_lookup.contains_key(&tag)
+    }
+
+    pub fn remove(&mut self, tag:u32) -> bool {
+        if self.tag_lookup.remove(&tag).is_none() {
+            return false;
+        }
+        self.tag_sort.tags.retain(|&t| t != tag);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.tag_lookup.clear();
+        self.tag_sort.tags.clear();
+    }
+
+    //copy_into replaces the content and the ordering of to with a copy of this FieldMap
+    pub fn copy_into(&self, to: &mut FieldMap) {
+        to.tag_lookup = self.tag_lookup.clone();
+        to.tag_sort.tags = self.tag_sort.tags.clone();
+        to.tag_sort.compare = self.tag_sort.compare;
+    }
+
+    //merge adds every field of other to this FieldMap, policy settles the tags present in both
+    pub fn merge(&mut self, other: &FieldMap, policy: MergePolicy) {
+        for &tag in other.tag_sort.tags.iter() {
+            if policy == MergePolicy::KeepExisting && self.has(tag) {
+                continue;
+            }
+            if let Some(field) = other.tag_lookup.get(&tag) {
+                self.add(field.clone());
+            }
         }
-        return fields;
     }
 
-    fn add(&mut self, f:Field) {
+    //sorted_tags lists the tags of this FieldMap according to its ordering
+    pub fn sorted_tags(&self) -> &[u32] {
+        &self.tag_sort.tags
+    }
+
+    //insert_tag keeps the tags sorted as they are added, a tag goes after those it does not sort before so that
+    //equal tags and received_field_order keep the order they were added in
+    fn insert_tag(&mut self, tag:u32) {
+        let compare = self.tag_sort.compare;
+        let pos = self.tag_sort.tags.iter().position(|&t| compare(tag, t)).unwrap_or(self.tag_sort.tags.len());
+        self.tag_sort.tags.insert(pos, tag);
+    }
+
+    pub fn add(&mut self, f:Field) {
         let tag = f.field_tag();
         if !self.tag_lookup.contains_key(&tag) {
             self.tag_lookup.insert(tag, f);
-            self.tag_sort.tags.push(tag);
+            self.insert_tag(tag);
         } else {
             *self.tag_lookup.entry(tag).or_insert(Field::new()) = f;
         }
     }
 
-    fn lookup_field(&self, tag:u32) -> Option<&Field> {
+    //try_add is add without the silent overwrite, a repeated tag is a TagAppearsMoreThanOnce reject
+    pub fn try_add(&mut self, f:Field) -> Result<(), MessageRejectError> {
+        let tag = f.field_tag();
+        if self.tag_lookup.contains_key(&tag) {
+            return Err(MessageRejectError::tag_appears_more_than_once(tag));
+        }
+        self.add(f);
+        Ok(())
+    }
+
+    pub fn lookup_field(&self, tag:u32) -> Option<&Field> {
         self.tag_lookup.get(&tag)
     }
 
-    fn get_field<T>(&self, tag:u32,  parser: &mut T)-> Result<(),MessageRejectError> where T: FieldValueReader {
+    pub fn get_field<T>(&self, tag:u32,  parser: &mut T)-> Result<(),MessageRejectError> where T: FieldValueReader {
         let mut field = match self.tag_lookup.get(&tag) {
             Some(f) => f,
             None => return Err(MessageRejectError::conditionally_required_field_missing(tag))
@@ -107,7 +315,29 @@ impl FieldMap {
         }
     }
 
-    fn get_bytes(&self, tag:u32) -> Result<&[u8] ,MessageRejectError> {
+    //set_group replaces tag with a repeating group of instances, whose fields are written in the order
+    //they were added when they use received_field_order
+    pub fn set_group(&mut self, tag:u32, instances: Vec<FieldMap>) {
+        if instances.is_empty() {
+            self.set_int(tag, 0);
+            return;
+        }
+        self.add(Field::from_group(tag, instances));
+    }
+
+    //get_group gives the instances of the repeating group counted by tag
+    pub fn get_group(&self, tag:u32) -> Result<&[FieldMap], MessageRejectError> {
+        let field = match self.tag_lookup.get(&tag) {
+            Some(f) => f,
+            None => return Err(MessageRejectError::conditionally_required_field_missing(tag))
+        };
+        if !field.is_group() && self.get_int(tag)? != 0 {
+            return Err(MessageRejectError::incorrect_num_in_group_count_for_repeating_group(tag));
+        }
+        Ok(field.instances())
+    }
+
+    pub fn get_bytes(&self, tag:u32) -> Result<&[u8] ,MessageRejectError> {
         if !self.tag_lookup.contains_key(&tag) {
             return Err(MessageRejectError::conditionally_required_field_missing(tag));
         }
@@ -118,42 +348,42 @@ impl FieldMap {
     {
         if !self.tag_lookup.contains_key(&tag) {
             self.tag_lookup.insert(tag, Field::new());
-            self.tag_sort.tags.push(tag);
+            self.insert_tag(tag);
         }
         self.tag_lookup.get_mut(&tag).unwrap()
     }
 
-    fn set_field<T:FieldValueWriter>(&mut self, tag:u32, field: T) {
+    pub fn set_field<T:FieldValueWriter>(&mut self, tag:u32, field: T) {
         self.set_bytes(tag, field.write().as_ref())
     }
 
-    fn set_bytes(&mut self, tag:u32, value: &[u8]) {
+    pub fn set_bytes(&mut self, tag:u32, value: &[u8]) {
         let mut f = self.get_or_create(tag);
         f.init_field(tag, value);
     }
 
-    fn set_int(&mut self, tag:u32, value:i32) {
+    pub fn set_int(&mut self, tag:u32, value:i32) {
         self.set_bytes(tag, value.write().as_ref());
     }
 
-    fn set_bool(&mut self, tag:u32, value:bool) {
+    pub fn set_bool(&mut self, tag:u32, value:bool) {
         self.set_bytes(tag, value.write().as_ref());
     }
 
-    fn set_time(&mut self, tag:u32, value:Tm) {
+    pub fn set_time(&mut self, tag:u32, value:Tm) {
         self.set_bytes(tag, FIXUTCTimestamp::new(value).write().as_ref());
     }
 
-    fn set_string(&mut self, tag:u32, value:&str) {
+    pub fn set_string(&mut self, tag:u32, value:&str) {
         self.set_bytes(tag, value.as_ref());
     }
 
     //Get parses out a field in this FieldMap. Returned reject may indicate the field is not present, or the field value is invalid.
-    fn get<T>(&self, parser: &mut T) -> Result<(),MessageRejectError> where T: FieldInterface {
+    pub fn get<T>(&self, parser: &mut T) -> Result<(),MessageRejectError> where T: FieldInterface {
         return self.get_field(parser.tag(), parser)
     }
 
-    fn get_string(&self, tag:u32) -> Result<String, MessageRejectError> {
+    pub fn get_string(&self, tag:u32) -> Result<String, MessageRejectError> {
         let mut value = String::new();
         {
             let value_mutable = &mut value;
@@ -165,7 +395,7 @@ impl FieldMap {
         Ok(value.into())
     }
 
-    fn get_bool(&self, tag:u32) -> Result<bool, MessageRejectError> {
+    pub fn get_bool(&self, tag:u32) -> Result<bool, MessageRejectError> {
         let mut value = false;
         {
             let value_mutable = &mut value;
@@ -177,7 +407,7 @@ impl FieldMap {
         Ok(value.into())
     }
 
-    fn get_int(&self, tag:u32) -> Result<i32, MessageRejectError> {
+    pub fn get_int(&self, tag:u32) -> Result<i32, MessageRejectError> {
         let mut value: i32 = 0;
         {
             let value_mutable = &mut value;
@@ -189,7 +419,7 @@ impl FieldMap {
         Ok(value)
     }
 
-    fn get_time(&self, tag:u32) -> Result<Tm, MessageRejectError> {
+    pub fn get_time(&self, tag:u32) -> Result<Tm, MessageRejectError> {
         let mut value = FIXUTCTimestamp::empty();
         {
             let value_mutable = &mut value;
@@ -202,6 +432,62 @@ impl FieldMap {
     }
 }
 
+impl Default for Field {
+    fn default() -> Field {
+        Field::new()
+    }
+}
+
+impl Default for FieldMap {
+    fn default() -> FieldMap {
+        FieldMap::new()
+    }
+}
+
+//two FieldMaps are equal when they hold the same fields, whatever the order they were added in
+impl PartialEq for FieldMap {
+    fn eq(&self, other: &FieldMap) -> bool {
+        self.tag_lookup == other.tag_lookup
+    }
+}
+
+impl fmt::Debug for FieldMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.tags()).finish()
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut fields = vec![];
+        self.flatten(&mut fields);
+        for (i, &(tag, value)) in fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            if TagNames.is_data(tag) {
+                write!(f, "{}={}", tag, escape_data(value))?;
+            } else {
+                write!(f, "{}={}", tag, String::from_utf8_lossy(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//FieldMap renders as tag=value pairs separated by '|', e.g. 8=FIX.4.2|35=D
+impl fmt::Display for FieldMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, field) in self.tags().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", field)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::*;
@@ -209,23 +495,33 @@ mod test {
     use tag_value::*;
     use tag::*;
     use time::*;
+    use error::Reject;
+    use error::MessageRejectError as MessageRejectErrorTrait;
 
     #[test]
     fn add_test() {
         let mut field_map = FieldMap::new();
         let expected_value= "blahblah".as_bytes();
         let tag_value = TagValue::new(Tags::BeginString.into(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         let field_count = field_map.tags().len();
         assert_eq!(1, field_count);
     }
 
+    #[test]
+    fn field_default_test() {
+        let mut field = Field::default();
+        assert_eq!(0, field.field_tag());
+        field.init_field(Tags::Text.to_num(), b"MSFT");
+        assert_eq!("58=MSFT", field.to_string());
+    }
+
     #[test]
     fn get_string_test() {
         let mut field_map = FieldMap::new();
         let expected_value= "blahblah".as_bytes();
         let tag_value = TagValue::new(Tags::BeginString.into(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         assert_eq!("blahblah", field_map.get_string(Tags::BeginString.into()).unwrap());
     }
 
@@ -234,7 +530,7 @@ mod test {
         let mut field_map = FieldMap::new();
         let expected_value= "Y".as_bytes();
         let tag_value = TagValue::new(Tags::PossDupFlag.into(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         assert_eq!(true, field_map.get_bool(Tags::PossDupFlag.into()).unwrap());
     }
 
@@ -243,7 +539,7 @@ mod test {
         let mut field_map = FieldMap::new();
         let expected_value= "N".as_bytes();
         let tag_value = TagValue::new(Tags::PossDupFlag.to_num(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         assert_eq!(false, field_map.get_bool(Tags::PossDupFlag.to_num()).unwrap());
     }
 
@@ -252,7 +548,7 @@ mod test {
         let mut field_map = FieldMap::new();
         let expected_value= "11".as_bytes();
         let tag_value = TagValue::new(Tags::MsgSeqNum.to_num(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         assert_eq!(11, field_map.get_int(Tags::MsgSeqNum.to_num()).unwrap());
     }
 
@@ -266,7 +562,9 @@ mod test {
 
     #[test]
     fn set_time_test() {
-        let expected_value = now_utc();
+        //UTCTimestamp carries milliseconds
+        let mut expected_value = now_utc();
+        expected_value.tm_nsec -= expected_value.tm_nsec % 1_000_000;
         let mut field_map = FieldMap::new();
         field_map.set_time(Tags::SendingTime.to_num(),expected_value);
         assert_eq!(expected_value.to_timespec(), field_map.get_time(Tags::SendingTime.to_num()).unwrap().to_timespec());
@@ -277,7 +575,7 @@ mod test {
         let mut field_map = FieldMap::new();
         let expected_value= "-2".as_bytes();
         let tag_value = TagValue::new(Tags::MsgSeqNum.to_num(), expected_value);
-        field_map.add(Field{field: vec![tag_value]});
+        field_map.add(Field::from_tag_value(tag_value));
         assert_eq!(-2, field_map.get_int(Tags::MsgSeqNum.to_num()).unwrap());
     }
 
@@ -295,4 +593,142 @@ mod test {
         //assert!(field_map.get_string(Tag::BodyLength).is_err() == true);
         let result = field_map.get_string(Tags::BodyLength.to_num());
     }
+
+    #[test]
+    fn try_add_duplicate_test() {
+        let mut field_map = FieldMap::new();
+        assert!(field_map.try_add(Field::from_tag_value(TagValue::new(Tags::Text.to_num(), "a".as_bytes()))).is_ok());
+        let result = field_map.try_add(Field::from_tag_value(TagValue::new(Tags::Text.to_num(), "b".as_bytes())));
+        assert_eq!(Reject::TagAppearsMoreThanOnce, result.unwrap_err().reject_reason());
+        assert_eq!("a", field_map.get_string(Tags::Text.to_num()).unwrap());
+    }
+
+    #[test]
+    fn header_sorted_tags_test() {
+        let mut field_map = FieldMap::new_with_ordering(FieldMap::header_field_order);
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        field_map.set_string(Tags::MsgType.to_num(), "0");
+        field_map.set_int(Tags::MsgSeqNum.to_num(), 1);
+        field_map.set_string(Tags::BeginString.to_num(), "FIX.4.2");
+        assert_eq!(vec![8, 35, 34, 49], field_map.sorted_tags());
+    }
+
+    #[test]
+    fn remove_and_has_test() {
+        let mut field_map = FieldMap::new();
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        field_map.set_string(Tags::TargetCompID.to_num(), "T");
+        assert!(field_map.has(Tags::SenderCompID.to_num()));
+        assert!(field_map.remove(Tags::SenderCompID.to_num()));
+        assert!(!field_map.remove(Tags::SenderCompID.to_num()));
+        assert!(!field_map.has(Tags::SenderCompID.to_num()));
+        assert_eq!(vec![Tags::TargetCompID.to_num()], field_map.sorted_tags());
+
+        field_map.clear();
+        assert!(field_map.is_empty());
+    }
+
+    #[test]
+    fn iter_test() {
+        let mut field_map = FieldMap::new();
+        field_map.set_string(Tags::TargetCompID.to_num(), "T");
+        field_map.set_int(Tags::MsgSeqNum.to_num(), 3);
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        let fields: Vec<(u32, &[u8])> = field_map.iter().collect();
+        assert_eq!(vec![(34, "3".as_bytes()), (49, "S".as_bytes()), (56, "T".as_bytes())], fields);
+    }
+
+    #[test]
+    fn iter_group_test() {
+        let mut nested = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        nested.set_string(524, "P");
+        let mut leg = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        leg.set_string(600, "A");
+        leg.set_group(539, vec![nested.clone(), nested]);
+        let mut field_map = FieldMap::new();
+        field_map.set_string(58, "done");
+        field_map.set_group(555, vec![leg.clone(), leg]);
+        field_map.set_string(55, "X");
+
+        let fields: Vec<(u32, String)> = field_map.iter().map(|(tag, value)| (tag, String::from_utf8_lossy(value).into_owned())).collect();
+        let mut flattened = vec![];
+        for field in field_map.tags() {
+            field.flatten(&mut flattened);
+        }
+        assert_eq!(flattened.len(), fields.len());
+        let tags: Vec<u32> = fields.iter().map(|&(tag, _)| tag).collect();
+        assert_eq!(vec![55, 58, 555, 600, 539, 524, 524, 600, 539, 524, 524], tags);
+    }
+
+    #[test]
+    fn init_with_ordering_test() {
+        let mut field_map = FieldMap::new();
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        field_map.init_with_ordering(FieldMap::header_field_order);
+        assert!(field_map.sorted_tags().is_empty());
+        assert!(field_map.tags().is_empty());
+        assert_eq!(0, field_map.iter().count());
+
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        assert_eq!(1, field_map.tags().len());
+    }
+
+    #[test]
+    fn copy_into_test() {
+        let mut from = FieldMap::new_with_ordering(FieldMap::header_field_order);
+        from.set_string(Tags::SenderCompID.to_num(), "S");
+        from.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        let mut to = FieldMap::new();
+        to.set_string(Tags::Text.to_num(), "gone");
+        from.copy_into(&mut to);
+        assert!(!to.has(Tags::Text.to_num()));
+        assert_eq!(vec![8, 49], to.sorted_tags());
+    }
+
+    #[test]
+    fn merge_test() {
+        let mut other = FieldMap::new();
+        other.set_string(Tags::SenderCompID.to_num(), "NEW");
+        other.set_string(Tags::OnBehalfOfCompID.to_num(), "OBO");
+
+        let mut overwrite = FieldMap::new();
+        overwrite.set_string(Tags::SenderCompID.to_num(), "OLD");
+        overwrite.merge(&other, MergePolicy::Overwrite);
+        assert_eq!("NEW", overwrite.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("OBO", overwrite.get_string(Tags::OnBehalfOfCompID.to_num()).unwrap());
+
+        let mut keep = FieldMap::new();
+        keep.set_string(Tags::SenderCompID.to_num(), "OLD");
+        keep.merge(&other, MergePolicy::KeepExisting);
+        assert_eq!("OLD", keep.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("OBO", keep.get_string(Tags::OnBehalfOfCompID.to_num()).unwrap());
+    }
+
+    #[test]
+    fn display_test() {
+        let mut field_map = FieldMap::new();
+        field_map.set_string(Tags::TargetCompID.to_num(), "T");
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        assert_eq!("49=S|56=T", format!("{}", field_map));
+
+        field_map.set_int(Tags::SignatureLength.to_num(), 3);
+        field_map.set_bytes(Tags::Signature.to_num(), b"\x01|a");
+        assert_eq!("49=S|56=T|89=\\x01\\x7ca|93=3", format!("{}", field_map));
+    }
+
+    #[test]
+    fn eq_and_clone_test() {
+        let mut field_map = FieldMap::new();
+        field_map.set_string(Tags::SenderCompID.to_num(), "S");
+        field_map.set_string(Tags::TargetCompID.to_num(), "T");
+
+        let mut other = FieldMap::default();
+        other.set_string(Tags::TargetCompID.to_num(), "T");
+        other.set_string(Tags::SenderCompID.to_num(), "S");
+        assert_eq!(field_map, other);
+
+        let mut cloned = field_map.clone();
+        cloned.set_string(Tags::SenderCompID.to_num(), "X");
+        assert!(field_map != cloned);
+    }
 }
\ No newline at end of file
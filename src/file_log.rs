@@ -0,0 +1,351 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use error::FixError;
+use field::*;
+use fix_utc_timestamp::*;
+use log::*;
+use session_id::*;
+use settings::*;
+use time::*;
+
+//Rotation limits how large or how old the current log file may grow before it is rotated
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rotation {
+    //in bytes, 0 never rotates on size
+    max_size: u64,
+    interval: Option<Duration>,
+    backup_count: u32
+}
+
+impl Rotation {
+
+    fn none() -> Rotation {
+        Rotation{max_size: 0, interval: None, backup_count: 0}
+    }
+}
+
+//LogFile appends timestamped lines to <name>.current.log, rotated files are kept as <name>.1.log, <name>.2.log and so on
+struct LogFile {
+    dir: PathBuf,
+    name: String,
+    file: File,
+    size: u64,
+    opened: Tm,
+    rotation: Rotation,
+    //the first failure since take_error was last called
+    error: Option<FixError>
+}
+
+impl LogFile {
+
+    fn open(dir: &Path, name: &str, rotation: Rotation) -> Result<LogFile, FixError> {
+        let path = dir.join(format!("{}.current.log", name));
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(FixError::IoError)?;
+        let size = file.metadata().map_err(FixError::IoError)?.len();
+        Ok(LogFile{dir: dir.to_path_buf(), name: name.to_string(), file: file, size: size, opened: now_utc(), rotation: rotation, error: None})
+    }
+
+    fn current_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.current.log", self.name))
+    }
+
+    fn backup_path(&self, index: u32) -> PathBuf {
+        self.dir.join(format!("{}.{}.log", self.name, index))
+    }
+
+    //write appends text as one line, the bytes are written as they are so binary data fields survive. A failed
+    //rotation or write is kept for take_error as the Log interface can not return it
+    fn write(&mut self, now: Tm, text: &[u8]) {
+        let mut line = FIXUTCTimestamp::new(now).write();
+        line.extend_from_slice(b" : ");
+        line.extend_from_slice(text);
+        line.push(b'\n');
+
+        if self.rotation_due(now, line.len() as u64) {
+            if let Err(err) = self.rotate(now) {
+                self.fail(err);
+            }
+        }
+
+        match self.file.write_all(&line).and_then(|_| self.file.flush()) {
+            Ok(_) => self.size += line.len() as u64,
+            Err(err) => self.fail(FixError::IoError(err))
+        }
+    }
+
+    fn fail(&mut self, err: FixError) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+
+    fn rotation_due(&self, now: Tm, len: u64) -> bool {
+        if self.rotation.max_size > 0 && self.size > 0 && self.size + len > self.rotation.max_size {
+            return true;
+        }
+        match self.rotation.interval {
+            Some(interval) => now - self.opened >= interval,
+            None => false
+        }
+    }
+
+    fn rotate(&mut self, now: Tm) -> Result<(), FixError> {
+        //without backups there is nothing to archive, the current file is truncated below
+        let backup_count = self.rotation.backup_count;
+        if backup_count > 0 {
+            let oldest = self.backup_path(backup_count);
+            if oldest.exists() {
+                fs::remove_file(oldest).map_err(FixError::IoError)?;
+            }
+            for index in (1..backup_count).rev() {
+                let backup = self.backup_path(index);
+                if backup.exists() {
+                    fs::rename(backup, self.backup_path(index + 1)).map_err(FixError::IoError)?;
+                }
+            }
+            fs::rename(self.current_path(), self.backup_path(1)).map_err(FixError::IoError)?;
+        }
+
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(self.current_path()).map_err(FixError::IoError)?;
+        self.size = 0;
+        self.opened = now;
+        Ok(())
+    }
+}
+
+//FileLog writes QuickFIX compatible <prefix>.messages.current.log and <prefix>.event.current.log files,
+//incoming and outgoing messages share the messages file
+pub struct FileLog {
+    messages: LogFile,
+    events: LogFile
+}
+
+impl FileLog {
+
+    fn open(dir: &Path, prefix: &str, rotation: Rotation) -> Result<FileLog, FixError> {
+        fs::create_dir_all(dir).map_err(FixError::IoError)?;
+        Ok(FileLog{
+            messages: LogFile::open(dir, &format!("{}.messages", prefix), rotation)?,
+            events: LogFile::open(dir, &format!("{}.event", prefix), rotation)?
+        })
+    }
+}
+
+impl Log for FileLog {
+    fn on_incoming(&mut self, msg: &[u8]) {
+        self.messages.write(now_utc(), msg);
+    }
+
+    fn on_outgoing(&mut self, msg: &[u8]) {
+        self.messages.write(now_utc(), msg);
+    }
+
+    fn on_event(&mut self, text: &str) {
+        self.events.write(now_utc(), text.as_bytes());
+    }
+
+    fn take_error(&mut self) -> Option<FixError> {
+        self.messages.error.take().or_else(|| self.events.error.take())
+    }
+}
+
+//FileLogFactory creates FileLogs under the FileLogPath directory, files grow without bound
+pub struct FileLogFactory {
+    path: PathBuf,
+    rotation: Rotation
+}
+
+impl FileLogFactory {
+
+    pub fn new(settings: &SessionSettings) -> Result<FileLogFactory, FixError> {
+        Ok(FileLogFactory{path: PathBuf::from(settings.setting(FILE_LOG_PATH)?), rotation: Rotation::none()})
+    }
+}
+
+impl LogFactory for FileLogFactory {
+    fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(FileLog::open(&self.path, "GLOBAL", self.rotation)?))
+    }
+
+    fn create_session_log(&self, session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(FileLog::open(&self.path, &session_id.filename_prefix(), self.rotation)?))
+    }
+}
+
+//RotatingFileLogFactory creates FileLogs that rotate once FileLogMaxSize bytes or FileLogRotateInterval seconds are
+//reached, keeping FileLogBackupCount rotated files (10 by default, at least 1)
+pub struct RotatingFileLogFactory {
+    factory: FileLogFactory
+}
+
+impl RotatingFileLogFactory {
+
+    pub fn new(settings: &SessionSettings) -> Result<RotatingFileLogFactory, FixError> {
+        let max_size = settings.int_setting_or(FILE_LOG_MAX_SIZE, 0)?;
+        let interval = settings.int_setting_or(FILE_LOG_ROTATE_INTERVAL, 0)?;
+        let backup_count = settings.int_setting_or(FILE_LOG_BACKUP_COUNT, 10)?;
+        if max_size < 0 || interval < 0 || backup_count < 0 {
+            return Err(FixError::ConfigError(format!("{}, {} and {} must not be negative", FILE_LOG_MAX_SIZE, FILE_LOG_ROTATE_INTERVAL, FILE_LOG_BACKUP_COUNT)));
+        }
+        if backup_count == 0 {
+            return Err(FixError::ConfigError(format!("{} must be at least 1", FILE_LOG_BACKUP_COUNT)));
+        }
+        if max_size == 0 && interval == 0 {
+            return Err(FixError::ConfigError(format!("Conditionally Required Setting: {} or {}", FILE_LOG_MAX_SIZE, FILE_LOG_ROTATE_INTERVAL)));
+        }
+
+        let mut factory = FileLogFactory::new(settings)?;
+        factory.rotation = Rotation{
+            max_size: max_size as u64,
+            interval: if interval > 0 { Some(Duration::seconds(interval as i64)) } else { None },
+            backup_count: backup_count as u32
+        };
+        Ok(RotatingFileLogFactory{factory: factory})
+    }
+}
+
+impl LogFactory for RotatingFileLogFactory {
+    fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+        self.factory.create()
+    }
+
+    fn create_session_log(&self, session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+        self.factory.create_session_log(session_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn log_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("quickfix-file-log-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn settings(dir: &Path) -> SessionSettings {
+        let mut settings = SessionSettings::new();
+        settings.set(FILE_LOG_PATH, dir.to_str().unwrap());
+        settings
+    }
+
+    fn read(path: PathBuf) -> String {
+        String::from_utf8(fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn file_log_test() {
+        let dir = log_dir("file");
+        let session_id = SessionID::new("FIX.4.2", "TW", "ISLD");
+        {
+            let mut log = FileLogFactory::new(&settings(&dir)).unwrap().create_session_log(&session_id).unwrap();
+            log.on_incoming(b"8=FIX.4.2\x0135=A\x01");
+            log.on_outgoing(b"8=FIX.4.2\x0135=0\x01");
+            log.on_event("Received logon");
+        }
+
+        let messages = read(dir.join("FIX.4.2-TW-ISLD.messages.current.log"));
+        let lines: Vec<&str> = messages.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].ends_with(" : 8=FIX.4.2\x0135=A\x01"));
+        assert!(lines[1].ends_with(" : 8=FIX.4.2\x0135=0\x01"));
+        assert!(read(dir.join("FIX.4.2-TW-ISLD.event.current.log")).ends_with(" : Received logon\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_on_size_test() {
+        let dir = log_dir("size");
+        let mut settings = settings(&dir);
+        settings.set(FILE_LOG_MAX_SIZE, "60");
+        settings.set(FILE_LOG_BACKUP_COUNT, "2");
+        {
+            let mut log = RotatingFileLogFactory::new(&settings).unwrap().create().unwrap();
+            for event in ["one", "two", "three", "four"].iter() {
+                log.on_event(&format!("{:<30}", event));
+            }
+        }
+
+        assert!(read(dir.join("GLOBAL.event.current.log")).contains("four"));
+        assert!(read(dir.join("GLOBAL.event.1.log")).contains("three"));
+        assert!(read(dir.join("GLOBAL.event.2.log")).contains("two"));
+        assert!(!dir.join("GLOBAL.event.3.log").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_on_interval_test() {
+        let dir = log_dir("interval");
+        fs::create_dir_all(&dir).unwrap();
+        let rotation = Rotation{max_size: 0, interval: Some(Duration::seconds(60)), backup_count: 1};
+        let mut file = LogFile::open(&dir, "GLOBAL.event", rotation).unwrap();
+        let opened = file.opened;
+        file.write(opened + Duration::seconds(30), b"first");
+        file.write(opened + Duration::seconds(61), b"second");
+
+        assert!(read(dir.join("GLOBAL.event.1.log")).contains("first"));
+        assert!(read(dir.join("GLOBAL.event.current.log")).contains("second"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotating_settings_test() {
+        let dir = log_dir("settings");
+        assert!(RotatingFileLogFactory::new(&settings(&dir)).is_err());
+        assert!(FileLogFactory::new(&SessionSettings::new()).is_err());
+
+        let mut settings = settings(&dir);
+        settings.set(FILE_LOG_MAX_SIZE, "60");
+        settings.set(FILE_LOG_BACKUP_COUNT, "0");
+        assert!(RotatingFileLogFactory::new(&settings).is_err());
+    }
+
+    #[test]
+    fn rotate_without_backups_test() {
+        let dir = log_dir("no-backups");
+        fs::create_dir_all(&dir).unwrap();
+        let rotation = Rotation{max_size: 40, interval: None, backup_count: 0};
+        let mut file = LogFile::open(&dir, "GLOBAL.event", rotation).unwrap();
+        let opened = file.opened;
+        file.write(opened, b"first");
+        file.write(opened, b"second");
+
+        assert!(file.error.is_none());
+        assert_eq!(1, read(dir.join("GLOBAL.event.current.log")).lines().count());
+        assert!(read(dir.join("GLOBAL.event.current.log")).contains("second"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn binary_message_test() {
+        let dir = log_dir("binary");
+        {
+            let mut log = FileLogFactory::new(&settings(&dir)).unwrap().create().unwrap();
+            log.on_incoming(b"8=FIX.4.4\x0193=2\x0189=\xff\x80\x01");
+        }
+
+        let messages = fs::read(dir.join("GLOBAL.messages.current.log")).unwrap();
+        assert!(messages.ends_with(b" : 8=FIX.4.4\x0193=2\x0189=\xff\x80\x01\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn take_error_test() {
+        let dir = log_dir("error");
+        let mut log = FileLog::open(&dir, "GLOBAL", Rotation{max_size: 1, interval: None, backup_count: 1}).unwrap();
+        log.on_event("first");
+        assert!(log.take_error().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+        log.on_event("second");
+        match log.take_error() {
+            Some(FixError::IoError(_)) => {},
+            other => panic!("expected IoError, got {:?}", other)
+        }
+        assert!(log.take_error().is_none());
+    }
+}
@@ -5,18 +5,10 @@ use error::{FixBooleanParseError, FixError};
 
 impl FieldValueReader for bool {
     fn read(&mut self, value: &[u8]) -> Result<(), FixError> {
-        let mut values: Vec<u8> = vec![];
-        values.extend(value.iter().cloned());
-
-        let bool_string = match String::from_utf8(values) {
-            Ok(str) => str,
-            Err(err) => return Err(FixError::FromUtf8Error(err))
-        };
-
-        match bool_string.as_str() {
-            "Y" => *self = true,
-            "N" => *self = false,
-            _ => return Err(FixError::BooleanParseError(FixBooleanParseError::new(bool_string)))
+        match value {
+            b"Y" => *self = true,
+            b"N" => *self = false,
+            _ => return Err(FixError::BooleanParseError(FixBooleanParseError::new(String::from_utf8_lossy(value).into_owned())))
         }
         Ok(())
     }
@@ -0,0 +1,38 @@
+//parse_decimal splits a FIX decimal such as 52.10 into its mantissa and exponent, 5210 and -2
+pub fn parse_decimal(value: &str) -> Option<(i64, i32)> {
+    let (integer, fraction) = match value.find('.') {
+        Some(dot) => (&value[..dot], &value[dot + 1..]),
+        None => (value, "")
+    };
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mantissa = format!("{}{}", integer, fraction).parse::<i64>().ok()?;
+    Some((mantissa, -(fraction.len() as i32)))
+}
+
+pub fn format_decimal(mantissa: i64, exponent: i32) -> String {
+    if exponent >= 0 {
+        return format!("{}{}", mantissa, "0".repeat(exponent as usize));
+    }
+    let digits = mantissa.abs().to_string();
+    let scale = (-exponent) as usize;
+    let digits = if digits.len() <= scale { format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits) } else { digits };
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", if mantissa < 0 { "-" } else { "" }, integer, fraction)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decimal_test() {
+        assert_eq!(Some((5210, -2)), parse_decimal("52.10"));
+        assert_eq!(Some((-5, 0)), parse_decimal("-5"));
+        assert_eq!(None, parse_decimal("5.x"));
+        assert_eq!("52.10", format_decimal(5210, -2));
+        assert_eq!("-0.05", format_decimal(-5, -2));
+        assert_eq!("500", format_decimal(5, 2));
+    }
+}
@@ -4,25 +4,86 @@ use error::{FixError};
 
 impl FieldValueReader for i32 {
     fn read(&mut self, value: &[u8]) -> Result<(), FixError> {
-        let mut values: Vec<u8> = vec![];
-        values.extend(value.iter().cloned());
-
-        let number_string = match String::from_utf8(values) {
-            Ok(str) => str,
-            Err(err) => return Err(FixError::FromUtf8Error(err))
-        };
-
-        match number_string.parse::<i32>()  {
-            Ok(number) => *self = number,
-            Err(err) => return Err(FixError::IntError(err))
+        match parse_int(value) {
+            Some(number) => *self = number,
+            None => {
+                //fall back to the std parser so that the error carries the usual ParseIntError
+                let number_string = match str::from_utf8(value) {
+                    Ok(str) => str,
+                    Err(err) => return Err(FixError::Utf8Error(err))
+                };
+                match number_string.parse::<i32>() {
+                    Ok(number) => *self = number,
+                    Err(err) => return Err(FixError::IntError(err))
+                }
+            }
         };
 
         Ok(())
     }
 }
 
+//parse_int reads a signed integer straight from its ascii digits without going through a String
+pub fn parse_int(value: &[u8]) -> Option<i32> {
+    let (negative, digits) = match value.first() {
+        Some(&b'-') => (true, &value[1..]),
+        Some(&b'+') => (false, &value[1..]),
+        _ => (false, value)
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut number: i32 = 0;
+    for &digit in digits {
+        if digit < b'0' || digit > b'9' {
+            return None;
+        }
+        let digit = (digit - b'0') as i32;
+        number = match number.checked_mul(10) {
+            Some(n) if negative => match n.checked_sub(digit) { Some(n) => n, None => return None },
+            Some(n) => match n.checked_add(digit) { Some(n) => n, None => return None },
+            None => return None
+        };
+    }
+    Some(number)
+}
+
 impl FieldValueWriter for i32 {
     fn write(&self) -> Vec<u8> {
-        self.to_string().into_bytes()
+        let mut digits = [0u8; MAX_INT_LEN];
+        format_int(*self as i64, &mut digits).to_vec()
+    }
+}
+
+//MAX_INT_LEN is enough room for any i64 or u64, sign included
+pub const MAX_INT_LEN: usize = 20;
+
+//format_int renders value as ascii digits at the end of out and returns the written slice
+pub fn format_int(value: i64, out: &mut [u8; MAX_INT_LEN]) -> &[u8] {
+    let start = {
+        let digits = format_uint(value.unsigned_abs(), out);
+        MAX_INT_LEN - digits.len()
+    };
+
+    if value < 0 {
+        out[start - 1] = b'-';
+        return &out[start - 1..];
+    }
+    &out[start..]
+}
+
+//format_uint renders value as ascii digits at the end of out and returns the written slice
+pub fn format_uint(mut value: u64, out: &mut [u8; MAX_INT_LEN]) -> &[u8] {
+    let mut pos = MAX_INT_LEN;
+    loop {
+        pos -= 1;
+        out[pos] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
     }
+    &out[pos..]
 }
\ No newline at end of file
@@ -4,12 +4,12 @@ use error::FixError;
 
 impl FieldValueReader for String {
     fn read(&mut self, value: &[u8]) -> Result<(), FixError> {
-        let mut values: Vec<u8> = vec![];
-        values.extend(value.iter().cloned());
-
-        match String::from_utf8(values) {
-            Ok(str) => *self = str,
-            Err(err) => return Err(FixError::FromUtf8Error(err))
+        match str::from_utf8(value) {
+            Ok(str) => {
+                self.clear();
+                self.push_str(str);
+            },
+            Err(err) => return Err(FixError::Utf8Error(err))
         }
         Ok(())
     }
@@ -5,8 +5,8 @@ use time::*;
 use std::iter::FromIterator;
 
 
-static UTCTIMESTAMPFORMAT: &'static str = "%Y-%m-%d %H:%M:%S.%f";
-static UTCTIMESTAMPNOMILLISFORMAT: &'static str = "%Y-%m-%d %H:%M:%S";
+static UTCTIMESTAMPFORMAT: &'static str = "%Y%m%d-%H:%M:%S.%f";
+static UTCTIMESTAMPNOMILLISFORMAT: &'static str = "%Y%m%d-%H:%M:%S";
 
 pub struct FIXUTCTimestamp {
     time: Tm,
@@ -34,22 +34,18 @@ impl FIXUTCTimestamp {
 
 impl FieldValueReader for FIXUTCTimestamp {
     fn read(&mut self, value: &[u8]) -> Result<(), FixError> {
-
-        let mut values: Vec<u8> = vec![];
-        values.extend(value.iter().cloned());
-
-        let str_time = match String::from_utf8(values) {
+        let str_time = match str::from_utf8(value) {
             Ok(str) => str,
-            Err(err) => return Err(FixError::FromUtf8Error(err))
+            Err(err) => return Err(FixError::Utf8Error(err))
         };
 
-        if let Ok(time) = strptime(str_time.as_str(), UTCTIMESTAMPFORMAT)
+        if let Ok(time) = strptime(str_time, UTCTIMESTAMPFORMAT)
         {
             self.time = time;
             return Ok(());
         }
 
-        match strptime(str_time.as_str(), UTCTIMESTAMPNOMILLISFORMAT)
+        match strptime(str_time, UTCTIMESTAMPNOMILLISFORMAT)
         {
             Ok(time) => self.time = time,
             Err(err) => return Err(FixError::TimeParseError(err))
@@ -65,7 +61,10 @@ impl FieldValueWriter for FIXUTCTimestamp {
         if self.no_millisecond {
             strftime(UTCTIMESTAMPNOMILLISFORMAT, &self.time).unwrap().into_bytes()
         } else {
-            strftime(UTCTIMESTAMPFORMAT, &self.time).unwrap().into_bytes()
+            //%f would print nanoseconds, FIX wants milliseconds
+            let mut value = strftime(UTCTIMESTAMPNOMILLISFORMAT, &self.time).unwrap();
+            value.push_str(&format!(".{:03}", self.time.tm_nsec / 1_000_000));
+            value.into_bytes()
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,496 @@
+use tag::*;
+use message::*;
+use message_writer::*;
+use begin_string::*;
+use error::FixError;
+use xml::{self, Element};
+
+//FixmlComponent is a component of a FIXML element, written as a child element named after its abbreviation.
+//Repeating groups are components with a count_tag, each instance being one child element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixmlComponent {
+    pub abbreviation: String,
+    pub count_tag: Option<u32>,
+    //fields is the member fields written as attributes, the first one delimits the instances of a group
+    pub fields: Vec<u32>
+}
+
+impl FixmlComponent {
+
+    pub fn new(abbreviation: &str, fields: Vec<u32>) -> FixmlComponent {
+        FixmlComponent{abbreviation: abbreviation.to_string(), count_tag: None, fields: fields}
+    }
+
+    pub fn group(abbreviation: &str, count_tag: u32, fields: Vec<u32>) -> FixmlComponent {
+        FixmlComponent{abbreviation: abbreviation.to_string(), count_tag: Some(count_tag), fields: fields}
+    }
+}
+
+//FixmlSchema holds the FIXML abbreviations and layout of messages. A DataDictionary provides them for every message
+//of its FIX version, taking abbreviations from abbr attributes, while FixmlTags only knows the standard header.
+pub trait FixmlSchema {
+    //message_abbreviation is the element of a message, e.g. Order for MsgType D
+    fn message_abbreviation(&self, msg_type: &str) -> Option<String>;
+
+    //message_type is the reverse of message_abbreviation
+    fn message_type(&self, abbreviation: &str) -> Option<String>;
+
+    //field_abbreviation is the attribute of tag in element, the same field may be abbreviated differently by
+    //different components
+    fn field_abbreviation(&self, element: &str, tag: u32) -> Option<String>;
+
+    //field_tag is the reverse of field_abbreviation
+    fn field_tag(&self, element: &str, abbreviation: &str) -> Option<u32>;
+
+    //components lists the child components of element, a message abbreviation or a component abbreviation
+    fn components(&self, element: &str) -> Vec<FixmlComponent>;
+}
+
+//HEADER is the element of the standard header
+pub const HEADER: &'static str = "Hdr";
+
+static MESSAGE_ABBREVIATIONS: &'static [(&'static str, &'static str)] = &[
+    ("0", "Heartbeat"), ("1", "TestReq"), ("2", "RsndReq"), ("3", "Reject"), ("4", "SeqRst"), ("5", "Logout"),
+    ("6", "IOI"), ("7", "Adv"), ("8", "ExecRpt"), ("9", "OrdCxlRej"), ("A", "Logon"), ("B", "News"), ("C", "Email"),
+    ("D", "Order"), ("E", "NewOrdList"), ("F", "OrdCxlReq"), ("G", "OrdCxlRplcReq"), ("H", "OrdStatReq"),
+    ("R", "QuotReq"), ("S", "Quot"), ("V", "MktDataReq"), ("W", "MktDataFull"), ("X", "MktDataInc"),
+    ("Y", "MktDataReqRej"), ("j", "BizMsgRej"), ("AE", "TrdCaptRpt")
+];
+
+static HEADER_ABBREVIATIONS: &'static [(Tags, &'static str)] = &[
+    (Tags::SenderCompID, "SID"), (Tags::TargetCompID, "TID"), (Tags::OnBehalfOfCompID, "OBID"),
+    (Tags::DeliverToCompID, "D2ID"), (Tags::SenderSubID, "SSub"), (Tags::SenderLocationID, "SLoc"),
+    (Tags::TargetSubID, "TSub"), (Tags::TargetLocationID, "TLoc"), (Tags::OnBehalfOfSubID, "OBSub"),
+    (Tags::OnBehalfOfLocationID, "OBLoc"), (Tags::DeliverToSubID, "D2Sub"), (Tags::DeliverToLocationID, "D2Loc"),
+    (Tags::PossDupFlag, "PosDup"), (Tags::PossResend, "PosRsnd"), (Tags::SendingTime, "Snt"),
+    (Tags::OrigSendingTime, "OrigSnt"), (Tags::MessageEncoding, "MsgEncd"), (Tags::MsgSeqNum, "SeqNum"),
+    (Tags::LastMsgSeqNumProcessed, "LastMsgSeqNumProcd"), (Tags::OnBehalfOfSendingTime, "OBSnt")
+];
+
+static HOP_ABBREVIATIONS: &'static [(Tags, &'static str)] = &[
+    (Tags::HopCompID, "ID"), (Tags::HopSendingTime, "Snt"), (Tags::HopRefID, "Ref")
+];
+
+//FixmlTags abbreviates the standard header and names the elements of the messages of msg_type
+pub struct FixmlTags;
+
+impl FixmlSchema for FixmlTags {
+    fn message_abbreviation(&self, msg_type: &str) -> Option<String> {
+        MESSAGE_ABBREVIATIONS.iter().find(|&&(t, _)| t == msg_type).map(|&(_, abbreviation)| abbreviation.to_string())
+    }
+
+    fn message_type(&self, abbreviation: &str) -> Option<String> {
+        MESSAGE_ABBREVIATIONS.iter().find(|&&(_, a)| a == abbreviation).map(|&(msg_type, _)| msg_type.to_string())
+    }
+
+    fn field_abbreviation(&self, element: &str, tag: u32) -> Option<String> {
+        let abbreviations = match element {
+            HEADER => HEADER_ABBREVIATIONS,
+            "Hop" => HOP_ABBREVIATIONS,
+            _ => return None
+        };
+        abbreviations.iter().find(|&&(t, _)| t.to_num() == tag).map(|&(_, abbreviation)| abbreviation.to_string())
+    }
+
+    fn field_tag(&self, element: &str, abbreviation: &str) -> Option<u32> {
+        let abbreviations = match element {
+            HEADER => HEADER_ABBREVIATIONS,
+            "Hop" => HOP_ABBREVIATIONS,
+            _ => return None
+        };
+        abbreviations.iter().find(|&&(_, a)| a == abbreviation).map(|&(tag, _)| tag.to_num())
+    }
+
+    fn components(&self, element: &str) -> Vec<FixmlComponent> {
+        match element {
+            HEADER => vec![FixmlComponent::group("Hop", Tags::NoHops.to_num(),
+                                                 HOP_ABBREVIATIONS.iter().map(|&(tag, _)| tag.to_num()).collect())],
+            _ => vec![]
+        }
+    }
+}
+
+//fixml_version is the v attribute of a FIX version, e.g. 4.4 for FIX.4.4 and 5.0 SP2 for FIX.5.0SP2
+fn fixml_version(begin_string: &str) -> Option<String> {
+    if !begin_string.starts_with("FIX.") {
+        return None;
+    }
+    let version = &begin_string[4..];
+    match version.find("SP") {
+        Some(sp) => Some(format!("{} {}", &version[..sp], &version[sp..])),
+        None => Some(version.to_string())
+    }
+}
+
+fn fixml_error(text: &str) -> FixError {
+    FixError::ParseMessageError(format!("FIXML: {}", text))
+}
+
+//contains tells whether tag is written inside component, as a member field or inside one of its own components
+fn contains(component: &FixmlComponent, tag: u32, schema: &dyn FixmlSchema) -> bool {
+    component.fields.contains(&tag) || schema.components(&component.abbreviation).iter()
+        .any(|child| child.count_tag == Some(tag) || contains(child, tag, schema))
+}
+
+type Pending = (usize, usize, Vec<(u32, String)>);
+
+//encode_element writes fields into element, fields of its components go to child elements. A non repeating
+//component collects its fields wherever they appear, the instances of a group follow their NumInGroup field.
+fn encode_element(element: &mut Element, fields: &[(u32, String)], schema: &dyn FixmlSchema) -> Result<(), FixError> {
+    let components = schema.components(&element.name);
+    //pending holds the fields of the non repeating components as (component, child element, fields)
+    let mut pending: Vec<Pending> = vec![];
+    let mut pos = 0;
+    while pos < fields.len() {
+        let (tag, ref value) = fields[pos];
+        pos += 1;
+
+        if let Some(group) = components.iter().find(|component| component.count_tag == Some(tag)) {
+            let delimiter = match group.fields.first() {
+                Some(delimiter) => *delimiter,
+                None => return Err(fixml_error(&format!("group {} has no fields", group.abbreviation)))
+            };
+            let count = value.parse::<usize>().map_err(|_| fixml_error(&format!("{} is not a NumInGroup value", value)))?;
+            for _ in 0..count {
+                if pos >= fields.len() || fields[pos].0 != delimiter {
+                    return Err(fixml_error(&format!("group {} holds less than {} instances", group.abbreviation, count)));
+                }
+                let start = pos;
+                pos += 1;
+                while pos < fields.len() && fields[pos].0 != delimiter && contains(group, fields[pos].0, schema) {
+                    pos += 1;
+                }
+                let mut instance = Element::new(&group.abbreviation);
+                encode_element(&mut instance, &fields[start..pos], schema)?;
+                element.children.push(instance);
+            }
+            continue;
+        }
+
+        match components.iter().position(|component| component.count_tag.is_none() && contains(component, tag, schema)) {
+            Some(index) => match pending.iter_mut().find(|&&mut (i, _, _)| i == index) {
+                Some(&mut (_, _, ref mut component_fields)) => component_fields.push((tag, value.clone())),
+                None => {
+                    //the child keeps its place among the groups
+                    element.children.push(Element::new(&components[index].abbreviation));
+                    pending.push((index, element.children.len() - 1, vec![(tag, value.clone())]));
+                }
+            },
+            None => match schema.field_abbreviation(&element.name, tag) {
+                Some(abbreviation) => element.attributes.push((abbreviation, value.clone())),
+                None => return Err(fixml_error(&format!("no abbreviation of tag {} in {}", tag, element.name)))
+            }
+        }
+    }
+
+    for (_, child, component_fields) in pending.into_iter() {
+        encode_element(&mut element.children[child], &component_fields, schema)?;
+    }
+    Ok(())
+}
+
+//to_fixml encodes msg as a FIXML document, e.g.
+//<FIXML v="4.4"><Order ID="123" Side="1"><Hdr SID="TW" TID="ISLD"/><Instrmt Sym="MSFT"/></Order></FIXML>
+//BeginString, BodyLength, MsgType and the trailer have no FIXML counterpart, the version is the v attribute.
+pub fn to_fixml(msg: &Message, schema: &dyn FixmlSchema) -> Result<String, FixError> {
+    let fields: Vec<(u32, String)> = if msg.fields().is_empty() {
+        msg.header.iter().chain(msg.body.iter()).chain(msg.trailer.iter())
+            .map(|(tag, value)| (tag, String::from_utf8_lossy(value).into_owned())).collect()
+    } else {
+        msg.fields().iter().map(|field| (field.tag(), String::from_utf8_lossy(field.value()).into_owned())).collect()
+    };
+    let get = |tag: Tags| fields.iter().find(|&&(t, _)| t == tag.to_num()).map(|(_, value)| value.as_str());
+
+    let begin_string = get(Tags::BeginString).ok_or_else(|| fixml_error("BeginString is missing"))?;
+    let begin_string = match get(Tags::ApplVerID) {
+        Some(appl_ver_id) if begin_string == BEGIN_STRING_FIXT11 => appl_ver_id_begin_string(appl_ver_id),
+        _ => begin_string
+    };
+    let version = fixml_version(begin_string).ok_or_else(|| fixml_error(&format!("no FIXML version of {}", begin_string)))?;
+    let msg_type = get(Tags::MsgType).ok_or_else(|| fixml_error("MsgType is missing"))?;
+    let abbreviation = schema.message_abbreviation(msg_type).ok_or_else(|| fixml_error(&format!("no abbreviation of MsgType {}", msg_type)))?;
+
+    let mut root = Element::new("FIXML");
+    root.attributes.push(("v".to_string(), version));
+    if let Some(cstm_appl_ver_id) = get(Tags::CstmApplVerID) {
+        root.attributes.push(("cv".to_string(), cstm_appl_ver_id.to_string()));
+    }
+    if let Some(appl_ext_id) = get(Tags::ApplExtID) {
+        root.attributes.push(("xv".to_string(), appl_ext_id.to_string()));
+    }
+
+    let skipped = [Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::MsgType.to_num(),
+                   Tags::ApplVerID.to_num(), Tags::CstmApplVerID.to_num(), Tags::ApplExtID.to_num()];
+    let header: Vec<(u32, String)> = fields.iter().filter(|&&(tag, _)| tag.is_header() && !skipped.contains(&tag)).cloned().collect();
+    let body: Vec<(u32, String)> = fields.iter().filter(|&&(tag, _)| !tag.is_header() && !tag.is_trailer()).cloned().collect();
+
+    let mut element = Element::new(&abbreviation);
+    encode_element(&mut element, &body, schema)?;
+    if !header.is_empty() {
+        let mut hdr = Element::new(HEADER);
+        encode_element(&mut hdr, &header, schema)?;
+        element.children.insert(0, hdr);
+    }
+    root.children.push(element);
+
+    let mut xml = String::new();
+    root.write(&mut xml);
+    Ok(xml)
+}
+
+//decode_element reads the attributes of element then its children, a run of instances of a group is
+//preceded by its NumInGroup field
+fn decode_element(element: &Element, schema: &dyn FixmlSchema, fields: &mut Vec<(u32, String)>) -> Result<(), FixError> {
+    for (name, value) in element.attributes.iter() {
+        match schema.field_tag(&element.name, name) {
+            Some(tag) => fields.push((tag, value.clone())),
+            None => return Err(fixml_error(&format!("unknown attribute {} of {}", name, element.name)))
+        }
+    }
+
+    let components = schema.components(&element.name);
+    let mut i = 0;
+    while i < element.children.len() {
+        let child = &element.children[i];
+        let component = components.iter().find(|component| component.abbreviation == child.name)
+            .ok_or_else(|| fixml_error(&format!("unknown element {} in {}", child.name, element.name)))?;
+        match component.count_tag {
+            Some(count_tag) => {
+                let count = element.children[i..].iter().take_while(|instance| instance.name == child.name).count();
+                fields.push((count_tag, count.to_string()));
+                for instance in element.children[i..i + count].iter() {
+                    decode_element(instance, schema, fields)?;
+                }
+                i += count;
+            },
+            None => {
+                decode_element(child, schema, fields)?;
+                i += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+//from_fixml decodes a FIXML document holding one message. FIX.5.0 and later versions are carried by FIXT.1.1
+//with the version as ApplVerID, BodyLength and CheckSum are computed.
+pub fn from_fixml(xml: &str, schema: &dyn FixmlSchema) -> Result<Message, FixError> {
+    let root = xml::parse(xml).map_err(|err| fixml_error(&err))?;
+    if root.name != "FIXML" {
+        return Err(fixml_error(&format!("unexpected root element {}", root.name)));
+    }
+    let version = root.attribute("v").ok_or_else(|| fixml_error("the v attribute is missing"))?;
+    let begin_string = format!("FIX.{}", version.replace(" ", ""));
+    let element = match root.children.len() {
+        1 => &root.children[0],
+        _ => return Err(fixml_error("FIXML must hold one message"))
+    };
+    let msg_type = schema.message_type(&element.name).ok_or_else(|| fixml_error(&format!("unknown message {}", element.name)))?;
+
+    let mut header = vec![];
+    let mut body = vec![];
+    let mut message = element.clone();
+    if let Some(index) = message.children.iter().position(|child| child.name == HEADER) {
+        decode_element(&message.children.remove(index), schema, &mut header)?;
+    }
+    decode_element(&message, schema, &mut body)?;
+
+    let mut raw = vec![];
+    {
+        let mut writer = if begin_string.starts_with(BEGIN_STRING_FIX50) {
+            let mut writer = MessageWriter::new(&mut raw, BEGIN_STRING_FIXT11, &msg_type);
+            writer.write_str(Tags::ApplVerID.to_num(), begin_string_appl_ver_id(&begin_string));
+            writer
+        } else {
+            MessageWriter::new(&mut raw, &begin_string, &msg_type)
+        };
+        if let Some(cstm_appl_ver_id) = root.attribute("cv") {
+            writer.write_str(Tags::CstmApplVerID.to_num(), cstm_appl_ver_id);
+        }
+        if let Some(appl_ext_id) = root.attribute("xv") {
+            writer.write_str(Tags::ApplExtID.to_num(), appl_ext_id);
+        }
+        for &(tag, ref value) in header.iter().chain(body.iter()) {
+            writer.write_str(tag, value);
+        }
+        writer.finish();
+    }
+    Message::parse(&raw)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data_dictionary::DataDictionary;
+
+    //OrderSchema knows enough of the FIXML schema for a NewOrderSingle
+    struct OrderSchema;
+
+    impl FixmlSchema for OrderSchema {
+        fn message_abbreviation(&self, msg_type: &str) -> Option<String> {
+            FixmlTags.message_abbreviation(msg_type)
+        }
+
+        fn message_type(&self, abbreviation: &str) -> Option<String> {
+            FixmlTags.message_type(abbreviation)
+        }
+
+        fn field_abbreviation(&self, element: &str, tag: u32) -> Option<String> {
+            let abbreviation = match (element, tag) {
+                ("Order", 11) => "ID",
+                ("Order", 54) => "Side",
+                ("Order", 40) => "Typ",
+                ("Order", 44) => "Px",
+                ("Order", 58) => "Txt",
+                ("Instrmt", 55) => "Sym",
+                ("OrdQty", 38) => "Qty",
+                ("Pty", 448) => "ID",
+                ("Pty", 452) => "R",
+                _ => return FixmlTags.field_abbreviation(element, tag)
+            };
+            Some(abbreviation.to_string())
+        }
+
+        fn field_tag(&self, element: &str, abbreviation: &str) -> Option<u32> {
+            [11, 54, 40, 44, 58, 55, 38, 448, 452].iter().cloned()
+                .find(|&tag| self.field_abbreviation(element, tag).map_or(false, |a| a == abbreviation))
+                .or_else(|| FixmlTags.field_tag(element, abbreviation))
+        }
+
+        fn components(&self, element: &str) -> Vec<FixmlComponent> {
+            match element {
+                "Order" => vec![FixmlComponent::group("Pty", 453, vec![448, 452]),
+                                FixmlComponent::new("Instrmt", vec![55]),
+                                FixmlComponent::new("OrdQty", vec![38])],
+                _ => FixmlTags.components(element)
+            }
+        }
+    }
+
+    //FIX44 lays the same NewOrderSingle out as OrderSchema, Parties has no abbr so its group sits in Order
+    static FIX44: &'static str = r#"<fix type="FIX" major="4" minor="4" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+    <field name="SenderCompID" required="Y"/>
+    <field name="TargetCompID" required="Y"/>
+    <field name="MsgSeqNum" required="Y"/>
+    <field name="SendingTime" required="Y"/>
+    <group name="NoHops" required="N">
+      <field name="HopCompID" required="N"/>
+      <field name="HopSendingTime" required="N"/>
+      <field name="HopRefID" required="N"/>
+    </group>
+  </header>
+  <trailer>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="NewOrderSingle" msgtype="D" msgcat="app">
+      <field name="ClOrdID" required="Y"/>
+      <component name="Parties" required="N"/>
+      <component name="Instrument" required="Y"/>
+      <field name="Side" required="Y"/>
+      <component name="OrderQtyData" required="N"/>
+      <field name="OrdType" required="Y"/>
+      <field name="Price" required="N"/>
+      <field name="Text" abbr="Txt" required="N"/>
+    </message>
+  </messages>
+  <components>
+    <component name="Instrument" abbr="Instrmt">
+      <field name="Symbol" abbr="Sym" required="Y"/>
+    </component>
+    <component name="OrderQtyData" abbr="OrdQty">
+      <field name="OrderQty" required="N"/>
+    </component>
+    <component name="Parties">
+      <group name="NoPartyIDs" abbr="Pty" required="N">
+        <field name="PartyID" abbr="ID" required="N"/>
+        <field name="PartyRole" abbr="R" required="N"/>
+      </group>
+    </component>
+  </components>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="11" name="ClOrdID" abbr="ID" type="STRING"/>
+    <field number="34" name="MsgSeqNum" type="SEQNUM"/>
+    <field number="35" name="MsgType" type="STRING"/>
+    <field number="38" name="OrderQty" abbr="Qty" type="QTY"/>
+    <field number="40" name="OrdType" abbr="Typ" type="CHAR"/>
+    <field number="44" name="Price" abbr="Px" type="PRICE"/>
+    <field number="49" name="SenderCompID" type="STRING"/>
+    <field number="52" name="SendingTime" type="UTCTIMESTAMP"/>
+    <field number="54" name="Side" type="CHAR"/>
+    <field number="55" name="Symbol" type="STRING"/>
+    <field number="56" name="TargetCompID" type="STRING"/>
+    <field number="58" name="Text" type="STRING"/>
+    <field number="448" name="PartyID" type="STRING"/>
+    <field number="452" name="PartyRole" type="INT"/>
+    <field number="453" name="NoPartyIDs" type="NUMINGROUP"/>
+    <field number="627" name="NoHops" type="NUMINGROUP"/>
+    <field number="628" name="HopCompID" type="STRING"/>
+    <field number="629" name="HopSendingTime" type="UTCTIMESTAMP"/>
+    <field number="630" name="HopRefID" type="INT"/>
+  </fields>
+</fix>"#;
+
+    static RAW: &'static [u8] = b"8=FIX.4.4\x019=0\x0135=D\x0149=TW\x0156=ISLD\x0134=2\x0152=20161012-10:05:01.005\x01\
+627=1\x01628=HOP1\x01630=R1\x0111=ID1\x01453=2\x01448=TW\x01452=1\x01448=ISLD\x01452=3\x0155=MSFT\x0154=1\x0138=100\x01\
+40=2\x0144=52.1\x0158=A&B <\"x\">\x0110=000\x01";
+
+    static FIXML: &'static str = "<FIXML v=\"4.4\"><Order ID=\"ID1\" Side=\"1\" Typ=\"2\" Px=\"52.1\" Txt=\"A&amp;B &lt;&quot;x&quot;&gt;\">\
+<Hdr SID=\"TW\" TID=\"ISLD\" SeqNum=\"2\" Snt=\"20161012-10:05:01.005\"><Hop ID=\"HOP1\" Ref=\"R1\"/></Hdr>\
+<Pty ID=\"TW\" R=\"1\"/><Pty ID=\"ISLD\" R=\"3\"/><Instrmt Sym=\"MSFT\"/><OrdQty Qty=\"100\"/></Order></FIXML>";
+
+    #[test]
+    fn to_fixml_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!(FIXML, to_fixml(&msg, &OrderSchema).unwrap());
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let msg = from_fixml(&format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!-- order -->\n{}\n", FIXML), &OrderSchema).unwrap();
+        assert_eq!("FIX.4.4", msg.header.get_string(Tags::BeginString.to_num()).unwrap());
+        assert_eq!("D", msg.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("A&B <\"x\">", msg.body.get_string(58).unwrap());
+        assert_eq!(FIXML, to_fixml(&msg, &OrderSchema).unwrap());
+    }
+
+    #[test]
+    fn dictionary_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        assert_eq!(Some("Order".to_string()), dictionary.message_abbreviation("D"));
+        assert_eq!(Some("SID".to_string()), dictionary.field_abbreviation(HEADER, 49));
+        assert_eq!(Some("Side".to_string()), dictionary.field_abbreviation("Order", 54));
+        assert_eq!(Some(448), dictionary.field_tag("Pty", "ID"));
+
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!(FIXML, to_fixml(&msg, &dictionary).unwrap());
+        let decoded = from_fixml(FIXML, &dictionary).unwrap();
+        assert_eq!(msg.body.get_group(453).unwrap(), decoded.body.get_group(453).unwrap());
+        assert_eq!(FIXML, to_fixml(&decoded, &dictionary).unwrap());
+    }
+
+    #[test]
+    fn fix50_test() {
+        let xml = "<FIXML v=\"5.0 SP2\" xv=\"1\"><Heartbeat><Hdr SID=\"TW\" TID=\"ISLD\"/></Heartbeat></FIXML>";
+        let msg = from_fixml(xml, &FixmlTags).unwrap();
+        assert_eq!("FIXT.1.1", msg.header.get_string(Tags::BeginString.to_num()).unwrap());
+        assert_eq!("9", msg.header.get_string(Tags::ApplVerID.to_num()).unwrap());
+        assert_eq!("1", msg.header.get_string(Tags::ApplExtID.to_num()).unwrap());
+        assert_eq!(xml, to_fixml(&msg, &FixmlTags).unwrap());
+    }
+
+    #[test]
+    fn unknown_field_test() {
+        let msg = Message::parse(b"8=FIX.4.4\x019=0\x0135=D\x0149=TW\x0111=ID1\x0110=000\x01").unwrap();
+        assert!(to_fixml(&msg, &FixmlTags).is_err());
+        assert!(from_fixml("<FIXML v=\"4.4\"><Order ID=\"ID1\"/></FIXML>", &FixmlTags).is_err());
+        assert!(from_fixml("<FIXML v=\"4.4\"><Order><Hdr SID=\"TW\"></Order></FIXML>", &FixmlTags).is_err());
+    }
+}
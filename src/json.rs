@@ -0,0 +1,555 @@
+use std::char;
+use tag::*;
+use message::*;
+use message_writer::*;
+use pretty::*;
+use tag_value::SOH;
+use error::FixError;
+
+//JsonKeys selects how fields are keyed in the JSON document
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JsonKeys {
+    //"35": "D"
+    TagNumbers,
+    //"MsgType": "D", fields without a name keep their tag number
+    FieldNames
+}
+
+//Node is a field or a repeating group with its instances
+enum Node {
+    Field(u32, String),
+    Group(u32, Vec<Vec<Node>>)
+}
+
+//to_json encodes msg following the FIX JSON encoding: {"Header": {...}, "Body": {...}, "Trailer": {...}}. Values are
+//kept as their original strings and the repeating groups of names, a DataDictionary or TagNames, become arrays of
+//objects keyed by the NumInGroup field, the array length standing for its value. Data fields such as Signature(89)
+//are Base64 encoded, any other value must be UTF-8.
+pub fn to_json(msg: &Message, keys: JsonKeys, names: &dyn FieldNames) -> Result<String, FixError> {
+    let tag_values: Vec<(u32, &[u8])> = if msg.fields().is_empty() {
+        msg.header.iter().chain(msg.body.iter()).chain(msg.trailer.iter()).collect()
+    } else {
+        msg.fields().iter().map(|field| (field.tag(), field.value())).collect()
+    };
+    let mut fields = Vec::with_capacity(tag_values.len());
+    for (tag, value) in tag_values.into_iter() {
+        fields.push((tag, json_value(tag, value, names)?));
+    }
+
+    let mut pos = 0;
+    let nodes = read_nodes(&fields, &mut pos, None, names);
+    let mut sections: Vec<Vec<&Node>> = vec![vec![], vec![], vec![]];
+    for node in nodes.iter() {
+        let tag = match *node {
+            Node::Field(tag, _) | Node::Group(tag, _) => tag
+        };
+        let section = if tag.is_header() { 0 } else if tag.is_trailer() { 2 } else { 1 };
+        sections[section].push(node);
+    }
+
+    let mut json = String::new();
+    json.push('{');
+    for (i, (name, nodes)) in ["Header", "Body", "Trailer"].iter().zip(sections.iter()).enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_string(&mut json, name);
+        json.push(':');
+        write_object(&mut json, nodes, keys, names);
+    }
+    json.push('}');
+    Ok(json)
+}
+
+fn json_value(tag: u32, value: &[u8], names: &dyn FieldNames) -> Result<String, FixError> {
+    if names.is_data(tag) {
+        return Ok(base64_encode(value));
+    }
+    match ::std::str::from_utf8(value) {
+        Ok(value) => Ok(value.to_string()),
+        Err(_) => Err(FixError::SerializeError(format!("JSON: field {} is not UTF-8 and not a data field", tag)))
+    }
+}
+
+static BASE64: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(value: &[u8]) -> String {
+    let mut encoded = String::with_capacity(value.len().div_ceil(3) * 4);
+    for chunk in value.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, b)| bits | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(value: &str) -> Option<Vec<u8>> {
+    let value = value.as_bytes();
+    if !value.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(value.len() / 4 * 3);
+    for (n, chunk) in value.chunks(4).enumerate() {
+        let last = n == value.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (i, c) in chunk[..4 - padding].iter().enumerate() {
+            let index = BASE64.iter().position(|b| b == c)? as u32;
+            bits |= index << (18 - 6 * i);
+        }
+        for i in 0..3 - padding {
+            decoded.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+//read_nodes reads fields into nodes, given members it stops at the first field that is not one of them
+fn read_nodes(fields: &[(u32, String)], pos: &mut usize, members: Option<&[u32]>, names: &dyn FieldNames) -> Vec<Node> {
+    let mut nodes = vec![];
+    while *pos < fields.len() {
+        if let Some(members) = members {
+            if !members.contains(&fields[*pos].0) {
+                break;
+            }
+        }
+        nodes.push(read_node(fields, pos, names));
+    }
+    nodes
+}
+
+//read_node reads one field, a NumInGroup field takes its instances along. Each instance starts with the
+//delimiter, the first member of the group.
+fn read_node(fields: &[(u32, String)], pos: &mut usize, names: &dyn FieldNames) -> Node {
+    let (tag, ref value) = fields[*pos];
+    *pos += 1;
+
+    let members = match names.group_fields(tag) {
+        Some(ref members) if !members.is_empty() => members.clone(),
+        _ => return Node::Field(tag, value.clone())
+    };
+    let mut instances = vec![];
+    while *pos < fields.len() && fields[*pos].0 == members[0] {
+        let mut instance = vec![read_node(fields, pos, names)];
+        instance.extend(read_nodes(fields, pos, Some(&members[1..]), names));
+        instances.push(instance);
+    }
+    Node::Group(tag, instances)
+}
+
+fn write_object(json: &mut String, nodes: &[&Node], keys: JsonKeys, names: &dyn FieldNames) {
+    json.push('{');
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        match **node {
+            Node::Field(tag, ref value) => {
+                write_key(json, tag, keys, names);
+                write_string(json, value);
+            },
+            Node::Group(tag, ref instances) => {
+                write_key(json, tag, keys, names);
+                json.push('[');
+                for (j, instance) in instances.iter().enumerate() {
+                    if j > 0 {
+                        json.push(',');
+                    }
+                    let instance: Vec<&Node> = instance.iter().collect();
+                    write_object(json, &instance, keys, names);
+                }
+                json.push(']');
+            }
+        }
+    }
+    json.push('}');
+}
+
+fn write_key(json: &mut String, tag: u32, keys: JsonKeys, names: &dyn FieldNames) {
+    let name = match keys {
+        JsonKeys::FieldNames => names.field_name(tag),
+        JsonKeys::TagNumbers => None
+    };
+    write_string(json, &name.unwrap_or(tag.to_string()));
+    json.push(':');
+}
+
+fn write_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+}
+
+//from_json decodes a document written by to_json, keys may be tag numbers or names known to names and the data fields
+//of names are Base64 decoded. When BodyLength and CheckSum are both present the message is rebuilt from the given
+//values, otherwise they are computed.
+pub fn from_json(json: &str, names: &dyn FieldNames) -> Result<Message, FixError> {
+    let mut parser = Parser{json: json.as_bytes(), pos: 0};
+    let document = parser.parse_document()?;
+
+    let mut fields = vec![];
+    let sections = match document {
+        Value::Object(sections) => sections,
+        _ => return Err(json_error("a message must be an object", 0))
+    };
+    for (section, value) in sections.into_iter() {
+        match (section.as_str(), value) {
+            ("Header", Value::Object(members)) | ("Body", Value::Object(members)) | ("Trailer", Value::Object(members)) =>
+                flatten(members, names, &mut fields)?,
+            (section, _) => return Err(FixError::ParseMessageError(format!("JSON: unexpected section {}", section)))
+        }
+    }
+
+    let has = |tag: Tags| fields.iter().any(|&(t, _)| t == tag.to_num());
+    let mut raw = vec![];
+    if has(Tags::BodyLength) && has(Tags::CheckSum) {
+        for &(tag, ref value) in fields.iter() {
+            raw.extend_from_slice(format!("{}=", tag).as_bytes());
+            raw.extend_from_slice(value);
+            raw.push(SOH);
+        }
+    } else {
+        let get = |tag: Tags| fields.iter().find(|&&(t, _)| t == tag.to_num())
+            .and_then(|(_, value)| ::std::str::from_utf8(value).ok()).unwrap_or("");
+        let mut writer = MessageWriter::new(&mut raw, get(Tags::BeginString), get(Tags::MsgType));
+        let skipped = [Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::MsgType.to_num(), Tags::CheckSum.to_num()];
+        for &(tag, ref value) in fields.iter().filter(|&&(tag, _)| !skipped.contains(&tag)) {
+            writer.write_bytes(tag, value);
+        }
+        writer.finish();
+    }
+    Message::parse(&raw)
+}
+
+fn flatten(members: Vec<(String, Value)>, names: &dyn FieldNames, fields: &mut Vec<(u32, Vec<u8>)>) -> Result<(), FixError> {
+    for (key, value) in members.into_iter() {
+        let tag = match key.parse::<u32>().ok().or_else(|| names.field_tag(&key)) {
+            Some(tag) => tag,
+            None => return Err(FixError::ParseMessageError(format!("JSON: unknown field {}", key)))
+        };
+        match value {
+            Value::String(ref value) if names.is_data(tag) => match base64_decode(value) {
+                Some(value) => fields.push((tag, value)),
+                None => return Err(FixError::ParseMessageError(format!("JSON: data field {} is not Base64", key)))
+            },
+            Value::String(value) => fields.push((tag, value.into_bytes())),
+            Value::Array(instances) => {
+                fields.push((tag, instances.len().to_string().into_bytes()));
+                for instance in instances.into_iter() {
+                    match instance {
+                        Value::Object(members) => flatten(members, names, fields)?,
+                        _ => return Err(FixError::ParseMessageError(format!("JSON: group {} must hold objects", key)))
+                    }
+                }
+            },
+            Value::Object(_) => return Err(FixError::ParseMessageError(format!("JSON: field {} can not be an object", key)))
+        }
+    }
+    Ok(())
+}
+
+fn json_error(what: &str, pos: usize) -> FixError {
+    FixError::ParseMessageError(format!("JSON: {} at offset {}", what, pos))
+}
+
+//Value is the part of JSON a FIX message needs, numbers are kept as their text. Objects keep their member order.
+enum Value {
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>)
+}
+
+struct Parser<'a> {
+    json: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+
+    fn parse_document(&mut self) -> Result<Value, FixError> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos != self.json.len() {
+            return Err(json_error("trailing characters", self.pos));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.json.len() && (self.json[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.json.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), FixError> {
+        if self.peek() != Some(c) {
+            return Err(json_error(&format!("expected '{}'", c as char), self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<Value, FixError> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(Value::String(self.parse_string()?)),
+            Some(b'-') | Some(b'0'..=b'9') => {
+                let start = self.pos;
+                while self.pos < self.json.len() && b"+-.eE0123456789".contains(&self.json[self.pos]) {
+                    self.pos += 1;
+                }
+                Ok(Value::String(String::from_utf8_lossy(&self.json[start..self.pos]).into_owned()))
+            },
+            _ => Err(json_error("expected a value", self.pos))
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, FixError> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            members.push((key, self.parse_value()?));
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                },
+                _ => return Err(json_error("expected ',' or '}'", self.pos))
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, FixError> {
+        self.expect(b'[')?;
+        let mut values = vec![];
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                },
+                _ => return Err(json_error("expected ',' or ']'", self.pos))
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, FixError> {
+        self.expect(b'"')?;
+        let mut bytes = vec![];
+        loop {
+            let c = match self.json.get(self.pos) {
+                Some(c) => *c,
+                None => return Err(json_error("unterminated string", self.pos))
+            };
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.json.get(self.pos) {
+                        Some(escaped) => *escaped,
+                        None => return Err(json_error("unterminated string", self.pos))
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'"' | b'\\' | b'/' => bytes.push(escaped),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'u' => {
+                            let c = self.parse_unicode_escape()?;
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        },
+                        _ => return Err(json_error("invalid escape", self.pos))
+                    }
+                },
+                c => bytes.push(c)
+            }
+        }
+        String::from_utf8(bytes).map_err(FixError::FromUtf8Error)
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, FixError> {
+        let hex = self.json.get(self.pos..self.pos + 4).and_then(|hex| ::std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u32::from_str_radix(hex, 16).ok()) {
+            Some(code) => {
+                self.pos += 4;
+                Ok(code)
+            },
+            None => Err(json_error("invalid \\u escape", self.pos))
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, FixError> {
+        let mut code = self.parse_hex4()?;
+        if (0xd800..0xdc00).contains(&code) && self.json[self.pos..].starts_with(b"\\u") {
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+        }
+        char::from_u32(code).ok_or_else(|| json_error("invalid \\u escape", self.pos))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data_dictionary::*;
+
+    static FIX44: &'static str = r#"<fix type="FIX" major="4" minor="4" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+  </header>
+  <trailer>
+    <field name="SignatureLength" required="N"/>
+    <field name="Signature" required="N"/>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="NewOrderSingle" msgtype="D" msgcat="app">
+      <field name="ClOrdID" required="Y"/>
+      <group name="NoPartyIDs" required="N">
+        <field name="PartyID" required="Y"/>
+        <field name="PartyRole" required="N"/>
+      </group>
+    </message>
+  </messages>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="11" name="ClOrdID" type="STRING"/>
+    <field number="35" name="MsgType" type="STRING"/>
+    <field number="89" name="Signature" type="DATA"/>
+    <field number="93" name="SignatureLength" type="LENGTH"/>
+    <field number="448" name="PartyID" type="STRING"/>
+    <field number="452" name="PartyRole" type="INT"/>
+    <field number="453" name="NoPartyIDs" type="NUMINGROUP"/>
+  </fields>
+</fix>"#;
+
+    static RAW: &'static [u8] = b"8=FIX.4.4\x019=85\x0135=A\x0149=TW\x0156=ISLD\x01627=2\x01628=HOP1\x01630=R1\x01628=HOP2\x01630=R2\x0198=0\x01108=030\x0158=a \"quoted\"\n\x0110=029\x01";
+
+    #[test]
+    fn tag_numbers_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!("{\"Header\":{\"8\":\"FIX.4.4\",\"9\":\"85\",\"35\":\"A\",\"49\":\"TW\",\"56\":\"ISLD\",\"627\":[{\"628\":\"HOP1\",\"630\":\"R1\"},{\"628\":\"HOP2\",\"630\":\"R2\"}]},\
+\"Body\":{\"98\":\"0\",\"108\":\"030\",\"58\":\"a \\\"quoted\\\"\\n\"},\"Trailer\":{\"10\":\"029\"}}", to_json(&msg, JsonKeys::TagNumbers, &TagNames).unwrap());
+    }
+
+    #[test]
+    fn field_names_test() {
+        let msg = Message::parse(RAW).unwrap();
+        let json = to_json(&msg, JsonKeys::FieldNames, &TagNames).unwrap();
+        assert!(json.starts_with("{\"Header\":{\"BeginString\":\"FIX.4.4\",\"BodyLength\":\"85\",\"MsgType\":\"A\""));
+        assert!(json.contains("\"NoHops\":[{\"HopCompID\":\"HOP1\",\"HopRefID\":\"R1\"},{\"HopCompID\":\"HOP2\",\"HopRefID\":\"R2\"}]"));
+        assert!(json.contains("\"HeartBtInt\":\"030\""));
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let msg = Message::parse(RAW).unwrap();
+        for keys in [JsonKeys::TagNumbers, JsonKeys::FieldNames].iter() {
+            let decoded = from_json(&to_json(&msg, *keys, &TagNames).unwrap(), &TagNames).unwrap();
+            assert_eq!(RAW, decoded.raw_message());
+        }
+    }
+
+    #[test]
+    fn computed_length_and_checksum_test() {
+        let json = "{\"Header\": {\"BeginString\": \"FIX.4.2\", \"MsgType\": \"0\", \"MsgSeqNum\": 1}, \"Body\": {}}";
+        let msg = from_json(json, &TagNames).unwrap();
+        assert_eq!(b"8=FIX.4.2\x019=10\x0135=0\x0134=1\x0110=163\x01".to_vec(), msg.raw_message().to_vec());
+    }
+
+    #[test]
+    fn invalid_json_test() {
+        assert!(from_json("{\"Header\": {\"Symbol\": \"MSFT\"}}", &TagNames).is_err());
+        assert!(from_json("{\"Header\": {\"8\": \"FIX.4.2\"", &TagNames).is_err());
+        assert!(from_json("[]", &TagNames).is_err());
+    }
+
+    #[test]
+    fn dictionary_groups_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        let raw = b"8=FIX.4.4\x019=46\x0135=D\x0111=ID1\x01453=2\x01448=TW\x01452=1\x01448=ISLD\x01452=3\x0110=255\x01";
+        let msg = Message::parse(raw).unwrap();
+        let json = to_json(&msg, JsonKeys::FieldNames, &dictionary).unwrap();
+        assert_eq!("{\"Header\":{\"BeginString\":\"FIX.4.4\",\"BodyLength\":\"46\",\"MsgType\":\"D\"},\"Body\":{\"ClOrdID\":\"ID1\",\
+\"NoPartyIDs\":[{\"PartyID\":\"TW\",\"PartyRole\":\"1\"},{\"PartyID\":\"ISLD\",\"PartyRole\":\"3\"}]},\"Trailer\":{\"CheckSum\":\"255\"}}", json);
+        assert_eq!(&raw[..], from_json(&json, &dictionary).unwrap().raw_message());
+    }
+
+    #[test]
+    fn data_fields_test() {
+        let dictionary = DataDictionary::parse(FIX44).unwrap();
+        let raw = b"8=FIX.4.4\x019=25\x0135=D\x0111=ID1\x0193=4\x0189=\x01\xff\x00=\x0110=017\x01";
+        let msg = Message::parse(raw).unwrap();
+        for names in [&dictionary as &dyn FieldNames, &TagNames].iter() {
+            let json = to_json(&msg, JsonKeys::TagNumbers, *names).unwrap();
+            assert!(json.contains("\"89\":\"Af8APQ==\""));
+            assert_eq!(&raw[..], from_json(&json, *names).unwrap().raw_message());
+        }
+
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        msg.body.set_bytes(58, b"\xff");
+        match to_json(&msg, JsonKeys::TagNumbers, &TagNames) {
+            Err(FixError::SerializeError(_)) => {},
+            other => panic!("expected a SerializeError, got {:?}", other)
+        }
+        assert!(from_json("{\"Trailer\": {\"89\": \"A===\"}}", &TagNames).is_err());
+    }
+
+    #[test]
+    fn base64_test() {
+        for value in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"].iter() {
+            assert_eq!(Some(value.to_vec()), base64_decode(&base64_encode(value)));
+        }
+        assert_eq!("Zm9vYg==", base64_encode(b"foob"));
+        assert_eq!(None, base64_decode("Zm9"));
+        assert_eq!(None, base64_decode("Zg==Zg=="));
+        assert_eq!(None, base64_decode("Z!=="));
+    }
+}
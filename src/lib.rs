@@ -1,6 +1,26 @@
 #[macro_use] extern crate enum_primitive;
 extern crate num;
 extern crate time;
+extern crate hmac;
+extern crate sha2;
+#[cfg(feature = "bytes")]
+extern crate bytes;
+#[cfg(feature = "serde")]
+#[macro_use] extern crate serde;
+#[cfg(feature = "tls")]
+extern crate rustls;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "async")]
+extern crate tokio_rustls;
+#[cfg(feature = "tls")]
+extern crate rustls_pemfile;
+#[cfg(all(test, feature = "tls"))]
+extern crate rcgen;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use] extern crate serde_derive;
 mod tag;
 mod tag_value;
 mod field_map;
@@ -10,10 +30,112 @@ mod fix_string;
 mod fix_boolean;
 mod fix_int;
 mod fix_utc_timestamp;
+mod fix_decimal;
+mod message;
+mod message_view;
+mod message_writer;
+mod signature;
+mod pretty;
+mod validator;
+mod data_dictionary;
+mod json;
+mod xml;
+mod fixml;
+mod sbe;
+mod fast;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "serde")]
+mod serde_tag_value;
+mod begin_string;
+mod msg_type;
+mod session;
+mod session_id;
+mod session_handle;
+mod message_router;
+mod authentication;
+mod dynamic_session;
+mod throttle;
+mod settings;
+mod store;
+mod log;
+mod screen_log;
+mod file_log;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+
+pub use tag::{Tag, Tags};
+pub use tag_value::{TagValue, SOH};
+pub use field_map::{Field, FieldMap, FieldMapIter, TagOrder, MergePolicy};
+pub use field::{FieldValueWriter, FieldValueReader, FieldValue, FieldWriter, FieldInterface, FieldGroupWriter, FieldGroupReader, FieldGroup};
+pub use error::{FixError, Reject, BusinessRejectReason, FixBooleanParseError};
+pub use error::error::MessageRejectError;
+pub use error::MessageRejectError as MessageRejectErrorTrait;
+pub use fix_utc_timestamp::FIXUTCTimestamp;
+pub use message::{Message, FieldOrder};
+pub use message_view::{MessageView, FieldOffset, FieldIter};
+pub use message_writer::{MessageWriter, WriteBuffer};
+pub use signature::{MessageSigner, HmacSha256Signer, sign_message, verify_message};
+pub use pretty::{PrettyMessage, FieldNames, TagNames};
+pub use validator::{validate, validate_with_dictionaries};
+pub use data_dictionary::{DataDictionary, DataDictionaryProvider, DictionaryField, DictionaryMember, DictionaryGroup, DictionaryMessage, FieldType};
+pub use json::{to_json, from_json, JsonKeys};
+pub use fixml::{to_fixml, from_fixml, FixmlSchema, FixmlComponent, FixmlTags};
+pub use sbe::{SbeCodec, SbeSchema, SbeView, SbeMessage, SbeBlock, SbeGroup, SbeData, SbeField, SbeType, Primitive};
+pub use sbe::{sofh_frame, sofh_unframe, is_sofh_frame, SOFH_SBE_LITTLE_ENDIAN, SOFH_LEN};
+pub use fast::{FastTemplates, FastTemplate, FastInstruction, FastField, FastOperation, FastOperator, FastType, FastDecoder, FastEncoder};
+#[cfg(feature = "serde")]
+pub use serde_tag_value::{to_bytes, to_bytes_with_names, to_message, from_message, from_message_with_names, Serializer, GroupSerializer, Deserializer};
+pub use begin_string::{BEGIN_STRING_FIX40, BEGIN_STRING_FIX41, BEGIN_STRING_FIX42, BEGIN_STRING_FIX43, BEGIN_STRING_FIX44, BEGIN_STRING_FIXT11};
+pub use begin_string::{BEGIN_STRING_FIX50, BEGIN_STRING_FIX50SP1, BEGIN_STRING_FIX50SP2, appl_ver_id_begin_string, begin_string_appl_ver_id};
+pub use msg_type::{MSG_TYPE_HEARTBEAT, MSG_TYPE_TEST_REQUEST, MSG_TYPE_RESEND_REQUEST, MSG_TYPE_REJECT, MSG_TYPE_SEQUENCE_RESET, MSG_TYPE_LOGOUT, MSG_TYPE_LOGON};
+pub use msg_type::{MSG_TYPE_BUSINESS_MESSAGE_REJECT, is_admin_message_type, msg_type_name};
+pub use session::{Session, SessionState, reject_reply};
+pub use session_id::SessionID;
+pub use session_handle::SessionHandle;
+pub use message_router::{MessageRouter, MessageRoute};
+pub use authentication::{Credentials, Authenticator, LogonRateLimiter};
+pub use dynamic_session::{SessionTemplate, DynamicSessions, WILDCARD};
+pub use throttle::{Throttle, ThrottleMode, TokenBucket};
+pub use settings::SessionSettings;
+pub use settings::{BEGIN_STRING, SENDER_COMP_ID, SENDER_SUB_ID, SENDER_LOCATION_ID, TARGET_COMP_ID, TARGET_SUB_ID, TARGET_LOCATION_ID, SESSION_QUALIFIER};
+pub use settings::{CHECK_LATENCY, MAX_LATENCY, CHECK_COMP_ID, HEART_BT_INT, RESET_ON_LOGON, RESET_ON_LOGOUT, RESET_ON_DISCONNECT, DEFAULT_APPL_VER_ID};
+pub use settings::{DATA_DICTIONARY, TRANSPORT_DATA_DICTIONARY, APP_DATA_DICTIONARY, SIGNATURE_KEY, ACCEPTOR_TEMPLATE};
+pub use settings::{THROTTLE_MESSAGES_PER_SECOND, THROTTLE_BURST, THROTTLE_ADMIN_MESSAGES_PER_SECOND, THROTTLE_ADMIN_BURST, THROTTLE_MODE};
+pub use settings::{USERNAME, PASSWORD, NEW_PASSWORD, SOCKET_CONNECT_HOST, SOCKET_CONNECT_PORT, SOCKET_ACCEPT_PORT, MAX_MESSAGE_SIZE};
+pub use settings::{SOCKET_USE_SSL, SOCKET_CA_FILE, SOCKET_CERTIFICATE_FILE, SOCKET_PRIVATE_KEY_FILE, SOCKET_SERVER_NAME, SOCKET_MINIMUM_TLS_VERSION, SOCKET_CIPHER_SUITES};
+pub use settings::{FILE_LOG_PATH, FILE_LOG_MAX_SIZE, FILE_LOG_ROTATE_INTERVAL, FILE_LOG_BACKUP_COUNT};
+pub use store::{MessageStore, MessageStoreFactory, MemoryStore, MemoryStoreFactory};
+pub use log::{Log, LogFactory, NullLog, NullLogFactory, mask_sensitive};
+pub use screen_log::{ScreenLog, ScreenLogFactory};
+pub use file_log::{FileLog, FileLogFactory, RotatingFileLogFactory};
+#[cfg(feature = "tls")]
+pub use tls::{use_tls, client_config, server_config, server_name, connect as tls_connect, accept as tls_accept};
+pub use transport::{MessageFramer, Stream, bind, connect, accept, run_initiator, run_acceptor, run_dynamic_acceptor};
+#[cfg(feature = "async")]
+pub use transport::{AsyncStream, AsyncConnect, AsyncAccept, AsyncRun, AsyncDynamicRun, connect_async, accept_async, run_initiator_async, run_acceptor_async, run_dynamic_acceptor_async};
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
     }
+
+    #[test]
+    fn public_api_test() {
+        let mut msg = Message::default();
+        msg.header.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        msg.header.set_string(Tags::MsgType.to_num(), "0");
+        msg.header.set_int(Tags::MsgSeqNum.to_num(), 1);
+
+        let parsed = Message::parse(&msg.build()).unwrap();
+        assert_eq!(1, parsed.header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert_eq!("8=FIX.4.4|9=10|35=0|34=1|10=165", parsed.to_string());
+
+        let err = MessageRejectError::new_message_reject_error("Required tag missing".to_string(), Reject::RequiredTagMissing, Tags::MsgSeqNum.to_num());
+        assert_eq!(Tags::MsgSeqNum.to_num(), MessageRejectErrorTrait::ref_tag_id(&err));
+    }
 }
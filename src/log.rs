@@ -0,0 +1,125 @@
+use error::FixError;
+use session_id::*;
+use tag::*;
+use tag_value::SOH;
+use pretty::MASK;
+
+//Log is a generic interface for logging FIX messages and events
+pub trait Log {
+    //on_incoming logs a message received
+    fn on_incoming(&mut self, msg: &[u8]);
+
+    //on_outgoing logs a message sent
+    fn on_outgoing(&mut self, msg: &[u8]);
+
+    //on_event logs a session event
+    fn on_event(&mut self, text: &str);
+
+    //take_error returns the first failure since the last call, logs writing to files keep failed writes here as
+    //the methods above can not return them
+    fn take_error(&mut self) -> Option<FixError> {
+        None
+    }
+}
+
+//The LogFactory interface creates global and session specific Log instances
+pub trait LogFactory {
+    //create creates a global log
+    fn create(&self) -> Result<Box<dyn Log + Send>, FixError>;
+
+    //create_session_log creates a log for session_id
+    fn create_session_log(&self, session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError>;
+}
+
+//NullLog discards everything, it is what a session logs to when no LogFactory is given
+pub struct NullLog;
+
+impl Log for NullLog {
+    fn on_incoming(&mut self, _msg: &[u8]) {}
+    fn on_outgoing(&mut self, _msg: &[u8]) {}
+    fn on_event(&mut self, _text: &str) {}
+}
+
+pub struct NullLogFactory;
+
+impl NullLogFactory {
+
+    pub fn new() -> NullLogFactory {
+        NullLogFactory
+    }
+}
+
+impl Default for NullLogFactory {
+    fn default() -> NullLogFactory {
+        NullLogFactory::new()
+    }
+}
+
+impl LogFactory for NullLogFactory {
+    fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(NullLog))
+    }
+
+    fn create_session_log(&self, _session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(NullLog))
+    }
+}
+
+//mask_sensitive replaces the values of sensitive fields such as Password(554) with MASK, sessions mask every
+//message before it reaches their log. The value of a data field such as Signature(89) is the number of bytes
+//its length field gives, SOH included.
+pub fn mask_sensitive(msg: &[u8]) -> Vec<u8> {
+    let mut masked = Vec::with_capacity(msg.len());
+    //data is the tag and length of the data field announced by the previous field
+    let mut data: Option<(u32, usize)> = None;
+    let mut pos = 0;
+    while pos < msg.len() {
+        let field_end = msg[pos..].iter().position(|b| *b == SOH).map_or(msg.len(), |end| pos + end);
+        let tag = msg[pos..field_end].iter().position(|b| *b == b'=')
+            .and_then(|equals| String::from_utf8_lossy(&msg[pos..pos + equals]).parse::<u32>().ok().map(|tag| (pos + equals + 1, tag)));
+        let (value_start, value_end) = match (tag, data.take()) {
+            (Some((value_start, tag)), Some((data_tag, length))) if tag == data_tag && value_start + length <= msg.len() =>
+                (value_start, value_start + length),
+            (Some((value_start, _)), _) => (value_start, field_end),
+            (None, _) => (field_end, field_end)
+        };
+        masked.extend_from_slice(&msg[pos..value_start]);
+        match tag {
+            Some((_, tag)) if tag.is_sensitive() => masked.extend_from_slice(MASK.as_bytes()),
+            Some((_, tag)) => {
+                let value = &msg[value_start..value_end];
+                data = tag.data_tag().and_then(|data_tag| String::from_utf8_lossy(value).parse::<usize>().ok().map(|length| (data_tag, length)));
+                masked.extend_from_slice(value);
+            },
+            None => {}
+        }
+        if value_end < msg.len() {
+            masked.push(msg[value_end]);
+        }
+        pos = value_end + 1;
+    }
+    masked
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mask_sensitive_test() {
+        let msg = b"8=FIX.4.4\x019=40\x0135=A\x01553=trader\x01554=secret\x01925=secret2\x0110=000\x01";
+        assert_eq!(&b"8=FIX.4.4\x019=40\x0135=A\x01553=trader\x01554=********\x01925=********\x0110=000\x01"[..], mask_sensitive(msg).as_slice());
+        assert_eq!(&b"35=0"[..], mask_sensitive(b"35=0").as_slice());
+    }
+
+    #[test]
+    fn mask_sensitive_data_test() {
+        //the signature holds SOH and what looks like a Password field
+        let msg = b"8=FIX.4.4\x0135=A\x01554=secret\x0193=9\x0189=\x01554=ab\x01|\x0110=000\x01";
+        assert_eq!(&b"8=FIX.4.4\x0135=A\x01554=********\x0193=9\x0189=\x01554=ab\x01|\x0110=000\x01"[..], mask_sensitive(msg).as_slice());
+        //a length beyond the message leaves the rest as it is
+        let msg = b"93=9\x0189=\x01554=a\x01";
+        assert_eq!(&b"93=9\x0189=\x01554=********\x01"[..], mask_sensitive(msg).as_slice());
+        assert_eq!(&b"=x\x01\x01abc"[..], mask_sensitive(b"=x\x01\x01abc").as_slice());
+    }
+}
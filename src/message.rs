@@ -1 +1,543 @@
-struct Header{ }
\ No newline at end of file
+use tag::*;
+use tag_value::*;
+use std::*;
+use field_map::*;
+use error::FixError;
+use message_view::*;
+use message_writer::*;
+use begin_string::*;
+use pretty::*;
+use data_dictionary::*;
+use error::error::MessageRejectError;
+
+//FieldOrder selects how a parsed message is serialized again. Either way the instances of a repeating group stay
+//together after their NumInGroup field, in the order they were received.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FieldOrder {
+    //header, body and trailer are each written in their canonical tag order, a repeated tag keeps its last value
+    Normalized,
+    //fields are written back in the exact sequence they were received, a repeated tag is a TagAppearsMoreThanOnce reject
+    Received
+}
+
+//Message is a FIX Message abstraction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub header: FieldMap,
+    pub body: FieldMap,
+    pub trailer: FieldMap,
+
+    //fields as they were received, in order
+    fields: Vec<TagValue>,
+    //tags of header, body and trailer fields as they were received, without the fields of group instances
+    received: Vec<u32>,
+    raw_message: Vec<u8>,
+    field_order: FieldOrder
+}
+
+impl Message {
+
+    pub fn new() -> Message {
+        Message{
+            header: FieldMap::new_with_ordering(FieldMap::header_field_order),
+            body: FieldMap::new(),
+            trailer: FieldMap::new_with_ordering(FieldMap::trailer_field_order),
+            fields: vec![],
+            received: vec![],
+            raw_message: vec![],
+            field_order: FieldOrder::Normalized
+        }
+    }
+
+    pub fn parse(raw: &[u8]) -> Result<Message, FixError> {
+        Message::parse_with_order(raw, FieldOrder::Normalized)
+    }
+
+    pub fn parse_with_order(raw: &[u8], field_order: FieldOrder) -> Result<Message, FixError> {
+        Message::parse_fields(raw, field_order, |fields, pos| Ok(read_field(fields, pos)))
+    }
+
+    //parse_with_dictionary reads the repeating groups the dictionaries define, a group of a single instance
+    //included. transport describes the header and the trailer, the body follows the dictionary defining the MsgType,
+    //application for application messages; outside FIXT.1.1 both are the same dictionary. A NumInGroup count that
+    //does not match the instances received is an IncorrectNumInGroupCountForRepeatingGroup reject.
+    pub fn parse_with_dictionary(raw: &[u8], transport: &DataDictionary, application: &DataDictionary) -> Result<Message, FixError> {
+        Message::parse_with_dictionary_and_order(raw, transport, application, FieldOrder::Normalized)
+    }
+
+    //parse_with_dictionary_and_order is parse_with_dictionary keeping the fields in field_order
+    pub fn parse_with_dictionary_and_order(raw: &[u8], transport: &DataDictionary, application: &DataDictionary,
+                                           field_order: FieldOrder) -> Result<Message, FixError> {
+        Message::parse_fields(raw, field_order, |fields, pos| {
+            let tag = fields[pos].0;
+            let members = if tag.is_header() {
+                transport.header()
+            } else if tag.is_trailer() {
+                transport.trailer()
+            } else {
+                let msg_type = str::from_utf8(fields[2].1).unwrap_or("");
+                match body_dictionary(transport, application, msg_type).message(msg_type) {
+                    Some(message) => message.members.as_slice(),
+                    None => &[]
+                }
+            };
+            read_dictionary_field(fields, pos, members)
+        })
+    }
+
+    //parse_fields reads the fields of raw into header, body and trailer, read_field taking the field at a position
+    //and giving it back with the number of fields it spans
+    fn parse_fields<F>(raw: &[u8], field_order: FieldOrder, read_field: F) -> Result<Message, FixError>
+        where F: Fn(&[(u32, &[u8])], usize) -> Result<(Field, usize), MessageRejectError> {
+        let view = MessageView::parse(raw)?;
+
+        let leading: Vec<u32> = view.iter().take(3).map(|(tag, _)| tag).collect();
+        if leading != vec![Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::MsgType.to_num()] {
+            return Err(FixError::ParseMessageError("Message.parse: BeginString, BodyLength and MsgType must lead the message".to_string()));
+        }
+
+        let mut msg = Message::new();
+        msg.field_order = field_order;
+        msg.raw_message.extend_from_slice(raw);
+
+        let fields: Vec<(u32, &[u8])> = view.iter().collect();
+        msg.fields = fields.iter().map(|&(tag, value)| TagValue::new(tag, value)).collect();
+
+        let mut pos = 0;
+        while pos < fields.len() {
+            let (field, span) = read_field(&fields, pos).map_err(FixError::RejectError)?;
+            let tag = field.field_tag();
+            msg.received.push(tag);
+            let section = msg.section_mut(tag);
+            match field_order {
+                FieldOrder::Normalized => section.add(field),
+                FieldOrder::Received => if let Err(err) = section.try_add(field) {
+                    return Err(FixError::RejectError(err));
+                }
+            }
+            pos += span;
+        }
+
+        Ok(msg)
+    }
+
+    pub fn field_order(&self) -> FieldOrder {
+        self.field_order
+    }
+
+    //raw_message is the buffer this message was parsed from
+    pub fn raw_message(&self) -> &[u8] {
+        self.raw_message.as_ref()
+    }
+
+    //fields lists the fields of the parsed message in the order they were received
+    pub fn fields(&self) -> &[TagValue] {
+        self.fields.as_ref()
+    }
+
+    //application_version is the FIX version of the application content, on FIXT.1.1 it is named by
+    //ApplVerID(1128) or, when that is absent, by the DefaultApplVerID negotiated at Logon
+    pub fn application_version(&self, default_appl_ver_id: &str) -> String {
+        let begin_string = self.header.get_string(Tags::BeginString.to_num()).unwrap_or(String::new());
+        if begin_string != BEGIN_STRING_FIXT11 {
+            return begin_string;
+        }
+
+        let appl_ver_id = self.header.get_string(Tags::ApplVerID.to_num()).unwrap_or(default_appl_ver_id.to_string());
+        appl_ver_id_begin_string(&appl_ver_id).to_string()
+    }
+
+    //cstm_appl_ver_id names the custom application version of CstmApplVerID(1129), if any
+    pub fn cstm_appl_ver_id(&self) -> Option<String> {
+        self.header.get_string(Tags::CstmApplVerID.to_num()).ok()
+    }
+
+    //pretty renders the message with field and value names, see PrettyMessage
+    pub fn pretty(&self) -> PrettyMessage<'_> {
+        PrettyMessage::new(self)
+    }
+
+    //reverse_route returns a new message whose header routes back to the sender of this one
+    pub fn reverse_route(&self) -> Message {
+        let mut reply = Message::new();
+        if let Ok(begin_string) = self.header.get_bytes(Tags::BeginString.to_num()) {
+            reply.header.set_bytes(Tags::BeginString.to_num(), begin_string);
+        }
+
+        let routes = [
+            (Tags::SenderCompID, Tags::TargetCompID),
+            (Tags::SenderSubID, Tags::TargetSubID),
+            (Tags::SenderLocationID, Tags::TargetLocationID),
+            (Tags::TargetCompID, Tags::SenderCompID),
+            (Tags::TargetSubID, Tags::SenderSubID),
+            (Tags::TargetLocationID, Tags::SenderLocationID),
+            (Tags::OnBehalfOfCompID, Tags::DeliverToCompID),
+            (Tags::OnBehalfOfSubID, Tags::DeliverToSubID),
+            (Tags::OnBehalfOfLocationID, Tags::DeliverToLocationID),
+            (Tags::DeliverToCompID, Tags::OnBehalfOfCompID),
+            (Tags::DeliverToSubID, Tags::OnBehalfOfSubID),
+            (Tags::DeliverToLocationID, Tags::OnBehalfOfLocationID),
+        ];
+        for &(from, to) in routes.iter() {
+            if let Ok(value) = self.header.get_bytes(from.to_num()) {
+                if !value.is_empty() {
+                    reply.header.set_bytes(to.to_num(), value);
+                }
+            }
+        }
+        reply
+    }
+
+    fn section(&self, tag: u32) -> &FieldMap {
+        if tag.is_header() {
+            &self.header
+        } else if tag.is_trailer() {
+            &self.trailer
+        } else {
+            &self.body
+        }
+    }
+
+    fn section_mut(&mut self, tag: u32) -> &mut FieldMap {
+        if tag.is_header() {
+            &mut self.header
+        } else if tag.is_trailer() {
+            &mut self.trailer
+        } else {
+            &mut self.body
+        }
+    }
+
+    //build serializes the message, BodyLength and CheckSum are always recomputed
+    pub fn build(&self) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::with_capacity(self.raw_message.len() + 64);
+        self.write_to(&mut buffer);
+        buffer
+    }
+
+    pub fn write_to<B: WriteBuffer>(&self, buffer: &mut B) -> usize {
+        let begin_string = self.header.get_bytes(Tags::BeginString.to_num()).unwrap_or(b"");
+        let msg_type = self.header.get_bytes(Tags::MsgType.to_num()).unwrap_or(b"");
+        let mut writer = MessageWriter::new(buffer, str::from_utf8(begin_string).unwrap_or(""), str::from_utf8(msg_type).unwrap_or(""));
+
+        let mut fields = vec![];
+        for tag in self.write_order() {
+            if let Some(field) = self.section(tag).lookup_field(tag) {
+                field.flatten(&mut fields);
+            }
+        }
+        for (tag, value) in fields {
+            writer.write_bytes(tag, value);
+        }
+        writer.finish()
+    }
+
+    //write_order lists every tag but BeginString, BodyLength, MsgType and CheckSum in the order they are written
+    fn write_order(&self) -> Vec<u32> {
+        let mut header = self.header.sorted_tags().to_vec();
+        let mut body = self.body.sorted_tags().to_vec();
+        let mut trailer = self.trailer.sorted_tags().to_vec();
+
+        let mut tags: Vec<u32> = vec![];
+        if self.field_order == FieldOrder::Received {
+            //received fields first, anything set afterwards goes at the end of its section
+            let received = &self.received;
+            header.retain(|t| !received.contains(t));
+            body.retain(|t| !received.contains(t));
+            trailer.retain(|t| !received.contains(t));
+
+            let trailer_start = received.iter().position(|t| t.is_trailer()).unwrap_or(received.len());
+            tags.extend_from_slice(&received[..trailer_start]);
+            tags.append(&mut header);
+            tags.append(&mut body);
+            tags.extend_from_slice(&received[trailer_start..]);
+            tags.append(&mut trailer);
+        } else {
+            tags.append(&mut header);
+            tags.append(&mut body);
+            tags.append(&mut trailer);
+        }
+
+        let skipped = [Tags::BeginString.to_num(), Tags::BodyLength.to_num(), Tags::MsgType.to_num(), Tags::CheckSum.to_num()];
+        tags.retain(|t| !skipped.contains(t));
+        tags
+    }
+}
+
+//read_field reads the field at pos of a parsed message and tells how many fields it spans. Without a data
+//dictionary a repeating group is recognized by its instances: a NumInGroup value N of 2 or more followed by N
+//runs of fields that each start with the same delimiter tag. A single instance is only a group when its count tag
+//is one TagNames knows the members of, other single instance groups need parse_with_dictionary.
+fn read_field(fields: &[(u32, &[u8])], pos: usize) -> (Field, usize) {
+    let (tag, value) = fields[pos];
+    if let Some((instances, span)) = read_group(fields, pos) {
+        return (Field::from_group(tag, instances), span);
+    }
+    (Field::from_tag_value(TagValue::new(tag, value)), 1)
+}
+
+fn read_group(fields: &[(u32, &[u8])], pos: usize) -> Option<(Vec<FieldMap>, usize)> {
+    //the members of a known group, an unknown one learns them from its first instances
+    let mut members: Vec<u32> = TagNames.group_fields(fields[pos].0).unwrap_or_default();
+    let count = match str::from_utf8(fields[pos].1).ok().and_then(|value| value.parse::<usize>().ok()) {
+        //every instance holds at least its delimiter
+        Some(count) if (count >= 2 || (count == 1 && !members.is_empty())) && count < fields.len() - pos => count,
+        _ => return None
+    };
+    let delimiter = fields[pos + 1].0;
+    if !members.is_empty() && members[0] != delimiter {
+        return None;
+    }
+
+    let mut instances = Vec::with_capacity(count);
+    let mut next = pos + 1;
+    for i in 0..count {
+        if next >= fields.len() || fields[next].0 != delimiter {
+            return None;
+        }
+        let last = i == count - 1;
+        let mut instance = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        instance.add(Field::from_tag_value(TagValue::new(delimiter, fields[next].1)));
+        next += 1;
+        while next < fields.len() {
+            let tag = fields[next].0;
+            //the last instance ends with the first field the previous ones did not hold
+            if tag == delimiter || instance.has(tag) || (last && !members.contains(&tag)) {
+                break;
+            }
+            let (field, span) = read_field(fields, next);
+            instance.add(field);
+            if !members.contains(&tag) {
+                members.push(tag);
+            }
+            next += span;
+        }
+        instances.push(instance);
+    }
+    Some((instances, next - pos))
+}
+
+//read_dictionary_field reads the field at pos, a NumInGroup field of members together with its instances. An
+//instance starts with the delimiter of the group and ends with the first field it does not define or already holds.
+fn read_dictionary_field(fields: &[(u32, &[u8])], pos: usize, members: &[DictionaryMember]) -> Result<(Field, usize), MessageRejectError> {
+    let (tag, value) = fields[pos];
+    let group = match members.iter().find(|member| member.tag() == tag) {
+        Some(DictionaryMember::Group(group)) => group,
+        _ => return Ok((Field::from_tag_value(TagValue::new(tag, value)), 1))
+    };
+    let count = match str::from_utf8(value).ok().and_then(|value| value.parse::<usize>().ok()) {
+        Some(count) => count,
+        None => return Err(MessageRejectError::incorrect_data_format_for_value(tag))
+    };
+
+    let mut instances = vec![];
+    let mut next = pos + 1;
+    while next < fields.len() && Some(fields[next].0) == group.delimiter() {
+        if instances.len() == count {
+            return Err(MessageRejectError::incorrect_num_in_group_count_for_repeating_group(tag));
+        }
+        let mut instance = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        loop {
+            let (field, span) = read_dictionary_field(fields, next, &group.members)?;
+            instance.add(field);
+            next += span;
+            if next >= fields.len() {
+                break;
+            }
+            let tag = fields[next].0;
+            if Some(tag) == group.delimiter() || !group.contains(tag) || instance.has(tag) {
+                break;
+            }
+        }
+        instances.push(instance);
+    }
+
+    if instances.len() != count {
+        return Err(MessageRejectError::incorrect_num_in_group_count_for_repeating_group(tag));
+    }
+    if count == 0 {
+        return Ok((Field::from_tag_value(TagValue::new(tag, value)), 1));
+    }
+    Ok((Field::from_group(tag, instances), next - pos))
+}
+
+impl Default for Message {
+    fn default() -> Message {
+        Message::new()
+    }
+}
+
+//Message renders header, body and trailer as tag=value pairs separated by '|'
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sections: Vec<String> = [&self.header, &self.body, &self.trailer].iter()
+            .filter(|section| !section.is_empty())
+            .map(|section| section.to_string())
+            .collect();
+        write!(f, "{}", sections.join("|"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tag::*;
+    use error::Reject;
+    use error::MessageRejectError as MessageRejectErrorTrait;
+
+    static RAW: &'static [u8] = b"8=FIX.4.2\x019=39\x0135=D\x0149=TW\x0134=1\x0155=MSFT\x0111=ID1\x0156=ISLD\x0110=045\x01";
+
+    #[test]
+    fn parse_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!("TW", msg.header.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("MSFT", msg.body.get_string(55).unwrap());
+        assert_eq!("045", msg.trailer.get_string(Tags::CheckSum.to_num()).unwrap());
+        assert_eq!(9, msg.fields().len());
+        assert_eq!(RAW, msg.raw_message());
+    }
+
+    #[test]
+    fn build_normalized_test() {
+        let msg = Message::parse(RAW).unwrap();
+        let expected: &[u8] = b"8=FIX.4.2\x019=39\x0135=D\x0134=1\x0149=TW\x0156=ISLD\x0111=ID1\x0155=MSFT\x0110=045\x01";
+        assert_eq!(expected, msg.build().as_slice());
+    }
+
+    #[test]
+    fn build_received_is_byte_for_byte_test() {
+        let msg = Message::parse_with_order(RAW, FieldOrder::Received).unwrap();
+        assert_eq!(RAW, msg.build().as_slice());
+    }
+
+    #[test]
+    fn build_received_with_new_field_test() {
+        let mut msg = Message::parse_with_order(RAW, FieldOrder::Received).unwrap();
+        msg.body.set_bytes(38, b"100");
+        let built = msg.build();
+        let view = MessageView::parse(&built).unwrap();
+        let tags: Vec<u32> = view.iter().map(|(tag, _)| tag).collect();
+        assert_eq!(vec![8, 9, 35, 49, 34, 55, 11, 56, 38, 10], tags);
+    }
+
+    #[test]
+    fn received_duplicate_tag_test() {
+        let raw = b"8=FIX.4.2\x019=20\x0135=D\x0158=a\x0158=b\x0110=000\x01";
+        match Message::parse_with_order(raw, FieldOrder::Received) {
+            Err(FixError::RejectError(err)) => {
+                assert_eq!(Reject::TagAppearsMoreThanOnce, err.reject_reason());
+                assert_eq!(Tags::Text.to_num(), err.ref_tag_id());
+            },
+            _ => panic!("expected TagAppearsMoreThanOnce")
+        }
+
+        let msg = Message::parse(raw).unwrap();
+        assert_eq!("b", msg.body.get_string(Tags::Text.to_num()).unwrap());
+    }
+
+    static GROUP: &'static [u8] = b"8=FIX.4.4\x019=40\x0135=D\x01453=2\x01448=A\x01452=1\x01448=B\x01452=3\x0155=X\x0110=071\x01";
+
+    #[test]
+    fn body_group_test() {
+        for &field_order in [FieldOrder::Normalized, FieldOrder::Received].iter() {
+            let msg = Message::parse_with_order(GROUP, field_order).unwrap();
+            let parties = msg.body.get_group(453).unwrap();
+            assert_eq!(2, parties.len());
+            assert_eq!("B", parties[1].get_string(448).unwrap());
+            assert_eq!(3, parties[1].get_int(452).unwrap());
+            assert_eq!("X", msg.body.get_string(55).unwrap());
+            assert_eq!(2, msg.body.get_int(453).unwrap());
+        }
+
+        let msg = Message::parse_with_order(GROUP, FieldOrder::Received).unwrap();
+        assert_eq!(GROUP, msg.build().as_slice());
+
+        let built = Message::parse(GROUP).unwrap().build();
+        assert!(built.starts_with(b"8=FIX.4.4\x019=40\x0135=D\x0155=X\x01453=2\x01448=A\x01452=1\x01448=B\x01452=3\x0110="));
+    }
+
+    #[test]
+    fn single_instance_group_test() {
+        let raw: &[u8] = b"8=FIX.4.4\x019=37\x0135=D\x01627=1\x01628=HUB\x01630=R1\x0155=X\x01628=X\x0110=071\x01";
+        let msg = Message::parse_with_order(raw, FieldOrder::Received).unwrap();
+        let hops = msg.header.get_group(Tags::NoHops.to_num()).unwrap();
+        assert_eq!(1, hops.len());
+        assert_eq!("R1", hops[0].get_string(Tags::HopRefID.to_num()).unwrap());
+        //628 after the group is a field of its own, not a second HopCompID
+        assert_eq!("X", msg.header.get_string(628).unwrap());
+        assert_eq!(raw, msg.build().as_slice());
+
+        //a count of 1 on a tag of unknown members stays a plain field
+        let raw: &[u8] = b"8=FIX.4.4\x019=21\x0135=D\x0138=1\x0140=2\x0110=000\x01";
+        let msg = Message::parse_with_order(raw, FieldOrder::Received).unwrap();
+        assert_eq!(1, msg.body.get_int(38).unwrap());
+        assert_eq!(2, msg.body.get_int(40).unwrap());
+    }
+
+    #[test]
+    fn nested_group_test() {
+        let raw: &[u8] = b"8=FIX.4.4\x019=71\x0135=D\x01555=2\x01600=A\x01539=2\x01524=P1\x01524=P2\x01600=B\x01539=2\x01524=P3\x01524=P4\x0158=done\x0110=005\x01";
+        let msg = Message::parse_with_order(raw, FieldOrder::Received).unwrap();
+        let legs = msg.body.get_group(555).unwrap();
+        assert_eq!(2, legs.len());
+        let parties: Vec<String> = legs[1].get_group(539).unwrap().iter().map(|party| party.get_string(524).unwrap()).collect();
+        assert_eq!(vec!["P3", "P4"], parties);
+        assert_eq!("done", msg.body.get_string(58).unwrap());
+
+        assert_eq!(raw, msg.build().as_slice());
+    }
+
+    #[test]
+    fn set_group_test() {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        msg.header.set_string(Tags::MsgType.to_num(), "D");
+        let mut party = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        party.set_string(452, "1");
+        party.set_string(448, "A");
+        msg.body.set_group(453, vec![party.clone(), party]);
+        msg.body.set_string(55, "X");
+
+        let parsed = Message::parse(&msg.build()).unwrap();
+        assert_eq!(msg.body, parsed.body);
+        assert!(format!("{}", msg).contains("|55=X|453=2|452=1|448=A|452=1|448=A"));
+    }
+
+    #[test]
+    fn parse_header_order_test() {
+        assert!(Message::parse(b"35=D\x018=FIX.4.2\x019=5\x0110=000\x01").is_err());
+    }
+
+    #[test]
+    fn display_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!("8=FIX.4.2|9=39|35=D|34=1|49=TW|56=ISLD|11=ID1|55=MSFT|10=045", format!("{}", msg));
+    }
+
+    #[test]
+    fn application_version_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!(BEGIN_STRING_FIX42, msg.application_version("9"));
+
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), BEGIN_STRING_FIXT11);
+        assert_eq!(BEGIN_STRING_FIX50SP2, msg.application_version("9"));
+        msg.header.set_string(Tags::ApplVerID.to_num(), "7");
+        msg.header.set_string(Tags::CstmApplVerID.to_num(), "VENUE1");
+        assert_eq!(BEGIN_STRING_FIX50, msg.application_version("9"));
+        assert_eq!(Some("VENUE1".to_string()), msg.cstm_appl_ver_id());
+    }
+
+    #[test]
+    fn reverse_route_test() {
+        let raw = b"8=FIX.4.2\x019=49\x0135=D\x0149=TW\x0150=KK\x0156=ISLD\x01115=OBO\x01145=LOC\x0110=000\x01";
+        let reply = Message::parse(raw).unwrap().reverse_route();
+        assert_eq!("FIX.4.2", reply.header.get_string(Tags::BeginString.to_num()).unwrap());
+        assert_eq!("ISLD", reply.header.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("TW", reply.header.get_string(Tags::TargetCompID.to_num()).unwrap());
+        assert_eq!("KK", reply.header.get_string(Tags::TargetSubID.to_num()).unwrap());
+        assert_eq!("OBO", reply.header.get_string(Tags::DeliverToCompID.to_num()).unwrap());
+        assert_eq!("LOC", reply.header.get_string(Tags::OnBehalfOfLocationID.to_num()).unwrap());
+        assert!(!reply.header.has(Tags::MsgType.to_num()));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,119 @@
+use std::collections::*;
+use tag::*;
+use message::*;
+use session_id::*;
+use error::error::MessageRejectError;
+
+//MessageRoute is a handler for one kind of application message
+pub type MessageRoute = Box<dyn FnMut(&Message, &SessionID) -> Result<(), MessageRejectError> + Send>;
+
+//MessageRouter dispatches application messages to the route registered for their FIX version and MsgType
+#[derive(Default)]
+pub struct MessageRouter {
+    routes: HashMap<(String, String), MessageRoute>
+}
+
+impl MessageRouter {
+
+    pub fn new() -> MessageRouter {
+        MessageRouter{routes: HashMap::new()}
+    }
+
+    //add_route registers route for begin_string and msg_type. FIX.5.0 and later messages are registered under
+    //the version their ApplVerID stands for, e.g. BEGIN_STRING_FIX50SP2.
+    pub fn add_route<F>(&mut self, begin_string: &str, msg_type: &str, route: F)
+        where F: FnMut(&Message, &SessionID) -> Result<(), MessageRejectError> + Send + 'static {
+        self.routes.insert((begin_string.to_string(), msg_type.to_string()), Box::new(route));
+    }
+
+    pub fn has_route(&self, begin_string: &str, msg_type: &str) -> bool {
+        self.routes.contains_key(&(begin_string.to_string(), msg_type.to_string()))
+    }
+
+    //route dispatches msg, a message nobody registered for is rejected with UnsupportedMessageType
+    pub fn route(&mut self, msg: &Message, session_id: &SessionID) -> Result<(), MessageRejectError> {
+        self.route_with_default_appl_ver_id(msg, session_id, "")
+    }
+
+    //route_with_default_appl_ver_id resolves a FIXT.1.1 message without ApplVerID(1128) through the
+    //DefaultApplVerID(1137) negotiated at Logon
+    pub fn route_with_default_appl_ver_id(&mut self, msg: &Message, session_id: &SessionID, default_appl_ver_id: &str) -> Result<(), MessageRejectError> {
+        let msg_type = match msg.header.get_string(Tags::MsgType.to_num()) {
+            Ok(msg_type) => msg_type,
+            Err(_) => return Err(MessageRejectError::required_tag_missing(Tags::MsgType.to_num()))
+        };
+
+        let begin_string = msg.application_version(default_appl_ver_id);
+        match self.routes.get_mut(&(begin_string, msg_type)) {
+            Some(route) => route(msg, session_id),
+            None => Err(MessageRejectError::unsupported_message_type())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::*;
+    use begin_string::*;
+    use error::BusinessRejectReason;
+    use error::MessageRejectError as MessageRejectErrorTrait;
+
+    fn new_message(begin_string: &str, msg_type: &str) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), begin_string);
+        msg.header.set_string(Tags::MsgType.to_num(), msg_type);
+        msg
+    }
+
+    fn session_id() -> SessionID {
+        SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW")
+    }
+
+    #[test]
+    fn route_by_begin_string_test() {
+        let received = Arc::new(Mutex::new(vec![]));
+        let mut router = MessageRouter::new();
+        let fix42 = received.clone();
+        router.add_route(BEGIN_STRING_FIX42, "D", move |_, _| { fix42.lock().unwrap().push("FIX.4.2 D"); Ok(()) });
+        let fix44 = received.clone();
+        router.add_route(BEGIN_STRING_FIX44, "D", move |_, _| { fix44.lock().unwrap().push("FIX.4.4 D"); Ok(()) });
+
+        router.route(&new_message(BEGIN_STRING_FIX44, "D"), &session_id()).unwrap();
+        router.route(&new_message(BEGIN_STRING_FIX42, "D"), &session_id()).unwrap();
+        assert_eq!(vec!["FIX.4.4 D", "FIX.4.2 D"], *received.lock().unwrap());
+    }
+
+    #[test]
+    fn route_by_appl_ver_id_test() {
+        let mut router = MessageRouter::new();
+        router.add_route(BEGIN_STRING_FIX50SP2, "D", |_, _| Ok(()));
+
+        let mut msg = new_message(BEGIN_STRING_FIXT11, "D");
+        assert!(router.route(&msg, &session_id()).is_err());
+        assert!(router.route_with_default_appl_ver_id(&msg, &session_id(), "9").is_ok());
+
+        msg.header.set_string(Tags::ApplVerID.to_num(), "7");
+        assert!(router.route_with_default_appl_ver_id(&msg, &session_id(), "9").is_err());
+        msg.header.set_string(Tags::ApplVerID.to_num(), "9");
+        assert!(router.route(&msg, &session_id()).is_ok());
+    }
+
+    #[test]
+    fn unsupported_message_type_test() {
+        let mut router = MessageRouter::new();
+        router.add_route(BEGIN_STRING_FIX42, "D", |_, _| Ok(()));
+
+        let err = router.route(&new_message(BEGIN_STRING_FIX42, "F"), &session_id()).unwrap_err();
+        assert!(err.is_business_reject());
+        assert_eq!(Some(BusinessRejectReason::UnsupportedMessageType), err.business_reject_reason());
+    }
+
+    #[test]
+    fn route_error_test() {
+        let mut router = MessageRouter::new();
+        router.add_route(BEGIN_STRING_FIX42, "D", |_, _| Err(MessageRejectError::required_tag_missing(55)));
+        let err = router.route(&new_message(BEGIN_STRING_FIX42, "D"), &session_id()).unwrap_err();
+        assert_eq!(55, err.ref_tag_id());
+    }
+}
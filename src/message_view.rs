@@ -0,0 +1,276 @@
+use tag::*;
+use tag_value::*;
+use std::*;
+use field::*;
+use error::FixError;
+use error::error::MessageRejectError;
+use fix_int::*;
+use fix_utc_timestamp::*;
+use time::*;
+
+//FieldOffset locates a single tag=value pair inside the raw buffer of a MessageView
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldOffset {
+    tag: u32,
+    start: usize,
+    value_start: usize,
+    value_end: usize
+}
+
+impl FieldOffset {
+
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    //start is the offset of the tag, the field spans start..end plus its SOH
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.value_end
+    }
+}
+
+//FieldIter walks the (tag, value) pairs of a MessageView without allocating
+pub struct FieldIter<'v, 'a> {
+    raw: &'a [u8],
+    fields: slice::Iter<'v, FieldOffset>
+}
+
+impl<'v, 'a> Iterator for FieldIter<'v, 'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<(u32, &'a [u8])> {
+        let raw = self.raw;
+        self.fields.next().map(|f| (f.tag, &raw[f.value_start..f.value_end]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.fields.size_hint()
+    }
+}
+
+//MessageView is a read only view over a raw FIX message. Fields are indexed by their offsets
+//into the original buffer and values are only parsed when a getter asks for them.
+pub struct MessageView<'a> {
+    raw: &'a [u8],
+    fields: Vec<FieldOffset>
+}
+
+impl<'a> MessageView<'a> {
+
+    pub fn parse(raw: &'a [u8]) -> Result<MessageView<'a>, FixError> {
+        MessageView::parse_with_offsets(raw, Vec::with_capacity(32))
+    }
+
+    //recycle parses a new buffer while keeping the offset storage of this view, a hot loop can
+    //parse every message through a single view without allocating
+    pub fn recycle<'b>(self, raw: &'b [u8]) -> Result<MessageView<'b>, FixError> {
+        MessageView::parse_with_offsets(raw, self.fields)
+    }
+
+    fn parse_with_offsets(raw: &'a [u8], mut fields: Vec<FieldOffset>) -> Result<MessageView<'a>, FixError> {
+        fields.clear();
+
+        let mut pos = 0;
+        let mut data_length: Option<(u32, usize)> = None;
+        while pos < raw.len() {
+            let sep_index = match raw[pos..].iter().position(|&c| c == b'=') {
+                Some(index) => pos + index,
+                None => return Err(FixError::ParseMessageError(format!("MessageView.parse: '=' not found at offset {}", pos)))
+            };
+
+            let tag = match parse_tag(&raw[pos..sep_index]) {
+                Some(tag) => tag,
+                None => return Err(FixError::ParseMessageError(format!("MessageView.parse: invalid tag number at offset {}", pos)))
+            };
+
+            let value_start = sep_index + 1;
+            let value_end = match data_length.take() {
+                Some((data_tag, length)) if data_tag == tag => {
+                    if value_start + length > raw.len() {
+                        return Err(FixError::ParseMessageError(format!("MessageView.parse: data field {} is truncated", tag)));
+                    }
+                    if value_start + length < raw.len() && raw[value_start + length] != SOH {
+                        return Err(FixError::ParseMessageError(format!("MessageView.parse: data field {} is longer than its length", tag)));
+                    }
+                    value_start + length
+                },
+                _ => match raw[value_start..].iter().position(|&c| c == SOH) {
+                    Some(index) => value_start + index,
+                    None => raw.len()
+                }
+            };
+
+            if let Some(data_tag) = tag.data_tag() {
+                if let Some(length) = parse_int(&raw[value_start..value_end]) {
+                    if length >= 0 {
+                        data_length = Some((data_tag, length as usize));
+                    }
+                }
+            }
+
+            fields.push(FieldOffset{tag: tag, start: pos, value_start: value_start, value_end: value_end});
+            pos = value_end + 1;
+        }
+
+        Ok(MessageView{raw: raw, fields: fields})
+    }
+
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    //offsets locates the fields in the raw buffer, in the order they were received
+    pub fn offsets(&self) -> &[FieldOffset] {
+        &self.fields
+    }
+
+    pub fn has(&self, tag: u32) -> bool {
+        self.fields.iter().any(|f| f.tag == tag)
+    }
+
+    //iter walks the fields in the order they were received, duplicates included
+    pub fn iter<'v>(&'v self) -> FieldIter<'v, 'a> {
+        FieldIter{raw: self.raw, fields: self.fields.iter()}
+    }
+
+    fn lookup(&self, tag: u32) -> Option<&'a [u8]> {
+        let raw = self.raw;
+        self.fields.iter().find(|f| f.tag == tag).map(|f| &raw[f.value_start..f.value_end])
+    }
+
+    pub fn get_bytes(&self, tag: u32) -> Result<&'a [u8], MessageRejectError> {
+        match self.lookup(tag) {
+            Some(value) => Ok(value),
+            None => Err(MessageRejectError::conditionally_required_field_missing(tag))
+        }
+    }
+
+    //get_field parses the value of tag with any FieldValueReader
+    pub fn get_field<T>(&self, tag: u32, parser: &mut T) -> Result<(), MessageRejectError> where T: FieldValueReader {
+        let value = self.get_bytes(tag)?;
+        match parser.read(value) {
+            Err(_) => Err(MessageRejectError::incorrect_data_format_for_value(tag)),
+            _ => Ok(())
+        }
+    }
+
+    pub fn get<T>(&self, parser: &mut T) -> Result<(), MessageRejectError> where T: FieldInterface {
+        self.get_field(parser.tag(), parser)
+    }
+
+    pub fn get_str(&self, tag: u32) -> Result<&'a str, MessageRejectError> {
+        let value = self.get_bytes(tag)?;
+        match str::from_utf8(value) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(MessageRejectError::incorrect_data_format_for_value(tag))
+        }
+    }
+
+    pub fn get_int(&self, tag: u32) -> Result<i32, MessageRejectError> {
+        let value = self.get_bytes(tag)?;
+        match parse_int(value) {
+            Some(number) => Ok(number),
+            None => Err(MessageRejectError::incorrect_data_format_for_value(tag))
+        }
+    }
+
+    pub fn get_bool(&self, tag: u32) -> Result<bool, MessageRejectError> {
+        match self.get_bytes(tag)? {
+            b"Y" => Ok(true),
+            b"N" => Ok(false),
+            _ => Err(MessageRejectError::incorrect_data_format_for_value(tag))
+        }
+    }
+
+    pub fn get_time(&self, tag: u32) -> Result<Tm, MessageRejectError> {
+        let mut value = FIXUTCTimestamp::empty();
+        self.get_field(tag, &mut value)?;
+        Ok(value.into())
+    }
+
+    pub fn msg_type(&self) -> Result<&'a str, MessageRejectError> {
+        self.get_str(Tags::MsgType.to_num())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tag::*;
+
+    static RAW: &'static [u8] = b"8=FIX.4.2\x019=61\x0135=D\x0134=-3\x0143=Y\x0152=20161206-15:20:10.123\x0158=a\x0158=b\x0110=137\x01";
+
+    #[test]
+    fn parse_test() {
+        let view = MessageView::parse(RAW).unwrap();
+        assert_eq!(9, view.len());
+        assert_eq!("D", view.msg_type().unwrap());
+        assert_eq!("FIX.4.2", view.get_str(Tags::BeginString.to_num()).unwrap());
+        assert_eq!(-3, view.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert_eq!(true, view.get_bool(Tags::PossDupFlag.to_num()).unwrap());
+    }
+
+    #[test]
+    fn iter_keeps_order_and_duplicates_test() {
+        let view = MessageView::parse(RAW).unwrap();
+        let texts: Vec<&[u8]> = view.iter().filter(|&(tag, _)| tag == Tags::Text.to_num()).map(|(_, value)| value).collect();
+        assert_eq!(vec!["a".as_bytes(), "b".as_bytes()], texts);
+    }
+
+    #[test]
+    fn missing_and_invalid_field_test() {
+        let view = MessageView::parse(RAW).unwrap();
+        assert!(view.get_int(Tags::HeartBtInt.to_num()).is_err());
+        assert!(view.get_int(Tags::BeginString.to_num()).is_err());
+        assert!(view.get_bool(Tags::MsgSeqNum.to_num()).is_err());
+    }
+
+    #[test]
+    fn data_field_with_soh_test() {
+        let raw = b"35=n\x01212=5\x01213=a\x01b\x01c\x0110=000\x01";
+        let view = MessageView::parse(raw).unwrap();
+        assert_eq!(b"a\x01b\x01c", view.get_bytes(Tags::XMLData.to_num()).unwrap());
+        assert_eq!("000", view.get_str(Tags::CheckSum.to_num()).unwrap());
+
+        let offsets: Vec<(usize, usize)> = view.offsets().iter().map(|offset| (offset.start(), offset.end())).collect();
+        assert_eq!(vec![(0, 4), (5, 10), (11, 20), (21, 27)], offsets);
+
+        //the data field must end where its length says
+        assert!(MessageView::parse(b"35=n\x01212=3\x01213=a\x01bc\x0110=000\x01").is_err());
+    }
+
+    #[test]
+    fn leading_zero_tag_test() {
+        let raw = b"8=FIX.4.2\x01035=D\x010058=a\x01";
+        let view = MessageView::parse(raw).unwrap();
+        assert_eq!("D", view.msg_type().unwrap());
+        let offsets: Vec<(usize, usize)> = view.offsets().iter().map(|offset| (offset.start(), offset.end())).collect();
+        assert_eq!(vec![(0, 9), (10, 15), (16, 22)], offsets);
+    }
+
+    #[test]
+    fn recycle_test() {
+        let view = MessageView::parse(RAW).unwrap();
+        let other = b"8=FIX.4.4\x0135=0\x01".to_vec();
+        let view = view.recycle(&other).unwrap();
+        assert_eq!(2, view.len());
+        assert_eq!("0", view.msg_type().unwrap());
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert!(MessageView::parse(b"8=FIX.4.2\x01garbage\x01").is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use tag::*;
+use tag_value::*;
+use field::*;
+use fix_int::*;
+
+//WriteBuffer is the byte sink a MessageWriter appends to, it is implemented for Vec<u8> and,
+//with the bytes feature, for BytesMut
+pub trait WriteBuffer {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn push(&mut self, byte: u8);
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+    fn truncate(&mut self, len: usize);
+    fn as_mut_slice(&mut self) -> &mut [u8];
+}
+
+impl WriteBuffer for Vec<u8> {
+    fn len(&self) -> usize { Vec::len(self) }
+    fn is_empty(&self) -> bool { Vec::is_empty(self) }
+    fn push(&mut self, byte: u8) { Vec::push(self, byte) }
+    fn extend_from_slice(&mut self, bytes: &[u8]) { Vec::extend_from_slice(self, bytes) }
+    fn truncate(&mut self, len: usize) { Vec::truncate(self, len) }
+    fn as_mut_slice(&mut self) -> &mut [u8] { Vec::as_mut_slice(self) }
+}
+
+#[cfg(feature = "bytes")]
+impl WriteBuffer for ::bytes::BytesMut {
+    fn len(&self) -> usize { ::bytes::BytesMut::len(self) }
+    fn is_empty(&self) -> bool { ::bytes::BytesMut::is_empty(self) }
+    fn push(&mut self, byte: u8) { ::bytes::BytesMut::extend_from_slice(self, &[byte]) }
+    fn extend_from_slice(&mut self, bytes: &[u8]) { ::bytes::BytesMut::extend_from_slice(self, bytes) }
+    fn truncate(&mut self, len: usize) { ::bytes::BytesMut::truncate(self, len) }
+    fn as_mut_slice(&mut self) -> &mut [u8] { &mut self[..] }
+}
+
+//room kept for "9=<length><SOH>" until the body length is known
+const BODY_LENGTH_RESERVE: usize = 2 + MAX_INT_LEN + 1;
+
+//MessageWriter serializes a message straight into a caller provided buffer. BeginString,
+//BodyLength and MsgType are written by new, BodyLength and CheckSum are patched in by finish.
+//Nothing is allocated as long as the buffer has enough capacity. A writer dropped without finish
+//truncates the buffer back to where the message started.
+pub struct MessageWriter<'b, B: WriteBuffer + 'b> {
+    buffer: &'b mut B,
+    start: usize,
+    body_length_start: usize,
+    body_start: usize,
+    finished: bool
+}
+
+impl<'b, B: WriteBuffer + 'b> MessageWriter<'b, B> {
+
+    pub fn new(buffer: &'b mut B, begin_string: &str, msg_type: &str) -> MessageWriter<'b, B> {
+        let start = buffer.len();
+        let mut writer = MessageWriter{buffer: buffer, start: start, body_length_start: 0, body_start: 0, finished: false};
+        writer.write_str(Tags::BeginString.to_num(), begin_string);
+
+        writer.body_length_start = writer.buffer.len();
+        for _ in 0..BODY_LENGTH_RESERVE {
+            writer.buffer.push(0);
+        }
+        writer.body_start = writer.buffer.len();
+
+        writer.write_str(Tags::MsgType.to_num(), msg_type);
+        writer
+    }
+
+    fn write_tag(&mut self, tag: u32) {
+        let mut digits = [0u8; MAX_INT_LEN];
+        self.buffer.extend_from_slice(format_uint(tag as u64, &mut digits));
+        self.buffer.push(b'=');
+    }
+
+    pub fn write_bytes(&mut self, tag: u32, value: &[u8]) {
+        self.write_tag(tag);
+        self.buffer.extend_from_slice(value);
+        self.buffer.push(SOH);
+    }
+
+    pub fn write_str(&mut self, tag: u32, value: &str) {
+        self.write_bytes(tag, value.as_bytes());
+    }
+
+    pub fn write_char(&mut self, tag: u32, value: u8) {
+        self.write_tag(tag);
+        self.buffer.push(value);
+        self.buffer.push(SOH);
+    }
+
+    pub fn write_bool(&mut self, tag: u32, value: bool) {
+        self.write_char(tag, if value { b'Y' } else { b'N' });
+    }
+
+    pub fn write_int(&mut self, tag: u32, value: i64) {
+        let mut digits = [0u8; MAX_INT_LEN];
+        self.write_bytes(tag, format_int(value, &mut digits));
+    }
+
+    pub fn write_uint(&mut self, tag: u32, value: u64) {
+        let mut digits = [0u8; MAX_INT_LEN];
+        self.write_bytes(tag, format_uint(value, &mut digits));
+    }
+
+    //write_decimal writes mantissa * 10^-scale, e.g. (12345, 2) is written as 123.45
+    pub fn write_decimal(&mut self, tag: u32, mantissa: i64, scale: u32) {
+        let mut digits = [0u8; MAX_INT_LEN];
+        let digits = format_uint(mantissa.unsigned_abs(), &mut digits);
+        let scale = scale as usize;
+
+        self.write_tag(tag);
+        if mantissa < 0 {
+            self.buffer.push(b'-');
+        }
+        if scale == 0 {
+            self.buffer.extend_from_slice(digits);
+        } else if digits.len() > scale {
+            let point = digits.len() - scale;
+            self.buffer.extend_from_slice(&digits[..point]);
+            self.buffer.push(b'.');
+            self.buffer.extend_from_slice(&digits[point..]);
+        } else {
+            self.buffer.extend_from_slice(b"0.");
+            for _ in digits.len()..scale {
+                self.buffer.push(b'0');
+            }
+            self.buffer.extend_from_slice(digits);
+        }
+        self.buffer.push(SOH);
+    }
+
+    //write_field goes through FieldValueWriter and therefore allocates, prefer the typed writers on hot paths
+    pub fn write_field<T: FieldValueWriter>(&mut self, tag: u32, value: &T) {
+        self.write_bytes(tag, value.write().as_ref());
+    }
+
+    pub fn write_tag_value(&mut self, tag_value: &TagValue) {
+        self.write_bytes(tag_value.tag(), tag_value.value());
+    }
+
+    //finish patches BodyLength, appends CheckSum and returns the length of the message written
+    pub fn finish(mut self) -> usize {
+        let body_end = self.buffer.len();
+        let body_length = body_end - self.body_start;
+
+        let mut field = [0u8; BODY_LENGTH_RESERVE];
+        let field_len = {
+            let mut digits = [0u8; MAX_INT_LEN];
+            let digits = format_uint(body_length as u64, &mut digits);
+            field[0] = b'9';
+            field[1] = b'=';
+            field[2..2 + digits.len()].copy_from_slice(digits);
+            field[2 + digits.len()] = SOH;
+            3 + digits.len()
+        };
+
+        //close the gap left by the reservation
+        let shift = BODY_LENGTH_RESERVE - field_len;
+        let body_length_start = self.body_length_start;
+        let body_start = self.body_start;
+        {
+            let bytes = self.buffer.as_mut_slice();
+            bytes[body_length_start..body_length_start + field_len].copy_from_slice(&field[..field_len]);
+            bytes.copy_within(body_start..body_end, body_start - shift);
+        }
+        self.buffer.truncate(body_end - shift);
+
+        let mut checksum: u32 = 0;
+        {
+            let bytes = self.buffer.as_mut_slice();
+            for &b in &bytes[self.start..] {
+                checksum += b as u32;
+            }
+        }
+        let checksum = checksum % 256;
+        self.buffer.extend_from_slice(b"10=");
+        self.buffer.push(b'0' + (checksum / 100) as u8);
+        self.buffer.push(b'0' + (checksum / 10 % 10) as u8);
+        self.buffer.push(b'0' + (checksum % 10) as u8);
+        self.buffer.push(SOH);
+
+        self.finished = true;
+        self.buffer.len() - self.start
+    }
+}
+
+impl<'b, B: WriteBuffer + 'b> Drop for MessageWriter<'b, B> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.buffer.truncate(self.start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tag::*;
+
+    #[test]
+    fn finish_test() {
+        let mut buffer: Vec<u8> = vec![];
+        let length = {
+            let mut writer = MessageWriter::new(&mut buffer, "FIX.4.2", "0");
+            writer.write_str(Tags::SenderCompID.to_num(), "TW");
+            writer.write_int(Tags::MsgSeqNum.to_num(), 1);
+            writer.finish()
+        };
+        let expected = b"8=FIX.4.2\x019=16\x0135=0\x0149=TW\x0134=1\x0110=255\x01";
+        assert_eq!(&expected[..], buffer.as_slice());
+        assert_eq!(expected.len(), length);
+    }
+
+    #[test]
+    fn append_to_existing_buffer_test() {
+        let mut buffer: Vec<u8> = b"previous".to_vec();
+        let length = {
+            let writer = MessageWriter::new(&mut buffer, "FIX.4.2", "0");
+            writer.finish()
+        };
+        assert_eq!(b"previous", &buffer[..8]);
+        assert_eq!(&b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01"[..], &buffer[8..]);
+        assert_eq!(buffer.len() - 8, length);
+    }
+
+    fn contains(buffer: &[u8], expected: &[u8]) -> bool {
+        buffer.windows(expected.len()).any(|window| window == expected)
+    }
+
+    #[test]
+    fn write_int_test() {
+        let mut buffer: Vec<u8> = vec![];
+        {
+            let mut writer = MessageWriter::new(&mut buffer, "FIX.4.2", "D");
+            writer.write_int(38, -1234);
+            writer.write_uint(38, 0);
+            writer.write_bool(43, true);
+            writer.finish();
+        }
+        assert!(contains(&buffer, b"\x0138=-1234\x0138=0\x0143=Y\x0110="));
+    }
+
+    #[test]
+    fn write_decimal_test() {
+        let cases: Vec<(i64, u32, &[u8])> = vec![
+            (12345, 2, b"44=123.45\x01"),
+            (-5, 3, b"44=-0.005\x01"),
+            (7, 0, b"44=7\x01"),
+            (100, 2, b"44=1.00\x01"),
+        ];
+        for (mantissa, scale, expected) in cases {
+            let mut buffer: Vec<u8> = vec![];
+            {
+                let mut writer = MessageWriter::new(&mut buffer, "FIX.4.2", "D");
+                writer.write_decimal(44, mantissa, scale);
+                writer.finish();
+            }
+            assert!(contains(&buffer, expected));
+        }
+    }
+
+    fn write_order(buffer: &mut Vec<u8>, fail: bool) -> Result<usize, String> {
+        let mut writer = MessageWriter::new(buffer, "FIX.4.2", "D");
+        writer.write_str(Tags::SenderCompID.to_num(), "TW");
+        if fail {
+            return Err("no price".to_string());
+        }
+        Ok(writer.finish())
+    }
+
+    #[test]
+    fn drop_without_finish_test() {
+        let mut buffer: Vec<u8> = b"previous".to_vec();
+        assert!(write_order(&mut buffer, true).is_err());
+        assert_eq!(b"previous", buffer.as_slice());
+
+        let length = write_order(&mut buffer, false).unwrap();
+        assert_eq!(8 + length, buffer.len());
+        assert!(buffer[8..].starts_with(b"8=FIX.4.2\x019=11\x0135=D\x0149=TW\x0110="));
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes_mut_test() {
+        let mut buffer = ::bytes::BytesMut::with_capacity(64);
+        let length = {
+            let mut writer = MessageWriter::new(&mut buffer, "FIX.4.2", "0");
+            writer.write_str(Tags::SenderCompID.to_num(), "TW");
+            writer.write_int(Tags::MsgSeqNum.to_num(), 1);
+            writer.finish()
+        };
+        assert_eq!(&b"8=FIX.4.2\x019=16\x0135=0\x0149=TW\x0134=1\x0110=255\x01"[..], &buffer[..]);
+        assert_eq!(buffer.len(), length);
+
+        {
+            let mut writer = MessageWriter::new(&mut buffer, "FIX.4.2", "0");
+            writer.write_str(Tags::SenderCompID.to_num(), "TW");
+        }
+        assert_eq!(length, buffer.len());
+    }
+}
@@ -0,0 +1,51 @@
+//MsgType values of the session level messages
+pub const MSG_TYPE_HEARTBEAT: &'static str = "0";
+pub const MSG_TYPE_TEST_REQUEST: &'static str = "1";
+pub const MSG_TYPE_RESEND_REQUEST: &'static str = "2";
+pub const MSG_TYPE_REJECT: &'static str = "3";
+pub const MSG_TYPE_SEQUENCE_RESET: &'static str = "4";
+pub const MSG_TYPE_LOGOUT: &'static str = "5";
+pub const MSG_TYPE_LOGON: &'static str = "A";
+pub const MSG_TYPE_BUSINESS_MESSAGE_REJECT: &'static str = "j";
+
+//is_admin_message_type reports whether msg_type is a session level message
+pub fn is_admin_message_type(msg_type: &str) -> bool {
+    match msg_type {
+        "0" | "1" | "2" | "3" | "4" | "5" | "A" => true,
+        _ => false
+    }
+}
+
+//msg_type_name is the FIX name of msg_type, e.g. NewOrderSingle for D
+pub fn msg_type_name(msg_type: &str) -> Option<&'static str> {
+    let name = match msg_type {
+        "0" => "Heartbeat",
+        "1" => "TestRequest",
+        "2" => "ResendRequest",
+        "3" => "Reject",
+        "4" => "SequenceReset",
+        "5" => "Logout",
+        "6" => "IOI",
+        "7" => "Advertisement",
+        "8" => "ExecutionReport",
+        "9" => "OrderCancelReject",
+        "A" => "Logon",
+        "B" => "News",
+        "C" => "Email",
+        "D" => "NewOrderSingle",
+        "E" => "NewOrderList",
+        "F" => "OrderCancelRequest",
+        "G" => "OrderCancelReplaceRequest",
+        "H" => "OrderStatusRequest",
+        "R" => "QuoteRequest",
+        "S" => "Quote",
+        "V" => "MarketDataRequest",
+        "W" => "MarketDataSnapshotFullRefresh",
+        "X" => "MarketDataIncrementalRefresh",
+        "Y" => "MarketDataRequestReject",
+        "j" => "BusinessMessageReject",
+        "AE" => "TradeCaptureReport",
+        _ => return None
+    };
+    Some(name)
+}
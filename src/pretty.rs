@@ -0,0 +1,294 @@
+use std::fmt;
+use tag::*;
+use message::*;
+use msg_type::*;
+use begin_string::*;
+
+//FieldNames resolves the names shown by the pretty printer, a DataDictionary provides them for every field
+//of its FIX version while TagNames only knows the session level fields of Tags
+pub trait FieldNames {
+    fn field_name(&self, tag: u32) -> Option<String>;
+
+    //field_tag is the reverse of field_name
+    fn field_tag(&self, name: &str) -> Option<u32>;
+
+    //value_name is the name of an enumerated value, e.g. NewOrderSingle for MsgType D
+    fn value_name(&self, tag: u32, value: &str) -> Option<String>;
+
+    //group_fields lists the member tags of the repeating group whose NumInGroup field is count_tag
+    fn group_fields(&self, count_tag: u32) -> Option<Vec<u32>>;
+
+    //is_data tells whether tag holds raw bytes that may be anything, SOH included
+    fn is_data(&self, tag: u32) -> bool;
+}
+
+//TagNames names fields after Tags
+pub struct TagNames;
+
+impl FieldNames for TagNames {
+    fn field_name(&self, tag: u32) -> Option<String> {
+        Tags::from_number(tag).map(|tag| tag.name())
+    }
+
+    fn field_tag(&self, name: &str) -> Option<u32> {
+        Tags::from_name(name).map(|tag| tag.to_num())
+    }
+
+    fn value_name(&self, tag: u32, value: &str) -> Option<String> {
+        let name = match Tags::from_number(tag) {
+            Some(Tags::MsgType) | Some(Tags::RefMsgType) => msg_type_name(value),
+            Some(Tags::SessionRejectReason) => session_reject_reason_name(value),
+            Some(Tags::BusinessRejectReason) => business_reject_reason_name(value),
+            Some(Tags::EncryptMethod) if value == "0" => Some("None"),
+            Some(Tags::PossDupFlag) | Some(Tags::PossResend) | Some(Tags::ResetSeqNumFlag) | Some(Tags::GapFillFlag) => match value {
+                "Y" => Some("Yes"),
+                "N" => Some("No"),
+                _ => None
+            },
+            Some(Tags::ApplVerID) | Some(Tags::DefaultApplVerID) => match appl_ver_id_begin_string(value) {
+                begin_string if begin_string != value => Some(begin_string),
+                _ => None
+            },
+            _ => None
+        };
+        name.map(|name| name.to_string())
+    }
+
+    fn group_fields(&self, count_tag: u32) -> Option<Vec<u32>> {
+        match Tags::from_number(count_tag) {
+            Some(Tags::NoHops) => Some(vec![Tags::HopCompID.to_num(), Tags::HopSendingTime.to_num(), Tags::HopRefID.to_num()]),
+            _ => None
+        }
+    }
+
+    fn is_data(&self, tag: u32) -> bool {
+        [Tags::SecureDataLen, Tags::XMLDataLen, Tags::SignatureLength].iter().any(|length| length.to_num().data_tag() == Some(tag))
+    }
+}
+
+fn session_reject_reason_name(value: &str) -> Option<&'static str> {
+    let name = match value {
+        "0" => "InvalidTagNumber",
+        "1" => "RequiredTagMissing",
+        "2" => "TagNotDefinedForThisMessageType",
+        "3" => "UndefinedTag",
+        "4" => "TagSpecifiedWithoutAValue",
+        "5" => "ValueIsIncorrect",
+        "6" => "IncorrectDataFormatForValue",
+        "7" => "DecryptionProblem",
+        "8" => "SignatureProblem",
+        "9" => "CompIDProblem",
+        "10" => "SendingTimeAccuracyProblem",
+        "11" => "InvalidMsgType",
+        "12" => "XMLValidationError",
+        "13" => "TagAppearsMoreThanOnce",
+        "14" => "TagSpecifiedOutOfRequiredOrder",
+        "15" => "RepeatingGroupFieldsOutOfOrder",
+        "16" => "IncorrectNumInGroupCountForRepeatingGroup",
+        "17" => "NonDataValueIncludesFieldDelimiter",
+        "99" => "Other",
+        _ => return None
+    };
+    Some(name)
+}
+
+fn business_reject_reason_name(value: &str) -> Option<&'static str> {
+    let name = match value {
+        "0" => "Other",
+        "1" => "UnknownID",
+        "2" => "UnknownSecurity",
+        "3" => "UnsupportedMessageType",
+        "4" => "ApplicationNotAvailable",
+        "5" => "ConditionallyRequiredFieldMissing",
+        "6" => "NotAuthorized",
+        "7" => "DeliverToFirmNotAvailableAtThisTime",
+        "18" => "InvalidPriceIncrement",
+        _ => return None
+    };
+    Some(name)
+}
+
+//MASK replaces the value of sensitive fields such as Password(554)
+pub const MASK: &'static str = "********";
+
+//escape_data renders the bytes of a data field such as Signature(89) on one line, bytes other than printable
+//ASCII, the '|' separator and the backslash are written as \xNN
+pub fn escape_data(value: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for &b in value.iter() {
+        if (b.is_ascii_graphic() && b != b'|' && b != b'\\') || b == b' ' {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(&format!("\\x{:02x}", b));
+        }
+    }
+    escaped
+}
+
+//PrettyMessage renders a message for humans. {} prints one line, e.g.
+//BeginString(8)=FIX.4.4 | MsgType(35)=D (NewOrderSingle) | ..., while {:#} prints one field per line with the
+//members of repeating groups indented below their NumInGroup field.
+pub struct PrettyMessage<'a> {
+    msg: &'a Message,
+    names: &'a dyn FieldNames
+}
+
+impl<'a> PrettyMessage<'a> {
+
+    pub fn new(msg: &'a Message) -> PrettyMessage<'a> {
+        PrettyMessage{msg: msg, names: &TagNames}
+    }
+
+    pub fn with_names(msg: &'a Message, names: &'a dyn FieldNames) -> PrettyMessage<'a> {
+        PrettyMessage{msg: msg, names: names}
+    }
+
+    //fields are taken as received when the message was parsed, so repeated group members are all shown
+    fn fields(&self) -> Vec<(u32, &'a [u8])> {
+        if !self.msg.fields().is_empty() {
+            return self.msg.fields().iter().map(|field| (field.tag(), field.value())).collect();
+        }
+        self.msg.header.iter().chain(self.msg.body.iter()).chain(self.msg.trailer.iter()).collect()
+    }
+
+    fn render(&self, fields: &[(u32, &[u8])], pos: &mut usize, depth: usize, lines: &mut Vec<(usize, String)>) {
+        let (tag, value) = fields[*pos];
+        *pos += 1;
+        lines.push((depth, self.render_field(tag, value)));
+
+        if let Some(members) = self.names.group_fields(tag) {
+            while *pos < fields.len() && members.contains(&fields[*pos].0) {
+                self.render(fields, pos, depth + 1, lines);
+            }
+        }
+    }
+
+    fn render_field(&self, tag: u32, value: &[u8]) -> String {
+        let label = match self.names.field_name(tag) {
+            Some(name) => format!("{}({})", name, tag),
+            None => tag.to_string()
+        };
+        if tag.is_sensitive() {
+            return format!("{}={}", label, MASK);
+        }
+        if self.names.is_data(tag) {
+            return format!("{}={}", label, escape_data(value));
+        }
+
+        let value = String::from_utf8_lossy(value);
+        match self.names.value_name(tag, &value) {
+            Some(name) => format!("{}={} ({})", label, value, name),
+            None => format!("{}={}", label, value)
+        }
+    }
+}
+
+impl<'a> fmt::Display for PrettyMessage<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.fields();
+        let mut lines = vec![];
+        let mut pos = 0;
+        while pos < fields.len() {
+            self.render(&fields, &mut pos, 0, &mut lines);
+        }
+
+        for (i, &(depth, ref line)) in lines.iter().enumerate() {
+            if f.alternate() {
+                if i > 0 {
+                    writeln!(f)?;
+                }
+                write!(f, "{:width$}{}", "", line, width = depth * 2)?;
+            } else {
+                if i > 0 {
+                    write!(f, " | ")?;
+                }
+                write!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static RAW: &'static [u8] = b"8=FIX.4.4\x019=0\x0135=A\x0149=TW\x0156=ISLD\x01627=2\x01628=HOP1\x01630=R1\x01628=HOP2\x01630=R2\x0198=0\x01553=trader\x01554=secret\x0110=000\x01";
+
+    #[test]
+    fn one_line_test() {
+        let msg = Message::parse(RAW).unwrap();
+        assert_eq!("BeginString(8)=FIX.4.4 | BodyLength(9)=0 | MsgType(35)=A (Logon) | SenderCompID(49)=TW | TargetCompID(56)=ISLD | \
+NoHops(627)=2 | HopCompID(628)=HOP1 | HopRefID(630)=R1 | HopCompID(628)=HOP2 | HopRefID(630)=R2 | EncryptMethod(98)=0 (None) | \
+Username(553)=trader | Password(554)=******** | CheckSum(10)=000", msg.pretty().to_string());
+    }
+
+    #[test]
+    fn groups_indented_test() {
+        let msg = Message::parse(RAW).unwrap();
+        let lines: Vec<String> = format!("{:#}", msg.pretty()).lines().map(|line| line.to_string()).collect();
+        assert_eq!("NoHops(627)=2", lines[5]);
+        assert_eq!("  HopCompID(628)=HOP1", lines[6]);
+        assert_eq!("  HopRefID(630)=R2", lines[9]);
+        assert_eq!("EncryptMethod(98)=0 (None)", lines[10]);
+    }
+
+    #[test]
+    fn data_field_test() {
+        let msg = Message::parse(b"8=FIX.4.4\x019=0\x0135=0\x0193=6\x0189=a|\x01\\\xff \x0110=000\x01").unwrap();
+        assert_eq!("BeginString(8)=FIX.4.4 | BodyLength(9)=0 | MsgType(35)=0 (Heartbeat) | SignatureLength(93)=6 | \
+Signature(89)=a\\x7c\\x01\\x5c\\xff  | CheckSum(10)=000", msg.pretty().to_string());
+        assert_eq!("ab\\x0a", escape_data(b"ab\n"));
+    }
+
+    #[test]
+    fn field_name_test() {
+        assert_eq!(Some("RefTagID".to_string()), TagNames.field_name(371));
+        assert_eq!(Some(371), TagNames.field_tag("RefTagID"));
+        assert_eq!(None, TagNames.field_tag("RefID"));
+        assert!(TagNames.is_data(89) && TagNames.is_data(213));
+        assert!(!TagNames.is_data(93));
+    }
+
+    struct Dictionary;
+
+    impl FieldNames for Dictionary {
+        fn field_name(&self, tag: u32) -> Option<String> {
+            match tag {
+                55 => Some("Symbol".to_string()),
+                _ => TagNames.field_name(tag)
+            }
+        }
+
+        fn field_tag(&self, name: &str) -> Option<u32> {
+            match name {
+                "Symbol" => Some(55),
+                _ => TagNames.field_tag(name)
+            }
+        }
+
+        fn value_name(&self, tag: u32, value: &str) -> Option<String> {
+            TagNames.value_name(tag, value)
+        }
+
+        fn group_fields(&self, _count_tag: u32) -> Option<Vec<u32>> {
+            None
+        }
+
+        fn is_data(&self, tag: u32) -> bool {
+            TagNames.is_data(tag)
+        }
+    }
+
+    #[test]
+    fn built_message_with_names_test() {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        msg.header.set_string(Tags::MsgType.to_num(), "D");
+        msg.body.set_string(55, "MSFT");
+        msg.body.set_string(5001, "x");
+        assert_eq!("BeginString(8)=FIX.4.4 | MsgType(35)=D (NewOrderSingle) | 55=MSFT | 5001=x", msg.pretty().to_string());
+        assert_eq!("BeginString(8)=FIX.4.4 | MsgType(35)=D (NewOrderSingle) | Symbol(55)=MSFT | 5001=x",
+                   PrettyMessage::with_names(&msg, &Dictionary).to_string());
+    }
+}
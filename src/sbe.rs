@@ -0,0 +1,1371 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use time::{self, Timespec};
+use tag::*;
+use field::*;
+use field_map::*;
+use tag_value::*;
+use message::*;
+use message_writer::*;
+use begin_string::*;
+use fix_utc_timestamp::*;
+use fix_decimal::*;
+use error::FixError;
+use xml::{self, Element};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+
+//SOFH_SBE_LITTLE_ENDIAN is the Simple Open Framing Header encoding type of SBE 1.0 little-endian messages
+pub const SOFH_SBE_LITTLE_ENDIAN: u16 = 0xEB50;
+
+//SOFH_LEN is the length of the framing header: the frame length and the encoding type, both big-endian
+pub const SOFH_LEN: usize = 6;
+
+fn sbe_error(text: &str) -> FixError {
+    FixError::ParseMessageError(format!("SBE: {}", text))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Primitive {
+    Char,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float,
+    Double
+}
+
+impl Primitive {
+
+    pub fn from_name(name: &str) -> Option<Primitive> {
+        let primitive = match name {
+            "char" => Primitive::Char,
+            "int8" => Primitive::Int8,
+            "int16" => Primitive::Int16,
+            "int32" => Primitive::Int32,
+            "int64" => Primitive::Int64,
+            "uint8" => Primitive::UInt8,
+            "uint16" => Primitive::UInt16,
+            "uint32" => Primitive::UInt32,
+            "uint64" => Primitive::UInt64,
+            "float" => Primitive::Float,
+            "double" => Primitive::Double,
+            _ => return None
+        };
+        Some(primitive)
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Primitive::Char | Primitive::Int8 | Primitive::UInt8 => 1,
+            Primitive::Int16 | Primitive::UInt16 => 2,
+            Primitive::Int32 | Primitive::UInt32 | Primitive::Float => 4,
+            Primitive::Int64 | Primitive::UInt64 | Primitive::Double => 8
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        match self {
+            Primitive::Int8 | Primitive::Int16 | Primitive::Int32 | Primitive::Int64 => true,
+            _ => false
+        }
+    }
+
+    //null is the value standing for an absent optional field
+    fn null(self) -> Scalar {
+        let bits = (self.size() * 8) as u32;
+        match self {
+            Primitive::Char => Scalar::Char(0),
+            Primitive::Float | Primitive::Double => Scalar::Float(f64::NAN),
+            _ if self.is_signed() => Scalar::Int(i64::MIN >> (64 - bits)),
+            _ => Scalar::UInt(u64::MAX >> (64 - bits))
+        }
+    }
+
+    fn is_null(self, scalar: Scalar) -> bool {
+        match (scalar, self.null()) {
+            (Scalar::Float(value), _) => value.is_nan(),
+            (scalar, null) => scalar == null
+        }
+    }
+
+    fn read(self, buf: &[u8]) -> Scalar {
+        let size = self.size();
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&buf[..size]);
+        let bits = u64::from_le_bytes(bytes);
+        let shift = (64 - size * 8) as u32;
+        match self {
+            Primitive::Char => Scalar::Char(buf[0]),
+            Primitive::Float => Scalar::Float(f32::from_bits(bits as u32) as f64),
+            Primitive::Double => Scalar::Float(f64::from_bits(bits)),
+            _ if self.is_signed() => Scalar::Int(((bits << shift) as i64) >> shift),
+            _ => Scalar::UInt(bits)
+        }
+    }
+
+    fn write(self, scalar: Scalar, buf: &mut [u8]) {
+        let bits = match scalar {
+            Scalar::Char(value) => value as u64,
+            Scalar::Int(value) => value as u64,
+            Scalar::UInt(value) => value,
+            Scalar::Float(value) if self == Primitive::Float => (value as f32).to_bits() as u64,
+            Scalar::Float(value) => value.to_bits()
+        };
+        let size = self.size();
+        buf[..size].copy_from_slice(&bits.to_le_bytes()[..size]);
+    }
+
+    //parse reads the FIX string value of a field encoded as self
+    fn parse(self, value: &str) -> Result<Scalar, FixError> {
+        let invalid = || sbe_error(&format!("{} is not a valid {:?}", value, self));
+        let bits = (self.size() * 8) as u32;
+        match self {
+            Primitive::Char if value.len() == 1 => Ok(Scalar::Char(value.as_bytes()[0])),
+            Primitive::Char => Err(invalid()),
+            Primitive::Float | Primitive::Double => value.parse::<f64>().map(Scalar::Float).map_err(|_| invalid()),
+            _ if self.is_signed() => {
+                let value = value.parse::<i64>().map_err(|_| invalid())?;
+                if bits < 64 && (value < i64::MIN >> (64 - bits) || value > i64::MAX >> (64 - bits)) {
+                    return Err(invalid());
+                }
+                Ok(Scalar::Int(value))
+            },
+            _ => {
+                let value = value.parse::<u64>().map_err(|_| invalid())?;
+                if bits < 64 && value > u64::MAX >> (64 - bits) {
+                    return Err(invalid());
+                }
+                Ok(Scalar::UInt(value))
+            }
+        }
+    }
+}
+
+//Scalar is a primitive value
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    Char(u8),
+    Int(i64),
+    UInt(u64),
+    Float(f64)
+}
+
+impl Scalar {
+
+    fn to_i64(self) -> i64 {
+        match self {
+            Scalar::Char(value) => value as i64,
+            Scalar::Int(value) => value,
+            Scalar::UInt(value) => value as i64,
+            Scalar::Float(value) => value as i64
+        }
+    }
+
+    fn to_fix(self) -> String {
+        match self {
+            Scalar::Char(value) => (value as char).to_string(),
+            Scalar::Int(value) => value.to_string(),
+            Scalar::UInt(value) => value.to_string(),
+            Scalar::Float(value) => value.to_string()
+        }
+    }
+}
+
+//SbeType is the encoding of a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum SbeType {
+    //a primitive or a fixed length array of primitives, a char array holds a string padded with NUL
+    Encoded{primitive: Primitive, length: usize, constant: Option<String>, semantic_type: Option<String>},
+    //the FIX value of an enum is the value of its validValue
+    Enum{encoding: Primitive, values: Vec<(String, String)>},
+    //members are (name, offset, type), a composite of a mantissa and an exponent is a FIX decimal
+    Composite{members: Vec<(String, usize, SbeType)>}
+}
+
+impl SbeType {
+
+    pub fn size(&self) -> usize {
+        match *self {
+            SbeType::Encoded{constant: Some(_), ..} => 0,
+            SbeType::Encoded{primitive, length, ..} => primitive.size() * length,
+            SbeType::Enum{encoding, ..} => encoding.size(),
+            SbeType::Composite{ref members} => members.iter().map(|(_, offset, member)| offset + member.size()).max().unwrap_or(0)
+        }
+    }
+
+    fn member(&self, name: &str) -> Option<(usize, &SbeType)> {
+        match *self {
+            SbeType::Composite{ref members} => members.iter().find(|(n, _, _)| n == name).map(|(_, offset, member)| (*offset, member)),
+            _ => None
+        }
+    }
+
+    //member_primitive is the offset and primitive of a non constant member such as numInGroup
+    fn member_primitive(&self, name: &str) -> Result<(usize, Primitive), FixError> {
+        match self.member(name) {
+            Some((offset, &SbeType::Encoded{primitive, length: 1, constant: None, ..})) => Ok((offset, primitive)),
+            _ => Err(sbe_error(&format!("composite without {}", name)))
+        }
+    }
+
+    fn read_member(&self, buf: &[u8], name: &str) -> Result<u64, FixError> {
+        let (offset, primitive) = self.member_primitive(name)?;
+        if offset + primitive.size() > buf.len() {
+            return Err(sbe_error(&format!("{} is truncated", name)));
+        }
+        match primitive.read(&buf[offset..]) {
+            Scalar::Int(value) if value < 0 => Err(sbe_error(&format!("{} is negative", name))),
+            scalar => Ok(scalar.to_i64() as u64)
+        }
+    }
+
+    fn write_member(&self, buf: &mut [u8], name: &str, value: u64) -> Result<(), FixError> {
+        let (offset, primitive) = self.member_primitive(name)?;
+        if primitive.size() < 8 && value > u64::MAX >> (64 - primitive.size() as u32 * 8) {
+            return Err(sbe_error(&format!("{} does not fit {}", value, name)));
+        }
+        primitive.write(Scalar::UInt(value), &mut buf[offset..]);
+        Ok(())
+    }
+
+    fn is_decimal(&self) -> bool {
+        self.member("mantissa").is_some() && self.member("exponent").is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeField {
+    pub name: String,
+    //id is the FIX tag
+    pub id: u32,
+    pub offset: usize,
+    pub encoding: SbeType,
+    pub optional: bool,
+    //constant fields take no room in the block, their value comes from the schema
+    pub constant: Option<String>
+}
+
+impl SbeField {
+
+    pub fn size(&self) -> usize {
+        if self.constant.is_some() { 0 } else { self.encoding.size() }
+    }
+}
+
+//SbeData is a variable length field, written after the groups as its length followed by its bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeData {
+    pub name: String,
+    pub id: u32,
+    pub encoding: SbeType
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeGroup {
+    pub name: String,
+    //id is the NumInGroup tag
+    pub id: u32,
+    //dimension is the composite of blockLength and numInGroup preceding the instances
+    pub dimension: SbeType,
+    pub block: SbeBlock
+}
+
+//SbeBlock is the layout of a message or a group instance: the fixed length block of fields, then the groups,
+//then the var data
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeBlock {
+    pub block_length: usize,
+    pub fields: Vec<SbeField>,
+    pub groups: Vec<SbeGroup>,
+    pub data: Vec<SbeData>
+}
+
+impl SbeBlock {
+
+    //contains tells whether tag is written inside the block, its groups included
+    fn contains(&self, tag: u32) -> bool {
+        self.fields.iter().any(|field| field.id == tag) || self.data.iter().any(|data| data.id == tag) ||
+            self.groups.iter().any(|group| group.id == tag || group.block.contains(tag))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeMessage {
+    pub name: String,
+    //id is the templateId of the message header
+    pub id: u16,
+    //msg_type is the semanticType of the message
+    pub msg_type: Option<String>,
+    pub block: SbeBlock
+}
+
+//SbeSchema is a loaded SBE XML message schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbeSchema {
+    pub id: u16,
+    pub version: u16,
+    pub semantic_version: String,
+    //header is the composite of blockLength, templateId, schemaId and version starting every message
+    pub header: SbeType,
+    pub messages: Vec<SbeMessage>
+}
+
+impl SbeSchema {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SbeSchema, FixError> {
+        let mut xml = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut xml)).map_err(FixError::IoError)?;
+        SbeSchema::parse(&xml)
+    }
+
+    pub fn parse(xml: &str) -> Result<SbeSchema, FixError> {
+        let root = xml::parse(xml).map_err(|err| sbe_error(&err))?;
+        if root.local_name() != "messageSchema" {
+            return Err(sbe_error(&format!("unexpected root element {}", root.name)));
+        }
+        if root.attribute("byteOrder").is_some_and(|order| order != "littleEndian") {
+            return Err(sbe_error("only littleEndian schemas are supported"));
+        }
+
+        let mut loader = SchemaLoader{types: HashMap::new()};
+        for types in root.children.iter().filter(|child| child.local_name() == "types") {
+            for definition in types.children.iter() {
+                let name = definition.attribute("name").ok_or_else(|| sbe_error("type without name"))?;
+                loader.types.insert(name.to_string(), definition);
+            }
+        }
+
+        let header = loader.resolve(root.attribute("headerType").unwrap_or("messageHeader"))?;
+        let mut messages = vec![];
+        for message in root.children.iter().filter(|child| child.local_name() == "message") {
+            messages.push(SbeMessage{
+                name: required(message, "name")?.to_string(),
+                id: number(message, "id")? as u16,
+                msg_type: message.attribute("semanticType").map(|msg_type| msg_type.to_string()),
+                block: loader.block(message)?
+            });
+        }
+
+        Ok(SbeSchema{
+            id: number(&root, "id")? as u16,
+            version: root.attribute("version").map_or(Ok(0), |_| number(&root, "version"))? as u16,
+            semantic_version: root.attribute("semanticVersion").unwrap_or("").to_string(),
+            header: header,
+            messages: messages
+        })
+    }
+
+    pub fn message(&self, template_id: u16) -> Option<&SbeMessage> {
+        self.messages.iter().find(|message| message.id == template_id)
+    }
+
+    pub fn message_by_type(&self, msg_type: &str) -> Option<&SbeMessage> {
+        self.messages.iter().find(|message| message.msg_type.as_deref() == Some(msg_type))
+    }
+}
+
+fn required<'e>(element: &'e Element, name: &str) -> Result<&'e str, FixError> {
+    element.attribute(name).ok_or_else(|| sbe_error(&format!("{} without {}", element.name, name)))
+}
+
+fn number(element: &Element, name: &str) -> Result<usize, FixError> {
+    let value = required(element, name)?;
+    value.parse::<usize>().map_err(|_| sbe_error(&format!("{} {} is not a number", name, value)))
+}
+
+//SchemaLoader resolves types by name, definitions may come after their use
+struct SchemaLoader<'x> {
+    types: HashMap<String, &'x Element>
+}
+
+impl<'x> SchemaLoader<'x> {
+
+    fn resolve(&self, name: &str) -> Result<SbeType, FixError> {
+        if let Some(primitive) = Primitive::from_name(name) {
+            return Ok(SbeType::Encoded{primitive: primitive, length: 1, constant: None, semantic_type: None});
+        }
+        match self.types.get(name) {
+            Some(definition) => self.definition(definition),
+            None => Err(sbe_error(&format!("unknown type {}", name)))
+        }
+    }
+
+    fn primitive(&self, name: &str) -> Result<Primitive, FixError> {
+        match self.resolve(name)? {
+            SbeType::Encoded{primitive, length: 1, ..} => Ok(primitive),
+            _ => Err(sbe_error(&format!("{} is not a primitive", name)))
+        }
+    }
+
+    fn definition(&self, definition: &Element) -> Result<SbeType, FixError> {
+        match definition.local_name() {
+            "type" => Ok(SbeType::Encoded{
+                primitive: self.primitive(required(definition, "primitiveType")?)?,
+                length: definition.attribute("length").map_or(Ok(1), |_| number(definition, "length"))?,
+                constant: match definition.attribute("presence") {
+                    Some("constant") => Some(definition.text.clone()),
+                    _ => None
+                },
+                semantic_type: definition.attribute("semanticType").map(|semantic_type| semantic_type.to_string())
+            }),
+            "enum" => Ok(SbeType::Enum{
+                encoding: self.primitive(required(definition, "encodingType")?)?,
+                values: definition.children.iter().filter(|child| child.local_name() == "validValue")
+                    .map(|value| (value.attribute("name").unwrap_or("").to_string(), value.text.clone())).collect()
+            }),
+            //a set is written as its bits
+            "set" => Ok(SbeType::Encoded{
+                primitive: self.primitive(required(definition, "encodingType")?)?,
+                length: 1,
+                constant: None,
+                semantic_type: None
+            }),
+            "composite" => {
+                let mut members = vec![];
+                let mut offset = 0;
+                for member in definition.children.iter() {
+                    let member_type = match member.local_name() {
+                        "ref" => self.resolve(required(member, "type")?)?,
+                        _ => self.definition(member)?
+                    };
+                    if member.attribute("offset").is_some() {
+                        offset = number(member, "offset")?;
+                    }
+                    let size = member_type.size();
+                    members.push((required(member, "name")?.to_string(), offset, member_type));
+                    offset += size;
+                }
+                Ok(SbeType::Composite{members: members})
+            },
+            other => Err(sbe_error(&format!("unknown type definition {}", other)))
+        }
+    }
+
+    //constant is the value of a field with presence constant, given as text or as valueRef="Enum.Value"
+    fn constant(&self, field: &Element) -> Result<String, FixError> {
+        let value_ref = match field.attribute("valueRef") {
+            Some(value_ref) => value_ref,
+            None => return Ok(field.text.clone())
+        };
+        let mut parts = value_ref.splitn(2, '.');
+        let (enum_name, value_name) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        match self.resolve(enum_name)? {
+            SbeType::Enum{ref values, ..} => values.iter().find(|(name, _)| name == value_name).map(|(_, value)| value.clone()),
+            _ => None
+        }.ok_or_else(|| sbe_error(&format!("unknown valueRef {}", value_ref)))
+    }
+
+    fn block(&self, element: &Element) -> Result<SbeBlock, FixError> {
+        let mut block = SbeBlock{block_length: 0, fields: vec![], groups: vec![], data: vec![]};
+        let mut offset = 0;
+        for child in element.children.iter() {
+            match child.local_name() {
+                "field" => {
+                    let encoding = self.resolve(required(child, "type")?)?;
+                    if child.attribute("offset").is_some() {
+                        offset = number(child, "offset")?;
+                    }
+                    let constant = match (child.attribute("presence"), &encoding) {
+                        (Some("constant"), _) => Some(self.constant(child)?),
+                        (_, SbeType::Encoded{constant, ..}) => constant.clone(),
+                        _ => None
+                    };
+                    let field = SbeField{
+                        name: required(child, "name")?.to_string(),
+                        id: number(child, "id")? as u32,
+                        offset: offset,
+                        optional: child.attribute("presence") == Some("optional"),
+                        encoding: encoding,
+                        constant: constant
+                    };
+                    offset += field.size();
+                    block.block_length = block.block_length.max(field.offset + field.size());
+                    block.fields.push(field);
+                },
+                "group" => block.groups.push(SbeGroup{
+                    name: required(child, "name")?.to_string(),
+                    id: number(child, "id")? as u32,
+                    dimension: self.resolve(child.attribute("dimensionType").unwrap_or("groupSizeEncoding"))?,
+                    block: self.block(child)?
+                }),
+                "data" => block.data.push(SbeData{
+                    name: required(child, "name")?.to_string(),
+                    id: number(child, "id")? as u32,
+                    encoding: self.resolve(required(child, "type")?)?
+                }),
+                _ => {}
+            }
+        }
+        if element.attribute("blockLength").is_some() {
+            block.block_length = number(element, "blockLength")?;
+        }
+        Ok(block)
+    }
+}
+
+//timestamps are nanoseconds since the epoch when the semanticType is UTCTimestamp
+fn parse_timestamp(value: &str) -> Result<Scalar, FixError> {
+    let mut timestamp = FIXUTCTimestamp::empty();
+    timestamp.read(value.as_bytes())?;
+    let timespec = timestamp.into().to_timespec();
+    Ok(Scalar::UInt(timespec.sec as u64 * 1_000_000_000 + timespec.nsec as u64))
+}
+
+fn format_timestamp(nanos: u64) -> String {
+    let timespec = Timespec::new((nanos / 1_000_000_000) as i64, (nanos % 1_000_000_000) as i32);
+    String::from_utf8_lossy(&FIXUTCTimestamp::new(time::at_utc(timespec)).write()).into_owned()
+}
+
+//write_value writes the FIX value of a field into buf, None writes the null value
+fn write_value(name: &str, encoding: &SbeType, value: Option<&str>, buf: &mut [u8]) -> Result<(), FixError> {
+    match *encoding {
+        SbeType::Encoded{primitive: Primitive::Char, length, ..} if length != 1 => {
+            let value = value.unwrap_or("").as_bytes();
+            if value.len() > length {
+                return Err(sbe_error(&format!("{} is longer than the {} chars of {}", String::from_utf8_lossy(value), length, name)));
+            }
+            buf[..value.len()].copy_from_slice(value);
+        },
+        SbeType::Encoded{primitive, length: 1, ref semantic_type, ..} => {
+            let scalar = match value {
+                Some(value) if primitive == Primitive::UInt64 && semantic_type.as_deref() == Some("UTCTimestamp") => parse_timestamp(value)?,
+                Some(value) => primitive.parse(value)?,
+                None => primitive.null()
+            };
+            primitive.write(scalar, buf);
+        },
+        SbeType::Enum{encoding, ref values} => {
+            let scalar = match value {
+                Some(value) if values.iter().any(|(_, v)| v == value) => encoding.parse(value)?,
+                Some(value) => return Err(sbe_error(&format!("{} is not a value of {}", value, name))),
+                None => encoding.null()
+            };
+            encoding.write(scalar, buf);
+        },
+        SbeType::Composite{..} if encoding.is_decimal() => {
+            let (mantissa_offset, mantissa) = encoding.member_primitive("mantissa")?;
+            let (exponent_offset, exponent) = match encoding.member("exponent") {
+                Some((offset, &SbeType::Encoded{primitive, ref constant, ..})) => (offset, (primitive, constant.clone())),
+                _ => return Err(sbe_error(&format!("the exponent of {} is not a primitive", name)))
+            };
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    mantissa.write(mantissa.null(), &mut buf[mantissa_offset..]);
+                    return Ok(());
+                }
+            };
+            let (mut m, mut e) = parse_decimal(value).ok_or_else(|| sbe_error(&format!("{} is not a decimal", value)))?;
+            if let (_, Some(ref constant)) = exponent {
+                let constant = constant.parse::<i32>().map_err(|_| sbe_error(&format!("bad exponent of {}", name)))?;
+                while e > constant {
+                    m = m.checked_mul(10).ok_or_else(|| sbe_error(&format!("{} overflows {}", value, name)))?;
+                    e -= 1;
+                }
+                while e < constant {
+                    if m % 10 != 0 {
+                        return Err(sbe_error(&format!("{} has more decimals than {}", value, name)));
+                    }
+                    m /= 10;
+                    e += 1;
+                }
+            }
+            mantissa.write(mantissa.parse(&m.to_string())?, &mut buf[mantissa_offset..]);
+            if exponent.1.is_none() {
+                exponent.0.write(exponent.0.parse(&e.to_string())?, &mut buf[exponent_offset..]);
+            }
+        },
+        _ => return Err(sbe_error(&format!("{} can not hold a FIX value", name)))
+    }
+    Ok(())
+}
+
+//read_value reads the FIX value of a field, None for the null value
+fn read_value(encoding: &SbeType, buf: &[u8]) -> Option<String> {
+    match *encoding {
+        SbeType::Encoded{primitive: Primitive::Char, length, ..} if length != 1 => {
+            let bytes = &buf[..length];
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(length);
+            if end == 0 { None } else { Some(String::from_utf8_lossy(&bytes[..end]).into_owned()) }
+        },
+        SbeType::Encoded{primitive, length: 1, ref semantic_type, ..} => {
+            let scalar = primitive.read(buf);
+            match scalar {
+                _ if primitive.is_null(scalar) => None,
+                Scalar::UInt(nanos) if semantic_type.as_deref() == Some("UTCTimestamp") => Some(format_timestamp(nanos)),
+                scalar => Some(scalar.to_fix())
+            }
+        },
+        SbeType::Enum{encoding, ..} => {
+            let scalar = encoding.read(buf);
+            if encoding.is_null(scalar) { None } else { Some(scalar.to_fix()) }
+        },
+        SbeType::Composite{..} if encoding.is_decimal() => {
+            let (mantissa_offset, mantissa) = encoding.member_primitive("mantissa").ok()?;
+            let m = mantissa.read(&buf[mantissa_offset..]);
+            if mantissa.is_null(m) {
+                return None;
+            }
+            let exponent = match encoding.member("exponent") {
+                Some((_, &SbeType::Encoded{constant: Some(ref constant), ..})) => constant.parse::<i32>().ok()?,
+                Some((offset, &SbeType::Encoded{primitive, ..})) => primitive.read(&buf[offset..]).to_i64() as i32,
+                _ => return None
+            };
+            Some(format_decimal(m.to_i64(), exponent))
+        },
+        _ => None
+    }
+}
+
+//SbeView reads a message or a group instance straight from the buffer, nothing is copied until a value is
+//converted to its FIX string
+#[derive(Debug, Clone, Copy)]
+pub struct SbeView<'a> {
+    buf: &'a [u8],
+    name: &'a str,
+    block: &'a SbeBlock,
+    offset: usize,
+    //block_length is the length given by the buffer, it may differ from the schema when versions differ
+    block_length: usize
+}
+
+impl<'a> SbeView<'a> {
+
+    fn new(buf: &'a [u8], name: &'a str, block: &'a SbeBlock, offset: usize, block_length: usize) -> Result<SbeView<'a>, FixError> {
+        if offset + block_length > buf.len() {
+            return Err(sbe_error(&format!("{} is truncated", name)));
+        }
+        Ok(SbeView{buf: buf, name: name, block: block, offset: offset, block_length: block_length})
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn block(&self) -> &'a SbeBlock {
+        self.block
+    }
+
+    //field_bytes is the bytes of a field, None for fields past the block length of an older version
+    fn field_bytes(&self, field: &SbeField) -> Option<&'a [u8]> {
+        if field.offset + field.size() > self.block_length {
+            return None;
+        }
+        Some(&self.buf[self.offset + field.offset..self.offset + field.offset + field.size()])
+    }
+
+    fn field(&self, name: &str) -> Option<&'a SbeField> {
+        self.block.fields.iter().find(|field| field.name == name)
+    }
+
+    //value is the FIX string value of a field
+    pub fn value(&self, name: &str) -> Option<String> {
+        let field = self.field(name)?;
+        if let Some(ref constant) = field.constant {
+            return Some(constant.clone());
+        }
+        read_value(&field.encoding, self.field_bytes(field)?)
+    }
+
+    //get_str is a char array field without its NUL padding
+    pub fn get_str(&self, name: &str) -> Option<&'a str> {
+        let field = self.field(name)?;
+        let bytes = self.field_bytes(field)?;
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        ::std::str::from_utf8(&bytes[..end]).ok()
+    }
+
+    fn get_scalar(&self, name: &str) -> Option<Scalar> {
+        let field = self.field(name)?;
+        let primitive = match field.encoding {
+            SbeType::Encoded{primitive, length: 1, constant: None, ..} => primitive,
+            SbeType::Enum{encoding, ..} => encoding,
+            _ => return None
+        };
+        let scalar = primitive.read(self.field_bytes(field)?);
+        if primitive.is_null(scalar) { None } else { Some(scalar) }
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get_scalar(name).map(|scalar| scalar.to_i64())
+    }
+
+    pub fn get_u64(&self, name: &str) -> Option<u64> {
+        match self.get_scalar(name)? {
+            Scalar::Int(value) if value < 0 => None,
+            scalar => Some(scalar.to_i64() as u64)
+        }
+    }
+
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        match self.get_scalar(name)? {
+            Scalar::Float(value) => Some(value),
+            scalar => Some(scalar.to_i64() as f64)
+        }
+    }
+
+    pub fn get_char(&self, name: &str) -> Option<char> {
+        match self.get_scalar(name)? {
+            Scalar::Char(value) => Some(value as char),
+            _ => None
+        }
+    }
+
+    //walk steps once over the groups and the var data following the block. Each group instance is handed to
+    //read_instance with the index of its group and read_instance gives back the offset following the instance. walk gives back the bytes of every var data field and the offset following them.
+    fn walk<F>(&self, mut read_instance: F) -> Result<(Vec<&'a [u8]>, usize), FixError>
+        where F: FnMut(usize, SbeView<'a>) -> Result<usize, FixError> {
+        let mut pos = self.offset + self.block_length;
+        for (index, group) in self.block.groups.iter().enumerate() {
+            let dimension_size = group.dimension.size();
+            if pos + dimension_size > self.buf.len() {
+                return Err(sbe_error(&format!("{} is truncated", group.name)));
+            }
+            let block_length = group.dimension.read_member(&self.buf[pos..], "blockLength")? as usize;
+            let count = group.dimension.read_member(&self.buf[pos..], "numInGroup")? as usize;
+            pos += dimension_size;
+            for _ in 0..count {
+                let instance = SbeView::new(self.buf, &group.name, &group.block, pos, block_length)?;
+                pos = read_instance(index, instance)?;
+            }
+        }
+        let mut bytes = Vec::with_capacity(self.block.data.len());
+        for data in self.block.data.iter() {
+            let length_size = data.encoding.member_primitive("length")?.1.size();
+            if pos + length_size > self.buf.len() {
+                return Err(sbe_error(&format!("{} is truncated", data.name)));
+            }
+            let length = data.encoding.read_member(&self.buf[pos..], "length")? as usize;
+            pos += length_size;
+            if pos + length > self.buf.len() {
+                return Err(sbe_error(&format!("{} is truncated", data.name)));
+            }
+            bytes.push(&self.buf[pos..pos + length]);
+            pos += length;
+        }
+        Ok((bytes, pos))
+    }
+
+    //group is the instances of a repeating group
+    pub fn group(&self, name: &str) -> Result<Vec<SbeView<'a>>, FixError> {
+        let index = match self.block.groups.iter().position(|group| group.name == name) {
+            Some(index) => index,
+            None => return Err(sbe_error(&format!("{} has no group {}", self.name, name)))
+        };
+        let mut instances = vec![];
+        self.walk(|group, instance| {
+            if group == index {
+                instances.push(instance);
+            }
+            instance.end()
+        })?;
+        Ok(instances)
+    }
+
+    //data is the bytes of a var data field
+    pub fn data(&self, name: &str) -> Result<&'a [u8], FixError> {
+        match self.block.data.iter().position(|data| data.name == name) {
+            Some(index) => self.walk(|_, instance| instance.end()).map(|(bytes, _)| bytes[index]),
+            None => Err(sbe_error(&format!("{} has no data {}", self.name, name)))
+        }
+    }
+
+    //end is the offset following the block, its groups and its data
+    pub fn end(&self) -> Result<usize, FixError> {
+        self.walk(|_, instance| instance.end()).map(|(_, pos)| pos)
+    }
+
+    //fields appends the FIX fields of the view in schema order, groups holding their instances, and gives back
+    //the offset following the view
+    fn fields(&self, fields: &mut Vec<Field>) -> Result<usize, FixError> {
+        for field in self.block.fields.iter() {
+            let value = match field.constant {
+                Some(ref constant) => Some(constant.clone()),
+                None => self.field_bytes(field).and_then(|bytes| read_value(&field.encoding, bytes))
+            };
+            if let Some(value) = value {
+                fields.push(Field::from_tag_value(TagValue::new(field.id, value.as_bytes())));
+            }
+        }
+        let mut groups: Vec<Vec<FieldMap>> = self.block.groups.iter().map(|_| vec![]).collect();
+        let (bytes, pos) = self.walk(|index, instance| {
+            let mut instance_fields = vec![];
+            let pos = instance.fields(&mut instance_fields)?;
+            let mut instance_map = FieldMap::new_with_ordering(FieldMap::received_field_order);
+            for field in instance_fields.into_iter() {
+                instance_map.add(field);
+            }
+            groups[index].push(instance_map);
+            Ok(pos)
+        })?;
+        for (group, instances) in self.block.groups.iter().zip(groups) {
+            if !instances.is_empty() {
+                fields.push(Field::from_group(group.id, instances));
+            }
+        }
+        for (data, bytes) in self.block.data.iter().zip(bytes) {
+            if !bytes.is_empty() {
+                fields.push(Field::from_tag_value(TagValue::new(data.id, bytes)));
+            }
+        }
+        Ok(pos)
+    }
+}
+
+//SbeCodec encodes messages of its schema to SBE and decodes them back. Messages are found by their
+//semanticType, the MsgType, and fields by their id, the FIX tag. Session level header fields the schema does not
+//define are left out, SBE messages are not carried by a FIX session.
+pub struct SbeCodec {
+    schema: SbeSchema,
+    begin_string: String
+}
+
+impl SbeCodec {
+
+    //new decodes messages as the FIX version named by the semanticVersion of the schema, FIX.5.0SP2 when it
+    //names none
+    pub fn new(schema: SbeSchema) -> SbeCodec {
+        let begin_string = if schema.semantic_version.starts_with("FIX.") {
+            schema.semantic_version.clone()
+        } else {
+            BEGIN_STRING_FIX50SP2.to_string()
+        };
+        SbeCodec{schema: schema, begin_string: begin_string}
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SbeCodec, FixError> {
+        Ok(SbeCodec::new(SbeSchema::load(path)?))
+    }
+
+    pub fn with_begin_string(mut self, begin_string: &str) -> SbeCodec {
+        self.begin_string = begin_string.to_string();
+        self
+    }
+
+    pub fn schema(&self) -> &SbeSchema {
+        &self.schema
+    }
+
+    pub fn encode(&self, msg: &Message) -> Result<Vec<u8>, FixError> {
+        let fields: Vec<(u32, String)> = if msg.fields().is_empty() {
+            msg.header.iter().chain(msg.body.iter()).chain(msg.trailer.iter())
+                .map(|(tag, value)| (tag, String::from_utf8_lossy(value).into_owned())).collect()
+        } else {
+            msg.fields().iter().map(|field| (field.tag(), String::from_utf8_lossy(field.value()).into_owned())).collect()
+        };
+        let msg_type = msg.header.get_string(Tags::MsgType.to_num()).map_err(|_| sbe_error("MsgType is missing"))?;
+        let message = self.schema.message_by_type(&msg_type).ok_or_else(|| sbe_error(&format!("no message of MsgType {}", msg_type)))?;
+
+        let fields: Vec<(u32, String)> = fields.into_iter()
+            .filter(|&(tag, _)| message.block.contains(tag) || (!tag.is_header() && !tag.is_trailer())).collect();
+        let mut out = vec![0; self.schema.header.size()];
+        {
+            let header = &self.schema.header;
+            header.write_member(&mut out, "blockLength", message.block.block_length as u64)?;
+            header.write_member(&mut out, "templateId", message.id as u64)?;
+            header.write_member(&mut out, "schemaId", self.schema.id as u64)?;
+            header.write_member(&mut out, "version", self.schema.version as u64)?;
+        }
+        encode_block(&message.name, &message.block, &fields, &mut out)?;
+        Ok(out)
+    }
+
+    //encode_value encodes a typed message through its tag=value serialization
+    #[cfg(feature = "serde")]
+    pub fn encode_value<T: ?Sized + Serialize>(&self, value: &T, msg_type: &str) -> Result<Vec<u8>, FixError> {
+        self.encode(&::serde_tag_value::to_message(value, &self.begin_string, msg_type)?)
+    }
+
+    //view reads the message header and gives the message without decoding it
+    pub fn view<'a>(&'a self, buf: &'a [u8]) -> Result<SbeView<'a>, FixError> {
+        let header = &self.schema.header;
+        if buf.len() < header.size() {
+            return Err(sbe_error("the message header is truncated"));
+        }
+        let schema_id = header.read_member(buf, "schemaId")?;
+        if schema_id != self.schema.id as u64 {
+            return Err(sbe_error(&format!("schemaId {} is not {}", schema_id, self.schema.id)));
+        }
+        let template_id = header.read_member(buf, "templateId")?;
+        let message = self.schema.message(template_id as u16).ok_or_else(|| sbe_error(&format!("unknown templateId {}", template_id)))?;
+        let block_length = header.read_member(buf, "blockLength")? as usize;
+        SbeView::new(buf, &message.name, &message.block, header.size(), block_length)
+    }
+
+    pub fn decode(&self, buf: &[u8]) -> Result<Message, FixError> {
+        let view = self.view(buf)?;
+        let message = self.schema.messages.iter().find(|message| message.name == view.name()).ok_or_else(|| sbe_error("unknown message"))?;
+        let msg_type = message.msg_type.as_ref().ok_or_else(|| sbe_error(&format!("{} has no semanticType", message.name)))?;
+        let mut fields = vec![];
+        view.fields(&mut fields)?;
+        let mut tag_values = vec![];
+        for field in fields.iter() {
+            field.flatten(&mut tag_values);
+        }
+
+        let mut raw = vec![];
+        {
+            let mut writer = if self.begin_string.starts_with(BEGIN_STRING_FIX50) {
+                let mut writer = MessageWriter::new(&mut raw, BEGIN_STRING_FIXT11, msg_type);
+                writer.write_str(Tags::ApplVerID.to_num(), begin_string_appl_ver_id(&self.begin_string));
+                writer
+            } else {
+                MessageWriter::new(&mut raw, &self.begin_string, msg_type)
+            };
+            for &(tag, value) in tag_values.iter().filter(|&&(tag, _)| tag.is_header()) {
+                writer.write_bytes(tag, value);
+            }
+            for &(tag, value) in tag_values.iter().filter(|&&(tag, _)| !tag.is_header()) {
+                writer.write_bytes(tag, value);
+            }
+            writer.finish();
+        }
+        //the groups are the ones of the view, parsing alone reads a single instance as plain fields
+        let mut msg = Message::parse(&raw)?;
+        for field in fields.iter().filter(|field| field.is_group()) {
+            let mut members = vec![];
+            field.flatten(&mut members);
+            for &(tag, _) in members.iter().skip(1) {
+                if !fields.iter().any(|field| field.field_tag() == tag) {
+                    msg.body.remove(tag);
+                }
+            }
+        }
+        for field in fields.into_iter().filter(|field| field.is_group()) {
+            if field.field_tag().is_header() {
+                msg.header.add(field);
+            } else {
+                msg.body.add(field);
+            }
+        }
+        Ok(msg)
+    }
+
+    //decode_value decodes a typed message through its tag=value deserialization
+    #[cfg(feature = "serde")]
+    pub fn decode_value<T: DeserializeOwned>(&self, buf: &[u8]) -> Result<T, FixError> {
+        ::serde_tag_value::from_message(&self.decode(buf)?)
+    }
+}
+
+//encode_block writes the block of fields, then the instances of the groups and the var data. The instances of
+//a group follow its NumInGroup field, each one starting with the first field of the group.
+fn encode_block(name: &str, block: &SbeBlock, fields: &[(u32, String)], out: &mut Vec<u8>) -> Result<(), FixError> {
+    let mut top: Vec<&(u32, String)> = vec![];
+    let mut instances: Vec<Vec<&[(u32, String)]>> = block.groups.iter().map(|_| vec![]).collect();
+    let mut pos = 0;
+    while pos < fields.len() {
+        let (tag, ref value) = fields[pos];
+        pos += 1;
+        let index = match block.groups.iter().position(|group| group.id == tag) {
+            Some(index) => index,
+            None if block.contains(tag) || tag.is_header() || tag.is_trailer() => {
+                top.push(&fields[pos - 1]);
+                continue;
+            },
+            None => return Err(sbe_error(&format!("tag {} is not part of {}", tag, name)))
+        };
+        let group = &block.groups[index];
+        let delimiter = group.block.fields.iter().find(|field| field.constant.is_none()).map(|field| field.id)
+            .ok_or_else(|| sbe_error(&format!("group {} has no fields", group.name)))?;
+        let count = value.parse::<usize>().map_err(|_| sbe_error(&format!("{} is not a NumInGroup value", value)))?;
+        for _ in 0..count {
+            if pos >= fields.len() || fields[pos].0 != delimiter {
+                return Err(sbe_error(&format!("group {} holds less than {} instances", group.name, count)));
+            }
+            let start = pos;
+            pos += 1;
+            while pos < fields.len() && fields[pos].0 != delimiter && group.block.contains(fields[pos].0) {
+                pos += 1;
+            }
+            instances[index].push(&fields[start..pos]);
+        }
+    }
+    let get = |tag: u32| top.iter().find(|&&&(t, _)| t == tag).map(|(_, value)| value.as_str());
+
+    let start = out.len();
+    out.resize(start + block.block_length, 0);
+    for field in block.fields.iter().filter(|field| field.constant.is_none()) {
+        let value = get(field.id);
+        if value.is_none() && !field.optional {
+            return Err(sbe_error(&format!("required field {}({}) is missing", field.name, field.id)));
+        }
+        let offset = start + field.offset;
+        write_value(&field.name, &field.encoding, value, &mut out[offset..offset + field.size()])?;
+    }
+
+    for (group, instances) in block.groups.iter().zip(instances.iter()) {
+        let dimension = out.len();
+        out.resize(dimension + group.dimension.size(), 0);
+        group.dimension.write_member(&mut out[dimension..], "blockLength", group.block.block_length as u64)?;
+        group.dimension.write_member(&mut out[dimension..], "numInGroup", instances.len() as u64)?;
+        for instance in instances.iter() {
+            encode_block(&group.name, &group.block, instance, out)?;
+        }
+    }
+
+    for data in block.data.iter() {
+        let value = get(data.id).unwrap_or("").as_bytes();
+        let length = out.len();
+        out.resize(length + data.encoding.member_primitive("length")?.1.size(), 0);
+        data.encoding.write_member(&mut out[length..], "length", value.len() as u64)?;
+        out.extend_from_slice(value);
+    }
+    Ok(())
+}
+
+//sofh_frame prefixes an SBE message with its Simple Open Framing Header
+pub fn sofh_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(SOFH_LEN + message.len());
+    frame.extend_from_slice(&((SOFH_LEN + message.len()) as u32).to_be_bytes());
+    frame.extend_from_slice(&SOFH_SBE_LITTLE_ENDIAN.to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+//is_sofh_frame tells whether buf starts with the framing header of an SBE message
+pub fn is_sofh_frame(buf: &[u8]) -> bool {
+    buf.len() >= SOFH_LEN && u16::from_be_bytes([buf[4], buf[5]]) == SOFH_SBE_LITTLE_ENDIAN
+}
+
+//sofh_unframe gives the SBE message at the start of buf and the length of its frame, None until the frame is
+//complete
+pub fn sofh_unframe(buf: &[u8]) -> Result<Option<(&[u8], usize)>, FixError> {
+    if buf.len() < SOFH_LEN {
+        return Ok(None);
+    }
+    if !is_sofh_frame(buf) {
+        return Err(sbe_error("not an SBE little-endian frame"));
+    }
+    let length = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+    if length < SOFH_LEN {
+        return Err(sbe_error(&format!("bad frame length {}", length)));
+    }
+    if buf.len() < length {
+        return Ok(None);
+    }
+    Ok(Some((&buf[SOFH_LEN..length], length)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static SCHEMA: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" package="orders" id="7" version="1"
+                   semanticVersion="FIX.4.4" byteOrder="littleEndian">
+  <types>
+    <composite name="messageHeader">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="templateId" primitiveType="uint16"/>
+      <type name="schemaId" primitiveType="uint16"/>
+      <type name="version" primitiveType="uint16"/>
+    </composite>
+    <composite name="groupSizeEncoding">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="numInGroup" primitiveType="uint16"/>
+    </composite>
+    <composite name="varStringEncoding">
+      <type name="length" primitiveType="uint16"/>
+      <type name="varData" primitiveType="uint8" length="0" characterEncoding="UTF-8"/>
+    </composite>
+    <type name="IDString" primitiveType="char" length="8"/>
+    <type name="PartyIDString" primitiveType="char" length="4"/>
+    <type name="Timestamp" primitiveType="uint64" semanticType="UTCTimestamp"/>
+    <composite name="Price">
+      <type name="mantissa" primitiveType="int64"/>
+      <type name="exponent" primitiveType="int8" presence="constant">-2</type>
+    </composite>
+  </types>
+  <types>
+    <enum name="SideEnum" encodingType="char">
+      <validValue name="Buy">1</validValue>
+      <validValue name="Sell">2</validValue>
+    </enum>
+    <enum name="OrdTypeEnum" encodingType="char">
+      <validValue name="Limit">2</validValue>
+    </enum>
+  </types>
+  <sbe:message name="NewOrderSingle" id="14" semanticType="D">
+    <field name="ClOrdID" id="11" type="IDString"/>
+    <field name="Side" id="54" type="SideEnum"/>
+    <field name="OrderQty" id="38" type="uint32"/>
+    <field name="OrdType" id="40" type="OrdTypeEnum" presence="constant" valueRef="OrdTypeEnum.Limit"/>
+    <field name="Price" id="44" type="Price" presence="optional"/>
+    <field name="TransactTime" id="60" type="Timestamp"/>
+    <group name="Parties" id="453">
+      <field name="PartyID" id="448" type="PartyIDString"/>
+      <field name="PartyRole" id="452" type="uint8"/>
+    </group>
+    <data name="Text" id="58" type="varStringEncoding"/>
+  </sbe:message>
+</sbe:messageSchema>"#;
+
+    static RAW: &'static [u8] = b"8=FIX.4.4\x019=0\x0135=D\x0149=TW\x0156=ISLD\x0111=ID1\x0154=1\x0138=100\x0140=2\x0144=52.1\x01\
+60=20161012-10:05:01.005000000\x01453=2\x01448=TW\x01452=1\x01448=ISLD\x01452=3\x0158=hello\x0110=000\x01";
+
+    fn codec() -> SbeCodec {
+        SbeCodec::new(SbeSchema::parse(SCHEMA).unwrap())
+    }
+
+    #[test]
+    fn schema_test() {
+        let schema = SbeSchema::parse(SCHEMA).unwrap();
+        assert_eq!(7, schema.id);
+        assert_eq!(8, schema.header.size());
+        let message = schema.message_by_type("D").unwrap();
+        assert_eq!(14, message.id);
+        //ClOrdID 8, Side 1, OrderQty 4, OrdType constant, Price 8, TransactTime 8
+        assert_eq!(29, message.block.block_length);
+        assert_eq!(vec![0, 8, 9, 13, 13, 21], message.block.fields.iter().map(|field| field.offset).collect::<Vec<usize>>());
+        assert_eq!(Some("2".to_string()), message.block.fields[3].constant);
+        assert_eq!(5, message.block.groups[0].block.block_length);
+    }
+
+    #[test]
+    fn encode_test() {
+        let buf = codec().encode(&Message::parse(RAW).unwrap()).unwrap();
+        assert_eq!(&[29, 0, 14, 0, 7, 0, 1, 0], &buf[..8]);
+        assert_eq!(b"ID1\x00\x00\x00\x00\x00", &buf[8..16]);
+        assert_eq!(b'1', buf[16]);
+        assert_eq!(&[100, 0, 0, 0], &buf[17..21]);
+        assert_eq!(&5210i64.to_le_bytes(), &buf[21..29]);
+        //the parties dimension, 2 instances of 5 bytes, then the text
+        assert_eq!(&[5, 0, 2, 0], &buf[37..41]);
+        assert_eq!(b"ISLD\x03", &buf[46..51]);
+        assert_eq!(b"\x05\x00hello", &buf[51..]);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let codec = codec();
+        let msg = codec.decode(&codec.encode(&Message::parse(RAW).unwrap()).unwrap()).unwrap();
+        assert_eq!("8=FIX.4.4|9=106|35=D|11=ID1|54=1|38=100|40=2|44=52.10|60=20161012-10:05:01.005|453=2|448=TW|452=1|\
+448=ISLD|452=3|58=hello|10=041|", String::from_utf8_lossy(msg.raw_message()).replace("\x01", "|"));
+    }
+
+    #[test]
+    fn view_test() {
+        let codec = codec();
+        let buf = codec.encode(&Message::parse(RAW).unwrap()).unwrap();
+        let view = codec.view(&buf).unwrap();
+        assert_eq!("NewOrderSingle", view.name());
+
+        let cl_ord_id = view.get_str("ClOrdID").unwrap();
+        assert_eq!("ID1", cl_ord_id);
+        assert_eq!(buf[8..].as_ptr(), cl_ord_id.as_ptr());
+        assert_eq!(Some('1'), view.get_char("Side"));
+        assert_eq!(Some(100), view.get_u64("OrderQty"));
+        assert_eq!(Some("52.10".to_string()), view.value("Price"));
+        assert_eq!(Some("2".to_string()), view.value("OrdType"));
+
+        let parties = view.group("Parties").unwrap();
+        assert_eq!(2, parties.len());
+        assert_eq!(Some("ISLD"), parties[1].get_str("PartyID"));
+        assert_eq!(Some(3), parties[1].get_i64("PartyRole"));
+        assert_eq!(b"hello", view.data("Text").unwrap());
+        assert_eq!(buf.len(), view.end().unwrap());
+        assert!(view.group("Legs").is_err());
+    }
+
+    #[test]
+    fn optional_field_test() {
+        let codec = codec();
+        let raw = b"8=FIX.4.4\x019=0\x0135=D\x0111=ID1\x0154=2\x0138=5\x0160=20161012-10:05:01\x0110=000\x01";
+        let buf = codec.encode(&Message::parse(raw).unwrap()).unwrap();
+        let view = codec.view(&buf).unwrap();
+        assert_eq!(None, view.value("Price"));
+        assert_eq!(0, view.group("Parties").unwrap().len());
+
+        let msg = codec.decode(&buf).unwrap();
+        assert!(msg.body.get_string(44).is_err());
+        assert!(msg.body.get_string(453).is_err());
+        assert_eq!("20161012-10:05:01.000", msg.body.get_string(60).unwrap());
+    }
+
+    #[test]
+    fn encode_error_test() {
+        let codec = codec();
+        let encode = |fields: &str| codec.encode(&Message::parse(format!("8=FIX.4.4\x019=0\x0135=D\x01{}10=000\x01", fields).as_bytes()).unwrap());
+        let valid = "11=ID1\x0154=1\x0138=100\x0160=20161012-10:05:01\x01";
+        assert!(encode(valid).is_ok());
+        //unknown field, ClOrdID too long, unknown enum value, missing required field, too many decimals
+        assert!(encode(&format!("{}5001=x\x01", valid)).is_err());
+        assert!(encode("11=ID123456789\x0154=1\x0138=100\x0160=20161012-10:05:01\x01").is_err());
+        assert!(encode("11=ID1\x0154=9\x0138=100\x0160=20161012-10:05:01\x01").is_err());
+        assert!(encode("11=ID1\x0154=1\x0160=20161012-10:05:01\x01").is_err());
+        assert!(encode(&format!("{}44=1.125\x01", valid)).is_err());
+        assert!(codec.encode(&Message::parse(b"8=FIX.4.4\x019=0\x0135=0\x0110=000\x01").unwrap()).is_err());
+    }
+
+    #[test]
+    fn decode_error_test() {
+        let codec = codec();
+        let buf = codec.encode(&Message::parse(RAW).unwrap()).unwrap();
+        assert!(codec.decode(&buf[..6]).is_err());
+        assert!(codec.decode(&buf[..20]).is_err());
+        assert!(codec.decode(&buf[..buf.len() - 1]).is_err());
+
+        let mut other_schema = buf.clone();
+        other_schema[4] = 8;
+        assert!(codec.decode(&other_schema).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn encode_value_test() {
+        #[derive(Serialize)]
+        struct Order {
+            #[serde(rename = "11")]
+            cl_ord_id: &'static str,
+            #[serde(rename = "54")]
+            side: char,
+            #[serde(rename = "38")]
+            order_qty: u32,
+            #[serde(rename = "60")]
+            transact_time: &'static str
+        }
+
+        let codec = codec();
+        let order = Order{cl_ord_id: "ID1", side: '2', order_qty: 10, transact_time: "20161012-10:05:01"};
+        let buf = codec.encode_value(&order, "D").unwrap();
+        let view = codec.view(&buf).unwrap();
+        assert_eq!(Some("ID1"), view.get_str("ClOrdID"));
+        assert_eq!(Some('2'), view.get_char("Side"));
+        assert_eq!(Some(10), view.get_u64("OrderQty"));
+    }
+
+    static NESTED_SCHEMA: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" package="legs" id="8" version="1"
+                   semanticVersion="FIX.4.4" byteOrder="littleEndian">
+  <types>
+    <composite name="messageHeader">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="templateId" primitiveType="uint16"/>
+      <type name="schemaId" primitiveType="uint16"/>
+      <type name="version" primitiveType="uint16"/>
+    </composite>
+    <composite name="groupSizeEncoding">
+      <type name="blockLength" primitiveType="uint16"/>
+      <type name="numInGroup" primitiveType="uint16"/>
+    </composite>
+    <composite name="varStringEncoding">
+      <type name="length" primitiveType="uint16"/>
+      <type name="varData" primitiveType="uint8" length="0" characterEncoding="UTF-8"/>
+    </composite>
+    <type name="Symbol" primitiveType="char" length="4"/>
+  </types>
+  <sbe:message name="NewOrderMultileg" id="15" semanticType="AB">
+    <field name="ClOrdID" id="11" type="Symbol"/>
+    <group name="Legs" id="555">
+      <field name="LegSymbol" id="600" type="Symbol"/>
+      <group name="NestedParties" id="539">
+        <field name="NestedPartyID" id="524" type="Symbol"/>
+      </group>
+    </group>
+    <data name="Text" id="58" type="varStringEncoding"/>
+  </sbe:message>
+</sbe:messageSchema>"#;
+
+    #[test]
+    fn nested_group_test() {
+        let codec = SbeCodec::new(SbeSchema::parse(NESTED_SCHEMA).unwrap());
+        let raw = b"8=FIX.4.4\x019=0\x0135=AB\x0111=ID1\x01555=2\x01600=A\x01539=2\x01524=X\x01524=Y\x01\
+600=B\x01539=1\x01524=Z\x0158=hi\x0110=000\x01";
+        let buf = codec.encode(&Message::parse(raw).unwrap()).unwrap();
+        let view = codec.view(&buf).unwrap();
+        let legs = view.group("Legs").unwrap();
+        assert_eq!(2, legs.len());
+        let parties = legs[0].group("NestedParties").unwrap();
+        assert_eq!(vec![Some("X"), Some("Y")], parties.iter().map(|party| party.get_str("NestedPartyID")).collect::<Vec<Option<&str>>>());
+        assert_eq!(Some("B"), legs[1].get_str("LegSymbol"));
+        assert_eq!(Some("Z"), legs[1].group("NestedParties").unwrap()[0].get_str("NestedPartyID"));
+        assert_eq!(buf.len() - 4, legs[1].end().unwrap());
+        assert_eq!(b"hi", view.data("Text").unwrap());
+        assert_eq!(buf.len(), view.end().unwrap());
+
+        //the single instance of the second leg stays a group
+        let msg = codec.decode(&buf).unwrap();
+        let legs = msg.body.get_group(555).unwrap();
+        assert_eq!(2, legs[0].get_group(539).unwrap().len());
+        assert_eq!("Z", legs[1].get_group(539).unwrap()[0].get_string(524).unwrap());
+        assert!(!msg.body.has(600));
+        assert!(!msg.body.has(524));
+        assert_eq!("hi", msg.body.get_string(58).unwrap());
+        assert_eq!(&buf, &codec.encode(&Message::parse(&msg.build()).unwrap()).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn decode_value_test() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Side {
+            #[serde(rename = "1")]
+            Buy,
+            #[serde(rename = "2")]
+            Sell
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Party {
+            #[serde(rename = "448")]
+            party_id: String,
+            #[serde(rename = "452")]
+            party_role: u32
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Order {
+            #[serde(rename = "11")]
+            cl_ord_id: String,
+            #[serde(rename = "54")]
+            side: Side,
+            #[serde(rename = "38")]
+            order_qty: u32,
+            #[serde(rename = "44")]
+            price: Option<f64>,
+            #[serde(rename = "453")]
+            parties: Vec<Party>,
+            #[serde(rename = "Text")]
+            text: Option<String>
+        }
+
+        let codec = codec();
+        let order: Order = codec.decode_value(&codec.encode(&Message::parse(RAW).unwrap()).unwrap()).unwrap();
+        assert_eq!(Order{
+            cl_ord_id: "ID1".to_string(),
+            side: Side::Buy,
+            order_qty: 100,
+            price: Some(52.1),
+            parties: vec![Party{party_id: "TW".to_string(), party_role: 1}, Party{party_id: "ISLD".to_string(), party_role: 3}],
+            text: Some("hello".to_string())
+        }, order);
+
+        let raw = b"8=FIX.4.4\x019=0\x0135=D\x0111=ID2\x0154=2\x0138=5\x0160=20161012-10:05:01\x01453=1\x01448=TW\x01452=1\x0110=000\x01";
+        let order: Order = codec.decode_value(&codec.encode(&Message::parse(raw).unwrap()).unwrap()).unwrap();
+        assert_eq!(Side::Sell, order.side);
+        assert_eq!(None, order.price);
+        assert_eq!(vec![Party{party_id: "TW".to_string(), party_role: 1}], order.parties);
+        assert_eq!(None, order.text);
+    }
+
+    #[test]
+    fn sofh_test() {
+        let frame = sofh_frame(b"abc");
+        assert_eq!(b"\x00\x00\x00\x09\xeb\x50abc", &frame[..]);
+        assert!(is_sofh_frame(&frame));
+        assert_eq!(None, sofh_unframe(&frame[..8]).unwrap());
+        assert_eq!(Some((&b"abc"[..], 9)), sofh_unframe(&frame).unwrap());
+        assert!(sofh_unframe(b"8=FIX.4.4\x01").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use error::FixError;
+use field::*;
+use fix_utc_timestamp::*;
+use log::*;
+use session_id::*;
+use time::*;
+
+//ScreenLog writes messages and events to standard output
+pub struct ScreenLog {
+    prefix: String
+}
+
+impl ScreenLog {
+
+    pub fn new(prefix: &str) -> ScreenLog {
+        ScreenLog{prefix: prefix.to_string()}
+    }
+
+    //entry formats one log entry, e.g. <20161012-10:05:01.005, FIX.4.2:TW->ISLD, incoming>
+    fn entry(&self, now: Tm, kind: &str, text: &str) -> String {
+        let log_time = FIXUTCTimestamp::new(now).write();
+        format!("<{}, {}, {}>\n  ({})", String::from_utf8_lossy(&log_time), self.prefix, kind, text)
+    }
+}
+
+impl Log for ScreenLog {
+    fn on_incoming(&mut self, msg: &[u8]) {
+        println!("{}", self.entry(now_utc(), "incoming", &String::from_utf8_lossy(msg)));
+    }
+
+    fn on_outgoing(&mut self, msg: &[u8]) {
+        println!("{}", self.entry(now_utc(), "outgoing", &String::from_utf8_lossy(msg)));
+    }
+
+    fn on_event(&mut self, text: &str) {
+        println!("{}", self.entry(now_utc(), "event", text));
+    }
+}
+
+pub struct ScreenLogFactory;
+
+impl ScreenLogFactory {
+
+    pub fn new() -> ScreenLogFactory {
+        ScreenLogFactory
+    }
+}
+
+impl Default for ScreenLogFactory {
+    fn default() -> ScreenLogFactory {
+        ScreenLogFactory::new()
+    }
+}
+
+impl LogFactory for ScreenLogFactory {
+    fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(ScreenLog::new("GLOBAL")))
+    }
+
+    fn create_session_log(&self, session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+        Ok(Box::new(ScreenLog::new(&session_id.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn entry_test() {
+        let log = ScreenLog::new("FIX.4.2:TW->ISLD");
+        let now = strptime("20161012-10:05:01", "%Y%m%d-%H:%M:%S").unwrap();
+        assert_eq!("<20161012-10:05:01.000, FIX.4.2:TW->ISLD, event>\n  (Received logon)", log.entry(now, "event", "Received logon"));
+
+        let mut now = now;
+        now.tm_nsec = 5_999_999;
+        assert_eq!("<20161012-10:05:01.005, FIX.4.2:TW->ISLD, event>\n  (Received logon)", log.entry(now, "event", "Received logon"));
+    }
+}
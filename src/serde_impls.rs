@@ -0,0 +1,209 @@
+use std::fmt;
+use serde::ser::{Serialize, Serializer, SerializeTuple, SerializeSeq, SerializeStruct};
+use serde::de::{self, Deserialize, Deserializer, Visitor, SeqAccess, MapAccess};
+use tag_value::*;
+use field_map::*;
+use message::*;
+use error::FixError;
+
+impl de::Error for FixError {
+    fn custom<T: fmt::Display>(msg: T) -> FixError {
+        FixError::SerializeError(msg.to_string())
+    }
+}
+
+impl ::serde::ser::Error for FixError {
+    fn custom<T: fmt::Display>(msg: T) -> FixError {
+        FixError::SerializeError(msg.to_string())
+    }
+}
+
+//Bytes serializes a value as bytes rather than as a sequence of u8
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ByteBuf, D::Error> {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a field value")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(value.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(value))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(value.as_bytes().to_vec()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+                let mut bytes = vec![];
+                while let Some(b) = seq.next_element::<u8>()? {
+                    bytes.push(b);
+                }
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+//TagValue is a (tag, value) tuple, the value being raw bytes
+impl Serialize for TagValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.tag())?;
+        tuple.serialize_element(&Bytes(self.value()))?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TagValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TagValue, D::Error> {
+        struct TagValueVisitor;
+
+        impl<'de> Visitor<'de> for TagValueVisitor {
+            type Value = TagValue;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a (tag, value) tuple")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TagValue, A::Error> {
+                let tag: u32 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let value: ByteBuf = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(TagValue::new(tag, &value.0))
+            }
+        }
+
+        deserializer.deserialize_tuple(2, TagValueVisitor)
+    }
+}
+
+//FieldMap is the sequence of its TagValues in field order
+impl Serialize for FieldMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for (tag, value) in self.iter() {
+            seq.serialize_element(&TagValue::new(tag, value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FieldMap, D::Error> {
+        let tag_values: Vec<TagValue> = Deserialize::deserialize(deserializer)?;
+        let mut field_map = FieldMap::new();
+        for tag_value in tag_values.iter() {
+            field_map.set_bytes(tag_value.tag(), tag_value.value());
+        }
+        Ok(field_map)
+    }
+}
+
+const MESSAGE_FIELDS: &'static [&'static str] = &["header", "body", "trailer"];
+
+//Message is a struct of its header, body and trailer FieldMaps
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Message", 3)?;
+        state.serialize_field("header", &self.header)?;
+        state.serialize_field("body", &self.body)?;
+        state.serialize_field("trailer", &self.trailer)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Message, D::Error> {
+        struct MessageVisitor;
+
+        //into_message copies the sections into a new Message so header and trailer keep their field ordering
+        fn into_message(sections: [FieldMap; 3]) -> Message {
+            let mut msg = Message::new();
+            let [header, body, trailer] = sections;
+            for (tag, value) in header.iter() {
+                msg.header.set_bytes(tag, value);
+            }
+            msg.body = body;
+            for (tag, value) in trailer.iter() {
+                msg.trailer.set_bytes(tag, value);
+            }
+            msg
+        }
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = Message;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a Message")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Message, A::Error> {
+                let header = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let body = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let trailer = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                Ok(into_message([header, body, trailer]))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Message, A::Error> {
+                let mut sections = [FieldMap::new(), FieldMap::new(), FieldMap::new()];
+                while let Some(key) = map.next_key::<String>()? {
+                    match MESSAGE_FIELDS.iter().position(|field| *field == key) {
+                        Some(section) => sections[section] = map.next_value()?,
+                        None => return Err(de::Error::unknown_field(&key, MESSAGE_FIELDS))
+                    }
+                }
+                Ok(into_message(sections))
+            }
+        }
+
+        deserializer.deserialize_struct("Message", MESSAGE_FIELDS, MessageVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+    use tag::*;
+
+    #[test]
+    fn tag_value_test() {
+        let tag_value = TagValue::new(55, b"MSFT");
+        let json = serde_json::to_string(&tag_value).unwrap();
+        assert_eq!("[55,[77,83,70,84]]", json);
+        assert_eq!(tag_value, serde_json::from_str::<TagValue>(&json).unwrap());
+        assert_eq!(tag_value, serde_json::from_str::<TagValue>("[55,\"MSFT\"]").unwrap());
+    }
+
+    #[test]
+    fn message_round_trip_test() {
+        let raw = b"8=FIX.4.2\x019=39\x0135=D\x0149=TW\x0134=1\x0155=MSFT\x0111=ID1\x0156=ISLD\x0110=045\x01";
+        let msg = Message::parse(raw).unwrap();
+        let json = serde_json::to_string(&msg).unwrap();
+        let decoded: Message = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(msg.build(), decoded.build());
+        assert_eq!("TW", decoded.header.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("MSFT", decoded.body.get_string(55).unwrap());
+    }
+}
@@ -0,0 +1,801 @@
+//! A serde data format writing FIX tag=value. Struct fields and map keys name FIX fields, either by tag number
+//! (`#[serde(rename = "55")]`) or by a name known to the FieldNames in use. Values are written as FIX strings:
+//! booleans as Y/N, unit enum variants as their (renamed) variant name, None is left out. Nested structs are
+//! components and are flattened, sequences of structs are repeating groups written as the NumInGroup field
+//! followed by every instance. Only data fields such as Signature(89) may hold SOH. Reading a value back follows
+//! the same rules.
+
+use serde::ser::{self, Serialize};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use tag::*;
+use tag_value::*;
+use field_map::*;
+use message::*;
+use pretty::*;
+use error::FixError;
+
+//to_bytes writes the fields of value as tag=value, each field closed by SOH
+pub fn to_bytes<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, FixError> {
+    to_bytes_with_names(value, &TagNames)
+}
+
+pub fn to_bytes_with_names<T: ?Sized + Serialize>(value: &T, names: &dyn FieldNames) -> Result<Vec<u8>, FixError> {
+    let mut output = vec![];
+    for field in to_fields(value, names)?.iter() {
+        let mut tag_values = vec![];
+        field.flatten(&mut tag_values);
+        for (tag, value) in tag_values.into_iter() {
+            output.extend_from_slice(tag.to_string().as_bytes());
+            output.push(b'=');
+            output.extend_from_slice(value);
+            output.push(SOH);
+        }
+    }
+    Ok(output)
+}
+
+//to_message puts the fields of value in the header, body or trailer of a new message, repeating groups keep the
+//instances they were serialized with. BodyLength and CheckSum are written when the message is built.
+pub fn to_message<T: ?Sized + Serialize>(value: &T, begin_string: &str, msg_type: &str) -> Result<Message, FixError> {
+    let mut msg = Message::new();
+    msg.header.set_string(Tags::BeginString.to_num(), begin_string);
+    msg.header.set_string(Tags::MsgType.to_num(), msg_type);
+    for field in to_fields(value, &TagNames)?.into_iter() {
+        let tag = field.field_tag();
+        if tag.is_header() {
+            msg.header.add(field);
+        } else if tag.is_trailer() {
+            msg.trailer.add(field);
+        } else {
+            msg.body.add(field);
+        }
+    }
+    Ok(msg)
+}
+
+fn to_fields<T: ?Sized + Serialize>(value: &T, names: &dyn FieldNames) -> Result<Vec<Field>, FixError> {
+    let mut serializer = Serializer{fields: vec![], names: names, key: None};
+    value.serialize(&mut serializer)?;
+    Ok(serializer.fields)
+}
+
+fn unsupported(what: &str) -> FixError {
+    FixError::SerializeError(format!("{} can not be written as FIX", what))
+}
+
+//Serializer writes a struct or map of fields
+pub struct Serializer<'n> {
+    fields: Vec<Field>,
+    names: &'n dyn FieldNames,
+    //the key of the map entry being written
+    key: Option<String>
+}
+
+impl<'n> Serializer<'n> {
+
+    fn tag(&self, key: &str) -> Result<u32, FixError> {
+        match key.parse::<u32>().ok().or_else(|| self.names.field_tag(key)) {
+            Some(tag) => Ok(tag),
+            None => Err(FixError::SerializeError(format!("{} is not a known field", key)))
+        }
+    }
+
+    fn write(&mut self, tag: u32, value: &[u8]) -> Result<(), FixError> {
+        if value.contains(&SOH) && !self.names.is_data(tag) {
+            return Err(FixError::SerializeError(format!("the value of {} contains SOH, only data fields may", tag)));
+        }
+        self.fields.push(Field::from_tag_value(TagValue::new(tag, value)));
+        Ok(())
+    }
+}
+
+macro_rules! unsupported_fields {
+    ($($method:ident($($arg:ty),*) -> $what:expr;)*) => {
+        $(
+        fn $method(self $(, _: $arg)*) -> Result<Self::Ok, FixError> {
+            Err(unsupported($what))
+        }
+        )*
+    }
+}
+
+impl<'a, 'n> ser::Serializer for &'a mut Serializer<'n> {
+    type Ok = ();
+    type Error = FixError;
+    type SerializeSeq = ser::Impossible<(), FixError>;
+    type SerializeTuple = ser::Impossible<(), FixError>;
+    type SerializeTupleStruct = ser::Impossible<(), FixError>;
+    type SerializeTupleVariant = ser::Impossible<(), FixError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = ser::Impossible<(), FixError>;
+
+    unsupported_fields! {
+        serialize_bool(bool) -> "a bool outside a field";
+        serialize_i8(i8) -> "a number outside a field";
+        serialize_i16(i16) -> "a number outside a field";
+        serialize_i32(i32) -> "a number outside a field";
+        serialize_i64(i64) -> "a number outside a field";
+        serialize_u8(u8) -> "a number outside a field";
+        serialize_u16(u16) -> "a number outside a field";
+        serialize_u32(u32) -> "a number outside a field";
+        serialize_u64(u64) -> "a number outside a field";
+        serialize_f32(f32) -> "a number outside a field";
+        serialize_f64(f64) -> "a number outside a field";
+        serialize_char(char) -> "a char outside a field";
+        serialize_str(&str) -> "a string outside a field";
+        serialize_bytes(&[u8]) -> "bytes outside a field";
+        serialize_none() -> "None outside a field";
+        serialize_unit() -> "a unit outside a field";
+        serialize_unit_struct(&'static str) -> "a unit struct outside a field";
+        serialize_unit_variant(&'static str, u32, &'static str) -> "an enum outside a field";
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), FixError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), FixError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<(), FixError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, FixError> {
+        Err(unsupported("a sequence outside a field"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, FixError> {
+        Err(unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, FixError> {
+        Err(unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, FixError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, FixError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, FixError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, FixError> {
+        Err(unsupported("an enum with data"))
+    }
+}
+
+impl<'a, 'n> ser::SerializeStruct for &'a mut Serializer<'n> {
+    type Ok = ();
+    type Error = FixError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), FixError> {
+        value.serialize(FieldSerializer{fields: self, key: key})
+    }
+
+    fn end(self) -> Result<(), FixError> {
+        Ok(())
+    }
+}
+
+impl<'a, 'n> ser::SerializeMap for &'a mut Serializer<'n> {
+    type Ok = ();
+    type Error = FixError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), FixError> {
+        let mut key_serializer = Serializer{fields: vec![], names: self.names, key: None};
+        key.serialize(FieldSerializer{fields: &mut key_serializer, key: "0"})?;
+        //the key was written as the value of field 0
+        match key_serializer.fields.first().map(|field| field.tag_values()[0].value()) {
+            Some(key) if !key.is_empty() => self.key = Some(String::from_utf8_lossy(key).into_owned()),
+            _ => return Err(unsupported("an empty map key"))
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FixError> {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(FixError::SerializeError("map value without key".to_string()))
+        };
+        value.serialize(FieldSerializer{fields: self, key: &key})
+    }
+
+    fn end(self) -> Result<(), FixError> {
+        Ok(())
+    }
+}
+
+//FieldSerializer writes the value of one field, the key is only resolved to a tag once the value turns out
+//not to be a component
+struct FieldSerializer<'a, 'n: 'a, 'k> {
+    fields: &'a mut Serializer<'n>,
+    key: &'k str
+}
+
+impl<'a, 'n, 'k> FieldSerializer<'a, 'n, 'k> {
+
+    fn write(self, value: &[u8]) -> Result<(), FixError> {
+        let tag = self.fields.tag(self.key)?;
+        self.fields.write(tag, value)
+    }
+}
+
+impl<'a, 'n, 'k> ser::Serializer for FieldSerializer<'a, 'n, 'k> {
+    type Ok = ();
+    type Error = FixError;
+    type SerializeSeq = GroupSerializer<'a, 'n>;
+    type SerializeTuple = ser::Impossible<(), FixError>;
+    type SerializeTupleStruct = ser::Impossible<(), FixError>;
+    type SerializeTupleVariant = ser::Impossible<(), FixError>;
+    type SerializeMap = &'a mut Serializer<'n>;
+    type SerializeStruct = &'a mut Serializer<'n>;
+    type SerializeStructVariant = ser::Impossible<(), FixError>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), FixError> {
+        self.write(if value { b"Y" } else { b"N" })
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_i16(self, value: i16) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_i32(self, value: i32) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_i64(self, value: i64) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_u8(self, value: u8) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_u16(self, value: u16) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_u32(self, value: u32) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_u64(self, value: u64) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_f32(self, value: f32) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+    fn serialize_f64(self, value: f64) -> Result<(), FixError> { self.write(value.to_string().as_bytes()) }
+
+    fn serialize_char(self, value: char) -> Result<(), FixError> {
+        self.write(value.to_string().as_bytes())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), FixError> {
+        self.write(value.as_bytes())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), FixError> {
+        self.write(value)
+    }
+
+    fn serialize_none(self) -> Result<(), FixError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), FixError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), FixError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), FixError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), FixError> {
+        self.write(variant.as_bytes())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), FixError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<(), FixError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    //a sequence is a repeating group, its length is the NumInGroup value
+    fn serialize_seq(self, len: Option<usize>) -> Result<GroupSerializer<'a, 'n>, FixError> {
+        match len {
+            Some(len) => {
+                let tag = self.fields.tag(self.key)?;
+                Ok(GroupSerializer{fields: self.fields, tag: tag, instances: Vec::with_capacity(len)})
+            },
+            None => Err(unsupported("a repeating group of unknown length"))
+        }
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, FixError> {
+        Err(unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, FixError> {
+        Err(unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, FixError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    //a nested map or struct is a component, its fields are written in place
+    fn serialize_map(self, _len: Option<usize>) -> Result<&'a mut Serializer<'n>, FixError> {
+        Ok(self.fields)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<&'a mut Serializer<'n>, FixError> {
+        Ok(self.fields)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, FixError> {
+        Err(unsupported("an enum with data"))
+    }
+}
+
+//GroupSerializer collects the instances of a repeating group, each one keeping its fields in serialization order
+pub struct GroupSerializer<'a, 'n: 'a> {
+    fields: &'a mut Serializer<'n>,
+    tag: u32,
+    instances: Vec<FieldMap>
+}
+
+impl<'a, 'n> ser::SerializeSeq for GroupSerializer<'a, 'n> {
+    type Ok = ();
+    type Error = FixError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FixError> {
+        let mut instance = FieldMap::new_with_ordering(FieldMap::received_field_order);
+        for field in to_fields(value, self.fields.names)?.into_iter() {
+            instance.add(field);
+        }
+        self.instances.push(instance);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), FixError> {
+        self.fields.fields.push(Field::from_group(self.tag, self.instances));
+        Ok(())
+    }
+}
+
+//from_message reads value back from the fields of a message, the reverse of to_message. Fields are looked up in
+//the header, the body and the trailer, those missing are None.
+pub fn from_message<T: DeserializeOwned>(msg: &Message) -> Result<T, FixError> {
+    from_message_with_names(msg, &TagNames)
+}
+
+pub fn from_message_with_names<T: DeserializeOwned>(msg: &Message, names: &dyn FieldNames) -> Result<T, FixError> {
+    T::deserialize(Deserializer{maps: vec![&msg.header, &msg.body, &msg.trailer], names: names})
+}
+
+fn invalid(tag: u32, what: &str) -> FixError {
+    FixError::SerializeError(format!("the value of {} is not {}", tag, what))
+}
+
+//Deserializer reads a struct or map of fields from the FieldMaps holding them
+#[derive(Clone)]
+pub struct Deserializer<'a> {
+    maps: Vec<&'a FieldMap>,
+    names: &'a dyn FieldNames
+}
+
+impl<'a> Deserializer<'a> {
+
+    fn lookup(&self, tag: u32) -> Option<&'a Field> {
+        self.maps.iter().filter_map(|map| map.lookup_field(tag)).next()
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = FixError;
+
+    //a map holds every field, keyed by tag
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        let fields = self.maps.iter().flat_map(|map| map.tags()).collect::<Vec<&Field>>();
+        visitor.visit_map(MapAccess{fields: fields.into_iter(), field: None, names: self.names})
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, keys: &'static [&'static str], visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_map(StructAccess{de: self, keys: keys.iter(), value: None})
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+//StructAccess gives the struct fields present, by tag number or name, and every component, whose fields are read
+//from the same FieldMaps
+struct StructAccess<'a> {
+    de: Deserializer<'a>,
+    keys: ::std::slice::Iter<'static, &'static str>,
+    value: Option<Option<&'a Field>>
+}
+
+impl<'de, 'a> de::MapAccess<'de> for StructAccess<'a> {
+    type Error = FixError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, FixError> {
+        let names = self.de.names;
+        for key in self.keys.by_ref() {
+            let field = match key.parse::<u32>().ok().or_else(|| names.field_tag(key)) {
+                Some(tag) => match self.de.lookup(tag) {
+                    Some(field) => Some(field),
+                    None => continue
+                },
+                None => None
+            };
+            self.value = Some(field);
+            return seed.deserialize(IntoDeserializer::<FixError>::into_deserializer(*key)).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, FixError> {
+        match self.value.take() {
+            Some(Some(field)) => seed.deserialize(FieldDeserializer::from_field(field, self.de.names)),
+            Some(None) => seed.deserialize(self.de.clone()),
+            None => Err(FixError::SerializeError("struct value without key".to_string()))
+        }
+    }
+}
+
+//MapAccess gives every field keyed by its tag
+struct MapAccess<'a> {
+    fields: ::std::vec::IntoIter<&'a Field>,
+    field: Option<&'a Field>,
+    names: &'a dyn FieldNames
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = FixError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, FixError> {
+        match self.fields.next() {
+            Some(field) => {
+                self.field = Some(field);
+                let key = field.field_tag().to_string();
+                seed.deserialize(FieldDeserializer{tag: 0, value: key.as_bytes(), instances: &[], names: self.names}).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, FixError> {
+        match self.field.take() {
+            Some(field) => seed.deserialize(FieldDeserializer::from_field(field, self.names)),
+            None => Err(FixError::SerializeError("map value without key".to_string()))
+        }
+    }
+}
+
+//FieldDeserializer reads the value of one field, or the instances of the repeating group it counts
+struct FieldDeserializer<'a> {
+    tag: u32,
+    value: &'a [u8],
+    instances: &'a [FieldMap],
+    names: &'a dyn FieldNames
+}
+
+impl<'a> FieldDeserializer<'a> {
+
+    fn from_field(field: &'a Field, names: &'a dyn FieldNames) -> FieldDeserializer<'a> {
+        FieldDeserializer{tag: field.field_tag(), value: field.tag_values()[0].value(), instances: field.instances(), names: names}
+    }
+
+    fn str(&self) -> Result<&'a str, FixError> {
+        ::std::str::from_utf8(self.value).map_err(|_| invalid(self.tag, "UTF-8"))
+    }
+
+    fn parse<T: ::std::str::FromStr>(&self, what: &str) -> Result<T, FixError> {
+        self.str()?.parse().map_err(|_| invalid(self.tag, what))
+    }
+}
+
+macro_rules! deserialize_numbers {
+    ($($method:ident => $visit:ident: $what:expr;)*) => {
+        $(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+            visitor.$visit(self.parse($what)?)
+        }
+        )*
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for FieldDeserializer<'a> {
+    type Error = FixError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        match ::std::str::from_utf8(self.value) {
+            Ok(value) => visitor.visit_str(value),
+            Err(_) => visitor.visit_bytes(self.value)
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        match self.value {
+            b"Y" => visitor.visit_bool(true),
+            b"N" => visitor.visit_bool(false),
+            _ => Err(invalid(self.tag, "Y or N"))
+        }
+    }
+
+    deserialize_numbers! {
+        deserialize_i8 => visit_i8: "an integer";
+        deserialize_i16 => visit_i16: "an integer";
+        deserialize_i32 => visit_i32: "an integer";
+        deserialize_i64 => visit_i64: "an integer";
+        deserialize_u8 => visit_u8: "an integer";
+        deserialize_u16 => visit_u16: "an integer";
+        deserialize_u32 => visit_u32: "an integer";
+        deserialize_u64 => visit_u64: "an integer";
+        deserialize_f32 => visit_f32: "a number";
+        deserialize_f64 => visit_f64: "a number";
+        deserialize_char => visit_char: "a char";
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_str(self.str()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_str(self.str()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_bytes(self.value)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_bytes(self.value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    //a sequence is a repeating group, a NumInGroup field of 0 holds none
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FixError> {
+        if self.instances.is_empty() && self.value != b"0" {
+            return Err(invalid(self.tag, "a repeating group"));
+        }
+        visitor.visit_seq(GroupAccess{instances: self.instances.iter(), names: self.names})
+    }
+
+    //an enum is a unit variant named by the value
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, FixError> {
+        visitor.visit_enum(IntoDeserializer::<FixError>::into_deserializer(self.str()?))
+    }
+
+    forward_to_deserialize_any! {
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+//GroupAccess reads each instance of a repeating group as a struct
+struct GroupAccess<'a> {
+    instances: ::std::slice::Iter<'a, FieldMap>,
+    names: &'a dyn FieldNames
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for GroupAccess<'a> {
+    type Error = FixError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, FixError> {
+        match self.instances.next() {
+            Some(instance) => seed.deserialize(Deserializer{maps: vec![instance], names: self.names}).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.instances.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tag::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Side {
+        #[serde(rename = "1")]
+        Buy
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Instrument {
+        #[serde(rename = "55")]
+        symbol: String
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Party {
+        #[serde(rename = "448")]
+        party_id: String,
+        #[serde(rename = "452")]
+        party_role: u32
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct NewOrderSingle {
+        #[serde(rename = "11")]
+        cl_ord_id: String,
+        instrument: Instrument,
+        #[serde(rename = "54")]
+        side: Side,
+        #[serde(rename = "38")]
+        order_qty: f64,
+        #[serde(rename = "44")]
+        price: Option<f64>,
+        #[serde(rename = "453")]
+        parties: Vec<Party>,
+        #[serde(rename = "Text")]
+        text: String,
+        #[serde(rename = "PossResend")]
+        poss_resend: bool
+    }
+
+    fn order() -> NewOrderSingle {
+        NewOrderSingle{
+            cl_ord_id: "ID1".to_string(),
+            instrument: Instrument{symbol: "MSFT".to_string()},
+            side: Side::Buy,
+            order_qty: 100.0,
+            price: None,
+            parties: vec![Party{party_id: "TW".to_string(), party_role: 1}, Party{party_id: "ISLD".to_string(), party_role: 3}],
+            text: "hello".to_string(),
+            poss_resend: false
+        }
+    }
+
+    #[test]
+    fn to_bytes_test() {
+        let expected = "11=ID1|55=MSFT|54=1|38=100|453=2|448=TW|452=1|448=ISLD|452=3|58=hello|97=N|";
+        assert_eq!(expected, String::from_utf8(to_bytes(&order()).unwrap()).unwrap().replace("\x01", "|"));
+    }
+
+    #[test]
+    fn map_test() {
+        let mut fields = BTreeMap::new();
+        fields.insert(55, "MSFT");
+        fields.insert(58, "hello");
+        assert_eq!(b"55=MSFT\x0158=hello\x01".to_vec(), to_bytes(&fields).unwrap());
+    }
+
+    #[test]
+    fn to_message_test() {
+        let msg = to_message(&order(), "FIX.4.4", "D").unwrap();
+        assert_eq!("D", msg.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("MSFT", msg.body.get_string(55).unwrap());
+        assert!(!msg.header.get_bool(Tags::PossResend.to_num()).unwrap());
+
+        //the groups read back from the built message are the serialized ones
+        let parties = msg.body.get_group(453).unwrap();
+        assert_eq!(2, parties.len());
+        let parsed = Message::parse(&msg.build()).unwrap();
+        assert_eq!(parties, parsed.body.get_group(453).unwrap());
+        assert_eq!("ISLD", parsed.body.get_group(453).unwrap()[1].get_string(448).unwrap());
+        assert_eq!(3, parsed.body.get_group(453).unwrap()[1].get_int(452).unwrap());
+    }
+
+    #[test]
+    fn single_instance_group_test() {
+        let mut order = order();
+        order.parties.truncate(1);
+        let msg = to_message(&order, "FIX.4.4", "D").unwrap();
+        let parties = msg.body.get_group(453).unwrap();
+        assert_eq!(1, parties.len());
+        assert_eq!("TW", parties[0].get_string(448).unwrap());
+        assert!(!msg.body.has(448));
+        assert!(String::from_utf8_lossy(&msg.build()).contains("453=1\x01448=TW\x01452=1\x01"));
+    }
+
+    #[test]
+    fn soh_test() {
+        let mut fields = BTreeMap::new();
+        fields.insert(58, "a\x01b");
+        match to_bytes(&fields) {
+            Err(FixError::SerializeError(_)) => {},
+            other => panic!("expected a SerializeError, got {:?}", other)
+        }
+
+        #[derive(Serialize)]
+        struct Signed<'a> {
+            #[serde(rename = "93")]
+            signature_length: usize,
+            #[serde(rename = "89", with = "serde_bytes_field")]
+            signature: &'a [u8]
+        }
+        let signed = Signed{signature_length: 3, signature: b"\x01\x02\x01"};
+        assert_eq!(b"93=3\x0189=\x01\x02\x01\x01".to_vec(), to_bytes(&signed).unwrap());
+    }
+
+    mod serde_bytes_field {
+        use serde::Serializer;
+
+        pub fn serialize<S: Serializer>(value: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(value)
+        }
+    }
+
+    #[test]
+    fn from_message_test() {
+        let msg = to_message(&order(), "FIX.4.4", "D").unwrap();
+        assert_eq!(order(), from_message::<NewOrderSingle>(&msg).unwrap());
+        assert_eq!(order(), from_message::<NewOrderSingle>(&Message::parse(&msg.build()).unwrap()).unwrap());
+
+        let mut order = order();
+        order.price = Some(52.5);
+        order.parties.truncate(1);
+        let msg = to_message(&order, "FIX.4.4", "D").unwrap();
+        assert_eq!(order, from_message::<NewOrderSingle>(&msg).unwrap());
+        //parsed without a dictionary a single instance is read as plain fields, not as a group
+        assert!(from_message::<NewOrderSingle>(&Message::parse(&msg.build()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn from_message_map_test() {
+        let msg = Message::parse(b"8=FIX.4.4\x019=12\x0135=0\x0158=hello\x0110=000\x01").unwrap();
+        let fields: BTreeMap<u32, String> = from_message(&msg).unwrap();
+        assert_eq!(Some(&"hello".to_string()), fields.get(&58));
+        assert_eq!(Some(&"0".to_string()), fields.get(&35));
+    }
+
+    #[test]
+    fn from_message_error_test() {
+        #[derive(Deserialize, Debug)]
+        struct Order {
+            #[serde(rename = "11")]
+            _cl_ord_id: String,
+            #[serde(rename = "38")]
+            _order_qty: u32,
+            #[serde(rename = "Text")]
+            _text: Option<String>
+        }
+        let parse = |fields: &str| Message::parse(format!("8=FIX.4.4\x019=0\x0135=D\x01{}10=000\x01", fields).as_bytes()).unwrap();
+        assert!(from_message::<Order>(&parse("11=ID1\x0138=100\x01")).is_ok());
+        //missing, not a number
+        assert!(from_message::<Order>(&parse("38=100\x01")).is_err());
+        assert!(from_message::<Order>(&parse("11=ID1\x0138=x\x01")).is_err());
+
+        #[derive(Deserialize, Debug)]
+        struct Flag {
+            #[serde(rename = "97")]
+            _poss_resend: bool
+        }
+        assert!(from_message::<Flag>(&parse("97=Y\x01")).is_ok());
+        assert!(from_message::<Flag>(&parse("97=1\x01")).is_err());
+    }
+
+    #[test]
+    fn unknown_field_test() {
+        #[derive(Serialize)]
+        struct Unknown {
+            symbol: String
+        }
+        assert!(to_bytes(&Unknown{symbol: "MSFT".to_string()}).is_err());
+    }
+}
@@ -0,0 +1,1511 @@
+use tag::*;
+use std::*;
+use message::*;
+use begin_string::*;
+use msg_type::*;
+use error::{BusinessRejectReason, Reject};
+use error::MessageRejectError as MessageRejectErrorTrait;
+use error::error::MessageRejectError;
+use error::FixError;
+use session_id::*;
+use settings::*;
+use store::*;
+use log::*;
+use message_router::*;
+use signature::*;
+use authentication::*;
+use throttle::*;
+use data_dictionary::*;
+use time::*;
+
+//SessionState tracks where a Session is in its logon/logout lifecycle
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SessionState {
+    Latent,
+    LogonSent,
+    InSession,
+    LogoutSent
+}
+
+//Session is the session level protocol engine of one SessionID. It is driven by the transport, which feeds it
+//the inbound messages and drains the outbound messages it queues.
+pub struct Session {
+    session_id: SessionID,
+    store: Box<dyn MessageStore + Send>,
+    log: Box<dyn Log + Send>,
+    check_latency: bool,
+    max_latency: Duration,
+    check_comp_id: bool,
+    heart_bt_int: i32,
+    reset_on_logon: bool,
+    reset_on_logout: bool,
+    reset_on_disconnect: bool,
+    state: SessionState,
+    //an in session ResetSeqNumFlag Logon was sent and its answer is pending
+    reset_pending: bool,
+    logon_enabled: bool,
+    last_received_time: Option<Tm>,
+    //FIXT.1.1 only, the ApplVerID values we announce and the counterparty announced at Logon
+    default_appl_ver_id: String,
+    target_default_appl_ver_id: String,
+    //validates inbound messages, empty when no dictionary is configured
+    data_dictionaries: DataDictionaryProvider,
+    //signs outbound messages and verifies the Signature(89) of inbound ones
+    signer: Option<Box<dyn MessageSigner + Send>>,
+    credentials: Credentials,
+    credentials_callback: Option<CredentialsCallback>,
+    authenticator: Option<sync::Arc<dyn Authenticator + Send + Sync>>,
+    logon_rate_limiter: Option<sync::Arc<LogonRateLimiter>>,
+    remote_address: Option<String>,
+    throttle: Throttle,
+    max_message_size: Option<usize>,
+    outgoing: Vec<Message>
+}
+
+type CredentialsCallback = Box<dyn FnMut(&SessionID) -> Credentials + Send>;
+
+impl Session {
+
+    pub fn new(session_id: SessionID, settings: &SessionSettings, store_factory: &dyn MessageStoreFactory) -> Result<Session, FixError> {
+        Session::new_with_log(session_id, settings, store_factory, &NullLogFactory::new())
+    }
+
+    //new_with_log creates a session whose messages and events are recorded in a log of log_factory
+    pub fn new_with_log(session_id: SessionID, settings: &SessionSettings, store_factory: &dyn MessageStoreFactory,
+                        log_factory: &dyn LogFactory) -> Result<Session, FixError> {
+        let max_latency = settings.int_setting_or(MAX_LATENCY, 120)?;
+        if max_latency <= 0 {
+            return Err(FixError::ConfigError(format!("{} must be positive", MAX_LATENCY)));
+        }
+
+        let heart_bt_int = settings.int_setting_or(HEART_BT_INT, 30)?;
+        if heart_bt_int <= 0 {
+            return Err(FixError::ConfigError(format!("{} must be positive", HEART_BT_INT)));
+        }
+
+        let mut default_appl_ver_id = String::new();
+        if session_id.begin_string == BEGIN_STRING_FIXT11 {
+            default_appl_ver_id = begin_string_appl_ver_id(settings.setting(DEFAULT_APPL_VER_ID)?).to_string();
+        }
+
+        let data_dictionaries = DataDictionaryProvider::from_settings(settings, &session_id.begin_string)?;
+
+        let mut signer: Option<Box<dyn MessageSigner + Send>> = None;
+        if settings.has(SIGNATURE_KEY) {
+            signer = Some(Box::new(HmacSha256Signer::new(settings.setting(SIGNATURE_KEY)?.as_bytes())));
+        }
+
+        let mut credentials = Credentials::default();
+        let fields = [(USERNAME, &mut credentials.username), (PASSWORD, &mut credentials.password), (NEW_PASSWORD, &mut credentials.new_password)];
+        for (setting, value) in fields {
+            if settings.has(setting) {
+                *value = settings.setting(setting)?.to_string();
+            }
+        }
+
+        let mut max_message_size = None;
+        if settings.has(MAX_MESSAGE_SIZE) {
+            let size = settings.int_setting(MAX_MESSAGE_SIZE)?;
+            if size <= 0 {
+                return Err(FixError::ConfigError(format!("{} must be positive", MAX_MESSAGE_SIZE)));
+            }
+            max_message_size = Some(size as usize);
+        }
+
+        let store = store_factory.create(&session_id)?;
+        let log = log_factory.create_session_log(&session_id)?;
+        Ok(Session{
+            session_id: session_id,
+            store: store,
+            log: log,
+            check_latency: settings.bool_setting_or(CHECK_LATENCY, true)?,
+            max_latency: Duration::seconds(max_latency as i64),
+            check_comp_id: settings.bool_setting_or(CHECK_COMP_ID, true)?,
+            heart_bt_int: heart_bt_int,
+            reset_on_logon: settings.bool_setting_or(RESET_ON_LOGON, false)?,
+            reset_on_logout: settings.bool_setting_or(RESET_ON_LOGOUT, false)?,
+            reset_on_disconnect: settings.bool_setting_or(RESET_ON_DISCONNECT, false)?,
+            state: SessionState::Latent,
+            reset_pending: false,
+            logon_enabled: true,
+            last_received_time: None,
+            default_appl_ver_id: default_appl_ver_id,
+            target_default_appl_ver_id: String::new(),
+            data_dictionaries: data_dictionaries,
+            signer: signer,
+            credentials: credentials,
+            credentials_callback: None,
+            authenticator: None,
+            logon_rate_limiter: None,
+            remote_address: None,
+            throttle: Throttle::from_settings(settings)?,
+            max_message_size: max_message_size,
+            outgoing: vec![]
+        })
+    }
+
+    pub fn session_id(&self) -> &SessionID {
+        &self.session_id
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn default_appl_ver_id(&self) -> &str {
+        &self.default_appl_ver_id
+    }
+
+    //target_default_appl_ver_id is the DefaultApplVerID(1137) the counterparty sent at Logon
+    pub fn target_default_appl_ver_id(&self) -> &str {
+        &self.target_default_appl_ver_id
+    }
+
+    //application_version picks the application version msg is validated against, on FIXT.1.1 sessions it
+    //follows ApplVerID(1128) and falls back to the DefaultApplVerID negotiated at Logon
+    pub fn application_version(&self, msg: &Message) -> String {
+        msg.application_version(&self.target_default_appl_ver_id)
+    }
+
+    //set_data_dictionary_provider replaces the dictionaries loaded from the DataDictionary settings
+    pub fn set_data_dictionary_provider(&mut self, data_dictionaries: DataDictionaryProvider) {
+        self.data_dictionaries = data_dictionaries;
+    }
+
+    pub fn data_dictionary_provider(&self) -> &DataDictionaryProvider {
+        &self.data_dictionaries
+    }
+
+    //set_signer signs and verifies the messages of the session with signer, replacing the SignatureKey one
+    pub fn set_signer(&mut self, signer: Box<dyn MessageSigner + Send>) {
+        self.signer = Some(signer);
+    }
+
+    //set_credentials_callback supplies the credentials of every Logon the initiator sends, replacing the
+    //Username, Password and NewPassword settings
+    pub fn set_credentials_callback<F>(&mut self, callback: F) where F: FnMut(&SessionID) -> Credentials + Send + 'static {
+        self.credentials_callback = Some(Box::new(callback));
+    }
+
+    //set_authenticator makes the acceptor check the credentials of the Logons it receives
+    pub fn set_authenticator(&mut self, authenticator: sync::Arc<dyn Authenticator + Send + Sync>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    //set_logon_rate_limiter counts the failed Logons of the remote address, it has no effect until the transport
+    //sets the address
+    pub fn set_logon_rate_limiter(&mut self, limiter: sync::Arc<LogonRateLimiter>) {
+        self.logon_rate_limiter = Some(limiter);
+    }
+
+    //set_remote_address is called by run_acceptor and DynamicSessions with the IP of the counterparty of each
+    //connection
+    pub fn set_remote_address(&mut self, address: Option<String>) {
+        self.remote_address = address;
+    }
+
+    //set_throttle replaces the throttle built from the Throttle settings
+    pub fn set_throttle(&mut self, throttle: Throttle) {
+        self.throttle = throttle;
+    }
+
+    pub fn throttle(&mut self) -> &mut Throttle {
+        &mut self.throttle
+    }
+
+    //max_message_size is the MaxMessageSize setting, the transports fall back to their default without it
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.max_message_size
+    }
+
+    pub fn is_logged_on(&self) -> bool {
+        self.state == SessionState::InSession
+    }
+
+    pub fn next_sender_msg_seq_num(&self) -> i32 {
+        self.store.next_sender_msg_seq_num()
+    }
+
+    pub fn next_target_msg_seq_num(&self) -> i32 {
+        self.store.next_target_msg_seq_num()
+    }
+
+    pub fn set_next_sender_msg_seq_num(&mut self, next: i32) -> Result<(), FixError> {
+        if next <= 0 {
+            return Err(FixError::SessionError(format!("Invalid sequence number {}", next)));
+        }
+        self.store.set_next_sender_msg_seq_num(next)
+    }
+
+    pub fn set_next_target_msg_seq_num(&mut self, next: i32) -> Result<(), FixError> {
+        if next <= 0 {
+            return Err(FixError::SessionError(format!("Invalid sequence number {}", next)));
+        }
+        self.store.set_next_target_msg_seq_num(next)
+    }
+
+    //reset_store sets both sequence numbers back to 1 without telling the counterparty
+    pub fn reset_store(&mut self) -> Result<(), FixError> {
+        self.store.reset()
+    }
+
+    //last_received_time is the time the last inbound message was processed
+    pub fn last_received_time(&self) -> Option<Tm> {
+        self.last_received_time
+    }
+
+    //logon_enabled tells the transport whether it may (re)connect and log on
+    pub fn is_logon_enabled(&self) -> bool {
+        self.logon_enabled
+    }
+
+    pub fn enable_logon(&mut self) {
+        self.logon_enabled = true;
+    }
+
+    pub fn disable_logon(&mut self) {
+        self.logon_enabled = false;
+    }
+
+    //check_sending_time rejects msg when its SendingTime is more than MaxLatency away from now
+    pub fn check_sending_time(&self, msg: &Message, now: Tm) -> Result<(), MessageRejectError> {
+        if !self.check_latency {
+            return Ok(());
+        }
+
+        if !msg.header.has(Tags::SendingTime.to_num()) {
+            return Err(MessageRejectError::required_tag_missing(Tags::SendingTime.to_num()));
+        }
+
+        let sending_time = msg.header.get_time(Tags::SendingTime.to_num())?;
+        let delta = now - sending_time;
+        if delta <= -self.max_latency || delta >= self.max_latency {
+            return Err(MessageRejectError::sending_time_accuracy_problem());
+        }
+        Ok(())
+    }
+
+    //check_comp_id rejects msg when its SenderCompID/TargetCompID do not mirror the SessionID
+    pub fn check_comp_id(&self, msg: &Message) -> Result<(), MessageRejectError> {
+        if !self.check_comp_id {
+            return Ok(());
+        }
+
+        let sender_comp_id = msg.header.get_string(Tags::SenderCompID.to_num()).unwrap_or_default();
+        let target_comp_id = msg.header.get_string(Tags::TargetCompID.to_num()).unwrap_or_default();
+
+        if sender_comp_id.is_empty() {
+            return Err(MessageRejectError::required_tag_missing(Tags::SenderCompID.to_num()));
+        }
+        if target_comp_id.is_empty() {
+            return Err(MessageRejectError::required_tag_missing(Tags::TargetCompID.to_num()));
+        }
+        if sender_comp_id != self.session_id.target_comp_id || target_comp_id != self.session_id.sender_comp_id {
+            return Err(MessageRejectError::comp_id_problem());
+        }
+        Ok(())
+    }
+
+    //check_signature rejects msg when a signer is set and the Signature of msg is missing or wrong
+    pub fn check_signature(&self, msg: &Message) -> Result<(), MessageRejectError> {
+        match self.signer {
+            Some(ref signer) => verify_message(msg, signer.as_ref()),
+            None => Ok(())
+        }
+    }
+
+    //check_data_dictionary validates msg against the dictionaries its BeginString, application version and
+    //CstmApplVerID select, it passes when the session has no dictionary for its BeginString
+    pub fn check_data_dictionary(&self, msg: &Message) -> Result<(), MessageRejectError> {
+        self.data_dictionaries.validate(msg, &self.application_version(msg))
+    }
+
+    //verify runs the session level checks on an inbound message. A failure queues the Reject, a CompID problem
+    //also queues a Logout since the counterparty cannot be trusted any more.
+    pub fn verify(&mut self, msg: &Message, now: Tm) -> Result<(), FixError> {
+        if let Err(err) = self.check_signature(msg) {
+            self.log.on_event(&format!("Message Rejected: {}", err.text()));
+            self.send(reject_reply(msg, &err), now)?;
+            return Err(FixError::RejectError(err));
+        }
+
+        if let Err(err) = self.check_comp_id(msg) {
+            self.log.on_event(&format!("Message Rejected: {}", err.text()));
+            self.send(reject_reply(msg, &err), now)?;
+            if err.reject_reason() == Reject::CompIDProblem {
+                self.initiate_logout("", now)?;
+            }
+            return Err(FixError::RejectError(err));
+        }
+
+        if let Err(err) = self.check_sending_time(msg, now) {
+            self.log.on_event(&format!("Message Rejected: {}", err.text()));
+            self.send(reject_reply(msg, &err), now)?;
+            return Err(FixError::RejectError(err));
+        }
+
+        if let Err(err) = self.check_data_dictionary(msg) {
+            self.log.on_event(&format!("Message Rejected: {}", err.text()));
+            self.send(reject_reply(msg, &err), now)?;
+            return Err(FixError::RejectError(err));
+        }
+        Ok(())
+    }
+
+    //incoming processes an inbound message, the transport drains the resulting replies with take_outgoing
+    pub fn incoming(&mut self, msg: &Message, now: Tm) -> Result<(), FixError> {
+        self.last_received_time = Some(now);
+        if msg.raw_message().is_empty() {
+            self.log.on_incoming(&mask_sensitive(&msg.build()));
+        } else {
+            self.log.on_incoming(&mask_sensitive(msg.raw_message()));
+        }
+        self.check_log()?;
+        let msg_type = msg.header.get_string(Tags::MsgType.to_num()).unwrap_or_default();
+
+        if msg_type == MSG_TYPE_LOGON {
+            return self.handle_logon(msg, now);
+        }
+
+        match self.state {
+            SessionState::Latent | SessionState::LogonSent => {
+                return Err(FixError::SessionError(format!("Logon expected, received MsgType {}", msg_type)));
+            },
+            _ => ()
+        }
+
+        self.verify(msg, now)?;
+        self.accept_seq_num(msg)?;
+
+        if msg_type == MSG_TYPE_LOGOUT {
+            return self.handle_logout(now);
+        }
+        Ok(())
+    }
+
+    //from_app hands an application message accepted by incoming to router, when the message is rejected the
+    //Reject or BusinessMessageReject reply is queued before the error is returned
+    pub fn from_app(&mut self, router: &mut MessageRouter, msg: &Message, now: Tm) -> Result<(), FixError> {
+        let msg_type = msg.header.get_string(Tags::MsgType.to_num()).unwrap_or_default();
+        if is_admin_message_type(&msg_type) {
+            return Ok(());
+        }
+
+        if let Err(err) = router.route_with_default_appl_ver_id(msg, &self.session_id, &self.target_default_appl_ver_id) {
+            self.log.on_event(&format!("Message Rejected: {}", err.text()));
+            self.send(reject_reply(msg, &err), now)?;
+            return Err(FixError::RejectError(err));
+        }
+        Ok(())
+    }
+
+    //accept_seq_num moves the expected target sequence number past msg, gap detection is not handled here yet
+    fn accept_seq_num(&mut self, msg: &Message) -> Result<(), FixError> {
+        if let Ok(seq_num) = msg.header.get_int(Tags::MsgSeqNum.to_num()) {
+            if seq_num >= self.store.next_target_msg_seq_num() {
+                self.store.set_next_target_msg_seq_num(seq_num + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_logon(&mut self, msg: &Message, now: Tm) -> Result<(), FixError> {
+        self.verify(msg, now)?;
+
+        if self.session_id.begin_string == BEGIN_STRING_FIXT11 {
+            match msg.body.get_string(Tags::DefaultApplVerID.to_num()) {
+                Ok(appl_ver_id) => self.target_default_appl_ver_id = appl_ver_id,
+                Err(_) => {
+                    let err = MessageRejectError::required_tag_missing(Tags::DefaultApplVerID.to_num());
+                    self.log.on_event(&format!("Message Rejected: {}", err.text()));
+                    self.send(reject_reply(msg, &err), now)?;
+                    self.initiate_logout("", now)?;
+                    return Err(FixError::RejectError(err));
+                }
+            }
+        }
+
+        let reset_seq_num_flag = msg.body.get_bool(Tags::ResetSeqNumFlag.to_num()).unwrap_or(false);
+        match self.state {
+            SessionState::InSession | SessionState::LogoutSent => {
+                //in session sequence reset, used by venues that keep the session up for days
+                if !reset_seq_num_flag {
+                    return Err(FixError::SessionError("Logon received while in session without ResetSeqNumFlag".to_string()));
+                }
+                self.log.on_event("Received logon with ResetSeqNumFlag");
+                if self.reset_pending {
+                    self.reset_pending = false;
+                } else {
+                    self.log.on_event("Sequence numbers reset");
+                    self.store.reset()?;
+                    self.send_logon(true, self.heart_bt_int, now)?;
+                }
+            },
+            SessionState::LogonSent => {
+                //answer to our Logon, a reset we did not ask for still restarts the inbound sequence
+                self.log.on_event("Received logon response");
+                if reset_seq_num_flag && !self.reset_pending {
+                    self.store.set_next_target_msg_seq_num(1)?;
+                }
+                self.reset_pending = false;
+                self.state = SessionState::InSession;
+            },
+            SessionState::Latent => {
+                //the counterparty initiates, answer with our Logon
+                self.log.on_event("Received logon request");
+                self.authenticate(msg, now)?;
+                if reset_seq_num_flag || self.reset_on_logon {
+                    self.store.reset()?;
+                }
+                let heart_bt_int = msg.body.get_int(Tags::HeartBtInt.to_num()).unwrap_or(self.heart_bt_int);
+                self.accept_seq_num(msg)?;
+                self.send_logon(reset_seq_num_flag || self.reset_on_logon, heart_bt_int, now)?;
+                self.state = SessionState::InSession;
+                return Ok(());
+            }
+        }
+
+        self.accept_seq_num(msg)
+    }
+
+    //authenticate checks the credentials of a Logon request, a refused Logon is answered with a Logout giving the
+    //reason in Text and the session stays latent
+    fn authenticate(&mut self, msg: &Message, now: Tm) -> Result<(), FixError> {
+        let address = self.remote_address.clone();
+        if let (Some(limiter), Some(address)) = (self.logon_rate_limiter.clone(), address.as_ref()) {
+            if limiter.is_blocked(address, now) {
+                return self.reject_logon("Too many failed logon attempts", now);
+            }
+        }
+
+        let authenticator = match self.authenticator {
+            Some(ref authenticator) => authenticator.clone(),
+            None => return Ok(())
+        };
+        let result = authenticator.authenticate(&self.session_id, &Credentials::from_logon(msg), address.as_deref());
+        if let (Some(limiter), Some(address)) = (self.logon_rate_limiter.as_ref(), address.as_ref()) {
+            match result {
+                Ok(_) => limiter.succeeded(address),
+                Err(_) => limiter.failed(address, now)
+            }
+        }
+        match result {
+            Ok(_) => Ok(()),
+            Err(reason) => self.reject_logon(&reason, now)
+        }
+    }
+
+    fn reject_logon(&mut self, reason: &str, now: Tm) -> Result<(), FixError> {
+        self.log.on_event(&format!("Logon rejected: {}", reason));
+        let mut logout = Message::new();
+        logout.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGOUT);
+        if !reason.is_empty() {
+            logout.body.set_string(Tags::Text.to_num(), reason);
+        }
+        self.send(logout, now)?;
+        Err(FixError::SessionError(format!("Logon rejected: {}", reason)))
+    }
+
+    fn handle_logout(&mut self, now: Tm) -> Result<(), FixError> {
+        if self.state == SessionState::LogoutSent {
+            self.log.on_event("Received logout response");
+        } else {
+            self.log.on_event("Received logout request");
+            let mut logout = Message::new();
+            logout.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGOUT);
+            self.send(logout, now)?;
+        }
+
+        self.state = SessionState::Latent;
+        if self.reset_on_logout {
+            self.store.reset()?;
+        }
+        Ok(())
+    }
+
+    //logon starts the initiator side of the session, with ResetOnLogon the store is reset and ResetSeqNumFlag=Y is sent
+    pub fn logon(&mut self, now: Tm) -> Result<(), FixError> {
+        if !self.logon_enabled {
+            return Err(FixError::SessionError("Logon is disabled".to_string()));
+        }
+        if self.state != SessionState::Latent {
+            return Err(FixError::SessionError("Logon already initiated".to_string()));
+        }
+
+        if self.reset_on_logon {
+            self.store.reset()?;
+            self.reset_pending = true;
+        }
+        let reset = self.reset_on_logon;
+        let heart_bt_int = self.heart_bt_int;
+        self.log.on_event("Sending logon request");
+        let mut logon = self.logon_message(reset, heart_bt_int);
+        let credentials = match self.credentials_callback {
+            Some(ref mut callback) => callback(&self.session_id),
+            None => self.credentials.clone()
+        };
+        credentials.set_on(&mut logon);
+        self.send(logon, now)?;
+        self.state = SessionState::LogonSent;
+        Ok(())
+    }
+
+    //reset_seq_num resets both sequence numbers of a live session by sending a Logon with ResetSeqNumFlag=Y
+    pub fn reset_seq_num(&mut self, now: Tm) -> Result<(), FixError> {
+        if self.state != SessionState::InSession {
+            return Err(FixError::SessionError("Sequence reset requires a logged on session".to_string()));
+        }
+
+        self.log.on_event("Sending logon with ResetSeqNumFlag");
+        self.store.reset()?;
+        self.reset_pending = true;
+        let heart_bt_int = self.heart_bt_int;
+        self.send_logon(true, heart_bt_int, now)
+    }
+
+    //disconnected is called by the transport once the connection is gone
+    pub fn disconnected(&mut self) -> Result<(), FixError> {
+        self.log.on_event("Disconnected");
+        self.state = SessionState::Latent;
+        self.throttle.clear_admin();
+        self.reset_pending = false;
+        if self.reset_on_disconnect {
+            self.store.reset()?;
+        }
+        Ok(())
+    }
+
+    fn send_logon(&mut self, reset_seq_num_flag: bool, heart_bt_int: i32, now: Tm) -> Result<(), FixError> {
+        let logon = self.logon_message(reset_seq_num_flag, heart_bt_int);
+        self.send(logon, now)
+    }
+
+    fn logon_message(&self, reset_seq_num_flag: bool, heart_bt_int: i32) -> Message {
+        let mut logon = Message::new();
+        logon.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGON);
+        logon.body.set_int(Tags::EncryptMethod.to_num(), 0);
+        logon.body.set_int(Tags::HeartBtInt.to_num(), heart_bt_int);
+        if !self.default_appl_ver_id.is_empty() {
+            logon.body.set_string(Tags::DefaultApplVerID.to_num(), &self.default_appl_ver_id);
+        }
+        if reset_seq_num_flag {
+            logon.body.set_bool(Tags::ResetSeqNumFlag.to_num(), true);
+        }
+        logon
+    }
+
+    pub fn initiate_logout(&mut self, text: &str, now: Tm) -> Result<(), FixError> {
+        let mut logout = Message::new();
+        logout.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGOUT);
+        if !text.is_empty() {
+            logout.body.set_string(Tags::Text.to_num(), text);
+        }
+        self.log.on_event("Sending logout request");
+        self.send(logout, now)?;
+        self.state = SessionState::LogoutSent;
+        Ok(())
+    }
+
+    fn fill_default_header(&self, msg: &mut Message, now: Tm) {
+        let session_id = &self.session_id;
+        msg.header.set_string(Tags::BeginString.to_num(), &session_id.begin_string);
+        msg.header.set_string(Tags::SenderCompID.to_num(), &session_id.sender_comp_id);
+        msg.header.set_string(Tags::TargetCompID.to_num(), &session_id.target_comp_id);
+
+        let optional = [
+            (Tags::SenderSubID, &session_id.sender_sub_id),
+            (Tags::SenderLocationID, &session_id.sender_location_id),
+            (Tags::TargetSubID, &session_id.target_sub_id),
+            (Tags::TargetLocationID, &session_id.target_location_id),
+        ];
+        for &(tag, value) in optional.iter() {
+            if !value.is_empty() {
+                msg.header.set_string(tag.to_num(), value);
+            }
+        }
+
+        msg.header.set_int(Tags::MsgSeqNum.to_num(), self.store.next_sender_msg_seq_num());
+        msg.header.set_time(Tags::SendingTime.to_num(), now);
+    }
+
+    //send passes msg through the throttle, a message held back gets its header and sequence number when
+    //release_throttled lets it through. Application messages are held until the session is logged on.
+    pub fn send(&mut self, msg: Message, now: Tm) -> Result<(), FixError> {
+        let admin = is_admin_message_type(&msg.header.get_string(Tags::MsgType.to_num()).unwrap_or_default());
+        if !admin && self.state != SessionState::InSession {
+            self.throttle.hold(msg);
+            return Ok(());
+        }
+        match self.throttle.admit(msg, admin, now)? {
+            Some(msg) => self.send_now(msg, now),
+            None => Ok(())
+        }
+    }
+
+    //release_throttled sends the messages the throttle holds back and now lets through, the transport calls it
+    //once next_release has passed. Application messages are held until the session is logged on again, they
+    //would otherwise be sent before the Logon of the next connection.
+    pub fn release_throttled(&mut self, now: Tm) -> Result<(), FixError> {
+        let app = self.state == SessionState::InSession;
+        for msg in self.throttle.release(now, app) {
+            self.send_now(msg, now)?;
+        }
+        Ok(())
+    }
+
+    //next_release is how long until release_throttled has something to send, None when nothing can be sent
+    pub fn next_release(&mut self, now: Tm) -> Option<Duration> {
+        let app = self.state == SessionState::InSession;
+        self.throttle.next_release(now, app)
+    }
+
+    //send_now stamps the session header on msg, signs it when a signer is set, saves it for resends and queues it for the transport
+    fn send_now(&mut self, mut msg: Message, now: Tm) -> Result<(), FixError> {
+        self.fill_default_header(&mut msg, now);
+        if let Some(ref signer) = self.signer {
+            sign_message(&mut msg, signer.as_ref())?;
+        }
+        let seq_num = self.store.next_sender_msg_seq_num();
+        let bytes = msg.build();
+        self.store.save_message(seq_num, &bytes)?;
+        self.log.on_outgoing(&mask_sensitive(&bytes));
+        self.check_log()?;
+        self.store.incr_next_sender_msg_seq_num()?;
+        self.outgoing.push(msg);
+        Ok(())
+    }
+
+    //check_log returns the log's latest failure, messages are neither processed nor sent once the log fails so
+    //nothing goes unrecorded
+    fn check_log(&mut self) -> Result<(), FixError> {
+        match self.log.take_error() {
+            Some(err) => Err(err),
+            None => Ok(())
+        }
+    }
+
+    //take_outgoing hands the queued messages over to the transport
+    pub fn take_outgoing(&mut self) -> Vec<Message> {
+        mem::take(&mut self.outgoing)
+    }
+}
+
+//reject_reply builds the Reject(3) or BusinessMessageReject(j) answering msg. SessionRejectReason,
+//RefTagID, RefMsgType and BusinessMessageReject only exist since FIX.4.2, older versions get a
+//plain Reject with the reason and the offending tag in Text.
+pub fn reject_reply(msg: &Message, err: &MessageRejectError) -> Message {
+    let mut reply = msg.reverse_route();
+
+    let begin_string = msg.header.get_string(Tags::BeginString.to_num()).unwrap_or_default();
+    let ref_msg_type = msg.header.get_string(Tags::MsgType.to_num());
+    let ref_seq_num = msg.header.get_int(Tags::MsgSeqNum.to_num());
+
+    if let Ok(seq_num) = ref_seq_num {
+        reply.body.set_int(Tags::RefSeqNum.to_num(), seq_num);
+    }
+
+    if begin_string.as_str() < BEGIN_STRING_FIX42 {
+        reply.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_REJECT);
+        if err.ref_tag_id() != 0 {
+            reply.body.set_string(Tags::Text.to_num(), &format!("{} ({})", err.text(), err.ref_tag_id()));
+        } else {
+            reply.body.set_string(Tags::Text.to_num(), err.text());
+        }
+        return reply;
+    }
+
+    if let Ok(msg_type) = ref_msg_type {
+        reply.body.set_string(Tags::RefMsgType.to_num(), &msg_type);
+    }
+
+    if err.is_business_reject() {
+        reply.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_BUSINESS_MESSAGE_REJECT);
+        let reason = err.business_reject_reason().unwrap_or(BusinessRejectReason::Other);
+        reply.body.set_int(Tags::BusinessRejectReason.to_num(), reason.code());
+    } else {
+        reply.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_REJECT);
+        reply.body.set_int(Tags::SessionRejectReason.to_num(), err.reject_reason() as i32);
+        if err.ref_tag_id() != 0 {
+            reply.body.set_int(Tags::RefTagID.to_num(), err.ref_tag_id() as i32);
+        }
+    }
+    reply.body.set_string(Tags::Text.to_num(), err.text());
+
+    reply
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tag::*;
+    use std::io;
+
+    fn new_order(begin_string: &str) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), begin_string);
+        msg.header.set_string(Tags::MsgType.to_num(), "D");
+        msg.header.set_string(Tags::SenderCompID.to_num(), "TW");
+        msg.header.set_string(Tags::TargetCompID.to_num(), "ISLD");
+        msg.header.set_int(Tags::MsgSeqNum.to_num(), 7);
+        msg
+    }
+
+    #[test]
+    fn session_reject_test() {
+        let err = MessageRejectError::incorrect_data_format_for_value(44);
+        let reply = reject_reply(&new_order(BEGIN_STRING_FIX44), &err);
+
+        assert_eq!("3", reply.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("ISLD", reply.header.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!("TW", reply.header.get_string(Tags::TargetCompID.to_num()).unwrap());
+        assert_eq!(7, reply.body.get_int(Tags::RefSeqNum.to_num()).unwrap());
+        assert_eq!(44, reply.body.get_int(Tags::RefTagID.to_num()).unwrap());
+        assert_eq!("D", reply.body.get_string(Tags::RefMsgType.to_num()).unwrap());
+        assert_eq!(6, reply.body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!("Incorrect data format for value", reply.body.get_string(Tags::Text.to_num()).unwrap());
+    }
+
+    #[test]
+    fn business_reject_test() {
+        let err = MessageRejectError::unsupported_message_type();
+        let reply = reject_reply(&new_order(BEGIN_STRING_FIX42), &err);
+
+        assert_eq!("j", reply.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(7, reply.body.get_int(Tags::RefSeqNum.to_num()).unwrap());
+        assert_eq!("D", reply.body.get_string(Tags::RefMsgType.to_num()).unwrap());
+        assert_eq!(3, reply.body.get_int(Tags::BusinessRejectReason.to_num()).unwrap());
+        assert!(!reply.body.has(Tags::SessionRejectReason.to_num()));
+        assert!(!reply.body.has(Tags::RefTagID.to_num()));
+    }
+
+    #[test]
+    fn fix41_reject_test() {
+        let err = MessageRejectError::conditionally_required_field_missing(55);
+        let reply = reject_reply(&new_order(BEGIN_STRING_FIX41), &err);
+
+        assert_eq!("3", reply.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(7, reply.body.get_int(Tags::RefSeqNum.to_num()).unwrap());
+        assert_eq!("Conditionally required field missing (55)", reply.body.get_string(Tags::Text.to_num()).unwrap());
+        assert!(!reply.body.has(Tags::SessionRejectReason.to_num()));
+        assert!(!reply.body.has(Tags::RefMsgType.to_num()));
+    }
+
+    #[test]
+    fn fix40_business_reject_falls_back_to_reject_test() {
+        let err = MessageRejectError::unknown_id(0);
+        let reply = reject_reply(&new_order(BEGIN_STRING_FIX40), &err);
+
+        assert_eq!("3", reply.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("Unknown ID", reply.body.get_string(Tags::Text.to_num()).unwrap());
+    }
+
+    fn new_session(settings: &SessionSettings) -> Session {
+        Session::new(SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW"), settings, &MemoryStoreFactory::new()).unwrap()
+    }
+
+    fn sending_time(msg: &mut Message, time: Tm) {
+        msg.header.set_time(Tags::SendingTime.to_num(), time);
+    }
+
+    #[test]
+    fn check_sending_time_test() {
+        let session = new_session(&SessionSettings::new());
+        let now = now_utc();
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+
+        assert_eq!(Reject::RequiredTagMissing, session.check_sending_time(&msg, now).unwrap_err().reject_reason());
+
+        sending_time(&mut msg, now - Duration::seconds(119));
+        assert!(session.check_sending_time(&msg, now).is_ok());
+
+        sending_time(&mut msg, now - Duration::seconds(121));
+        assert_eq!(Reject::SendingTimeAccuracyProblem, session.check_sending_time(&msg, now).unwrap_err().reject_reason());
+
+        sending_time(&mut msg, now + Duration::seconds(121));
+        assert_eq!(Reject::SendingTimeAccuracyProblem, session.check_sending_time(&msg, now).unwrap_err().reject_reason());
+    }
+
+    #[test]
+    fn check_sending_time_settings_test() {
+        let now = now_utc();
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+        sending_time(&mut msg, now - Duration::seconds(10));
+
+        let mut settings = SessionSettings::new();
+        settings.set(MAX_LATENCY, "5");
+        assert!(new_session(&settings).check_sending_time(&msg, now).is_err());
+
+        settings.set(CHECK_LATENCY, "N");
+        assert!(new_session(&settings).check_sending_time(&msg, now).is_ok());
+    }
+
+    #[test]
+    fn max_message_size_settings_test() {
+        assert_eq!(None, new_session(&SessionSettings::new()).max_message_size());
+
+        let mut settings = SessionSettings::new();
+        settings.set(MAX_MESSAGE_SIZE, "4096");
+        assert_eq!(Some(4096), new_session(&settings).max_message_size());
+
+        settings.set(MAX_MESSAGE_SIZE, "0");
+        assert!(Session::new(SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW"), &settings, &MemoryStoreFactory::new()).is_err());
+    }
+
+    #[test]
+    fn check_comp_id_test() {
+        let session = new_session(&SessionSettings::new());
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+        assert!(session.check_comp_id(&msg).is_ok());
+
+        msg.header.set_string(Tags::SenderCompID.to_num(), "XX");
+        assert_eq!(Reject::CompIDProblem, session.check_comp_id(&msg).unwrap_err().reject_reason());
+
+        msg.header.remove(Tags::SenderCompID.to_num());
+        assert_eq!(Reject::RequiredTagMissing, session.check_comp_id(&msg).unwrap_err().reject_reason());
+
+        let mut settings = SessionSettings::new();
+        settings.set(CHECK_COMP_ID, "N");
+        assert!(new_session(&settings).check_comp_id(&msg).is_ok());
+    }
+
+    #[test]
+    fn verify_comp_id_problem_logs_out_test() {
+        let mut session = new_session(&SessionSettings::new());
+        let now = now_utc();
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+        sending_time(&mut msg, now);
+        msg.header.set_string(Tags::TargetCompID.to_num(), "XX");
+
+        assert!(session.verify(&msg, now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!(2, outgoing.len());
+        assert_eq!("3", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(9, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert_eq!("5", outgoing[1].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(2, outgoing[1].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert_eq!(SessionState::LogoutSent, session.state());
+    }
+
+    #[test]
+    fn verify_sending_time_problem_rejects_test() {
+        let mut session = new_session(&SessionSettings::new());
+        let now = now_utc();
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+        sending_time(&mut msg, now - Duration::seconds(600));
+
+        assert!(session.verify(&msg, now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!(1, outgoing.len());
+        assert_eq!(10, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!("ISLD", outgoing[0].header.get_string(Tags::SenderCompID.to_num()).unwrap());
+        assert_eq!(SessionState::Latent, session.state());
+    }
+
+    fn inbound(msg_type: &str, seq_num: i32, now: Tm) -> Message {
+        let mut msg = new_order(BEGIN_STRING_FIX42);
+        msg.header.set_string(Tags::MsgType.to_num(), msg_type);
+        msg.header.set_int(Tags::MsgSeqNum.to_num(), seq_num);
+        sending_time(&mut msg, now);
+        msg
+    }
+
+    fn reset_logon(seq_num: i32, now: Tm) -> Message {
+        let mut logon = inbound(MSG_TYPE_LOGON, seq_num, now);
+        logon.body.set_int(Tags::HeartBtInt.to_num(), 30);
+        logon.body.set_bool(Tags::ResetSeqNumFlag.to_num(), true);
+        logon
+    }
+
+    fn logged_on_session(settings: &SessionSettings, now: Tm) -> Session {
+        let mut session = new_session(settings);
+        session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now).unwrap();
+        session.take_outgoing();
+        session
+    }
+
+    fn reset_flag(msg: &Message) -> bool {
+        msg.body.get_bool(Tags::ResetSeqNumFlag.to_num()).unwrap_or(false)
+    }
+
+    #[test]
+    fn logon_with_reset_on_logon_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(RESET_ON_LOGON, "Y");
+        let mut session = new_session(&settings);
+        session.store.set_next_sender_msg_seq_num(10).unwrap();
+
+        session.logon(now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!("A", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert!(reset_flag(&outgoing[0]));
+        assert_eq!(SessionState::LogonSent, session.state());
+
+        session.incoming(&reset_logon(1, now), now).unwrap();
+        assert_eq!(SessionState::InSession, session.state());
+        assert_eq!(2, session.next_target_msg_seq_num());
+        assert!(session.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn accept_logon_with_reset_on_logon_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(RESET_ON_LOGON, "Y");
+        let mut session = new_session(&settings);
+        session.store.set_next_sender_msg_seq_num(10).unwrap();
+
+        session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!("A", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert!(reset_flag(&outgoing[0]));
+        assert_eq!(SessionState::InSession, session.state());
+    }
+
+    #[test]
+    fn logon_without_reset_test() {
+        let now = now_utc();
+        let mut session = new_session(&SessionSettings::new());
+        session.store.set_next_sender_msg_seq_num(10).unwrap();
+
+        session.logon(now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!(10, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert!(!reset_flag(&outgoing[0]));
+    }
+
+    #[test]
+    fn accept_counterparty_reset_logon_test() {
+        let now = now_utc();
+        let mut session = new_session(&SessionSettings::new());
+        session.store.set_next_sender_msg_seq_num(20).unwrap();
+        session.store.set_next_target_msg_seq_num(30).unwrap();
+
+        session.incoming(&reset_logon(1, now), now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!(1, outgoing.len());
+        assert_eq!("A", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert!(reset_flag(&outgoing[0]));
+        assert_eq!(2, session.next_sender_msg_seq_num());
+        assert_eq!(2, session.next_target_msg_seq_num());
+        assert_eq!(SessionState::InSession, session.state());
+    }
+
+    #[test]
+    fn in_session_reset_from_counterparty_test() {
+        let now = now_utc();
+        let mut session = logged_on_session(&SessionSettings::new(), now);
+        session.incoming(&inbound("D", 2, now), now).unwrap();
+        assert_eq!(3, session.next_target_msg_seq_num());
+
+        session.incoming(&reset_logon(1, now), now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!(1, outgoing.len());
+        assert!(reset_flag(&outgoing[0]));
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+        assert_eq!(2, session.next_target_msg_seq_num());
+        assert_eq!(SessionState::InSession, session.state());
+    }
+
+    #[test]
+    fn in_session_reset_initiated_test() {
+        let now = now_utc();
+        let mut session = logged_on_session(&SessionSettings::new(), now);
+
+        session.reset_seq_num(now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert!(reset_flag(&outgoing[0]));
+        assert_eq!(1, outgoing[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+
+        session.incoming(&reset_logon(1, now), now).unwrap();
+        assert!(session.take_outgoing().is_empty());
+        assert_eq!(2, session.next_target_msg_seq_num());
+    }
+
+    #[test]
+    fn reset_on_logout_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(RESET_ON_LOGOUT, "Y");
+        let mut session = logged_on_session(&settings, now);
+
+        session.incoming(&inbound(MSG_TYPE_LOGOUT, 2, now), now).unwrap();
+        let outgoing = session.take_outgoing();
+        assert_eq!("5", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(SessionState::Latent, session.state());
+        assert_eq!(1, session.next_sender_msg_seq_num());
+        assert_eq!(1, session.next_target_msg_seq_num());
+    }
+
+    #[test]
+    fn logout_keeps_seq_nums_test() {
+        let now = now_utc();
+        let mut session = logged_on_session(&SessionSettings::new(), now);
+        session.incoming(&inbound(MSG_TYPE_LOGOUT, 2, now), now).unwrap();
+        assert_eq!(3, session.next_sender_msg_seq_num());
+        assert_eq!(3, session.next_target_msg_seq_num());
+    }
+
+    #[test]
+    fn reset_on_disconnect_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(RESET_ON_DISCONNECT, "Y");
+        let mut session = logged_on_session(&settings, now);
+
+        session.disconnected().unwrap();
+        assert_eq!(SessionState::Latent, session.state());
+        assert_eq!(1, session.next_sender_msg_seq_num());
+        assert_eq!(1, session.next_target_msg_seq_num());
+
+        let mut session = logged_on_session(&SessionSettings::new(), now);
+        session.disconnected().unwrap();
+        assert_eq!(2, session.next_sender_msg_seq_num());
+    }
+
+    #[test]
+    fn from_app_unsupported_message_type_test() {
+        let now = now_utc();
+        let mut session = logged_on_session(&SessionSettings::new(), now);
+        let mut router = MessageRouter::new();
+        router.add_route(BEGIN_STRING_FIX42, "D", |_, _| Ok(()));
+
+        let order = inbound("D", 2, now);
+        session.incoming(&order, now).unwrap();
+        session.from_app(&mut router, &order, now).unwrap();
+        assert!(session.take_outgoing().is_empty());
+
+        let cancel = inbound("F", 3, now);
+        session.incoming(&cancel, now).unwrap();
+        assert!(session.from_app(&mut router, &cancel, now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!("j", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(3, outgoing[0].body.get_int(Tags::BusinessRejectReason.to_num()).unwrap());
+        assert_eq!("F", outgoing[0].body.get_string(Tags::RefMsgType.to_num()).unwrap());
+    }
+
+    fn fixt_session() -> Session {
+        let mut settings = SessionSettings::new();
+        settings.set(DEFAULT_APPL_VER_ID, BEGIN_STRING_FIX50SP2);
+        Session::new(SessionID::new(BEGIN_STRING_FIXT11, "ISLD", "TW"), &settings, &MemoryStoreFactory::new()).unwrap()
+    }
+
+    fn fixt_inbound(msg_type: &str, seq_num: i32, now: Tm) -> Message {
+        let mut msg = inbound(msg_type, seq_num, now);
+        msg.header.set_string(Tags::BeginString.to_num(), BEGIN_STRING_FIXT11);
+        msg
+    }
+
+    #[test]
+    fn fixt_requires_default_appl_ver_id_test() {
+        let session_id = SessionID::new(BEGIN_STRING_FIXT11, "ISLD", "TW");
+        assert!(Session::new(session_id, &SessionSettings::new(), &MemoryStoreFactory::new()).is_err());
+        assert_eq!("9", fixt_session().default_appl_ver_id());
+    }
+
+    #[test]
+    fn fixt_logon_negotiates_default_appl_ver_id_test() {
+        let now = now_utc();
+        let mut session = fixt_session();
+        let mut logon = fixt_inbound(MSG_TYPE_LOGON, 1, now);
+        logon.body.set_string(Tags::DefaultApplVerID.to_num(), "8");
+        session.incoming(&logon, now).unwrap();
+
+        assert_eq!("8", session.target_default_appl_ver_id());
+        let outgoing = session.take_outgoing();
+        assert_eq!("FIXT.1.1", outgoing[0].header.get_string(Tags::BeginString.to_num()).unwrap());
+        assert_eq!("9", outgoing[0].body.get_string(Tags::DefaultApplVerID.to_num()).unwrap());
+
+        let mut order = fixt_inbound("D", 2, now);
+        assert_eq!(BEGIN_STRING_FIX50SP1, session.application_version(&order));
+        order.header.set_string(Tags::ApplVerID.to_num(), "9");
+        assert_eq!(BEGIN_STRING_FIX50SP2, session.application_version(&order));
+    }
+
+    #[test]
+    fn fixt_logon_without_default_appl_ver_id_test() {
+        let now = now_utc();
+        let mut session = fixt_session();
+        assert!(session.incoming(&fixt_inbound(MSG_TYPE_LOGON, 1, now), now).is_err());
+
+        let outgoing = session.take_outgoing();
+        assert_eq!(2, outgoing.len());
+        assert_eq!(1, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!("5", outgoing[1].header.get_string(Tags::MsgType.to_num()).unwrap());
+    }
+
+    #[test]
+    fn fixt_from_app_uses_default_appl_ver_id_test() {
+        let now = now_utc();
+        let mut session = fixt_session();
+        let mut logon = fixt_inbound(MSG_TYPE_LOGON, 1, now);
+        logon.body.set_string(Tags::DefaultApplVerID.to_num(), "9");
+        session.incoming(&logon, now).unwrap();
+
+        let mut router = MessageRouter::new();
+        router.add_route(BEGIN_STRING_FIX50SP2, "D", |_, _| Ok(()));
+        let order = fixt_inbound("D", 2, now);
+        session.incoming(&order, now).unwrap();
+        session.from_app(&mut router, &order, now).unwrap();
+    }
+
+    //dictionary builds a small dictionary of version with the given header and messages
+    fn dictionary(version: &str, header: &str, messages: &str) -> sync::Arc<DataDictionary> {
+        let (kind, rest) = version.split_at(version.find('.').unwrap());
+        let numbers: Vec<&str> = rest[1..].split(|c| c == '.' || c == 'S' || c == 'P').filter(|n| !n.is_empty()).collect();
+        let xml = format!("<fix type=\"{}\" major=\"{}\" minor=\"{}\" servicepack=\"{}\"><header>{}</header><messages>{}</messages><fields>\
+            <field number=\"8\" name=\"BeginString\" type=\"STRING\"/><field number=\"11\" name=\"ClOrdID\" type=\"STRING\"/>\
+            <field number=\"34\" name=\"MsgSeqNum\" type=\"SEQNUM\"/><field number=\"35\" name=\"MsgType\" type=\"STRING\"/>\
+            <field number=\"49\" name=\"SenderCompID\" type=\"STRING\"/><field number=\"52\" name=\"SendingTime\" type=\"UTCTIMESTAMP\"/>\
+            <field number=\"55\" name=\"Symbol\" type=\"STRING\"/><field number=\"56\" name=\"TargetCompID\" type=\"STRING\"/>\
+            <field number=\"1128\" name=\"ApplVerID\" type=\"STRING\"/><field number=\"1129\" name=\"CstmApplVerID\" type=\"STRING\"/>\
+            <field number=\"1137\" name=\"DefaultApplVerID\" type=\"STRING\"/></fields></fix>",
+            kind, numbers[0], numbers[1], numbers.get(2).unwrap_or(&"0"), header, messages);
+        sync::Arc::new(DataDictionary::parse(&xml).unwrap())
+    }
+
+    fn fixt_dictionaries() -> DataDictionaryProvider {
+        let header = ["BeginString", "MsgType", "SenderCompID", "TargetCompID", "MsgSeqNum", "SendingTime", "ApplVerID", "CstmApplVerID"].iter()
+            .map(|name| format!("<field name=\"{}\" required=\"N\"/>", name)).collect::<String>();
+        let mut provider = DataDictionaryProvider::new();
+        provider.add_transport_dictionary(dictionary(BEGIN_STRING_FIXT11, &header,
+            "<message name=\"Logon\" msgtype=\"A\" msgcat=\"admin\"><field name=\"DefaultApplVerID\" required=\"Y\"/></message>"));
+        provider.add_application_dictionary(dictionary(BEGIN_STRING_FIX50SP2, "",
+            "<message name=\"NewOrderSingle\" msgtype=\"D\"><field name=\"ClOrdID\" required=\"Y\"/><field name=\"Symbol\" required=\"N\"/></message>"));
+        //VENUE1 requires the Symbol
+        provider.add_custom_application_dictionary("VENUE1", dictionary(BEGIN_STRING_FIX50SP2, "",
+            "<message name=\"NewOrderSingle\" msgtype=\"D\"><field name=\"ClOrdID\" required=\"Y\"/><field name=\"Symbol\" required=\"Y\"/></message>"));
+        provider
+    }
+
+    #[test]
+    fn fixt_appl_ver_id_selects_data_dictionary_test() {
+        let now = now_utc();
+        let mut session = fixt_session();
+        session.set_data_dictionary_provider(fixt_dictionaries());
+        let mut logon = fixt_inbound(MSG_TYPE_LOGON, 1, now);
+        logon.body.set_string(Tags::DefaultApplVerID.to_num(), "9");
+        session.incoming(&logon, now).unwrap();
+        session.take_outgoing();
+
+        let mut order = fixt_inbound("D", 2, now);
+        order.body.set_string(11, "ID1");
+        session.incoming(&order, now).unwrap();
+        assert!(session.take_outgoing().is_empty());
+
+        //no dictionary for FIX.5.0SP1
+        let mut order = fixt_inbound("D", 3, now);
+        order.header.set_string(Tags::ApplVerID.to_num(), "8");
+        order.body.set_string(11, "ID2");
+        assert!(session.incoming(&order, now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!(Reject::InvalidApplVerID as i32, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!(1128, outgoing[0].body.get_int(Tags::RefTagID.to_num()).unwrap());
+
+        //the custom dictionary of VENUE1 wants the Symbol
+        let mut order = fixt_inbound("D", 4, now);
+        order.header.set_string(Tags::CstmApplVerID.to_num(), "VENUE1");
+        order.body.set_string(11, "ID3");
+        assert!(session.incoming(&order, now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!(Reject::RequiredTagMissing as i32, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+        assert_eq!(55, outgoing[0].body.get_int(Tags::RefTagID.to_num()).unwrap());
+
+        order.body.set_string(55, "MSFT");
+        order.header.set_int(Tags::MsgSeqNum.to_num(), 5);
+        session.incoming(&order, now).unwrap();
+    }
+
+    #[test]
+    fn data_dictionary_settings_test() {
+        let mut settings = SessionSettings::new();
+        settings.set(DATA_DICTIONARY, "/nonexistent/FIX42.xml");
+        assert!(Session::new(SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW"), &settings, &MemoryStoreFactory::new()).is_err());
+        assert!(new_session(&SessionSettings::new()).data_dictionary_provider().is_empty());
+    }
+
+    struct RecordingLog {
+        entries: sync::Arc<sync::Mutex<Vec<String>>>
+    }
+
+    impl Log for RecordingLog {
+        fn on_incoming(&mut self, msg: &[u8]) {
+            self.entries.lock().unwrap().push(format!("incoming {}", String::from_utf8_lossy(msg).replace("\x01", "|")));
+        }
+
+        fn on_outgoing(&mut self, msg: &[u8]) {
+            self.entries.lock().unwrap().push(format!("outgoing {}", String::from_utf8_lossy(msg).replace("\x01", "|")));
+        }
+
+        fn on_event(&mut self, text: &str) {
+            self.entries.lock().unwrap().push(format!("event {}", text));
+        }
+    }
+
+    impl LogFactory for RecordingLog {
+        fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+            Ok(Box::new(RecordingLog{entries: self.entries.clone()}))
+        }
+
+        fn create_session_log(&self, _session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+            self.create()
+        }
+    }
+
+    #[test]
+    fn log_audit_trail_test() {
+        let entries = sync::Arc::new(sync::Mutex::new(vec![]));
+        let log_factory = RecordingLog{entries: entries.clone()};
+        let session_id = SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW");
+        let mut session = Session::new_with_log(session_id, &SessionSettings::new(), &MemoryStoreFactory::new(), &log_factory).unwrap();
+
+        let now = now_utc();
+        session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now).unwrap();
+        session.incoming(&inbound(MSG_TYPE_LOGOUT, 2, now), now).unwrap();
+
+        let entries = entries.lock().unwrap();
+        let kinds: Vec<&str> = entries.iter().map(|entry| entry.split(' ').next().unwrap()).collect();
+        assert_eq!(vec!["incoming", "event", "outgoing", "incoming", "event", "outgoing"], kinds);
+        assert!(entries[0].contains("|35=A|"));
+        assert_eq!("event Received logon request", entries[1]);
+        assert!(entries[2].contains("|35=A|") && entries[2].contains("|49=ISLD|"));
+        assert!(entries[5].contains("|35=5|"));
+    }
+
+    struct FailingLog;
+
+    impl Log for FailingLog {
+        fn on_incoming(&mut self, _msg: &[u8]) {}
+        fn on_outgoing(&mut self, _msg: &[u8]) {}
+        fn on_event(&mut self, _text: &str) {}
+
+        fn take_error(&mut self) -> Option<FixError> {
+            Some(FixError::IoError(io::Error::new(io::ErrorKind::Other, "disk full")))
+        }
+    }
+
+    impl LogFactory for FailingLog {
+        fn create(&self) -> Result<Box<dyn Log + Send>, FixError> {
+            Ok(Box::new(FailingLog))
+        }
+
+        fn create_session_log(&self, _session_id: &SessionID) -> Result<Box<dyn Log + Send>, FixError> {
+            self.create()
+        }
+    }
+
+    #[test]
+    fn log_error_test() {
+        let session_id = SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW");
+        let mut session = Session::new_with_log(session_id, &SessionSettings::new(), &MemoryStoreFactory::new(), &FailingLog).unwrap();
+
+        let now = now_utc();
+        match session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now) {
+            Err(FixError::IoError(_)) => {},
+            other => panic!("expected IoError, got {:?}", other)
+        }
+        assert!(!session.is_logged_on());
+        assert!(session.logon(now).is_err());
+        assert!(session.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn logon_expected_test() {
+        let now = now_utc();
+        let mut session = new_session(&SessionSettings::new());
+        assert!(session.incoming(&inbound("D", 1, now), now).is_err());
+    }
+
+    #[test]
+    fn signed_session_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(SIGNATURE_KEY, "secret");
+        let mut initiator = Session::new(SessionID::new(BEGIN_STRING_FIX42, "TW", "ISLD"), &settings, &MemoryStoreFactory::new()).unwrap();
+        let mut acceptor = new_session(&settings);
+
+        initiator.logon(now).unwrap();
+        let logon = initiator.take_outgoing().remove(0);
+        assert_eq!(32, logon.trailer.get_int(Tags::SignatureLength.to_num()).unwrap());
+        acceptor.incoming(&Message::parse(&logon.build()).unwrap(), now).unwrap();
+        let reply = acceptor.take_outgoing().remove(0);
+        initiator.incoming(&Message::parse(&reply.build()).unwrap(), now).unwrap();
+        assert!(initiator.is_logged_on() && acceptor.is_logged_on());
+
+        let mut forged = inbound("D", 2, now);
+        forged.header.set_string(Tags::SenderCompID.to_num(), "TW");
+        forged.header.set_string(Tags::TargetCompID.to_num(), "ISLD");
+        sign_message(&mut forged, &HmacSha256Signer::new(b"guess")).unwrap();
+        assert!(acceptor.incoming(&Message::parse(&forged.build()).unwrap(), now).is_err());
+        let outgoing = acceptor.take_outgoing();
+        assert_eq!("3", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(8, outgoing[0].body.get_int(Tags::SessionRejectReason.to_num()).unwrap());
+
+        assert!(acceptor.incoming(&inbound("D", 3, now), now).is_err());
+    }
+
+    #[test]
+    fn initiator_credentials_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(USERNAME, "trader");
+        settings.set(PASSWORD, "secret");
+        let entries = sync::Arc::new(sync::Mutex::new(vec![]));
+        let log_factory = RecordingLog{entries: entries.clone()};
+        let mut session = Session::new_with_log(SessionID::new(BEGIN_STRING_FIX42, "TW", "ISLD"), &settings, &MemoryStoreFactory::new(), &log_factory).unwrap();
+
+        session.logon(now).unwrap();
+        let logon = session.take_outgoing().remove(0);
+        assert_eq!(Credentials::new("trader", "secret"), Credentials::from_logon(&logon));
+        assert!(entries.lock().unwrap().iter().all(|entry| !entry.contains("secret")));
+        assert!(entries.lock().unwrap().iter().any(|entry| entry.contains("|554=********|")));
+
+        session.disconnected().unwrap();
+        session.set_credentials_callback(|session_id: &SessionID| {
+            let mut credentials = Credentials::new(&session_id.sender_comp_id, "old");
+            credentials.new_password = "new".to_string();
+            credentials
+        });
+        session.logon(now).unwrap();
+        let logon = session.take_outgoing().remove(0);
+        assert_eq!("TW", logon.body.get_string(Tags::Username.to_num()).unwrap());
+        assert_eq!("new", logon.body.get_string(Tags::NewPassword.to_num()).unwrap());
+    }
+
+    fn logon_with_password(password: &str, seq_num: i32, now: Tm) -> Message {
+        let mut logon = inbound(MSG_TYPE_LOGON, seq_num, now);
+        Credentials::new("trader", password).set_on(&mut logon);
+        logon
+    }
+
+    #[test]
+    fn authenticator_test() {
+        let now = now_utc();
+        let entries = sync::Arc::new(sync::Mutex::new(vec![]));
+        let log_factory = RecordingLog{entries: entries.clone()};
+        let mut session = Session::new_with_log(SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW"), &SessionSettings::new(), &MemoryStoreFactory::new(), &log_factory).unwrap();
+        session.set_authenticator(sync::Arc::new(|_: &SessionID, credentials: &Credentials, _: Option<&str>| {
+            if credentials.password == "secret" { Ok(()) } else { Err("Invalid password".to_string()) }
+        }));
+
+        assert!(session.incoming(&logon_with_password("guess", 1, now), now).is_err());
+        assert_eq!(SessionState::Latent, session.state());
+        let logout = session.take_outgoing().remove(0);
+        assert_eq!(MSG_TYPE_LOGOUT, logout.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("Invalid password", logout.body.get_string(Tags::Text.to_num()).unwrap());
+
+        session.incoming(&logon_with_password("secret", 2, now), now).unwrap();
+        assert!(session.is_logged_on());
+
+        let entries = entries.lock().unwrap();
+        assert!(entries.iter().any(|entry| entry == "event Logon rejected: Invalid password"));
+        assert!(entries.iter().all(|entry| !entry.contains("guess") && !entry.contains("secret")));
+    }
+
+    #[test]
+    fn logon_rate_limit_test() {
+        let now = now_utc();
+        let limiter = sync::Arc::new(LogonRateLimiter::new(2, Duration::seconds(60)));
+        let mut session = new_session(&SessionSettings::new());
+        session.set_authenticator(sync::Arc::new(|_: &SessionID, credentials: &Credentials, address: Option<&str>| {
+            assert_eq!(Some("10.0.0.1"), address);
+            if credentials.password == "secret" { Ok(()) } else { Err("Invalid password".to_string()) }
+        }));
+        session.set_logon_rate_limiter(limiter.clone());
+        session.set_remote_address(Some("10.0.0.1".to_string()));
+
+        assert!(session.incoming(&logon_with_password("guess", 1, now), now).is_err());
+        assert!(session.incoming(&logon_with_password("guess", 2, now), now).is_err());
+        assert!(session.incoming(&logon_with_password("secret", 3, now), now).is_err());
+        let outgoing = session.take_outgoing();
+        assert_eq!("Too many failed logon attempts", outgoing[2].body.get_string(Tags::Text.to_num()).unwrap());
+
+        let later = now + Duration::seconds(61);
+        session.incoming(&logon_with_password("secret", 4, later), later).unwrap();
+        assert!(session.is_logged_on());
+    }
+
+    #[test]
+    fn throttle_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(THROTTLE_MESSAGES_PER_SECOND, "1");
+        let mut session = new_session(&settings);
+        session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now).unwrap();
+        session.take_outgoing();
+
+        session.send(new_order(BEGIN_STRING_FIX42), now).unwrap();
+        session.send(new_order(BEGIN_STRING_FIX42), now).unwrap();
+        let mut heartbeat = Message::new();
+        heartbeat.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_HEARTBEAT);
+        session.send(heartbeat, now).unwrap();
+        let seq_nums: Vec<i32> = session.take_outgoing().iter().map(|msg| msg.header.get_int(Tags::MsgSeqNum.to_num()).unwrap()).collect();
+        assert_eq!(vec![2, 3], seq_nums);
+        assert_eq!(1, session.throttle().queued());
+
+        session.release_throttled(now).unwrap();
+        assert!(session.take_outgoing().is_empty());
+        let later = now + session.next_release(now).unwrap();
+        session.release_throttled(later).unwrap();
+        let released = session.take_outgoing();
+        assert_eq!("D", released[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!(4, released[0].header.get_int(Tags::MsgSeqNum.to_num()).unwrap());
+
+        session.set_throttle(Throttle::new(ThrottleMode::Reject).with_app_limit(1, 1));
+        session.send(new_order(BEGIN_STRING_FIX42), later).unwrap();
+        assert!(session.send(new_order(BEGIN_STRING_FIX42), later).is_err());
+        assert_eq!(6, session.next_sender_msg_seq_num());
+    }
+
+    #[test]
+    fn throttle_reconnect_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(THROTTLE_MESSAGES_PER_SECOND, "1");
+        let mut session = logged_on_session(&settings, now);
+        session.send(new_order(BEGIN_STRING_FIX42), now).unwrap();
+        session.send(new_order(BEGIN_STRING_FIX42), now).unwrap();
+        assert_eq!(1, session.take_outgoing().len());
+        session.disconnected().unwrap();
+
+        //the queued order waits for the next Logon instead of going out on the next connection first
+        let later = now + Duration::seconds(2);
+        assert_eq!(None, session.next_release(later));
+        session.release_throttled(later).unwrap();
+        assert!(session.take_outgoing().is_empty());
+        assert_eq!(1, session.throttle().queued());
+
+        session.incoming(&inbound(MSG_TYPE_LOGON, 2, later), later).unwrap();
+        assert_eq!(Some(Duration::zero()), session.next_release(later));
+        session.release_throttled(later).unwrap();
+        let sent: Vec<(String, i32)> = session.take_outgoing().iter().map(|msg| (
+            msg.header.get_string(Tags::MsgType.to_num()).unwrap(),
+            msg.header.get_int(Tags::MsgSeqNum.to_num()).unwrap()
+        )).collect();
+        assert_eq!(vec![(MSG_TYPE_LOGON.to_string(), 3), ("D".to_string(), 4)], sent);
+        assert_eq!(0, session.throttle().queued());
+    }
+
+    #[test]
+    fn send_before_logon_test() {
+        let now = now_utc();
+        let mut settings = SessionSettings::new();
+        settings.set(THROTTLE_MESSAGES_PER_SECOND, "10");
+        let mut session = new_session(&settings);
+
+        //the bucket is full, the order still waits for the Logon
+        session.send(new_order(BEGIN_STRING_FIX42), now).unwrap();
+        assert!(session.take_outgoing().is_empty());
+        assert_eq!(None, session.next_release(now));
+
+        session.incoming(&inbound(MSG_TYPE_LOGON, 1, now), now).unwrap();
+        session.release_throttled(now).unwrap();
+        let sent: Vec<String> = session.take_outgoing().iter().map(|msg| msg.header.get_string(Tags::MsgType.to_num()).unwrap()).collect();
+        assert_eq!(vec![MSG_TYPE_LOGON.to_string(), "D".to_string()], sent);
+        assert_eq!(0, session.throttle().queued());
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use session::*;
+use session_id::*;
+use error::FixError;
+use time::*;
+
+//SessionHandle is a thread safe reference to a running Session. The transport driving the session and the
+//operators stepping in share the same handle, whatever transport is used.
+#[derive(Clone)]
+pub struct SessionHandle {
+    session: Arc<Mutex<Session>>
+}
+
+impl SessionHandle {
+
+    pub fn new(session: Session) -> SessionHandle {
+        SessionHandle{session: Arc::new(Mutex::new(session))}
+    }
+
+    //lock gives exclusive access to the session, a panic in another holder does not make the session unusable
+    pub fn lock(&self) -> MutexGuard<'_, Session> {
+        match self.session.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner()
+        }
+    }
+
+    pub fn session_id(&self) -> SessionID {
+        self.lock().session_id().clone()
+    }
+
+    //logout disables automatic logon and, when logged on, sends a Logout carrying reason in Text
+    pub fn logout(&self, reason: &str) -> Result<(), FixError> {
+        let mut session = self.lock();
+        session.disable_logon();
+        if session.is_logged_on() {
+            return session.initiate_logout(reason, now_utc());
+        }
+        Ok(())
+    }
+
+    pub fn enable_logon(&self) {
+        self.lock().enable_logon();
+    }
+
+    pub fn disable_logon(&self) {
+        self.lock().disable_logon();
+    }
+
+    pub fn is_logon_enabled(&self) -> bool {
+        self.lock().is_logon_enabled()
+    }
+
+    pub fn set_next_sender_msg_seq_num(&self, next: i32) -> Result<(), FixError> {
+        self.lock().set_next_sender_msg_seq_num(next)
+    }
+
+    pub fn set_next_target_msg_seq_num(&self, next: i32) -> Result<(), FixError> {
+        self.lock().set_next_target_msg_seq_num(next)
+    }
+
+    pub fn reset(&self) -> Result<(), FixError> {
+        self.lock().reset_store()
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.lock().state()
+    }
+
+    pub fn is_logged_on(&self) -> bool {
+        self.lock().is_logged_on()
+    }
+
+    pub fn next_sender_msg_seq_num(&self) -> i32 {
+        self.lock().next_sender_msg_seq_num()
+    }
+
+    pub fn next_target_msg_seq_num(&self) -> i32 {
+        self.lock().next_target_msg_seq_num()
+    }
+
+    pub fn last_received_time(&self) -> Option<Tm> {
+        self.lock().last_received_time()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+    use tag::*;
+    use message::*;
+    use settings::*;
+    use store::*;
+    use begin_string::*;
+    use msg_type::*;
+
+    fn new_handle() -> SessionHandle {
+        let session = Session::new(SessionID::new(BEGIN_STRING_FIX44, "ISLD", "TW"), &SessionSettings::new(), &MemoryStoreFactory::new()).unwrap();
+        SessionHandle::new(session)
+    }
+
+    fn logon(handle: &SessionHandle, now: Tm) {
+        let mut logon = Message::new();
+        logon.header.set_string(Tags::BeginString.to_num(), BEGIN_STRING_FIX44);
+        logon.header.set_string(Tags::MsgType.to_num(), MSG_TYPE_LOGON);
+        logon.header.set_string(Tags::SenderCompID.to_num(), "TW");
+        logon.header.set_string(Tags::TargetCompID.to_num(), "ISLD");
+        logon.header.set_int(Tags::MsgSeqNum.to_num(), 1);
+        logon.header.set_time(Tags::SendingTime.to_num(), now);
+        logon.body.set_int(Tags::HeartBtInt.to_num(), 30);
+        handle.lock().incoming(&logon, now).unwrap();
+        handle.lock().take_outgoing();
+    }
+
+    #[test]
+    fn logout_test() {
+        let handle = new_handle();
+        let now = now_utc();
+        logon(&handle, now);
+        assert!(handle.is_logged_on());
+        assert_eq!(Some(now.to_timespec()), handle.last_received_time().map(|t| t.to_timespec()));
+
+        handle.logout("end of day").unwrap();
+        assert!(!handle.is_logon_enabled());
+        assert_eq!(SessionState::LogoutSent, handle.state());
+        let outgoing = handle.lock().take_outgoing();
+        assert_eq!("5", outgoing[0].header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert_eq!("end of day", outgoing[0].body.get_string(Tags::Text.to_num()).unwrap());
+    }
+
+    #[test]
+    fn disable_logon_test() {
+        let handle = new_handle();
+        handle.disable_logon();
+        assert!(handle.lock().logon(now_utc()).is_err());
+        handle.enable_logon();
+        assert!(handle.lock().logon(now_utc()).is_ok());
+    }
+
+    #[test]
+    fn seq_num_test() {
+        let handle = new_handle();
+        handle.set_next_sender_msg_seq_num(42).unwrap();
+        handle.set_next_target_msg_seq_num(7).unwrap();
+        assert!(handle.set_next_target_msg_seq_num(0).is_err());
+        assert_eq!(42, handle.next_sender_msg_seq_num());
+        assert_eq!(7, handle.next_target_msg_seq_num());
+
+        handle.reset().unwrap();
+        assert_eq!(1, handle.next_sender_msg_seq_num());
+        assert_eq!(1, handle.next_target_msg_seq_num());
+    }
+
+    #[test]
+    fn shared_across_threads_test() {
+        let handle = new_handle();
+        let operator = handle.clone();
+        thread::spawn(move || {
+            operator.set_next_sender_msg_seq_num(100).unwrap();
+        }).join().unwrap();
+        assert_eq!(100, handle.next_sender_msg_seq_num());
+        assert_eq!("FIX.4.4:ISLD->TW", handle.session_id().to_string());
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+//SessionID is a unique identifier of a Session
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct SessionID {
+    pub begin_string: String,
+    pub target_comp_id: String,
+    pub target_sub_id: String,
+    pub target_location_id: String,
+    pub sender_comp_id: String,
+    pub sender_sub_id: String,
+    pub sender_location_id: String,
+    pub qualifier: String
+}
+
+impl SessionID {
+
+    pub fn new(begin_string: &str, sender_comp_id: &str, target_comp_id: &str) -> SessionID {
+        SessionID{
+            begin_string: begin_string.to_string(),
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            ..SessionID::default()
+        }
+    }
+
+    //filename_prefix names the files kept for this session, e.g. FIX.4.2-TW_SS-ISLD-Q
+    pub fn filename_prefix(&self) -> String {
+        let join = |ids: &[&String]| ids.iter().filter(|id| !id.is_empty()).map(|id| id.as_str()).collect::<Vec<&str>>().join("_");
+        let mut parts = vec![
+            self.begin_string.clone(),
+            join(&[&self.sender_comp_id, &self.sender_sub_id, &self.sender_location_id]),
+            join(&[&self.target_comp_id, &self.target_sub_id, &self.target_location_id])
+        ];
+        if !self.qualifier.is_empty() {
+            parts.push(self.qualifier.clone());
+        }
+        parts.join("-")
+    }
+}
+
+fn write_optional(f: &mut fmt::Formatter, delim: &str, value: &str) -> fmt::Result {
+    if value.is_empty() {
+        return Ok(());
+    }
+    write!(f, "{}{}", delim, value)
+}
+
+//SessionID renders as BeginString:Sender->Target, e.g. FIX.4.2:TW->ISLD
+impl fmt::Display for SessionID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.begin_string, self.sender_comp_id)?;
+        write_optional(f, "/", &self.sender_sub_id)?;
+        write_optional(f, "/", &self.sender_location_id)?;
+        write!(f, "->{}", self.target_comp_id)?;
+        write_optional(f, "/", &self.target_sub_id)?;
+        write_optional(f, "/", &self.target_location_id)?;
+        write_optional(f, ":", &self.qualifier)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_test() {
+        let mut session_id = SessionID::new("FIX.4.2", "TW", "ISLD");
+        assert_eq!("FIX.4.2:TW->ISLD", session_id.to_string());
+
+        session_id.sender_sub_id = "SS".to_string();
+        session_id.target_location_id = "TL".to_string();
+        session_id.qualifier = "Q".to_string();
+        assert_eq!("FIX.4.2:TW/SS->ISLD/TL:Q", session_id.to_string());
+    }
+
+    #[test]
+    fn filename_prefix_test() {
+        let mut session_id = SessionID::new("FIX.4.2", "TW", "ISLD");
+        assert_eq!("FIX.4.2-TW-ISLD", session_id.filename_prefix());
+
+        session_id.sender_sub_id = "SS".to_string();
+        session_id.qualifier = "Q".to_string();
+        assert_eq!("FIX.4.2-TW_SS-ISLD-Q", session_id.filename_prefix());
+    }
+}
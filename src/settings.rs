@@ -0,0 +1,295 @@
+use std::collections::*;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use error::FixError;
+use session_id::*;
+
+//Settings keys, named after the QuickFIX configuration
+pub const BEGIN_STRING: &'static str = "BeginString";
+pub const SENDER_COMP_ID: &'static str = "SenderCompID";
+pub const SENDER_SUB_ID: &'static str = "SenderSubID";
+pub const SENDER_LOCATION_ID: &'static str = "SenderLocationID";
+pub const TARGET_COMP_ID: &'static str = "TargetCompID";
+pub const TARGET_SUB_ID: &'static str = "TargetSubID";
+pub const TARGET_LOCATION_ID: &'static str = "TargetLocationID";
+pub const SESSION_QUALIFIER: &'static str = "SessionQualifier";
+pub const CHECK_LATENCY: &'static str = "CheckLatency";
+pub const MAX_LATENCY: &'static str = "MaxLatency";
+pub const CHECK_COMP_ID: &'static str = "CheckCompID";
+pub const HEART_BT_INT: &'static str = "HeartBtInt";
+pub const RESET_ON_LOGON: &'static str = "ResetOnLogon";
+pub const RESET_ON_LOGOUT: &'static str = "ResetOnLogout";
+pub const RESET_ON_DISCONNECT: &'static str = "ResetOnDisconnect";
+pub const DEFAULT_APPL_VER_ID: &'static str = "DefaultApplVerID";
+//QuickFIX XML data dictionaries validating inbound messages: DataDictionary on FIX.4.x sessions, the
+//TransportDataDictionary and one or more AppDataDictionary[.<suffix>] on FIXT.1.1 ones, see DataDictionaryProvider
+pub const DATA_DICTIONARY: &'static str = "DataDictionary";
+pub const TRANSPORT_DATA_DICTIONARY: &'static str = "TransportDataDictionary";
+pub const APP_DATA_DICTIONARY: &'static str = "AppDataDictionary";
+//the HMAC-SHA256 key signing and verifying every message of the session
+pub const SIGNATURE_KEY: &'static str = "SignatureKey";
+//a template of the sessions an acceptor creates when their Logon arrives, see DynamicSessions
+pub const ACCEPTOR_TEMPLATE: &'static str = "AcceptorTemplate";
+//outbound rate limits, a missing rate means no limit and a missing burst defaults to the rate. ThrottleMode is
+//Queue, Reject or Drop and applies to application messages, admin messages over their limit are always queued.
+pub const THROTTLE_MESSAGES_PER_SECOND: &'static str = "ThrottleMessagesPerSecond";
+pub const THROTTLE_BURST: &'static str = "ThrottleBurst";
+pub const THROTTLE_ADMIN_MESSAGES_PER_SECOND: &'static str = "ThrottleAdminMessagesPerSecond";
+pub const THROTTLE_ADMIN_BURST: &'static str = "ThrottleAdminBurst";
+pub const THROTTLE_MODE: &'static str = "ThrottleMode";
+//the credentials an initiator sends on Logon
+pub const USERNAME: &'static str = "Username";
+pub const PASSWORD: &'static str = "Password";
+pub const NEW_PASSWORD: &'static str = "NewPassword";
+//Sockets of the transports, the initiator connects to SocketConnectHost and SocketConnectPort, the acceptor
+//listens on SocketAcceptPort
+pub const SOCKET_CONNECT_HOST: &'static str = "SocketConnectHost";
+pub const SOCKET_CONNECT_PORT: &'static str = "SocketConnectPort";
+pub const SOCKET_ACCEPT_PORT: &'static str = "SocketAcceptPort";
+//the largest message in bytes the transports read, a longer one closes the connection
+pub const MAX_MESSAGE_SIZE: &'static str = "MaxMessageSize";
+//TLS, with the tls feature. The files are PEM encoded, a SocketCAFile on an acceptor requires client certificates.
+pub const SOCKET_USE_SSL: &'static str = "SocketUseSSL";
+pub const SOCKET_CA_FILE: &'static str = "SocketCAFile";
+pub const SOCKET_CERTIFICATE_FILE: &'static str = "SocketCertificateFile";
+pub const SOCKET_PRIVATE_KEY_FILE: &'static str = "SocketPrivateKeyFile";
+pub const SOCKET_SERVER_NAME: &'static str = "SocketServerName";
+pub const SOCKET_MINIMUM_TLS_VERSION: &'static str = "SocketMinimumTLSVersion";
+pub const SOCKET_CIPHER_SUITES: &'static str = "SocketCipherSuites";
+pub const FILE_LOG_PATH: &'static str = "FileLogPath";
+pub const FILE_LOG_MAX_SIZE: &'static str = "FileLogMaxSize";
+pub const FILE_LOG_ROTATE_INTERVAL: &'static str = "FileLogRotateInterval";
+pub const FILE_LOG_BACKUP_COUNT: &'static str = "FileLogBackupCount";
+
+//SessionSettings maps session settings keys to their values
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SessionSettings {
+    settings: HashMap<String, String>
+}
+
+impl SessionSettings {
+
+    pub fn new() -> SessionSettings {
+        SessionSettings{settings: HashMap::new()}
+    }
+
+    pub fn set(&mut self, setting: &str, value: &str) {
+        self.settings.insert(setting.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, setting: &str) {
+        self.settings.remove(setting);
+    }
+
+    pub fn has(&self, setting: &str) -> bool {
+        self.settings.contains_key(setting)
+    }
+
+    pub fn setting(&self, setting: &str) -> Result<&str, FixError> {
+        match self.settings.get(setting) {
+            Some(value) => Ok(value.as_str()),
+            None => Err(FixError::ConfigError(format!("Conditionally Required Setting: {}", setting)))
+        }
+    }
+
+    pub fn int_setting(&self, setting: &str) -> Result<i32, FixError> {
+        let value = self.setting(setting)?;
+        match value.parse::<i32>() {
+            Ok(number) => Ok(number),
+            Err(_) => Err(FixError::ConfigError(format!("{} is invalid for {}", value, setting)))
+        }
+    }
+
+    //bool_setting accepts Y or N
+    pub fn bool_setting(&self, setting: &str) -> Result<bool, FixError> {
+        match self.setting(setting)? {
+            "Y" => Ok(true),
+            "N" => Ok(false),
+            value => Err(FixError::ConfigError(format!("{} is invalid for {}", value, setting)))
+        }
+    }
+
+    //the *_or variants fall back to default when the setting is absent, an invalid value is still an error
+    pub fn int_setting_or(&self, setting: &str, default: i32) -> Result<i32, FixError> {
+        if !self.has(setting) {
+            return Ok(default);
+        }
+        self.int_setting(setting)
+    }
+
+    pub fn bool_setting_or(&self, setting: &str, default: bool) -> Result<bool, FixError> {
+        if !self.has(setting) {
+            return Ok(default);
+        }
+        self.bool_setting(setting)
+    }
+
+    //with_prefix lists the settings whose key starts with prefix, sorted by key
+    pub fn with_prefix(&self, prefix: &str) -> Vec<(&str, &str)> {
+        let mut settings: Vec<(&str, &str)> = self.settings.iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        settings.sort();
+        settings
+    }
+
+    //overlay copies every setting of other over this one
+    pub fn overlay(&mut self, other: &SessionSettings) {
+        for (key, value) in other.settings.iter() {
+            self.settings.insert(key.clone(), value.clone());
+        }
+    }
+
+    //load reads a QuickFIX settings file, see parse
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<SessionSettings>, FixError> {
+        let mut text = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut text)).map_err(FixError::IoError)?;
+        SessionSettings::parse(&text)
+    }
+
+    //parse gives the settings of every [SESSION] of a QuickFIX settings file, the [DEFAULT] settings apply to all
+    //of them wherever they stand in the file. Blank lines and lines starting with # are skipped.
+    pub fn parse(text: &str) -> Result<Vec<SessionSettings>, FixError> {
+        let mut defaults = SessionSettings::new();
+        let mut sessions: Vec<SessionSettings> = vec![];
+        //section is None before the first section, Some(true) in [DEFAULT] and Some(false) in [SESSION]
+        let mut section = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim();
+                match name.to_uppercase().as_str() {
+                    "DEFAULT" => section = Some(true),
+                    "SESSION" => {
+                        sessions.push(SessionSettings::new());
+                        section = Some(false);
+                    },
+                    _ => return Err(FixError::ConfigError(format!("line {}: unknown section [{}]", number + 1, name)))
+                }
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(equals) => (line[..equals].trim(), line[equals + 1..].trim()),
+                None => return Err(FixError::ConfigError(format!("line {}: key=value expected", number + 1)))
+            };
+            if key.is_empty() {
+                return Err(FixError::ConfigError(format!("line {}: key=value expected", number + 1)));
+            }
+            match (section, sessions.last_mut()) {
+                (Some(true), _) => defaults.set(key, value),
+                (Some(false), Some(session)) => session.set(key, value),
+                _ => return Err(FixError::ConfigError(format!("line {}: {} is outside of a section", number + 1, key)))
+            }
+        }
+
+        Ok(sessions.into_iter().map(|session| {
+            let mut settings = defaults.clone();
+            settings.overlay(&session);
+            settings
+        }).collect())
+    }
+
+    //session_id builds the SessionID described by these settings
+    pub fn session_id(&self) -> Result<SessionID, FixError> {
+        let optional = |setting: &str| self.setting(setting).unwrap_or("").to_string();
+        Ok(SessionID{
+            begin_string: self.setting(BEGIN_STRING)?.to_string(),
+            sender_comp_id: self.setting(SENDER_COMP_ID)?.to_string(),
+            target_comp_id: self.setting(TARGET_COMP_ID)?.to_string(),
+            sender_sub_id: optional(SENDER_SUB_ID),
+            sender_location_id: optional(SENDER_LOCATION_ID),
+            target_sub_id: optional(TARGET_SUB_ID),
+            target_location_id: optional(TARGET_LOCATION_ID),
+            qualifier: optional(SESSION_QUALIFIER)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn typed_settings_test() {
+        let mut settings = SessionSettings::new();
+        settings.set(MAX_LATENCY, "30");
+        settings.set(CHECK_LATENCY, "N");
+        settings.set(CHECK_COMP_ID, "maybe");
+
+        assert_eq!(30, settings.int_setting(MAX_LATENCY).unwrap());
+        assert_eq!(false, settings.bool_setting(CHECK_LATENCY).unwrap());
+        assert!(settings.bool_setting(CHECK_COMP_ID).is_err());
+        assert!(settings.setting(BEGIN_STRING).is_err());
+        assert_eq!(true, settings.bool_setting_or("ResetOnLogon", true).unwrap());
+    }
+
+    #[test]
+    fn with_prefix_test() {
+        let mut settings = SessionSettings::new();
+        settings.set(APP_DATA_DICTIONARY, "FIX50SP2.xml");
+        settings.set("AppDataDictionary.VENUE1", "VENUE1.xml");
+        settings.set(TRANSPORT_DATA_DICTIONARY, "FIXT11.xml");
+        assert_eq!(vec![(APP_DATA_DICTIONARY, "FIX50SP2.xml"), ("AppDataDictionary.VENUE1", "VENUE1.xml")], settings.with_prefix(APP_DATA_DICTIONARY));
+    }
+
+    #[test]
+    fn parse_test() {
+        let text = "# venue sessions\n\
+            [DEFAULT]\n\
+            BeginString=FIX.4.2\n\
+            HeartBtInt = 30\n\
+            \n\
+            [SESSION]\n\
+            SenderCompID=TW\n\
+            TargetCompID=ISLD\n\
+            HeartBtInt=60\n\
+            [session]\n\
+            SenderCompID=TW\n\
+            TargetCompID=*\n\
+            AcceptorTemplate=Y\n\
+            [DEFAULT]\n\
+            SignatureKey=a=b\n";
+        let sessions = SessionSettings::parse(text).unwrap();
+        assert_eq!(2, sessions.len());
+        assert_eq!("FIX.4.2:TW->ISLD", sessions[0].session_id().unwrap().to_string());
+        assert_eq!(60, sessions[0].int_setting(HEART_BT_INT).unwrap());
+        assert_eq!(30, sessions[1].int_setting(HEART_BT_INT).unwrap());
+        assert_eq!(true, sessions[1].bool_setting(ACCEPTOR_TEMPLATE).unwrap());
+        //[DEFAULT] applies to the sessions before it too, values may hold =
+        assert_eq!("a=b", sessions[0].setting(SIGNATURE_KEY).unwrap());
+
+        assert!(SessionSettings::parse("[DEFAULT]\nBeginString=FIX.4.2\n").unwrap().is_empty());
+        assert!(SessionSettings::parse("BeginString=FIX.4.2\n[SESSION]\n").is_err());
+        assert!(SessionSettings::parse("[SESSION]\nBeginString\n").is_err());
+        assert!(SessionSettings::parse("[SESSION]\n=FIX.4.2\n").is_err());
+        assert!(SessionSettings::parse("[SESSIONS]\n").is_err());
+    }
+
+    #[test]
+    fn load_test() {
+        let path = ::std::env::temp_dir().join(format!("quickfix-settings-{}.cfg", ::std::process::id()));
+        ::std::fs::write(&path, "[SESSION]\nBeginString=FIX.4.4\nSenderCompID=A\nTargetCompID=B\n").unwrap();
+        let sessions = SessionSettings::load(&path).unwrap();
+        let _ = ::std::fs::remove_file(&path);
+        assert_eq!("FIX.4.4:A->B", sessions[0].session_id().unwrap().to_string());
+        assert!(SessionSettings::load(&path).is_err());
+    }
+
+    #[test]
+    fn session_id_test() {
+        let mut settings = SessionSettings::new();
+        settings.set(BEGIN_STRING, "FIX.4.2");
+        settings.set(SENDER_COMP_ID, "TW");
+        assert!(settings.session_id().is_err());
+
+        settings.set(TARGET_COMP_ID, "ISLD");
+        settings.set(SESSION_QUALIFIER, "Q");
+        let session_id = settings.session_id().unwrap();
+        assert_eq!("FIX.4.2:TW->ISLD:Q", session_id.to_string());
+    }
+}
@@ -0,0 +1,180 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tag::*;
+use message::*;
+use message_view::*;
+use error::FixError;
+use error::error::MessageRejectError;
+
+//MessageSigner computes and checks the Signature(89) of the messages exchanged with a counterparty sharing its
+//secret
+pub trait MessageSigner {
+    fn sign(&self, data: &[u8]) -> Vec<u8>;
+
+    //signature_length is the length of every signature, the SignatureLength is written before signing
+    fn signature_length(&self) -> usize {
+        self.sign(&[]).len()
+    }
+
+    //verify tells whether signature is the one of data, implementations should compare in constant time
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        let expected = self.sign(data);
+        expected.len() == signature.len() && expected.iter().zip(signature.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+//HmacSha256Signer signs with HMAC-SHA256, the Signature is the 32 bytes digest
+pub struct HmacSha256Signer {
+    key: Vec<u8>
+}
+
+impl HmacSha256Signer {
+
+    pub fn new(key: &[u8]) -> HmacSha256Signer {
+        HmacSha256Signer{key: key.to_vec()}
+    }
+
+    fn mac(&self, data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(data);
+        mac
+    }
+}
+
+impl MessageSigner for HmacSha256Signer {
+
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        self.mac(data).finalize().into_bytes().to_vec()
+    }
+
+    fn signature_length(&self) -> usize {
+        32
+    }
+
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        self.mac(data).verify_slice(signature).is_ok()
+    }
+}
+
+//signed_range locates the bytes a signature covers in raw, from BeginString up to SignatureLength or, when the
+//message is not signed, up to CheckSum
+fn signed_range(raw: &[u8]) -> Result<(usize, usize), FixError> {
+    let view = MessageView::parse(raw)?;
+    let offsets = view.offsets();
+    let msg_type = offsets.iter().position(|offset| offset.tag() == Tags::MsgType.to_num())
+        .ok_or_else(|| FixError::ParseMessageError("signature: MsgType not found".to_string()))?;
+    let trailer = offsets[msg_type..].iter().find(|offset| offset.tag().is_trailer())
+        .ok_or_else(|| FixError::ParseMessageError("signature: CheckSum not found".to_string()))?;
+    Ok((0, trailer.start()))
+}
+
+//sign_message sets SignatureLength and Signature in the trailer of msg, replacing a previous signature. The
+//header must be complete as the signature covers it. BodyLength counts the signature too, so the message is
+//first built with a placeholder as long as the signature and the signature then takes its place.
+pub fn sign_message(msg: &mut Message, signer: &dyn MessageSigner) -> Result<(), FixError> {
+    let length = signer.signature_length();
+    msg.trailer.set_int(Tags::SignatureLength.to_num(), length as i32);
+    msg.trailer.set_bytes(Tags::Signature.to_num(), &vec![0; length]);
+
+    let raw = msg.build();
+    let (start, end) = signed_range(&raw)?;
+    let signature = signer.sign(&raw[start..end]);
+    if signature.len() != length {
+        return Err(FixError::ConfigError(format!("signature of {} bytes, {} expected", signature.len(), length)));
+    }
+    msg.trailer.set_bytes(Tags::Signature.to_num(), &signature);
+    Ok(())
+}
+
+//verify_message checks the Signature of msg against the bytes it was received as
+pub fn verify_message(msg: &Message, signer: &dyn MessageSigner) -> Result<(), MessageRejectError> {
+    let signature = msg.trailer.get_bytes(Tags::Signature.to_num())
+        .map_err(|_| MessageRejectError::required_tag_missing(Tags::Signature.to_num()))?;
+
+    let built;
+    let raw = if msg.raw_message().is_empty() {
+        built = msg.build();
+        &built
+    } else {
+        msg.raw_message()
+    };
+    let (start, end) = signed_range(raw).map_err(|_| MessageRejectError::signature_problem())?;
+    if !signer.verify(&raw[start..end], signature) {
+        return Err(MessageRejectError::signature_problem());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Reject;
+    use error::MessageRejectError as MessageRejectErrorTrait;
+
+    fn order() -> Message {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::BeginString.to_num(), "FIX.4.4");
+        msg.header.set_string(Tags::MsgType.to_num(), "D");
+        msg.header.set_int(Tags::MsgSeqNum.to_num(), 2);
+        msg.body.set_string(11, "ORD1");
+        msg
+    }
+
+    #[test]
+    fn hmac_sha256_test() {
+        //RFC 4231 test case 2
+        let signer = HmacSha256Signer::new(b"Jefe");
+        let signature = signer.sign(b"what do ya want for nothing?");
+        let hex: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", hex);
+        assert!(signer.verify(b"what do ya want for nothing?", &signature));
+        assert!(!signer.verify(b"what do ya want for nothing!", &signature));
+    }
+
+    #[test]
+    fn sign_and_verify_test() {
+        let signer = HmacSha256Signer::new(b"secret");
+        let mut msg = order();
+        sign_message(&mut msg, &signer).unwrap();
+        assert_eq!(32, msg.trailer.get_int(Tags::SignatureLength.to_num()).unwrap());
+
+        let raw = msg.build();
+        assert!(raw.windows(10).any(|field| field == b"\x0193=32\x0189="));
+        let end = raw.windows(4).position(|field| field == b"\x0193=").unwrap() + 1;
+        assert_eq!(signer.sign(&raw[..end]), msg.trailer.get_bytes(Tags::Signature.to_num()).unwrap());
+        let parsed = Message::parse(&raw).unwrap();
+        assert!(verify_message(&parsed, &signer).is_ok());
+        assert!(verify_message(&msg, &signer).is_ok());
+
+        //signing again replaces the signature
+        sign_message(&mut msg, &signer).unwrap();
+        assert!(verify_message(&Message::parse(&msg.build()).unwrap(), &signer).is_ok());
+
+        let err = verify_message(&parsed, &HmacSha256Signer::new(b"other")).unwrap_err();
+        assert_eq!(Reject::SignatureProblem, err.reject_reason());
+        assert_eq!(8, err.reject_reason() as i32);
+    }
+
+    #[test]
+    fn tampered_message_test() {
+        let signer = HmacSha256Signer::new(b"secret");
+        let mut msg = order();
+        sign_message(&mut msg, &signer).unwrap();
+        let mut raw = msg.build();
+
+        let pos = raw.windows(7).position(|field| field == b"11=ORD1").unwrap();
+        raw[pos + 6] = b'2';
+        let tampered = Message::parse(&raw).unwrap();
+        assert_eq!(Reject::SignatureProblem, verify_message(&tampered, &signer).unwrap_err().reject_reason());
+
+        //BeginString and BodyLength are signed too
+        let mut raw = msg.build();
+        raw[8] = b'2';
+        let tampered = Message::parse(&raw).unwrap();
+        assert_eq!(Reject::SignatureProblem, verify_message(&tampered, &signer).unwrap_err().reject_reason());
+
+        let err = verify_message(&order(), &signer).unwrap_err();
+        assert_eq!(Reject::RequiredTagMissing, err.reject_reason());
+        assert_eq!(Tags::Signature.to_num(), err.ref_tag_id());
+    }
+}
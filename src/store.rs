@@ -0,0 +1,167 @@
+use std::collections::*;
+use error::FixError;
+use session_id::*;
+use time::*;
+
+//The MessageStore interface provides methods to record and retrieve messages for resend purposes
+pub trait MessageStore {
+    fn next_sender_msg_seq_num(&self) -> i32;
+    fn next_target_msg_seq_num(&self) -> i32;
+
+    fn incr_next_sender_msg_seq_num(&mut self) -> Result<(), FixError>;
+    fn incr_next_target_msg_seq_num(&mut self) -> Result<(), FixError>;
+
+    fn set_next_sender_msg_seq_num(&mut self, next: i32) -> Result<(), FixError>;
+    fn set_next_target_msg_seq_num(&mut self, next: i32) -> Result<(), FixError>;
+
+    fn creation_time(&self) -> Tm;
+
+    fn save_message(&mut self, seq_num: i32, msg: &[u8]) -> Result<(), FixError>;
+    fn get_messages(&self, begin_seq_num: i32, end_seq_num: i32) -> Result<Vec<Vec<u8>>, FixError>;
+
+    fn refresh(&mut self) -> Result<(), FixError>;
+    //reset sets both sequence numbers back to 1 and drops the saved messages
+    fn reset(&mut self) -> Result<(), FixError>;
+}
+
+//The MessageStoreFactory interface is used by session to create a session specific message store
+pub trait MessageStoreFactory {
+    fn create(&self, session_id: &SessionID) -> Result<Box<dyn MessageStore + Send>, FixError>;
+}
+
+//MemoryStore keeps the sequence numbers and the sent messages in memory only
+pub struct MemoryStore {
+    sender_msg_seq_num: i32,
+    target_msg_seq_num: i32,
+    creation_time: Tm,
+    message_map: BTreeMap<i32, Vec<u8>>
+}
+
+impl MemoryStore {
+
+    pub fn new() -> MemoryStore {
+        MemoryStore{sender_msg_seq_num: 0, target_msg_seq_num: 0, creation_time: now_utc(), message_map: BTreeMap::new()}
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> MemoryStore {
+        MemoryStore::new()
+    }
+}
+
+impl MessageStore for MemoryStore {
+
+    fn next_sender_msg_seq_num(&self) -> i32 {
+        self.sender_msg_seq_num + 1
+    }
+
+    fn next_target_msg_seq_num(&self) -> i32 {
+        self.target_msg_seq_num + 1
+    }
+
+    fn incr_next_sender_msg_seq_num(&mut self) -> Result<(), FixError> {
+        self.sender_msg_seq_num += 1;
+        Ok(())
+    }
+
+    fn incr_next_target_msg_seq_num(&mut self) -> Result<(), FixError> {
+        self.target_msg_seq_num += 1;
+        Ok(())
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, next: i32) -> Result<(), FixError> {
+        self.sender_msg_seq_num = next - 1;
+        Ok(())
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, next: i32) -> Result<(), FixError> {
+        self.target_msg_seq_num = next - 1;
+        Ok(())
+    }
+
+    fn creation_time(&self) -> Tm {
+        self.creation_time
+    }
+
+    fn save_message(&mut self, seq_num: i32, msg: &[u8]) -> Result<(), FixError> {
+        self.message_map.insert(seq_num, msg.to_vec());
+        Ok(())
+    }
+
+    fn get_messages(&self, begin_seq_num: i32, end_seq_num: i32) -> Result<Vec<Vec<u8>>, FixError> {
+        Ok(self.message_map.range(begin_seq_num..end_seq_num + 1).map(|(_, msg)| msg.clone()).collect())
+    }
+
+    fn refresh(&mut self) -> Result<(), FixError> {
+        //nothing to refresh
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), FixError> {
+        self.sender_msg_seq_num = 0;
+        self.target_msg_seq_num = 0;
+        self.creation_time = now_utc();
+        self.message_map.clear();
+        Ok(())
+    }
+}
+
+pub struct MemoryStoreFactory;
+
+impl MemoryStoreFactory {
+
+    pub fn new() -> MemoryStoreFactory {
+        MemoryStoreFactory
+    }
+}
+
+impl Default for MemoryStoreFactory {
+    fn default() -> MemoryStoreFactory {
+        MemoryStoreFactory::new()
+    }
+}
+
+impl MessageStoreFactory for MemoryStoreFactory {
+    fn create(&self, _session_id: &SessionID) -> Result<Box<dyn MessageStore + Send>, FixError> {
+        Ok(Box::new(MemoryStore::new()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seq_num_test() {
+        let mut store = MemoryStore::new();
+        assert_eq!(1, store.next_sender_msg_seq_num());
+        assert_eq!(1, store.next_target_msg_seq_num());
+
+        store.incr_next_sender_msg_seq_num().unwrap();
+        store.set_next_target_msg_seq_num(12).unwrap();
+        assert_eq!(2, store.next_sender_msg_seq_num());
+        assert_eq!(12, store.next_target_msg_seq_num());
+    }
+
+    #[test]
+    fn get_messages_test() {
+        let mut store = MemoryStore::new();
+        store.save_message(1, b"one").unwrap();
+        store.save_message(2, b"two").unwrap();
+        store.save_message(3, b"three").unwrap();
+        assert_eq!(vec![b"two".to_vec(), b"three".to_vec()], store.get_messages(2, 3).unwrap());
+    }
+
+    #[test]
+    fn reset_test() {
+        let mut store = MemoryStore::new();
+        store.save_message(1, b"one").unwrap();
+        store.set_next_sender_msg_seq_num(5).unwrap();
+        store.set_next_target_msg_seq_num(7).unwrap();
+        store.reset().unwrap();
+        assert_eq!(1, store.next_sender_msg_seq_num());
+        assert_eq!(1, store.next_target_msg_seq_num());
+        assert!(store.get_messages(1, 1).unwrap().is_empty());
+    }
+}
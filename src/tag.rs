@@ -7,6 +7,8 @@ pub trait Tag {
 
   fn is_trailer(&self) -> bool;
   fn is_header(&self) -> bool;
+  fn data_tag(&self) -> Option<u32>;
+  fn is_sensitive(&self) -> bool;
 }
 
 
@@ -68,6 +70,24 @@ impl Tag for u32 {
     }
     false
   }
+
+  //data_tag returns the raw data field whose byte length is carried by this tag
+  fn data_tag(&self) -> Option<u32> {
+      match Tags::from_number(*self) {
+          Some(Tags::SecureDataLen) => Some(Tags::SecureData.to_num()),
+          Some(Tags::XMLDataLen) => Some(Tags::XMLData.to_num()),
+          Some(Tags::SignatureLength) => Some(Tags::Signature.to_num()),
+          _ => None
+      }
+  }
+
+  //is_sensitive tags carry credentials, their values must never show up in logs or printouts
+  fn is_sensitive(&self) -> bool {
+      match Tags::from_number(*self) {
+          Some(Tags::Password) | Some(Tags::NewPassword) => true,
+          _ => false
+      }
+  }
 }
 
 enum_from_primitive! {
@@ -115,7 +135,7 @@ pub enum  Tags {
   BusinessRejectReason  = 380,
   SessionRejectReason   = 373,
   RefMsgType            = 372,
-  RefID              = 371,
+  RefTagID              = 371,
   RefSeqNum             = 45,
   EncryptMethod         = 98,
   ResetSeqNumFlag       = 141,
@@ -126,6 +146,9 @@ pub enum  Tags {
   NewSeqNo              = 36,
   BeginSeqNo            = 7,
   EndSeqNo              = 16,
+  Username              = 553,
+  Password              = 554,
+  NewPassword           = 925,
 
   SignatureLength  = 93,
   Signature        = 89,
@@ -143,11 +166,21 @@ impl Tags {
   pub fn to_num(&self) -> u32 {
     *self as u32
   }
+
+  //name is the FIX field name of the tag, e.g. MsgType
+  pub fn name(&self) -> String {
+    format!("{:?}", self)
+  }
+
+  //from_name looks a tag up by its field name, every Tags number lies below 2000
+  pub fn from_name(name: &str) -> Option<Tags> {
+    (1..2000).filter_map(Tags::from_number).find(|tag| tag.name() == name)
+  }
 }
 
 impl Display for Tags {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f, "({})", *self)
+        write!(f, "{}({})", self.name(), self.to_num())
     }
 }
 
@@ -175,6 +208,31 @@ mod test {
       assert!(tag.to_num().is_header());
   }
 
+  #[test]
+  fn data_tag_test() {
+      assert_eq!(Some(Tags::XMLData.to_num()), Tags::XMLDataLen.to_num().data_tag());
+      assert_eq!(None, Tags::MsgType.to_num().data_tag());
+  }
+
+  #[test]
+  fn is_sensitive_test() {
+      assert!(Tags::Password.to_num().is_sensitive());
+      assert!(!Tags::Username.to_num().is_sensitive());
+  }
+
+  #[test]
+  fn from_name_test() {
+      assert_eq!(Some(Tags::DefaultApplVerID), Tags::from_name("DefaultApplVerID"));
+      assert_eq!(None, Tags::from_name("Symbol"));
+      assert_eq!(Some(Tags::RefTagID), Tags::from_name("RefTagID"));
+  }
+
+  #[test]
+  fn display_test() {
+      assert_eq!("MsgType(35)", Tags::MsgType.to_string());
+      assert_eq!("RefTagID(371)", Tags::RefTagID.to_string());
+  }
+
   #[test]
   fn to_num_test() {
       assert!(Tags::CheckSum.to_num() == 10);
@@ -1,16 +1,20 @@
 use tag::*;
 use std::*;
 
+pub const SOH: u8 = 0x01;
+
+#[derive(Clone, PartialEq)]
 pub struct TagValue {
     tag: u32,
-    value: Vec<u8>,
-    bytes: Vec<u8>
+    bytes: Vec<u8>,
+    value_start: usize,
+    value_end: usize
 }
 
 impl TagValue {
 
     pub fn empty() -> TagValue {
-        TagValue{tag:0, value:vec![], bytes: vec![]}
+        TagValue{tag:0, bytes: vec![], value_start: 0, value_end: 0}
     }
 
     pub fn tag(&self) -> u32 {
@@ -18,65 +22,95 @@ impl TagValue {
     }
 
     pub fn value(&self) -> &[u8] {
-        self.value.as_ref()
+        &self.bytes[self.value_start..self.value_end]
     }
 
     pub fn new(tag_val: u32, value: &[u8]) -> TagValue {
-        let mut bytes: Vec<u8> = vec![];
-        bytes.extend(tag_val.to_string().as_bytes().iter().cloned());
-        bytes.extend("=".as_bytes().iter().cloned());
-        bytes.extend(value.iter().cloned());
-        bytes.extend("".as_bytes().iter().cloned());
-
-        let mut  value_vec: Vec<u8> = vec![];
-        value_vec.extend(value);
-        TagValue{tag: tag_val, value: value_vec, bytes: bytes}
+        let mut tag_value = TagValue::empty();
+        tag_value.init(tag_val, value);
+        tag_value
     }
 
+    //init reuses the existing buffer, the value is only copied once into tag=value<SOH>
     pub fn init(&mut self, tag_val: u32, value: &[u8]) {
-        let mut bytes: Vec<u8> = vec![];
-        bytes.extend(tag_val.to_string().as_bytes().iter().cloned());
-        bytes.extend("=".as_bytes().iter().cloned());
-        bytes.extend(value.iter().cloned());
-        bytes.extend("".as_bytes().iter().cloned());
-
-        let mut  value_vec: Vec<u8> = vec![];
-        value_vec.extend(value);
-
-        self.value = value_vec;
-        self.bytes = bytes;
+        self.bytes.clear();
+        self.bytes.extend_from_slice(tag_val.to_string().as_bytes());
+        self.bytes.push(b'=');
+        self.value_start = self.bytes.len();
+        self.bytes.extend_from_slice(value);
+        self.value_end = self.bytes.len();
+        self.bytes.push(SOH);
         self.tag = tag_val;
     }
 
     pub fn parse(raw_bytes: &[u8]) -> Result<TagValue, String> {
         let sep_index = match raw_bytes.iter().position(|&c| c == b'=') {
             Some(index) => index,
-            None => usize::max_value()
+            None => return Err("TagValue.parse: '=' not found".to_string())
         };
 
-        if sep_index == usize::max_value() {
-          return Err("TagValue.parse: '=' not found".to_string())
-        }
+        let tag_num = match parse_tag(&raw_bytes[0..sep_index]) {
+            Some(tag) => tag,
+            None => return Err("TagValue.parse: invalid tag number".to_string())
+        };
 
-        let tag_string = str::from_utf8(raw_bytes).unwrap();
-        let tag_numstring = &tag_string[0..sep_index];
-        let tag_num = tag_numstring.parse::<u32>().unwrap();
-        let value_bytes = (&tag_string[(sep_index + 1)..]).as_bytes();
+        let value_end = match raw_bytes.last() {
+            Some(&SOH) => raw_bytes.len() - 1,
+            _ => raw_bytes.len()
+        };
 
         let mut bytes: Vec<u8> = vec![];
-        bytes.extend(raw_bytes);
+        bytes.extend_from_slice(raw_bytes);
 
-        let mut value_vec: Vec<u8> = vec![];
-        value_vec.extend(value_bytes);
+        Ok(TagValue{tag: tag_num, bytes: bytes, value_start: sep_index + 1, value_end: value_end})
+    }
 
-        Ok(TagValue{tag: tag_num, value: value_vec, bytes: bytes})
+    pub fn bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
     }
 
-    pub fn len(self:TagValue) -> usize {
+    pub fn len(&self) -> usize {
         self.bytes.len()
     }
 }
 
+impl Default for TagValue {
+    fn default() -> TagValue {
+        TagValue::empty()
+    }
+}
+
+impl fmt::Debug for TagValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TagValue({}={:?})", self.tag, String::from_utf8_lossy(self.value()))
+    }
+}
+
+impl fmt::Display for TagValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.tag, String::from_utf8_lossy(self.value()))
+    }
+}
+
+//parse_tag reads a tag number straight from its ascii digits
+pub fn parse_tag(digits: &[u8]) -> Option<u32> {
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut tag: u32 = 0;
+    for &digit in digits {
+        if digit < b'0' || digit > b'9' {
+            return None;
+        }
+        tag = match tag.checked_mul(10).and_then(|t| t.checked_add((digit - b'0') as u32)) {
+            Some(t) => t,
+            None => return None
+        };
+    }
+    Some(tag)
+}
+
 #[cfg(test)]
 mod test {
 
@@ -88,8 +122,8 @@ mod test {
         let expected_value= "blahblah".as_bytes();
         let expected_data= "8=blahblah".as_bytes();
         let tag_value = TagValue::new(Tags::BeginString.to_num(), expected_value);
-        assert_eq!(expected_value, tag_value.value.as_slice());
-        assert_eq!(expected_data, tag_value.bytes.as_slice());
+        assert_eq!(expected_value, tag_value.value());
+        assert_eq!(expected_data, tag_value.bytes());
     }
 
     #[test]
@@ -101,8 +135,8 @@ mod test {
 
         let expected_data= "8=blahblah".as_bytes();
         tag_value.init(tag.into(), expected_value);
-        assert_eq!(expected_value, tag_value.value.as_slice());
-        assert_eq!(expected_data, tag_value.bytes.as_slice());
+        assert_eq!(expected_value, tag_value.value());
+        assert_eq!(expected_data, tag_value.bytes());
     }
 
     #[test]
@@ -114,7 +148,25 @@ mod test {
           _ =>  true
         };
         assert!(res == true);
-        assert!("A" == String::from_utf8(result.unwrap().value).unwrap());
+        assert!("A" == String::from_utf8(result.unwrap().value().to_vec()).unwrap());
+    }
+
+    #[test]
+    fn parse_trailing_soh_test() {
+        let result = TagValue::parse("35=A\x01".as_bytes()).unwrap();
+        assert_eq!(35, result.tag());
+        assert_eq!("A".as_bytes(), result.value());
+    }
+
+    #[test]
+    fn parse_invalid_tag_test() {
+        assert!(TagValue::parse("3x=A".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn display_test() {
+        let tag_value = TagValue::new(Tags::MsgType.to_num(), "D".as_bytes());
+        assert_eq!("35=D", format!("{}", tag_value));
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,359 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use message::*;
+use error::FixError;
+use settings::*;
+use time::*;
+
+//TokenBucket lets rate messages per second through on average and up to burst at once
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: Option<Tm>
+}
+
+impl TokenBucket {
+
+    //new creates a full bucket, a burst of 0 is taken as 1
+    pub fn new(rate: u32, burst: u32) -> TokenBucket {
+        let burst = if burst == 0 { 1.0 } else { burst as f64 };
+        TokenBucket{rate: rate as f64, burst: burst, tokens: burst, last: None}
+    }
+
+    fn refill(&mut self, now: Tm) {
+        if let Some(last) = self.last {
+            let elapsed = (now - last).num_microseconds().unwrap_or(i64::MAX);
+            if elapsed <= 0 {
+                return;
+            }
+            self.tokens = (self.tokens + elapsed as f64 * self.rate / 1_000_000.0).min(self.burst);
+        }
+        self.last = Some(now);
+    }
+
+    pub fn try_acquire(&mut self, now: Tm) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    //wait is how long until the next message can go through
+    pub fn wait(&mut self, now: Tm) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            return Duration::zero();
+        }
+        if self.rate <= 0.0 {
+            return Duration::max_value();
+        }
+        Duration::microseconds(((1.0 - self.tokens) * 1_000_000.0 / self.rate).ceil() as i64)
+    }
+}
+
+//ThrottleMode is what happens to an application message over the limit, admin messages are always queued
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleMode {
+    //Queue holds the message until release lets it through
+    Queue,
+    //Reject fails the send with an error
+    Reject,
+    //Drop discards the message after handing it to the drop callback
+    Drop
+}
+
+impl FromStr for ThrottleMode {
+    type Err = FixError;
+
+    fn from_str(value: &str) -> Result<ThrottleMode, FixError> {
+        match value {
+            "Queue" => Ok(ThrottleMode::Queue),
+            "Reject" => Ok(ThrottleMode::Reject),
+            "Drop" => Ok(ThrottleMode::Drop),
+            _ => Err(FixError::ConfigError(format!("{} is invalid for {}", value, THROTTLE_MODE)))
+        }
+    }
+}
+
+type DropCallback = Box<dyn FnMut(&Message) + Send>;
+
+//Throttle limits the messages a session sends. Application and admin messages have their own limits, queued
+//admin messages are released first so that heartbeats are never starved by application traffic.
+pub struct Throttle {
+    mode: ThrottleMode,
+    app: Option<TokenBucket>,
+    admin: Option<TokenBucket>,
+    app_queue: VecDeque<Message>,
+    admin_queue: VecDeque<Message>,
+    on_drop: Option<DropCallback>
+}
+
+impl Throttle {
+
+    //new creates a throttle without limits
+    pub fn new(mode: ThrottleMode) -> Throttle {
+        Throttle{mode: mode, app: None, admin: None, app_queue: VecDeque::new(), admin_queue: VecDeque::new(), on_drop: None}
+    }
+
+    //from_settings reads ThrottleMessagesPerSecond, ThrottleBurst, their admin counterparts and ThrottleMode, a
+    //missing rate means no limit and a missing burst defaults to the rate
+    pub fn from_settings(settings: &SessionSettings) -> Result<Throttle, FixError> {
+        let mut mode = ThrottleMode::Queue;
+        if settings.has(THROTTLE_MODE) {
+            mode = settings.setting(THROTTLE_MODE)?.parse()?;
+        }
+        let mut throttle = Throttle::new(mode);
+        throttle.app = bucket(settings, THROTTLE_MESSAGES_PER_SECOND, THROTTLE_BURST)?;
+        throttle.admin = bucket(settings, THROTTLE_ADMIN_MESSAGES_PER_SECOND, THROTTLE_ADMIN_BURST)?;
+        Ok(throttle)
+    }
+
+    pub fn with_app_limit(mut self, rate: u32, burst: u32) -> Throttle {
+        self.app = Some(TokenBucket::new(rate, burst));
+        self
+    }
+
+    pub fn with_admin_limit(mut self, rate: u32, burst: u32) -> Throttle {
+        self.admin = Some(TokenBucket::new(rate, burst));
+        self
+    }
+
+    pub fn mode(&self) -> ThrottleMode {
+        self.mode
+    }
+
+    pub fn set_drop_callback<F>(&mut self, on_drop: F) where F: FnMut(&Message) + Send + 'static {
+        self.on_drop = Some(Box::new(on_drop));
+    }
+
+    //admit gives msg back when it can be sent now, None when it was queued or dropped and an error when the
+    //Reject mode refuses it. Queued messages keep their order, a message behind them is queued in every mode.
+    pub fn admit(&mut self, msg: Message, admin: bool, now: Tm) -> Result<Option<Message>, FixError> {
+        if admin {
+            if self.admin_queue.is_empty() && acquire(&mut self.admin, now) {
+                return Ok(Some(msg));
+            }
+            self.admin_queue.push_back(msg);
+            return Ok(None);
+        }
+
+        if !self.app_queue.is_empty() {
+            self.app_queue.push_back(msg);
+            return Ok(None);
+        }
+        if acquire(&mut self.app, now) {
+            return Ok(Some(msg));
+        }
+        match self.mode {
+            ThrottleMode::Queue => self.app_queue.push_back(msg),
+            ThrottleMode::Reject => return Err(FixError::SessionError("Message rate limit exceeded".to_string())),
+            ThrottleMode::Drop => if let Some(ref mut on_drop) = self.on_drop {
+                on_drop(&msg);
+            }
+        }
+        Ok(None)
+    }
+
+    //hold queues an application message whatever the limits, the session holds them this way while it is not
+    //logged on
+    pub fn hold(&mut self, msg: Message) {
+        self.app_queue.push_back(msg);
+    }
+
+    //release takes the queued messages the limits let through by now, admin messages first. Application
+    //messages stay queued unless app is set, the session only lets them out while logged on.
+    pub fn release(&mut self, now: Tm, app: bool) -> Vec<Message> {
+        let mut released = vec![];
+        while !self.admin_queue.is_empty() && acquire(&mut self.admin, now) {
+            released.extend(self.admin_queue.pop_front());
+        }
+        while app && !self.app_queue.is_empty() && acquire(&mut self.app, now) {
+            released.extend(self.app_queue.pop_front());
+        }
+        released
+    }
+
+    //next_release is how long until release has something to give, None when nothing can be released
+    pub fn next_release(&mut self, now: Tm, app: bool) -> Option<Duration> {
+        let mut next = None;
+        if !self.admin_queue.is_empty() {
+            next = Some(wait(&mut self.admin, now));
+        }
+        if app && !self.app_queue.is_empty() {
+            let app = wait(&mut self.app, now);
+            next = Some(next.map_or(app, |admin: Duration| admin.min(app)));
+        }
+        next
+    }
+
+    pub fn queued(&self) -> usize {
+        self.app_queue.len() + self.admin_queue.len()
+    }
+
+    //clear_admin discards the queued admin messages, they are stale once the connection is gone
+    pub fn clear_admin(&mut self) {
+        self.admin_queue.clear();
+    }
+}
+
+fn bucket(settings: &SessionSettings, rate_setting: &str, burst_setting: &str) -> Result<Option<TokenBucket>, FixError> {
+    if !settings.has(rate_setting) {
+        return Ok(None);
+    }
+    let rate = settings.int_setting(rate_setting)?;
+    let burst = settings.int_setting_or(burst_setting, rate)?;
+    if rate <= 0 || burst <= 0 {
+        return Err(FixError::ConfigError(format!("{} and {} must be positive", rate_setting, burst_setting)));
+    }
+    Ok(Some(TokenBucket::new(rate as u32, burst as u32)))
+}
+
+fn acquire(bucket: &mut Option<TokenBucket>, now: Tm) -> bool {
+    match *bucket {
+        Some(ref mut bucket) => bucket.try_acquire(now),
+        None => true
+    }
+}
+
+fn wait(bucket: &mut Option<TokenBucket>, now: Tm) -> Duration {
+    match *bucket {
+        Some(ref mut bucket) => bucket.wait(now),
+        None => Duration::zero()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tag::*;
+
+    fn message(msg_type: &str) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_string(Tags::MsgType.to_num(), msg_type);
+        msg
+    }
+
+    #[test]
+    fn token_bucket_test() {
+        let now = now_utc();
+        let mut bucket = TokenBucket::new(10, 2);
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+        assert_eq!(Duration::milliseconds(100), bucket.wait(now));
+
+        assert!(!bucket.try_acquire(now + Duration::milliseconds(50)));
+        assert!(bucket.try_acquire(now + Duration::milliseconds(100)));
+        //an idle bucket refills up to the burst only
+        let later = now + Duration::seconds(10);
+        assert!(bucket.try_acquire(later) && bucket.try_acquire(later));
+        assert!(!bucket.try_acquire(later));
+    }
+
+    #[test]
+    fn queue_test() {
+        let now = now_utc();
+        let mut throttle = Throttle::new(ThrottleMode::Queue).with_app_limit(1, 1).with_admin_limit(1, 1);
+        assert!(throttle.admit(message("D"), false, now).unwrap().is_some());
+        assert!(throttle.admit(message("F"), false, now).unwrap().is_none());
+        assert!(throttle.admit(message("0"), true, now).unwrap().is_some());
+        assert!(throttle.admit(message("1"), true, now).unwrap().is_none());
+        assert_eq!(2, throttle.queued());
+        assert!(throttle.release(now, true).is_empty());
+        assert_eq!(Some(Duration::seconds(1)), throttle.next_release(now, true));
+
+        let released = throttle.release(now + Duration::seconds(1), true);
+        let msg_types: Vec<String> = released.iter().map(|msg| msg.header.get_string(Tags::MsgType.to_num()).unwrap()).collect();
+        assert_eq!(vec!["1", "F"], msg_types);
+        assert_eq!(None, throttle.next_release(now, true));
+    }
+
+    #[test]
+    fn hold_app_test() {
+        let now = now_utc();
+        let mut throttle = Throttle::new(ThrottleMode::Queue).with_app_limit(1, 1);
+        throttle.admit(message("D"), false, now).unwrap();
+        assert!(throttle.admit(message("F"), false, now).unwrap().is_none());
+
+        let later = now + Duration::seconds(1);
+        assert_eq!(None, throttle.next_release(later, false));
+        assert!(throttle.release(later, false).is_empty());
+        assert_eq!(1, throttle.queued());
+        assert_eq!(Some(Duration::zero()), throttle.next_release(later, true));
+        assert_eq!(1, throttle.release(later, true).len());
+
+        let mut throttle = Throttle::new(ThrottleMode::Reject);
+        throttle.hold(message("D"));
+        assert!(throttle.admit(message("F"), false, now).unwrap().is_none());
+        assert_eq!(2, throttle.release(now, true).len());
+    }
+
+    #[test]
+    fn admin_never_starved_test() {
+        let now = now_utc();
+        let mut throttle = Throttle::new(ThrottleMode::Reject).with_app_limit(1, 1);
+        assert!(throttle.admit(message("D"), false, now).unwrap().is_some());
+        assert!(throttle.admit(message("D"), false, now).is_err());
+        for _ in 0..10 {
+            assert!(throttle.admit(message("0"), true, now).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn drop_test() {
+        let now = now_utc();
+        let dropped = Arc::new(Mutex::new(0));
+        let counter = dropped.clone();
+        let mut throttle = Throttle::new(ThrottleMode::Drop).with_app_limit(1, 1);
+        throttle.set_drop_callback(move |_: &Message| *counter.lock().unwrap() += 1);
+        assert!(throttle.admit(message("D"), false, now).unwrap().is_some());
+        assert!(throttle.admit(message("D"), false, now).unwrap().is_none());
+        assert_eq!(1, *dropped.lock().unwrap());
+        assert_eq!(0, throttle.queued());
+    }
+
+    #[test]
+    fn from_settings_test() {
+        let mut settings = SessionSettings::new();
+        let throttle = Throttle::from_settings(&settings).unwrap();
+        assert!(throttle.app.is_none() && throttle.admin.is_none());
+        assert_eq!(ThrottleMode::Queue, throttle.mode());
+
+        settings.set(THROTTLE_MESSAGES_PER_SECOND, "50");
+        settings.set(THROTTLE_ADMIN_MESSAGES_PER_SECOND, "5");
+        settings.set(THROTTLE_ADMIN_BURST, "10");
+        settings.set(THROTTLE_MODE, "Drop");
+        let throttle = Throttle::from_settings(&settings).unwrap();
+        assert_eq!(50.0, throttle.app.as_ref().unwrap().burst);
+        assert_eq!(10.0, throttle.admin.as_ref().unwrap().burst);
+        assert_eq!(ThrottleMode::Drop, throttle.mode());
+
+        settings.set(THROTTLE_MODE, "Block");
+        assert!(Throttle::from_settings(&settings).is_err());
+        settings.set(THROTTLE_MODE, "Reject");
+        settings.set(THROTTLE_BURST, "0");
+        assert!(Throttle::from_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn settings_file_test() {
+        let text = "[DEFAULT]\nThrottleMessagesPerSecond=20\nThrottleMode=Reject\n\
+            [SESSION]\nThrottleBurst=40\n\
+            [SESSION]\nThrottleMode=Queue\nThrottleAdminMessagesPerSecond=5\n";
+        let sessions = SessionSettings::parse(text).unwrap();
+        let throttle = Throttle::from_settings(&sessions[0]).unwrap();
+        assert_eq!(40.0, throttle.app.as_ref().unwrap().burst);
+        assert!(throttle.admin.is_none());
+        assert_eq!(ThrottleMode::Reject, throttle.mode());
+
+        let throttle = Throttle::from_settings(&sessions[1]).unwrap();
+        assert_eq!(20.0, throttle.app.as_ref().unwrap().burst);
+        assert_eq!(5.0, throttle.admin.as_ref().unwrap().burst);
+        assert_eq!(ThrottleMode::Queue, throttle.mode());
+    }
+}
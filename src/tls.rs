@@ -0,0 +1,282 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::sync::Arc;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned, SupportedProtocolVersion};
+use rustls::crypto::CryptoProvider;
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::version::{TLS12, TLS13};
+use error::FixError;
+use settings::*;
+
+//TLS on top of any byte stream, the transport module wraps its sockets with connect or accept when SocketUseSSL
+//is set. Only TLS 1.2 and 1.3 are offered.
+
+fn config_error<E: ::std::fmt::Debug>(setting: &str, err: E) -> FixError {
+    FixError::ConfigError(format!("{}: {:?}", setting, err))
+}
+
+//use_tls tells whether the session asks for TLS with SocketUseSSL
+pub fn use_tls(settings: &SessionSettings) -> Result<bool, FixError> {
+    settings.bool_setting_or(SOCKET_USE_SSL, false)
+}
+
+fn open(settings: &SessionSettings, setting: &str) -> Result<BufReader<File>, FixError> {
+    let path = settings.setting(setting)?;
+    File::open(path).map(BufReader::new).map_err(|err| FixError::ConfigError(format!("{} {}: {}", setting, path, err)))
+}
+
+fn load_certificates(settings: &SessionSettings, setting: &str) -> Result<Vec<CertificateDer<'static>>, FixError> {
+    let certificates = rustls_pemfile::certs(&mut open(settings, setting)?).collect::<Result<Vec<_>, _>>()
+        .map_err(|err| config_error(setting, err))?;
+    if certificates.is_empty() {
+        return Err(FixError::ConfigError(format!("{} holds no certificate", setting)));
+    }
+    Ok(certificates)
+}
+
+fn load_private_key(settings: &SessionSettings) -> Result<PrivateKeyDer<'static>, FixError> {
+    rustls_pemfile::private_key(&mut open(settings, SOCKET_PRIVATE_KEY_FILE)?)
+        .map_err(|err| config_error(SOCKET_PRIVATE_KEY_FILE, err))?
+        .ok_or_else(|| FixError::ConfigError(format!("{} holds no private key", SOCKET_PRIVATE_KEY_FILE)))
+}
+
+fn load_roots(settings: &SessionSettings) -> Result<RootCertStore, FixError> {
+    let mut roots = RootCertStore::empty();
+    for certificate in load_certificates(settings, SOCKET_CA_FILE)? {
+        roots.add(certificate).map_err(|err| config_error(SOCKET_CA_FILE, err))?;
+    }
+    Ok(roots)
+}
+
+//protocol_versions follows SocketMinimumTLSVersion, TLS12 by default
+fn protocol_versions(settings: &SessionSettings) -> Result<Vec<&'static SupportedProtocolVersion>, FixError> {
+    match settings.setting(SOCKET_MINIMUM_TLS_VERSION).unwrap_or("TLS12") {
+        "TLS12" => Ok(vec![&TLS13, &TLS12]),
+        "TLS13" => Ok(vec![&TLS13]),
+        version => Err(FixError::ConfigError(format!("{} is invalid for {}, TLS12 or TLS13 expected", version, SOCKET_MINIMUM_TLS_VERSION)))
+    }
+}
+
+//provider keeps the cipher suites listed in SocketCipherSuites, named as in the IANA registry, e.g.
+//TLS13_AES_256_GCM_SHA384 or TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+fn provider(settings: &SessionSettings) -> Result<Arc<CryptoProvider>, FixError> {
+    let mut provider = default_provider();
+    if let Ok(names) = settings.setting(SOCKET_CIPHER_SUITES) {
+        let mut cipher_suites = vec![];
+        for name in names.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+            match provider.cipher_suites.iter().find(|suite| format!("{:?}", suite.suite()) == name) {
+                Some(suite) => cipher_suites.push(*suite),
+                None => return Err(FixError::ConfigError(format!("{} is not a supported cipher suite for {}", name, SOCKET_CIPHER_SUITES)))
+            }
+        }
+        provider.cipher_suites = cipher_suites;
+    }
+    Ok(Arc::new(provider))
+}
+
+//client_config is the initiator side: the counterparty is checked against SocketCAFile and, when
+//SocketCertificateFile is set, our certificate and SocketPrivateKeyFile are presented for mutual authentication
+pub fn client_config(settings: &SessionSettings) -> Result<Arc<ClientConfig>, FixError> {
+    let builder = ClientConfig::builder_with_provider(provider(settings)?)
+        .with_protocol_versions(&protocol_versions(settings)?)
+        .map_err(|err| config_error(SOCKET_CIPHER_SUITES, err))?
+        .with_root_certificates(load_roots(settings)?);
+
+    let config = if settings.has(SOCKET_CERTIFICATE_FILE) {
+        builder.with_client_auth_cert(load_certificates(settings, SOCKET_CERTIFICATE_FILE)?, load_private_key(settings)?)
+            .map_err(|err| config_error(SOCKET_PRIVATE_KEY_FILE, err))?
+    } else {
+        builder.with_no_client_auth()
+    };
+    Ok(Arc::new(config))
+}
+
+//server_config is the acceptor side, SocketCAFile makes client certificates signed by one of its authorities
+//mandatory
+pub fn server_config(settings: &SessionSettings) -> Result<Arc<ServerConfig>, FixError> {
+    let provider = provider(settings)?;
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&protocol_versions(settings)?)
+        .map_err(|err| config_error(SOCKET_CIPHER_SUITES, err))?;
+
+    let builder = if settings.has(SOCKET_CA_FILE) {
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(load_roots(settings)?), provider).build()
+            .map_err(|err| config_error(SOCKET_CA_FILE, err))?;
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+    let config = builder.with_single_cert(load_certificates(settings, SOCKET_CERTIFICATE_FILE)?, load_private_key(settings)?)
+        .map_err(|err| config_error(SOCKET_PRIVATE_KEY_FILE, err))?;
+    Ok(Arc::new(config))
+}
+
+//server_name is the name sent with SNI and matched against the certificate of the acceptor, SocketServerName or
+//else the host connected to
+pub fn server_name(settings: &SessionSettings, host: &str) -> Result<ServerName<'static>, FixError> {
+    let name = settings.setting(SOCKET_SERVER_NAME).unwrap_or(host).to_string();
+    ServerName::try_from(name).map_err(|err| config_error(SOCKET_SERVER_NAME, err))
+}
+
+//connect runs the client handshake over stream, a failed handshake is reported here rather than on the first
+//message
+pub fn connect<S: Read + Write>(config: Arc<ClientConfig>, server_name: ServerName<'static>, mut stream: S) -> Result<StreamOwned<ClientConnection, S>, FixError> {
+    let mut connection = ClientConnection::new(config, server_name).map_err(|err| FixError::IoError(::std::io::Error::other(err)))?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut stream).map_err(FixError::IoError)?;
+    }
+    Ok(StreamOwned::new(connection, stream))
+}
+
+//accept runs the server handshake over a stream accepted by the acceptor
+pub fn accept<S: Read + Write>(config: Arc<ServerConfig>, mut stream: S) -> Result<StreamOwned<ServerConnection, S>, FixError> {
+    let mut connection = ServerConnection::new(config).map_err(|err| FixError::IoError(::std::io::Error::other(err)))?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut stream).map_err(FixError::IoError)?;
+    }
+    Ok(StreamOwned::new(connection, stream))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use std::{env, fs, process, thread};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+    use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+
+    //Pki is a self-signed authority with a server and a client certificate, written as PEM files. The transport
+    //tests use it too.
+    pub struct Pki {
+        dir: PathBuf
+    }
+
+    impl Pki {
+
+        pub fn new(test: &str) -> Pki {
+            let dir = env::temp_dir().join(format!("quickfix-tls-{}-{}", test, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+
+            let ca_key = KeyPair::generate().unwrap();
+            let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            ca_params.distinguished_name.push(DnType::CommonName, "QuickFIX test CA");
+            let ca = ca_params.self_signed(&ca_key).unwrap();
+            fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+
+            for (name, purpose) in vec![("server", ExtendedKeyUsagePurpose::ServerAuth), ("client", ExtendedKeyUsagePurpose::ClientAuth)] {
+                let key = KeyPair::generate().unwrap();
+                let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+                params.extended_key_usages = vec![purpose];
+                let certificate = params.signed_by(&key, &ca, &ca_key).unwrap();
+                fs::write(dir.join(format!("{}.pem", name)), certificate.pem()).unwrap();
+                fs::write(dir.join(format!("{}.key", name)), key.serialize_pem()).unwrap();
+            }
+            Pki{dir: dir}
+        }
+
+        pub fn path(&self, file: &str) -> String {
+            self.dir.join(file).to_str().unwrap().to_string()
+        }
+
+        pub fn acceptor_settings(&self, mutual: bool) -> SessionSettings {
+            let mut settings = SessionSettings::new();
+            settings.set(SOCKET_USE_SSL, "Y");
+            settings.set(SOCKET_CERTIFICATE_FILE, &self.path("server.pem"));
+            settings.set(SOCKET_PRIVATE_KEY_FILE, &self.path("server.key"));
+            if mutual {
+                settings.set(SOCKET_CA_FILE, &self.path("ca.pem"));
+            }
+            settings
+        }
+
+        pub fn initiator_settings(&self, mutual: bool) -> SessionSettings {
+            let mut settings = SessionSettings::new();
+            settings.set(SOCKET_USE_SSL, "Y");
+            settings.set(SOCKET_CA_FILE, &self.path("ca.pem"));
+            settings.set(SOCKET_SERVER_NAME, "localhost");
+            if mutual {
+                settings.set(SOCKET_CERTIFICATE_FILE, &self.path("client.pem"));
+                settings.set(SOCKET_PRIVATE_KEY_FILE, &self.path("client.key"));
+            }
+            settings
+        }
+    }
+
+    //exchange sends a message from the initiator and reads the echo of the acceptor
+    fn exchange(acceptor: &SessionSettings, initiator: &SessionSettings) -> Result<Vec<u8>, FixError> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server_config = server_config(acceptor)?;
+        let server = thread::spawn(move || -> Result<(), FixError> {
+            let (socket, _) = listener.accept().map_err(FixError::IoError)?;
+            let mut stream = accept(server_config, socket)?;
+            let mut buf = [0u8; 64];
+            let n = stream.read(&mut buf).map_err(FixError::IoError)?;
+            stream.write_all(&buf[..n]).map_err(FixError::IoError)?;
+            stream.flush().map_err(FixError::IoError)
+        });
+
+        let socket = TcpStream::connect(address).unwrap();
+        let result = connect(client_config(initiator)?, server_name(initiator, "127.0.0.1")?, socket).and_then(|mut stream| {
+            stream.write_all(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01").map_err(FixError::IoError)?;
+            let mut buf = [0u8; 64];
+            let n = stream.read(&mut buf).map_err(FixError::IoError)?;
+            Ok(buf[..n].to_vec())
+        });
+        let _ = server.join();
+        result
+    }
+
+    #[test]
+    fn tls_test() {
+        let pki = Pki::new("server");
+        assert!(use_tls(&pki.acceptor_settings(false)).unwrap());
+        assert!(!use_tls(&SessionSettings::new()).unwrap());
+
+        let echo = exchange(&pki.acceptor_settings(false), &pki.initiator_settings(false)).unwrap();
+        assert_eq!(&b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01"[..], echo.as_slice());
+    }
+
+    #[test]
+    fn mutual_tls_test() {
+        let pki = Pki::new("mutual");
+        assert!(exchange(&pki.acceptor_settings(true), &pki.initiator_settings(true)).is_ok());
+        //the acceptor requires a client certificate
+        assert!(exchange(&pki.acceptor_settings(true), &pki.initiator_settings(false)).is_err());
+    }
+
+    #[test]
+    fn server_name_test() {
+        let pki = Pki::new("sni");
+        let mut initiator = pki.initiator_settings(false);
+        initiator.set(SOCKET_SERVER_NAME, "venue.example.com");
+        assert!(exchange(&pki.acceptor_settings(false), &initiator).is_err());
+    }
+
+    #[test]
+    fn restrictions_test() {
+        let pki = Pki::new("restrictions");
+        let mut acceptor = pki.acceptor_settings(false);
+        acceptor.set(SOCKET_MINIMUM_TLS_VERSION, "TLS13");
+        let mut initiator = pki.initiator_settings(false);
+        initiator.set(SOCKET_CIPHER_SUITES, "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256");
+        assert!(exchange(&pki.acceptor_settings(false), &initiator).is_ok());
+        assert!(exchange(&acceptor, &initiator).is_err());
+
+        initiator.set(SOCKET_CIPHER_SUITES, "TLS13_AES_256_GCM_SHA384, TLS13_CHACHA20_POLY1305_SHA256");
+        assert!(exchange(&acceptor, &initiator).is_ok());
+
+        initiator.set(SOCKET_CIPHER_SUITES, "TLS_RSA_WITH_RC4_128_MD5");
+        assert!(client_config(&initiator).is_err());
+        acceptor.set(SOCKET_MINIMUM_TLS_VERSION, "TLS10");
+        assert!(server_config(&acceptor).is_err());
+        acceptor.set(SOCKET_MINIMUM_TLS_VERSION, "TLS12");
+        acceptor.set(SOCKET_PRIVATE_KEY_FILE, &pki.path("missing.key"));
+        assert!(server_config(&acceptor).is_err());
+    }
+}
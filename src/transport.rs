@@ -0,0 +1,1037 @@
+use std::io::{self, Read, Write};
+#[cfg(feature = "async")]
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration as StdDuration;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+#[cfg(feature = "tls")]
+use rustls::{ClientConnection, ServerConnection, StreamOwned};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+#[cfg(feature = "async")]
+use tokio::time::{Instant, Sleep};
+use time::*;
+use message::*;
+use message_router::*;
+use session::*;
+use session_handle::*;
+use dynamic_session::DynamicSessions;
+use settings::*;
+#[cfg(feature = "tls")]
+use tls;
+use error::FixError;
+
+//The transports carry a session over a socket, on std::net threads or on tokio with the async feature. Both
+//wrap their sockets in TLS when SocketUseSSL is set, with the certificates the tls module loads from the settings.
+
+//POLL_INTERVAL bounds how long a transport waits for the counterparty before it sends what other threads queued
+//on the session, e.g. a Logout from SessionHandle::logout
+const POLL_INTERVAL: u64 = 100;
+
+//CHECKSUM_LEN is the length of the CheckSum field closing every message, 10=nnn and SOH
+const CHECKSUM_LEN: usize = 7;
+
+//DEFAULT_MAX_MESSAGE_SIZE bounds the messages a framer buffers when MaxMessageSize is not set
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+//MessageFramer cuts the bytes read from a socket into messages following their BodyLength
+pub struct MessageFramer {
+    buf: Vec<u8>,
+    max_message_size: usize
+}
+
+impl MessageFramer {
+
+    pub fn new() -> MessageFramer {
+        MessageFramer::with_max_message_size(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    //with_max_message_size creates a framer refusing messages longer than max_message_size bytes, whatever
+    //their BodyLength announces
+    pub fn with_max_message_size(max_message_size: usize) -> MessageFramer {
+        MessageFramer{buf: vec![], max_message_size: max_message_size}
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    //next_message takes the next complete message, None until all of its bytes arrived. Bytes before a
+    //BeginString are dropped.
+    pub fn next_message(&mut self) -> Result<Option<Message>, FixError> {
+        match self.buf.windows(2).position(|start| start == b"8=") {
+            Some(start) => { self.buf.drain(..start); },
+            None => {
+                let keep = if self.buf.last() == Some(&b'8') { 1 } else { 0 };
+                let len = self.buf.len();
+                self.buf.drain(..len - keep);
+                return Ok(None);
+            }
+        }
+        let begin_string_end = match self.buf.iter().position(|b| *b == b'\x01') {
+            Some(end) => end,
+            None => return self.incomplete()
+        };
+        let body_length_end = match self.buf[begin_string_end + 1..].iter().position(|b| *b == b'\x01') {
+            Some(end) => begin_string_end + 1 + end,
+            None => return self.incomplete()
+        };
+        let body_length = &self.buf[begin_string_end + 1..body_length_end];
+        if !body_length.starts_with(b"9=") {
+            return Err(FixError::ParseMessageError("BodyLength expected after BeginString".to_string()));
+        }
+        let body_length = String::from_utf8_lossy(&body_length[2..]).parse::<usize>()
+            .map_err(|_| FixError::ParseMessageError("invalid BodyLength".to_string()))?;
+        let end = match (body_length_end + 1 + CHECKSUM_LEN).checked_add(body_length) {
+            Some(end) if end <= self.max_message_size => end,
+            _ => return Err(self.too_large())
+        };
+        if self.buf.len() < end {
+            return Ok(None);
+        }
+        let msg = Message::parse(&self.buf[..end]);
+        self.buf.drain(..end);
+        msg.map(Some)
+    }
+
+    //incomplete waits for the rest of a header, which cannot grow past the maximum message size
+    fn incomplete(&self) -> Result<Option<Message>, FixError> {
+        if self.buf.len() > self.max_message_size {
+            return Err(self.too_large());
+        }
+        Ok(None)
+    }
+
+    fn too_large(&self) -> FixError {
+        FixError::ParseMessageError(format!("message larger than the maximum message size {}", self.max_message_size))
+    }
+}
+
+impl Default for MessageFramer {
+    fn default() -> MessageFramer {
+        MessageFramer::new()
+    }
+}
+
+//Driver is the part of a transport that talks to the session, whatever the socket
+struct Driver<'r> {
+    handle: SessionHandle,
+    router: &'r mut MessageRouter,
+    framer: MessageFramer,
+    //started is set once the session left Latent, it is done when it gets back there
+    started: bool
+}
+
+impl<'r> Driver<'r> {
+
+    fn new(handle: &SessionHandle, router: &'r mut MessageRouter, logon: bool) -> Result<Driver<'r>, FixError> {
+        let mut session = handle.lock();
+        if logon {
+            session.logon(now_utc())?;
+        }
+        let framer = MessageFramer::with_max_message_size(session.max_message_size().unwrap_or(DEFAULT_MAX_MESSAGE_SIZE));
+        Ok(Driver{handle: handle.clone(), router: router, framer: framer, started: false})
+    }
+
+    //receive hands the messages completed by bytes to the session
+    fn receive(&mut self, bytes: &[u8]) -> Result<(), FixError> {
+        self.framer.push(bytes);
+        while let Some(msg) = self.framer.next_message()? {
+            self.deliver(&msg)?;
+        }
+        Ok(())
+    }
+
+    //deliver hands msg to the session and, once accepted, to the router. Rejected messages only cost the Reject
+    //the session queued.
+    fn deliver(&mut self, msg: &Message) -> Result<(), FixError> {
+        let now = now_utc();
+        let mut session = self.handle.lock();
+        let router = &mut *self.router;
+        match session.incoming(msg, now).and_then(|_| session.from_app(router, msg, now)) {
+            Ok(()) | Err(FixError::RejectError(_)) => Ok(()),
+            Err(err) => Err(err)
+        }
+    }
+
+    //accept hands the session the Logon that selected it, then the bytes framed after it
+    fn accept(&mut self, logon: &Message, framer: MessageFramer) -> Result<(), FixError> {
+        self.deliver(logon)?;
+        self.receive(&framer.buf)
+    }
+
+    //outgoing releases the throttled messages that are due and gives the bytes of everything queued
+    fn outgoing(&mut self) -> Result<Vec<u8>, FixError> {
+        let mut session = self.handle.lock();
+        session.release_throttled(now_utc())?;
+        if session.state() != SessionState::Latent {
+            self.started = true;
+        }
+        let mut bytes = vec![];
+        for msg in session.take_outgoing() {
+            bytes.extend(msg.build());
+        }
+        Ok(bytes)
+    }
+
+    fn is_done(&self) -> bool {
+        self.started && self.handle.lock().state() == SessionState::Latent
+    }
+
+    //timeout is how long to wait for the counterparty, shorter when the throttle releases a message sooner
+    fn timeout(&self) -> StdDuration {
+        let poll_interval = StdDuration::from_millis(POLL_INTERVAL);
+        match self.handle.lock().next_release(now_utc()).and_then(|next| next.to_std().ok()) {
+            Some(next) => next.max(StdDuration::from_millis(1)).min(poll_interval),
+            None => poll_interval
+        }
+    }
+
+    fn disconnected(&self) -> Result<(), FixError> {
+        self.handle.lock().disconnected()
+    }
+}
+
+//Stream is a connected socket, wrapped in TLS when SocketUseSSL is set
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    TlsClient(Box<StreamOwned<ClientConnection, TcpStream>>),
+    #[cfg(feature = "tls")]
+    TlsServer(Box<StreamOwned<ServerConnection, TcpStream>>)
+}
+
+impl Stream {
+
+    fn socket(&self) -> &TcpStream {
+        match *self {
+            Stream::Tcp(ref socket) => socket,
+            #[cfg(feature = "tls")]
+            Stream::TlsClient(ref stream) => stream.get_ref(),
+            #[cfg(feature = "tls")]
+            Stream::TlsServer(ref stream) => stream.get_ref()
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut socket) => socket.read(buf),
+            #[cfg(feature = "tls")]
+            Stream::TlsClient(ref mut stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Stream::TlsServer(ref mut stream) => stream.read(buf)
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut socket) => socket.write(buf),
+            #[cfg(feature = "tls")]
+            Stream::TlsClient(ref mut stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Stream::TlsServer(ref mut stream) => stream.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Stream::Tcp(ref mut socket) => socket.flush(),
+            #[cfg(feature = "tls")]
+            Stream::TlsClient(ref mut stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Stream::TlsServer(ref mut stream) => stream.flush()
+        }
+    }
+}
+
+//use_tls reads SocketUseSSL, which can only be honoured with the tls feature
+fn use_tls(settings: &SessionSettings) -> Result<bool, FixError> {
+    let use_tls = settings.bool_setting_or(SOCKET_USE_SSL, false)?;
+    if use_tls && cfg!(not(feature = "tls")) {
+        return Err(FixError::ConfigError(format!("{} requires the tls feature", SOCKET_USE_SSL)));
+    }
+    Ok(use_tls)
+}
+
+fn connect_port(settings: &SessionSettings) -> Result<u16, FixError> {
+    let port = settings.int_setting(SOCKET_CONNECT_PORT)?;
+    if port <= 0 || port > u16::MAX as i32 {
+        return Err(FixError::ConfigError(format!("{} is not a valid {}", port, SOCKET_CONNECT_PORT)));
+    }
+    Ok(port as u16)
+}
+
+//bind opens the acceptor socket on SocketAcceptPort of every interface
+pub fn bind(settings: &SessionSettings) -> Result<TcpListener, FixError> {
+    let port = settings.int_setting(SOCKET_ACCEPT_PORT)?;
+    if port < 0 || port > u16::MAX as i32 {
+        return Err(FixError::ConfigError(format!("{} is not a valid {}", port, SOCKET_ACCEPT_PORT)));
+    }
+    TcpListener::bind(("0.0.0.0", port as u16)).map_err(FixError::IoError)
+}
+
+//connect opens the initiator socket to SocketConnectHost and SocketConnectPort, the TLS handshake is done here
+pub fn connect(settings: &SessionSettings) -> Result<Stream, FixError> {
+    let host = settings.setting(SOCKET_CONNECT_HOST)?;
+    let socket = TcpStream::connect((host, connect_port(settings)?)).map_err(FixError::IoError)?;
+    if use_tls(settings)? {
+        #[cfg(feature = "tls")]
+        return Ok(Stream::TlsClient(Box::new(tls::connect(tls::client_config(settings)?, tls::server_name(settings, host)?, socket)?)));
+    }
+    Ok(Stream::Tcp(socket))
+}
+
+//accept takes a socket the acceptor accepted and answers the TLS handshake
+pub fn accept(settings: &SessionSettings, socket: TcpStream) -> Result<Stream, FixError> {
+    if use_tls(settings)? {
+        #[cfg(feature = "tls")]
+        return Ok(Stream::TlsServer(Box::new(tls::accept(tls::server_config(settings)?, socket)?)));
+    }
+    Ok(Stream::Tcp(socket))
+}
+
+//run_initiator logs the session on over stream and runs it, see run_acceptor
+pub fn run_initiator(handle: &SessionHandle, router: &mut MessageRouter, stream: &mut Stream) -> Result<(), FixError> {
+    run(Driver::new(handle, router, true)?, stream)
+}
+
+//run_acceptor runs the session over stream until the counterparty disconnects or the session is logged out.
+//The session is told it is disconnected either way. It learns the address of the counterparty first, for the
+//authenticator and the logon rate limiter.
+pub fn run_acceptor(handle: &SessionHandle, router: &mut MessageRouter, stream: &mut Stream) -> Result<(), FixError> {
+    set_remote_address(handle, stream.socket().peer_addr())?;
+    run(Driver::new(handle, router, false)?, stream)
+}
+
+//run_dynamic_acceptor runs the session of sessions the first message of the connection, a Logon, is for. The
+//sessions created from a template stay in sessions for the next connections, the lock is only held to find the
+//session.
+pub fn run_dynamic_acceptor(sessions: &Mutex<DynamicSessions>, router: &mut MessageRouter, stream: &mut Stream) -> Result<(), FixError> {
+    let mut framer = MessageFramer::new();
+    let mut buf = [0u8; 4096];
+    let logon = loop {
+        if let Some(msg) = framer.next_message()? {
+            break msg;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) => return Err(disconnected_before_logon()),
+            Ok(n) => framer.push(&buf[..n]),
+            Err(err) => return Err(FixError::IoError(err))
+        }
+    };
+    let handle = session_for_logon(sessions, &logon, stream.socket().peer_addr())?;
+    let mut driver = Driver::new(&handle, router, false)?;
+    let result = driver.accept(&logon, framer).and_then(|_| serve(&mut driver, stream));
+    driver.disconnected().and(result)
+}
+
+//remote_address is the IP of the counterparty, without the port so that the failed logons of every connection
+//from a host count together
+fn remote_address(peer_addr: io::Result<SocketAddr>) -> Result<String, FixError> {
+    peer_addr.map(|peer_addr| peer_addr.ip().to_string()).map_err(FixError::IoError)
+}
+
+fn set_remote_address(handle: &SessionHandle, peer_addr: io::Result<SocketAddr>) -> Result<(), FixError> {
+    handle.lock().set_remote_address(Some(remote_address(peer_addr)?));
+    Ok(())
+}
+
+fn session_for_logon(sessions: &Mutex<DynamicSessions>, logon: &Message, peer_addr: io::Result<SocketAddr>) -> Result<SessionHandle, FixError> {
+    let remote_address = remote_address(peer_addr)?;
+    let mut sessions = match sessions.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner()
+    };
+    sessions.session_for_logon(logon, Some(&remote_address))
+}
+
+fn disconnected_before_logon() -> FixError {
+    FixError::SessionError("Disconnected before Logon".to_string())
+}
+
+fn run(mut driver: Driver, stream: &mut Stream) -> Result<(), FixError> {
+    let result = serve(&mut driver, stream);
+    driver.disconnected().and(result)
+}
+
+fn serve(driver: &mut Driver, stream: &mut Stream) -> Result<(), FixError> {
+    let mut buf = [0u8; 4096];
+    loop {
+        let bytes = driver.outgoing()?;
+        if !bytes.is_empty() {
+            stream.write_all(&bytes).and_then(|_| stream.flush()).map_err(FixError::IoError)?;
+        }
+        if driver.is_done() {
+            return Ok(());
+        }
+        stream.socket().set_read_timeout(Some(driver.timeout())).map_err(FixError::IoError)?;
+        match stream.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => driver.receive(&buf[..n])?,
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut => {},
+            Err(err) => return Err(FixError::IoError(err))
+        }
+    }
+}
+
+//AsyncStream is a tokio socket, wrapped in TLS when SocketUseSSL is set
+#[cfg(feature = "async")]
+pub enum AsyncStream {
+    Tcp(tokio::net::TcpStream),
+    #[cfg(feature = "tls")]
+    TlsClient(Box<tokio_rustls::client::TlsStream<tokio::net::TcpStream>>),
+    #[cfg(feature = "tls")]
+    TlsServer(Box<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>)
+}
+
+#[cfg(feature = "async")]
+impl AsyncStream {
+
+    fn socket(&self) -> &tokio::net::TcpStream {
+        match *self {
+            AsyncStream::Tcp(ref socket) => socket,
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsClient(ref stream) => stream.get_ref().0,
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsServer(ref stream) => stream.get_ref().0
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRead for AsyncStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf) -> Poll<io::Result<()>> {
+        match *self.get_mut() {
+            AsyncStream::Tcp(ref mut socket) => Pin::new(socket).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsClient(ref mut stream) => Pin::new(&mut **stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsServer(ref mut stream) => Pin::new(&mut **stream).poll_read(cx, buf)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncWrite for AsyncStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match *self.get_mut() {
+            AsyncStream::Tcp(ref mut socket) => Pin::new(socket).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsClient(ref mut stream) => Pin::new(&mut **stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsServer(ref mut stream) => Pin::new(&mut **stream).poll_write(cx, buf)
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match *self.get_mut() {
+            AsyncStream::Tcp(ref mut socket) => Pin::new(socket).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsClient(ref mut stream) => Pin::new(&mut **stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsServer(ref mut stream) => Pin::new(&mut **stream).poll_flush(cx)
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match *self.get_mut() {
+            AsyncStream::Tcp(ref mut socket) => Pin::new(socket).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsClient(ref mut stream) => Pin::new(&mut **stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            AsyncStream::TlsServer(ref mut stream) => Pin::new(&mut **stream).poll_shutdown(cx)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+type BoxedConnect = Pin<Box<dyn Future<Output = io::Result<tokio::net::TcpStream>> + Send>>;
+
+//AsyncConnect connects to SocketConnectHost and SocketConnectPort, then runs the TLS handshake
+#[cfg(feature = "async")]
+pub struct AsyncConnect {
+    socket: Option<BoxedConnect>,
+    #[cfg(feature = "tls")]
+    tls: Option<(tokio_rustls::TlsConnector, rustls::pki_types::ServerName<'static>)>,
+    #[cfg(feature = "tls")]
+    handshake: Option<tokio_rustls::Connect<tokio::net::TcpStream>>
+}
+
+//connect_async is connect on tokio, the settings are read before the future is polled
+#[cfg(feature = "async")]
+pub fn connect_async(settings: &SessionSettings) -> Result<AsyncConnect, FixError> {
+    let host = settings.setting(SOCKET_CONNECT_HOST)?.to_string();
+    let port = connect_port(settings)?;
+    #[cfg(feature = "tls")]
+    let tls = if use_tls(settings)? {
+        Some((tokio_rustls::TlsConnector::from(tls::client_config(settings)?), tls::server_name(settings, &host)?))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "tls"))]
+    use_tls(settings)?;
+    let socket: BoxedConnect = Box::pin(tokio::net::TcpStream::connect((host, port)));
+    #[cfg(feature = "tls")]
+    return Ok(AsyncConnect{socket: Some(socket), tls: tls, handshake: None});
+    #[cfg(not(feature = "tls"))]
+    Ok(AsyncConnect{socket: Some(socket)})
+}
+
+#[cfg(feature = "async")]
+impl Future for AsyncConnect {
+    type Output = Result<AsyncStream, FixError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<AsyncStream, FixError>> {
+        let this = self.get_mut();
+        if let Some(mut connecting) = this.socket.take() {
+            let socket = match connecting.as_mut().poll(cx) {
+                Poll::Ready(socket) => socket.map_err(FixError::IoError)?,
+                Poll::Pending => {
+                    this.socket = Some(connecting);
+                    return Poll::Pending;
+                }
+            };
+            #[cfg(feature = "tls")]
+            match this.tls.take() {
+                Some((connector, server_name)) => this.handshake = Some(connector.connect(server_name, socket)),
+                None => return Poll::Ready(Ok(AsyncStream::Tcp(socket)))
+            }
+            #[cfg(not(feature = "tls"))]
+            return Poll::Ready(Ok(AsyncStream::Tcp(socket)));
+        }
+        #[cfg(feature = "tls")]
+        {
+            if let Some(ref mut handshake) = this.handshake {
+                return match Pin::new(handshake).poll(cx) {
+                    Poll::Ready(stream) => Poll::Ready(stream.map(|stream| AsyncStream::TlsClient(Box::new(stream))).map_err(FixError::IoError)),
+                    Poll::Pending => Poll::Pending
+                };
+            }
+        }
+        Poll::Ready(Err(FixError::IoError(io::Error::other("connection polled after completion"))))
+    }
+}
+
+//AsyncAccept answers the TLS handshake on a socket the acceptor accepted
+#[cfg(feature = "async")]
+pub struct AsyncAccept {
+    socket: Option<tokio::net::TcpStream>,
+    #[cfg(feature = "tls")]
+    handshake: Option<tokio_rustls::Accept<tokio::net::TcpStream>>
+}
+
+//accept_async is accept on tokio
+#[cfg(feature = "async")]
+pub fn accept_async(settings: &SessionSettings, socket: tokio::net::TcpStream) -> Result<AsyncAccept, FixError> {
+    if use_tls(settings)? {
+        #[cfg(feature = "tls")]
+        return Ok(AsyncAccept{socket: None, handshake: Some(tokio_rustls::TlsAcceptor::from(tls::server_config(settings)?).accept(socket))});
+    }
+    #[cfg(feature = "tls")]
+    return Ok(AsyncAccept{socket: Some(socket), handshake: None});
+    #[cfg(not(feature = "tls"))]
+    Ok(AsyncAccept{socket: Some(socket)})
+}
+
+#[cfg(feature = "async")]
+impl Future for AsyncAccept {
+    type Output = Result<AsyncStream, FixError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<AsyncStream, FixError>> {
+        let this = self.get_mut();
+        if let Some(socket) = this.socket.take() {
+            return Poll::Ready(Ok(AsyncStream::Tcp(socket)));
+        }
+        #[cfg(feature = "tls")]
+        {
+            if let Some(ref mut handshake) = this.handshake {
+                return match Pin::new(handshake).poll(cx) {
+                    Poll::Ready(stream) => Poll::Ready(stream.map(|stream| AsyncStream::TlsServer(Box::new(stream))).map_err(FixError::IoError)),
+                    Poll::Pending => Poll::Pending
+                };
+            }
+        }
+        let _ = cx;
+        Poll::Ready(Err(FixError::IoError(io::Error::other("accept polled after completion"))))
+    }
+}
+
+//AsyncRun is run_initiator or run_acceptor on tokio
+#[cfg(feature = "async")]
+pub struct AsyncRun<'r, 's> {
+    driver: Driver<'r>,
+    stream: &'s mut AsyncStream,
+    //pending holds the bytes not written yet
+    pending: Vec<u8>,
+    written: usize,
+    //sleep is made on the first poll, inside the runtime
+    sleep: Option<Pin<Box<Sleep>>>,
+    done: bool
+}
+
+#[cfg(feature = "async")]
+pub fn run_initiator_async<'r, 's>(handle: &SessionHandle, router: &'r mut MessageRouter, stream: &'s mut AsyncStream) -> Result<AsyncRun<'r, 's>, FixError> {
+    Ok(AsyncRun::new(Driver::new(handle, router, true)?, stream))
+}
+
+#[cfg(feature = "async")]
+pub fn run_acceptor_async<'r, 's>(handle: &SessionHandle, router: &'r mut MessageRouter, stream: &'s mut AsyncStream) -> Result<AsyncRun<'r, 's>, FixError> {
+    set_remote_address(handle, stream.socket().peer_addr())?;
+    Ok(AsyncRun::new(Driver::new(handle, router, false)?, stream))
+}
+
+//AsyncDynamicRun is run_dynamic_acceptor on tokio
+#[cfg(feature = "async")]
+pub struct AsyncDynamicRun<'d, 'r, 's> {
+    sessions: &'d Mutex<DynamicSessions>,
+    //the router and the stream until the Logon arrives, they then belong to the run of its session
+    accepting: Option<(&'r mut MessageRouter, &'s mut AsyncStream)>,
+    framer: MessageFramer,
+    run: Option<AsyncRun<'r, 's>>
+}
+
+#[cfg(feature = "async")]
+pub fn run_dynamic_acceptor_async<'d, 'r, 's>(sessions: &'d Mutex<DynamicSessions>, router: &'r mut MessageRouter, stream: &'s mut AsyncStream) -> AsyncDynamicRun<'d, 'r, 's> {
+    AsyncDynamicRun{sessions: sessions, accepting: Some((router, stream)), framer: MessageFramer::new(), run: None}
+}
+
+#[cfg(feature = "async")]
+impl<'d, 'r, 's> AsyncDynamicRun<'d, 'r, 's> {
+
+    //accept reads the Logon and starts the run of its session
+    fn accept(&mut self, cx: &mut Context) -> Poll<Result<AsyncRun<'r, 's>, FixError>> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(logon) = self.framer.next_message()? {
+                let (router, stream) = match self.accepting.take() {
+                    Some(accepting) => accepting,
+                    None => return Poll::Ready(Err(FixError::SessionError("the session already ran".to_string())))
+                };
+                let handle = session_for_logon(self.sessions, &logon, stream.socket().peer_addr())?;
+                let mut driver = Driver::new(&handle, router, false)?;
+                let framer = mem::take(&mut self.framer);
+                if let Err(err) = driver.accept(&logon, framer) {
+                    return Poll::Ready(driver.disconnected().and(Err(err)));
+                }
+                return Poll::Ready(Ok(AsyncRun::new(driver, stream)));
+            }
+
+            let stream = match self.accepting {
+                Some((_, ref mut stream)) => stream,
+                None => return Poll::Ready(Err(FixError::SessionError("the session already ran".to_string())))
+            };
+            let mut read_buf = ReadBuf::new(&mut buf);
+            match Pin::new(&mut **stream).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => return Poll::Ready(Err(disconnected_before_logon())),
+                Poll::Ready(Ok(())) => self.framer.push(read_buf.filled()),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(FixError::IoError(err))),
+                Poll::Pending => return Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'d, 'r, 's> Future for AsyncDynamicRun<'d, 'r, 's> {
+    type Output = Result<(), FixError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), FixError>> {
+        let this = self.get_mut();
+        if this.run.is_none() {
+            match this.accept(cx) {
+                Poll::Ready(Ok(run)) => this.run = Some(run),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending
+            }
+        }
+        match this.run {
+            Some(ref mut run) => Pin::new(run).poll(cx),
+            None => Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, 's> AsyncRun<'r, 's> {
+
+    fn new(driver: Driver<'r>, stream: &'s mut AsyncStream) -> AsyncRun<'r, 's> {
+        AsyncRun{driver: driver, stream: stream, pending: vec![], written: 0, sleep: None, done: false}
+    }
+
+    fn serve(&mut self, cx: &mut Context) -> Poll<Result<(), FixError>> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if self.pending.is_empty() {
+                self.pending = self.driver.outgoing()?;
+                self.written = 0;
+            }
+            while self.written < self.pending.len() {
+                match Pin::new(&mut *self.stream).poll_write(cx, &self.pending[self.written..]) {
+                    Poll::Ready(Ok(0)) => return Poll::Ready(Err(FixError::IoError(io::ErrorKind::WriteZero.into()))),
+                    Poll::Ready(Ok(n)) => self.written += n,
+                    Poll::Ready(Err(err)) => return Poll::Ready(Err(FixError::IoError(err))),
+                    Poll::Pending => return Poll::Pending
+                }
+            }
+            self.pending.clear();
+            match Pin::new(&mut *self.stream).poll_flush(cx) {
+                Poll::Ready(Ok(())) => {},
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(FixError::IoError(err))),
+                Poll::Pending => return Poll::Pending
+            }
+            if self.driver.is_done() {
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut read_buf = ReadBuf::new(&mut buf);
+            match Pin::new(&mut *self.stream).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => return Poll::Ready(Ok(())),
+                Poll::Ready(Ok(())) => self.driver.receive(read_buf.filled())?,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(FixError::IoError(err))),
+                Poll::Pending => {
+                    let deadline = Instant::now() + self.driver.timeout();
+                    let sleep = self.sleep.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+                    sleep.as_mut().reset(deadline);
+                    if sleep.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, 's> Future for AsyncRun<'r, 's> {
+    type Output = Result<(), FixError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), FixError>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(Err(FixError::SessionError("the session already ran".to_string())));
+        }
+        match this.serve(cx) {
+            Poll::Ready(result) => {
+                this.done = true;
+                Poll::Ready(this.driver.disconnected().and(result))
+            },
+            Poll::Pending => Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Instant as StdInstant;
+    use session_id::SessionID;
+    use authentication::{Credentials, LogonRateLimiter};
+    use dynamic_session::{SessionTemplate, WILDCARD};
+    use store::MemoryStoreFactory;
+    use log::NullLogFactory;
+    use tag::Tags;
+    use begin_string::BEGIN_STRING_FIX42;
+    #[cfg(feature = "tls")]
+    use tls::test::Pki;
+
+    #[test]
+    fn framer_test() {
+        let logon = b"8=FIX.4.2\x019=5\x0135=A\x0110=178\x01";
+        let heartbeat = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01";
+        let mut framer = MessageFramer::new();
+
+        framer.push(b"garbage8");
+        assert!(framer.next_message().unwrap().is_none());
+        framer.push(&logon[1..20]);
+        assert!(framer.next_message().unwrap().is_none());
+        framer.push(&logon[20..]);
+        framer.push(heartbeat);
+        let msg = framer.next_message().unwrap().unwrap();
+        assert_eq!("A", msg.header.get_string(Tags::MsgType.to_num()).unwrap());
+        let msg = framer.next_message().unwrap().unwrap();
+        assert_eq!("0", msg.header.get_string(Tags::MsgType.to_num()).unwrap());
+        assert!(framer.next_message().unwrap().is_none());
+
+        framer.push(b"8=FIX.4.2\x0135=0\x01");
+        assert!(framer.next_message().is_err());
+        let mut framer = MessageFramer::new();
+        framer.push(b"8=FIX.4.2\x019=five\x01");
+        assert!(framer.next_message().is_err());
+    }
+
+    #[test]
+    fn framer_max_message_size_test() {
+        let mut framer = MessageFramer::new();
+        framer.push(b"8=FIX.4.2\x019=18446744073709551615\x01");
+        assert!(framer.next_message().is_err());
+
+        let mut framer = MessageFramer::with_max_message_size(32);
+        framer.push(b"8=FIX.4.2\x019=5\x0135=A\x0110=178\x01");
+        assert_eq!("A", framer.next_message().unwrap().unwrap().header.get_string(Tags::MsgType.to_num()).unwrap());
+        framer.push(b"8=FIX.4.2\x019=1000\x0135=A\x01");
+        match framer.next_message() {
+            Err(FixError::ParseMessageError(_)) => {},
+            _ => panic!("BodyLength over the maximum message size")
+        }
+
+        let mut framer = MessageFramer::with_max_message_size(32);
+        framer.push(b"8=FIX.4.2");
+        framer.push(&[b'0'; 32]);
+        assert!(framer.next_message().is_err());
+    }
+
+    #[test]
+    fn settings_test() {
+        let mut settings = SessionSettings::new();
+        settings.set(SOCKET_USE_SSL, "Y");
+        assert_eq!(cfg!(feature = "tls"), use_tls(&settings).is_ok());
+
+        let mut settings = SessionSettings::new();
+        assert!(connect(&settings).is_err());
+        settings.set(SOCKET_CONNECT_HOST, "127.0.0.1");
+        settings.set(SOCKET_CONNECT_PORT, "70000");
+        assert!(connect(&settings).is_err());
+        settings.set(SOCKET_ACCEPT_PORT, "-1");
+        assert!(bind(&settings).is_err());
+    }
+
+    //Sessions is an acceptor and an initiator session, the acceptor counts the orders it routes. A dynamic
+    //acceptor is created by its template on Logon instead.
+    struct Sessions {
+        acceptor: SessionHandle,
+        initiator: SessionHandle,
+        acceptor_settings: SessionSettings,
+        initiator_settings: SessionSettings,
+        dynamic: Option<Arc<Mutex<DynamicSessions>>>,
+        orders: Arc<AtomicUsize>,
+        listener: TcpListener
+    }
+
+    impl Sessions {
+
+        fn new(mut acceptor_settings: SessionSettings, mut initiator_settings: SessionSettings) -> Sessions {
+            acceptor_settings.set(SOCKET_ACCEPT_PORT, "0");
+            let listener = bind(&acceptor_settings).unwrap();
+            initiator_settings.set(SOCKET_CONNECT_HOST, "127.0.0.1");
+            initiator_settings.set(SOCKET_CONNECT_PORT, &listener.local_addr().unwrap().port().to_string());
+
+            let store_factory = MemoryStoreFactory::new();
+            let acceptor = Session::new(SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW"), &acceptor_settings, &store_factory).unwrap();
+            let initiator = Session::new(SessionID::new(BEGIN_STRING_FIX42, "TW", "ISLD"), &initiator_settings, &store_factory).unwrap();
+            Sessions{acceptor: SessionHandle::new(acceptor), initiator: SessionHandle::new(initiator), acceptor_settings: acceptor_settings,
+                initiator_settings: initiator_settings, dynamic: None, orders: Arc::new(AtomicUsize::new(0)), listener: listener}
+        }
+
+        //dynamic replaces the acceptor with a template of ISLD accepting any counterparty
+        fn dynamic(mut self) -> Sessions {
+            let mut settings = self.acceptor_settings.clone();
+            settings.set(BEGIN_STRING, BEGIN_STRING_FIX42);
+            settings.set(SENDER_COMP_ID, "ISLD");
+            settings.set(TARGET_COMP_ID, WILDCARD);
+            let mut sessions = DynamicSessions::new(Box::new(MemoryStoreFactory::new()), Box::new(NullLogFactory::new()));
+            sessions.add_template(SessionTemplate::new(settings).unwrap());
+            self.dynamic = Some(Arc::new(Mutex::new(sessions)));
+            self
+        }
+
+        //acceptor is the acceptor session, the one the template created for a dynamic acceptor
+        fn acceptor(&self) -> SessionHandle {
+            match self.dynamic {
+                Some(ref sessions) => sessions.lock().unwrap().session(&SessionID::new(BEGIN_STRING_FIX42, "ISLD", "TW")).unwrap(),
+                None => self.acceptor.clone()
+            }
+        }
+
+        fn router(&self) -> MessageRouter {
+            let orders = self.orders.clone();
+            let mut router = MessageRouter::new();
+            router.add_route(BEGIN_STRING_FIX42, "D", move |_, _| {
+                orders.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+            router
+        }
+
+        //operate sends an order once the initiator is logged on, then logs it out
+        fn operate(&self) -> thread::JoinHandle<()> {
+            let initiator = self.initiator.clone();
+            thread::spawn(move || {
+                let deadline = StdInstant::now() + StdDuration::from_secs(10);
+                while !initiator.is_logged_on() && StdInstant::now() < deadline {
+                    thread::sleep(StdDuration::from_millis(5));
+                }
+                let mut order = Message::new();
+                order.header.set_string(Tags::MsgType.to_num(), "D");
+                initiator.lock().send(order, now_utc()).unwrap();
+                initiator.logout("done").unwrap();
+            })
+        }
+
+        fn check(&self) {
+            assert_eq!(1, self.orders.load(Ordering::SeqCst));
+            assert_eq!(SessionState::Latent, self.acceptor().state());
+            assert_eq!(SessionState::Latent, self.initiator.state());
+            assert_eq!(4, self.initiator.next_sender_msg_seq_num());
+            assert_eq!(3, self.acceptor().next_sender_msg_seq_num());
+        }
+
+        fn run(self) -> Sessions {
+            let operator = self.operate();
+            self.connection().unwrap();
+            operator.join().unwrap();
+            self
+        }
+
+        //connection runs the sessions over one connection, the result is the one of the initiator
+        fn connection(&self) -> Result<(), FixError> {
+            let listener = self.listener.try_clone().unwrap();
+            let acceptor = self.acceptor.clone();
+            let dynamic = self.dynamic.clone();
+            let settings = self.acceptor_settings.clone();
+            let mut router = self.router();
+            let server = thread::spawn(move || {
+                let (socket, _) = listener.accept().unwrap();
+                let mut stream = accept(&settings, socket).unwrap();
+                match dynamic {
+                    Some(sessions) => run_dynamic_acceptor(&sessions, &mut router, &mut stream),
+                    None => run_acceptor(&acceptor, &mut router, &mut stream)
+                }
+            });
+
+            let mut stream = connect(&self.initiator_settings).unwrap();
+            let result = run_initiator(&self.initiator, &mut MessageRouter::new(), &mut stream);
+            drop(stream);
+            server.join().unwrap().and(result)
+        }
+
+        #[cfg(feature = "async")]
+        fn run_async(self) -> Sessions {
+            let operator = self.operate();
+            self.connection_async().unwrap();
+            operator.join().unwrap();
+            self
+        }
+
+        #[cfg(feature = "async")]
+        fn connection_async(&self) -> Result<(), FixError> {
+            let listener = self.listener.try_clone().unwrap();
+            listener.set_nonblocking(true).unwrap();
+            let acceptor = self.acceptor.clone();
+            let dynamic = self.dynamic.clone();
+            let settings = self.acceptor_settings.clone();
+            let mut router = self.router();
+            let server = thread::spawn(move || {
+                let runtime = runtime();
+                let listener = {
+                    let _context = runtime.enter();
+                    tokio::net::TcpListener::from_std(listener).unwrap()
+                };
+                let (socket, _) = runtime.block_on(listener.accept()).unwrap();
+                let mut stream = runtime.block_on(accept_async(&settings, socket).unwrap()).unwrap();
+                match dynamic {
+                    Some(sessions) => runtime.block_on(run_dynamic_acceptor_async(&sessions, &mut router, &mut stream)),
+                    None => runtime.block_on(run_acceptor_async(&acceptor, &mut router, &mut stream)?)
+                }
+            });
+
+            let runtime = runtime();
+            let mut stream = runtime.block_on(connect_async(&self.initiator_settings).unwrap()).unwrap();
+            let mut router = MessageRouter::new();
+            let result = runtime.block_on(run_initiator_async(&self.initiator, &mut router, &mut stream).unwrap());
+            drop(stream);
+            server.join().unwrap().and(result)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+    }
+
+    #[test]
+    fn transport_test() {
+        Sessions::new(SessionSettings::new(), SessionSettings::new()).run().check();
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn tls_transport_test() {
+        let pki = Pki::new("transport");
+        Sessions::new(pki.acceptor_settings(true), pki.initiator_settings(true)).run().check();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_transport_test() {
+        Sessions::new(SessionSettings::new(), SessionSettings::new()).run_async().check();
+    }
+
+    #[cfg(all(feature = "async", feature = "tls"))]
+    #[test]
+    fn async_tls_transport_test() {
+        let pki = Pki::new("async-transport");
+        Sessions::new(pki.acceptor_settings(true), pki.initiator_settings(true)).run_async().check();
+    }
+
+    #[test]
+    fn dynamic_transport_test() {
+        Sessions::new(SessionSettings::new(), SessionSettings::new()).dynamic().run().check();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_dynamic_transport_test() {
+        Sessions::new(SessionSettings::new(), SessionSettings::new()).dynamic().run_async().check();
+    }
+
+    //logon_rate_limit connects three times with a wrong password, the limiter refuses the third Logon from
+    //127.0.0.1 before the authenticator sees it
+    fn logon_rate_limit(connection: fn(&Sessions) -> Result<(), FixError>) {
+        let limiter = Arc::new(LogonRateLimiter::new(2, Duration::minutes(1)));
+        let addresses = Arc::new(Mutex::new(vec![]));
+        for _ in 0..3 {
+            let mut initiator_settings = SessionSettings::new();
+            initiator_settings.set(PASSWORD, "guess");
+            let sessions = Sessions::new(SessionSettings::new(), initiator_settings);
+            let seen = addresses.clone();
+            {
+                let mut acceptor = sessions.acceptor.lock();
+                acceptor.set_authenticator(Arc::new(move |_: &SessionID, _: &Credentials, address: Option<&str>| {
+                    seen.lock().unwrap().push(address.map(|address| address.to_string()));
+                    Err("wrong password".to_string())
+                }));
+                acceptor.set_logon_rate_limiter(limiter.clone());
+            }
+
+            assert!(connection(&sessions).is_err());
+            assert_eq!(SessionState::Latent, sessions.acceptor.state());
+            assert_eq!(SessionState::Latent, sessions.initiator.state());
+        }
+        assert_eq!(vec![Some("127.0.0.1".to_string()); 2], *addresses.lock().unwrap());
+        assert!(limiter.is_blocked("127.0.0.1", now_utc()));
+    }
+
+    #[test]
+    fn logon_rate_limit_test() {
+        logon_rate_limit(Sessions::connection);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_logon_rate_limit_test() {
+        logon_rate_limit(Sessions::connection_async);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::*;
+use tag::*;
+use tag_value::*;
+use message::*;
+use message_view::*;
+use fix_int::*;
+use data_dictionary::*;
+use error::FixError;
+use error::error::MessageRejectError;
+
+//validate parses raw and checks it against the FIX session layer: framing, BodyLength, CheckSum, the required
+//header fields and field placement. Application fields are only checked for empty values, validate_with_dictionaries
+//adds the message specific rules. Framing problems are ParseMessageErrors, every other problem is the RejectError
+//a session would answer with.
+pub fn validate(raw: &[u8]) -> Result<Message, FixError> {
+    let view = MessageView::parse(raw)?;
+    let msg = Message::parse(raw)?;
+    let fields: Vec<(u32, &[u8])> = view.iter().collect();
+
+    validate_length_and_checksum(raw, &fields)?;
+
+    let check = |result: Result<(), MessageRejectError>| result.map_err(FixError::RejectError);
+    check(validate_placement(&fields))?;
+    check(validate_required(&view))?;
+    Ok(msg)
+}
+
+//validate_with_dictionaries runs validate, then reads raw with the repeating groups of its DataDictionaries and
+//checks the fields defined for its MsgType, the required fields, value types and enumerations. A message whose
+//BeginString has no dictionary in dictionaries only gets the checks of validate.
+pub fn validate_with_dictionaries(raw: &[u8], dictionaries: &DataDictionaryProvider) -> Result<Message, FixError> {
+    validate(raw)?;
+    let msg = dictionaries.parse(raw, "")?;
+    dictionaries.validate(&msg, &msg.application_version("")).map_err(FixError::RejectError)?;
+    Ok(msg)
+}
+
+fn validate_length_and_checksum(raw: &[u8], fields: &[(u32, &[u8])]) -> Result<(), FixError> {
+    let (tag, checksum) = fields[fields.len() - 1];
+    if tag != Tags::CheckSum.to_num() {
+        return Err(FixError::ParseMessageError("CheckSum(10) must be the last field".to_string()));
+    }
+
+    //BodyLength counts from the field after BodyLength up to and including the SOH before CheckSum
+    let trailer_len = 3 + checksum.len() + if raw.ends_with(&[SOH]) { 1 } else { 0 };
+    let checksum_start = raw.len() - trailer_len;
+    let body_start = 2 + fields[0].1.len() + 1 + 2 + fields[1].1.len() + 1;
+    let body_length = checksum_start as i32 - body_start as i32;
+    if parse_int(fields[1].1) != Some(body_length) {
+        return Err(FixError::ParseMessageError(format!("Incorrect BodyLength, expected {}", body_length)));
+    }
+
+    let sum = raw[..checksum_start].iter().fold(0u32, |sum, b| sum + *b as u32) % 256;
+    if checksum != format!("{:03}", sum).as_bytes() {
+        return Err(FixError::ParseMessageError(format!("Incorrect CheckSum, expected {:03}", sum)));
+    }
+    Ok(())
+}
+
+fn validate_placement(fields: &[(u32, &[u8])]) -> Result<(), MessageRejectError> {
+    let mut seen = HashSet::new();
+    let mut in_body = false;
+    let mut in_trailer = false;
+
+    for &(tag, value) in fields.iter() {
+        if value.is_empty() {
+            return Err(MessageRejectError::tag_specified_without_a_value(tag));
+        }
+
+        if tag.is_header() {
+            if in_body || in_trailer {
+                return Err(MessageRejectError::tag_specified_out_of_required_order(tag));
+            }
+        } else if tag.is_trailer() {
+            in_trailer = true;
+        } else {
+            if in_trailer {
+                return Err(MessageRejectError::tag_specified_out_of_required_order(tag));
+            }
+            in_body = true;
+        }
+
+        //body fields may repeat inside repeating groups, so only session level fields are checked for repeats
+        let hop = tag == Tags::HopCompID.to_num() || tag == Tags::HopSendingTime.to_num() || tag == Tags::HopRefID.to_num();
+        if (tag.is_header() || tag.is_trailer()) && !hop && !seen.insert(tag) {
+            return Err(MessageRejectError::tag_appears_more_than_once(tag));
+        }
+    }
+    Ok(())
+}
+
+fn validate_required(view: &MessageView) -> Result<(), MessageRejectError> {
+    for tag in [Tags::SenderCompID, Tags::TargetCompID, Tags::MsgSeqNum, Tags::SendingTime].iter() {
+        if !view.has(tag.to_num()) {
+            return Err(MessageRejectError::required_tag_missing(tag.to_num()));
+        }
+    }
+
+    view.get_int(Tags::MsgSeqNum.to_num())?;
+    view.get_time(Tags::SendingTime.to_num())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Reject;
+    use error::MessageRejectError as MessageRejectErrorTrait;
+    use std::sync::Arc;
+
+    static FIX42: &'static str = r#"<fix type="FIX" major="4" minor="2" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+    <field name="SenderCompID" required="Y"/>
+    <field name="TargetCompID" required="Y"/>
+    <field name="MsgSeqNum" required="Y"/>
+    <field name="SendingTime" required="Y"/>
+  </header>
+  <trailer>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="NewOrderSingle" msgtype="D" msgcat="app">
+      <field name="ClOrdID" required="Y"/>
+      <field name="Symbol" required="Y"/>
+      <field name="Side" required="Y"/>
+      <field name="OrderQty" required="N"/>
+    </message>
+  </messages>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="11" name="ClOrdID" type="STRING"/>
+    <field number="34" name="MsgSeqNum" type="SEQNUM"/>
+    <field number="35" name="MsgType" type="STRING"/>
+    <field number="38" name="OrderQty" type="QTY"/>
+    <field number="49" name="SenderCompID" type="STRING"/>
+    <field number="52" name="SendingTime" type="UTCTIMESTAMP"/>
+    <field number="54" name="Side" type="CHAR">
+      <value enum="1" description="BUY"/>
+      <value enum="2" description="SELL"/>
+    </field>
+    <field number="55" name="Symbol" type="STRING"/>
+    <field number="56" name="TargetCompID" type="STRING"/>
+    <field number="58" name="Text" type="STRING"/>
+  </fields>
+</fix>"#;
+
+    //frame wraps body in BeginString, BodyLength and CheckSum
+    fn frame(body: &str) -> Vec<u8> {
+        let mut raw = format!("8=FIX.4.2\x019={}\x01{}", body.len(), body).into_bytes();
+        let sum = raw.iter().fold(0u32, |sum, b| sum + *b as u32) % 256;
+        raw.extend_from_slice(format!("10={:03}\x01", sum).as_bytes());
+        raw
+    }
+
+    fn reject_reason(raw: &[u8]) -> Reject {
+        reject_reason_of(validate(raw))
+    }
+
+    fn reject_reason_of(result: Result<Message, FixError>) -> Reject {
+        match result {
+            Err(FixError::RejectError(err)) => err.reject_reason(),
+            other => panic!("expected a reject, got {:?}", other.map(|msg| msg.to_string()))
+        }
+    }
+
+    #[test]
+    fn valid_message_test() {
+        let raw = frame("35=D\x0149=TW\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x0155=MSFT\x01");
+        assert!(validate(&raw).is_ok());
+    }
+
+    #[test]
+    fn length_and_checksum_test() {
+        let mut raw = frame("35=D\x0149=TW\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x01");
+        let len = raw.len();
+        raw[len - 2] = b'0' + (raw[len - 2] - b'0' + 1) % 10;
+        assert!(validate(&raw).is_err());
+
+        let raw = b"8=FIX.4.2\x019=7\x0135=D\x0110=000\x01";
+        match validate(raw) {
+            Err(FixError::ParseMessageError(err)) => assert!(err.contains("BodyLength")),
+            _ => panic!("expected a BodyLength error")
+        }
+    }
+
+    #[test]
+    fn rejects_test() {
+        assert_eq!(Reject::RequiredTagMissing, reject_reason(&frame("35=D\x0149=TW\x0156=ISLD\x0134=1\x01")));
+        assert_eq!(Reject::IncorrectDataFormatForValue, reject_reason(&frame("35=D\x0149=TW\x0156=ISLD\x0134=x\x0152=20161012-10:05:01\x01")));
+        assert_eq!(Reject::TagAppearsMoreThanOnce, reject_reason(&frame("35=D\x0149=TW\x0149=TW\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x01")));
+        assert_eq!(Reject::TagSpecifiedWithoutAValue, reject_reason(&frame("35=D\x0149=TW\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x0155=\x01")));
+        assert_eq!(Reject::SpecifiedOutOfRequiredOrder, reject_reason(&frame("35=D\x0149=TW\x0155=MSFT\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x01")));
+    }
+
+    #[test]
+    fn dictionary_test() {
+        let mut dictionaries = DataDictionaryProvider::new();
+        dictionaries.add_dictionary(Arc::new(DataDictionary::parse(FIX42).unwrap()));
+        let header = "35=D\x0149=TW\x0156=ISLD\x0134=1\x0152=20161012-10:05:01\x01";
+        let check = |body: &str| validate_with_dictionaries(&frame(&format!("{}{}", header, body)), &dictionaries);
+
+        assert!(check("11=ID1\x0155=MSFT\x0154=1\x0138=100\x01").is_ok());
+        assert!(validate(&frame(&format!("{}11=ID1\x01", header))).is_ok());
+        assert_eq!(Reject::RequiredTagMissing, reject_reason_of(check("11=ID1\x0154=1\x01")));
+        assert_eq!(Reject::ValueIsIncorrect, reject_reason_of(check("11=ID1\x0155=MSFT\x0154=9\x01")));
+        assert_eq!(Reject::IncorrectDataFormatForValue, reject_reason_of(check("11=ID1\x0155=MSFT\x0154=1\x0138=x\x01")));
+        assert_eq!(Reject::TagNotDefinedForThisMessageType, reject_reason_of(check("11=ID1\x0155=MSFT\x0154=1\x0158=X\x01")));
+        assert_eq!(Reject::InvalidTagNumber, reject_reason_of(check("11=ID1\x0155=MSFT\x0154=1\x015001=x\x01")));
+
+        //without a dictionary for FIX.4.2 only the session layer is checked
+        assert!(validate_with_dictionaries(&frame(&format!("{}11=ID1\x01", header)), &DataDictionaryProvider::new()).is_ok());
+    }
+}
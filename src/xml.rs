@@ -0,0 +1,275 @@
+use std::char;
+
+//Element is an XML element with its attributes in document order, text is its trimmed character data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String
+}
+
+impl Element {
+
+    pub fn new(name: &str) -> Element {
+        Element{name: name.to_string(), attributes: vec![], children: vec![], text: String::new()}
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    //local_name is the name without its namespace prefix, e.g. message for sbe:message
+    pub fn local_name(&self) -> &str {
+        match self.name.rfind(':') {
+            Some(colon) => &self.name[colon + 1..],
+            None => &self.name
+        }
+    }
+
+    pub fn write(&self, xml: &mut String) {
+        xml.push('<');
+        xml.push_str(&self.name);
+        for (name, value) in self.attributes.iter() {
+            xml.push(' ');
+            xml.push_str(name);
+            xml.push_str("=\"");
+            write_escaped(xml, value);
+            xml.push('"');
+        }
+        if self.children.is_empty() && self.text.is_empty() {
+            xml.push_str("/>");
+            return;
+        }
+        xml.push('>');
+        write_escaped(xml, &self.text);
+        for child in self.children.iter() {
+            child.write(xml);
+        }
+        xml.push_str("</");
+        xml.push_str(&self.name);
+        xml.push('>');
+    }
+}
+
+fn write_escaped(xml: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '"' => xml.push_str("&quot;"),
+            c if (c as u32) < 0x20 => xml.push_str(&format!("&#{};", c as u32)),
+            c => xml.push(c)
+        }
+    }
+}
+
+//parse reads the root element of a document
+pub fn parse(xml: &str) -> Result<Element, String> {
+    let mut parser = Parser{xml: xml.as_bytes(), pos: 0};
+    parser.parse_document()
+}
+
+//Parser reads the subset of XML used by FIXML and SBE schemas: elements, attributes, text, the declaration and
+//comments
+struct Parser<'a> {
+    xml: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+
+    fn error(&self, text: &str) -> String {
+        format!("{} at {}", text, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.xml.len() && (self.xml[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.xml[self.pos..].starts_with(prefix)
+    }
+
+    fn skip_past(&mut self, end: &[u8]) -> Result<(), String> {
+        match (self.pos..self.xml.len()).find(|&i| self.xml[i..].starts_with(end)) {
+            Some(i) => {
+                self.pos = i + end.len();
+                Ok(())
+            },
+            None => Err(self.error(&format!("missing {}", String::from_utf8_lossy(end))))
+        }
+    }
+
+    //skip_misc skips whitespace, comments and processing instructions such as the XML declaration
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with(b"<?") {
+                self.skip_past(b"?>")?;
+            } else if self.starts_with(b"<!--") {
+                self.skip_past(b"-->")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Element, String> {
+        self.skip_misc()?;
+        let root = self.parse_element()?;
+        self.skip_misc()?;
+        if self.pos != self.xml.len() {
+            return Err(self.error("unexpected content after the root element"));
+        }
+        Ok(root)
+    }
+
+    fn parse_name(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while self.pos < self.xml.len() {
+            match self.xml[self.pos] {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-' | b'.' | b':' => self.pos += 1,
+                _ => break
+            }
+        }
+        if start == self.pos {
+            return Err(self.error("expected a name"));
+        }
+        Ok(String::from_utf8_lossy(&self.xml[start..self.pos]).into_owned())
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.pos < self.xml.len() && self.xml[self.pos] == b {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", b as char)))
+        }
+    }
+
+    fn parse_element(&mut self) -> Result<Element, String> {
+        self.expect(b'<')?;
+        let mut element = Element::new(&self.parse_name()?);
+        loop {
+            self.skip_whitespace();
+            if self.starts_with(b"/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.starts_with(b">") {
+                self.pos += 1;
+                break;
+            }
+            let name = self.parse_name()?;
+            self.skip_whitespace();
+            self.expect(b'=')?;
+            self.skip_whitespace();
+            let value = self.parse_attribute_value()?;
+            element.attributes.push((name, value));
+        }
+
+        let mut text = String::new();
+        loop {
+            let start = self.pos;
+            while self.pos < self.xml.len() && self.xml[self.pos] != b'<' {
+                self.pos += 1;
+            }
+            if self.pos == self.xml.len() {
+                return Err(self.error(&format!("{} is not closed", element.name)));
+            }
+            text.push_str(&String::from_utf8_lossy(&self.xml[start..self.pos]));
+
+            if self.starts_with(b"<!--") {
+                self.skip_past(b"-->")?;
+            } else if self.starts_with(b"</") {
+                self.pos += 2;
+                let name = self.parse_name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{}> closes <{}>", name, element.name)));
+                }
+                self.skip_whitespace();
+                self.expect(b'>')?;
+                element.text = unescape(text.trim()).ok_or_else(|| self.error(&format!("bad entity in {}", text.trim())))?;
+                return Ok(element);
+            } else {
+                let child = self.parse_element()?;
+                element.children.push(child);
+            }
+        }
+    }
+
+    fn parse_attribute_value(&mut self) -> Result<String, String> {
+        let quote = match self.xml.get(self.pos) {
+            Some(&b'"') => b'"',
+            Some(&b'\'') => b'\'',
+            _ => return Err(self.error("expected a quoted value"))
+        };
+        self.pos += 1;
+        let start = self.pos;
+        while self.pos < self.xml.len() && self.xml[self.pos] != quote {
+            self.pos += 1;
+        }
+        if self.pos == self.xml.len() {
+            return Err(self.error("unterminated value"));
+        }
+        let raw = String::from_utf8_lossy(&self.xml[start..self.pos]).into_owned();
+        self.pos += 1;
+        unescape(&raw).ok_or_else(|| self.error(&format!("bad entity in {}", raw)))
+    }
+}
+
+fn unescape(raw: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        value.push_str(&rest[..amp]);
+        let end = rest[amp..].find(';')? + amp;
+        let entity = &rest[amp + 1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ if entity.starts_with("#x") => char::from_u32(u32::from_str_radix(&entity[2..], 16).ok()?)?,
+            _ if entity.starts_with('#') => char::from_u32(entity[1..].parse::<u32>().ok()?)?,
+            _ => return None
+        };
+        value.push(c);
+        rest = &rest[end + 1..];
+    }
+    value.push_str(rest);
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let root = parse("<?xml version=\"1.0\"?>\n<!-- c --><sbe:types a='1' b=\"&lt;x&gt;\">\
+<enum name=\"Side\"><validValue name=\"Buy\"> 1 </validValue><!-- c --></enum><type/></sbe:types>").unwrap();
+        assert_eq!("types", root.local_name());
+        assert_eq!(Some("<x>"), root.attribute("b"));
+        assert_eq!(2, root.children.len());
+        assert_eq!("1", root.children[0].children[0].text);
+
+        let mut xml = String::new();
+        root.children[0].write(&mut xml);
+        assert_eq!("<enum name=\"Side\"><validValue name=\"Buy\">1</validValue></enum>", xml);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("<a b=1/>").is_err());
+        assert!(parse("<a/><b/>").is_err());
+        assert!(parse("<a>&unknown;</a>").is_err());
+    }
+}